@@ -0,0 +1,46 @@
+/* The C API of skia_gl, built into the shared library of the crate with
+ * `cargo build --release --features capi`. Every function is called from the thread
+ * that created the backend, usually the main thread. */
+#ifndef SKIA_GL_H
+#define SKIA_GL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct SkglBackend SkglBackend;
+
+/* Draws a frame on `canvas`, the `SkCanvas*` of the Skia skia_gl was built against, only
+ * valid during the call. The size is in pixels. With the default `independent_ui`
+ * feature it is called on the render thread. */
+typedef void (*SkglRenderCallback)(void *canvas, int32_t width, int32_t height,
+                                   size_t frame, void *user_data);
+
+/* Returns NULL when the window or the GL context could not be created. */
+SkglBackend *skgl_backend_create(uint32_t width, uint32_t height, const char *title);
+
+/* The functions returning bool return false on a NULL backend or a failure, the
+ * backend should be destroyed after a failure. */
+bool skgl_backend_set_render_callback(SkglBackend *backend, SkglRenderCallback callback,
+                                      void *user_data);
+bool skgl_backend_resize(SkglBackend *backend, uint32_t width, uint32_t height);
+bool skgl_backend_send_cursor_moved(SkglBackend *backend, float x, float y);
+/* 0 is the left button, 1 the right one, 2 the middle one. */
+bool skgl_backend_send_mouse_button(SkglBackend *backend, uint32_t button, bool pressed);
+bool skgl_backend_send_scroll(SkglBackend *backend, float dx, float dy);
+
+/* Handles the pending events and draws the due frames, waiting at most `timeout_ms`.
+ * Returns false once the window was closed. */
+bool skgl_backend_pump(SkglBackend *backend, uint32_t timeout_ms);
+
+void skgl_backend_destroy(SkglBackend *backend);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SKIA_GL_H */
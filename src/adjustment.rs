@@ -0,0 +1,50 @@
+use skia_safe::{color_filters, ColorFilter};
+
+/// The last correction of the frame colors, for the displays that can't be calibrated,
+/// e.g. the panels of kiosk deployments. The gamma is applied first, then the contrast
+/// around the mid gray and the brightness offset, to the RGB channels of every pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputAdjustment {
+    /// Above `1` brightens the mid tones, below darkens them.
+    pub gamma: f32,
+    /// Added to the channels, `-1..=1`.
+    pub brightness: f32,
+    /// The factor the distance from the mid gray is scaled by.
+    pub contrast: f32,
+}
+
+impl Default for OutputAdjustment {
+    fn default() -> Self {
+        Self {
+            gamma: 1.,
+            brightness: 0.,
+            contrast: 1.,
+        }
+    }
+}
+
+impl OutputAdjustment {
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The adjusted channel, both in `0..=1`.
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = value.clamp(0., 1.).powf(1. / self.gamma.max(f32::EPSILON));
+        ((value - 0.5) * self.contrast + 0.5 + self.brightness).clamp(0., 1.)
+    }
+
+    /// The filter to draw the frame with, `None` when nothing is adjusted. The channels go
+    /// through a lookup table, the alpha is kept.
+    pub fn color_filter(&self) -> Option<ColorFilter> {
+        if self.is_identity() {
+            return None;
+        }
+        let mut table = [0u8; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            *entry = (self.apply(index as f32 / 255.) * 255.).round() as u8;
+        }
+        color_filters::table_argb(None, &table, &table, &table)
+    }
+}
@@ -0,0 +1,198 @@
+use std::cell::Cell;
+
+use bumpalo::{collections::Vec as BumpVec, Bump};
+
+pub const DEFAULT_ARENA_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A bump allocator for the transient data of a frame, e.g. the points of a path or the
+/// glyphs of a text blob, handed to the renderers in `FrameInfo::arena`. The allocations
+/// are pointer bumps into large chunks of a `bumpalo::Bump` and are all freed at once when
+/// the backend resets the arena after the frame, so building the draw data doesn't churn
+/// the heap. The largest chunk is kept on reset, a steady frame allocates nothing after the
+/// first ones. Only `Copy` data is stored, nothing is dropped.
+pub struct FrameArena {
+    bump: Bump,
+    allocated: Cell<usize>,
+    peak: usize,
+}
+
+impl Default for FrameArena {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_ARENA_CHUNK_SIZE)
+    }
+}
+
+impl FrameArena {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            bump: Bump::with_capacity(bytes),
+            allocated: Cell::new(0),
+            peak: 0,
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+        self.count::<T>(1);
+        self.bump.alloc(value)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        self.count::<T>(values.len());
+        self.bump.alloc_slice_copy(values)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill<T: Copy>(&self, len: usize, value: T) -> &mut [T] {
+        self.count::<T>(len);
+        self.bump.alloc_slice_fill_copy(len, value)
+    }
+
+    /// The items of the iterator, fewer if it ends before its reported length.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_iter<T: Copy>(
+        &self,
+        items: impl IntoIterator<Item = T, IntoIter = impl ExactSizeIterator<Item = T>>,
+    ) -> &mut [T] {
+        let items = items.into_iter();
+        let mut slice = BumpVec::with_capacity_in(items.len(), &self.bump);
+        slice.extend(items);
+        self.count::<T>(slice.len());
+        slice.into_bump_slice_mut()
+    }
+
+    pub fn alloc_str(&self, text: &str) -> &str {
+        self.count::<u8>(text.len());
+        self.bump.alloc_str(text)
+    }
+
+    /// The bytes allocated since the last reset.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated.get()
+    }
+
+    /// The most bytes allocated in a frame so far.
+    #[inline]
+    pub fn peak_bytes(&self) -> usize {
+        self.peak.max(self.allocated.get())
+    }
+
+    /// The bytes of all the chunks.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    /// Free the allocations at once, the borrow checker ensures none is still used. The
+    /// chunks of an overflowing frame are freed but the largest one.
+    pub fn reset(&mut self) {
+        self.peak = self.peak_bytes();
+        self.allocated.set(0);
+        self.bump.reset();
+    }
+
+    fn count<T>(&self, len: usize) {
+        let bytes = std::mem::size_of::<T>()
+            .checked_mul(len)
+            .expect("Arena allocation too large");
+        self.allocated.set(self.allocated.get() + bytes);
+    }
+}
+
+impl std::fmt::Debug for FrameArena {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameArena")
+            .field("allocated", &self.allocated_bytes())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_are_aligned() {
+        #[derive(Clone, Copy)]
+        #[repr(align(32))]
+        struct Aligned(u8);
+
+        let arena = FrameArena::with_capacity(256);
+        arena.alloc(1u8);
+        let aligned = arena.alloc(Aligned(2));
+        assert_eq!(aligned as *const Aligned as usize % 32, 0);
+        assert_eq!(aligned.0, 2);
+        let values = arena.alloc_slice_copy(&[1u64, 2, 3]);
+        assert_eq!(values.as_ptr() as usize % std::mem::align_of::<u64>(), 0);
+        assert_eq!(values, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn grows_past_the_chunk_and_keeps_the_values() {
+        let arena = FrameArena::with_capacity(64);
+        let first = arena.alloc_slice_fill(16, 7u32);
+        let second = arena.alloc_slice_fill(1024, 9u32);
+        assert!(first.iter().all(|&value| value == 7));
+        assert!(second.iter().all(|&value| value == 9));
+        assert_eq!(arena.allocated_bytes(), (16 + 1024) * 4);
+        assert!(arena.capacity() >= arena.allocated_bytes());
+    }
+
+    #[test]
+    fn reset_keeps_the_peak_and_the_capacity() {
+        let mut arena = FrameArena::with_capacity(64);
+        arena.alloc_slice_fill(4096, 0u8);
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), 0);
+        assert_eq!(arena.peak_bytes(), 4096);
+        let capacity = arena.capacity();
+        assert!(capacity >= 4096);
+        arena.alloc_slice_fill(4096, 0u8);
+        assert_eq!(arena.capacity(), capacity);
+    }
+
+    #[test]
+    fn zero_sized_and_empty_allocations() {
+        let arena = FrameArena::with_capacity(64);
+        arena.alloc(());
+        assert!(arena.alloc_slice_copy::<u32>(&[]).is_empty());
+        assert_eq!(arena.alloc_slice_fill(3, ()).len(), 3);
+        assert_eq!(arena.alloc_str(""), "");
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+
+    #[test]
+    fn fill_iter_stops_at_the_end_of_the_items() {
+        struct Short(std::ops::Range<u32>);
+        impl Iterator for Short {
+            type Item = u32;
+            fn next(&mut self) -> Option<u32> {
+                self.0.next()
+            }
+        }
+        impl ExactSizeIterator for Short {
+            fn len(&self) -> usize {
+                self.0.len() + 2
+            }
+        }
+
+        let arena = FrameArena::new();
+        assert_eq!(arena.alloc_slice_fill_iter(Short(0..3)), &[0, 1, 2]);
+        assert_eq!(arena.alloc_str("frame"), "frame");
+    }
+
+    #[test]
+    #[should_panic(expected = "Arena allocation too large")]
+    fn overflowing_allocation_panics() {
+        let arena = FrameArena::new();
+        arena.alloc_slice_fill(usize::MAX / 2, 0u32);
+    }
+}
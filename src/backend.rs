@@ -1,28 +1,90 @@
 use gl::types::GLint;
 use glutin::{
     config::{Config, GlConfig},
-    context::{NotCurrentContext, NotCurrentGlContext, PossiblyCurrentContext},
+    context::{
+        ContextApi, ContextAttributesBuilder, GlContext, NotCurrentContext, NotCurrentGlContext,
+        PossiblyCurrentContext, PossiblyCurrentGlContext,
+    },
     display::{GetGlDisplay, GlDisplay},
     surface::{GlSurface, Surface, SwapInterval, WindowSurface},
 };
 use skia_safe::{
-    gpu::{gl::FramebufferInfo, BackendRenderTarget, DirectContext, SurfaceOrigin},
-    Canvas, Color, ColorType,
+    canvas::SaveLayerRec,
+    color_filters,
+    gpu::{gl::FramebufferInfo, BackendRenderTarget, ContextOptions, DirectContext, SurfaceOrigin},
+    Canvas, Color, Image, Paint, Picture, Rect, Typeface,
 };
 use std::{
+    any::Any,
     ffi::CString,
     num::NonZeroU32,
-    sync::{Arc, Mutex},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
+use tracing::info_span;
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    monitor::VideoMode,
+    window::{Fullscreen, UserAttentionType, Window, WindowId, WindowLevel},
 };
-use winit::window::Window;
 
 #[cfg(feature = "independent_ui")]
-use std::{
-    sync::mpsc::{channel, Receiver, Sender},
-    thread,
+use crate::{
+    channel::{self, BufferedSender, Prioritized, Receiver, TryRecvError},
+    thread::RenderThreadConfig,
+    watchdog::{Heartbeat, Watchdog, WatchdogAction, WatchdogConfig},
 };
+#[cfg(feature = "independent_ui")]
+use std::{sync::mpsc, thread};
 
-use crate::{renderer, SkiaSurface};
+#[cfg(feature = "profiling")]
+use crate::puffin_overlay::PuffinOverlay;
+use crate::{
+    adjustment::OutputAdjustment,
+    arena::FrameArena,
+    bindings::Action,
+    blur,
+    camera::{Camera, CameraConfig},
+    capture::{self, CaptureOptions},
+    clock::{self, FrameClock},
+    color_vision::ColorVision,
+    context_menu::{ContextMenu, MenuEntry, MenuResponse},
+    cross_fade::{CrossFade, CrossFadeConfig, GlobalChange},
+    decorations::{DragRegions, HitTest},
+    diagnostics::{DiagnosticPattern, Diagnostics},
+    fbo::Framebuffer,
+    focus::{FocusManager, KeyRouting},
+    frame_hash::{self, FrameHashConfig, FrameHashes, Verification},
+    geometry::SavedGeometry,
+    gl_info::GlInfo,
+    glyphs::{self, GlyphSet},
+    idle::{IdleContext, IdleQueue, IdleStatus},
+    input::{InputEvent, InputState, MouseButton},
+    invalidation::{Damage, Invalidator},
+    locale::Locale,
+    pacer::FramePacer,
+    print::{self, PrintOptions, PrintOutcome},
+    profiler::DrawProfiler,
+    quality::{Quality, QualityFeature, QualityGovernor, QualityPolicy},
+    readback::AsyncReadback,
+    record::{InputRecorder, RecordedEvent},
+    recovery::{RecoveryConfig, StateRecovery},
+    renderer::{Antialiasing, FrameInfo, Renderer},
+    resolution::{RenderScale, ResolutionScaler},
+    ruler::RulerOverlay,
+    safe_area::{Insets, SafeAreaConfig},
+    scene,
+    screenshot::{ScreenshotConfig, Screenshotter},
+    stats::{self, DroppedFrame, FrameStats, FrameTimings, SlowFrameConfig},
+    stream::{FrameStreamer, StreamConfig},
+    surface::{ConfigReport, MsaaResolve, SurfaceColorType, SurfaceConfig},
+    theme::{SystemTheme, Theme, ThemeAnimator, SYSTEM_THEME_TRANSITION},
+    upload::TextureUploader,
+    user_event::UserEvent,
+    SkiaSurface,
+};
 
 pub struct GlCtx {
     not_current_context: Option<NotCurrentContext>,
@@ -38,204 +100,2988 @@ impl GlCtx {
     }
 
     #[inline]
-    pub fn make_current(&mut self, surface: &Surface<WindowSurface>) {
-        if let Some(not_current_ctx) = self.not_current_context.take() {
-            self.possibly_current_context = Some(not_current_ctx.make_current(surface).unwrap())
+    pub fn make_current(&mut self, surface: &Surface<WindowSurface>) {
+        if let Some(not_current_ctx) = self.not_current_context.take() {
+            self.possibly_current_context = Some(not_current_ctx.make_current(surface).unwrap())
+        }
+    }
+
+    #[inline]
+    pub fn possibly_current_context(&self) -> Option<&PossiblyCurrentContext> {
+        self.possibly_current_context.as_ref()
+    }
+
+    pub fn make_not_current(&mut self) {
+        if let Some(ctx) = self.possibly_current_context.take() {
+            match ctx.make_not_current() {
+                Ok(ctx) => self.not_current_context = Some(ctx),
+                Err(err) => eprintln!("Error releasing the GL context: {err}"),
+            }
+        }
+    }
+}
+
+pub struct GlEnv {
+    gl_surface: Surface<WindowSurface>,
+    gl_ctx: Mutex<GlCtx>,
+    gl_config: Config,
+    config_report: Option<ConfigReport>,
+    /// Queried by `load` on the thread the context is current on.
+    info: OnceLock<GlInfo>,
+}
+unsafe impl Sync for GlEnv {}
+unsafe impl Send for GlEnv {}
+impl GlEnv {
+    #[inline]
+    pub fn new(gl_surface: Surface<WindowSurface>, gl_ctx: GlCtx, gl_config: Config) -> Self {
+        Self {
+            gl_surface,
+            gl_ctx: Mutex::new(gl_ctx),
+            gl_config,
+            config_report: None,
+            info: OnceLock::new(),
+        }
+    }
+
+    /// Attach the outcome of the config negotiation, for the application to query.
+    #[inline]
+    pub fn with_config_report(mut self, report: ConfigReport) -> Self {
+        self.config_report = Some(report);
+        self
+    }
+
+    /// How the GL config matches the requested one, `None` when it wasn't negotiated.
+    #[inline]
+    pub fn config_report(&self) -> Option<&ConfigReport> {
+        self.config_report.as_ref()
+    }
+
+    #[inline]
+    pub fn set_vsync(&self) {
+        if let Err(res) = self.gl_surface.set_swap_interval(
+            self.gl_ctx
+                .lock()
+                .unwrap()
+                .possibly_current_context()
+                .unwrap(),
+            SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
+        ) {
+            eprintln!("Error setting vsync: {res:?}");
+        }
+    }
+
+    #[inline]
+    pub fn make_current(&self) {
+        self.gl_ctx.lock().unwrap().make_current(&self.gl_surface)
+    }
+
+    /// Release the context from the current thread, also after a panic poisoned the lock.
+    #[inline]
+    pub fn release(&self) {
+        self.gl_ctx
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .make_not_current()
+    }
+
+    /// Load the GL functions, and query the `info` of the context the first time.
+    pub fn load(&self) {
+        gl::load_with(|s| {
+            self.gl_config
+                .display()
+                .get_proc_address(CString::new(s).unwrap().as_c_str())
+        });
+        if self.info.get().is_none() {
+            if let Some(info) = self.query_info() {
+                self.info.set(info).ok();
+            }
+        }
+    }
+
+    #[inline]
+    pub fn resize(&self, size: (u32, u32)) {
+        if let Some(ctx) = self.gl_ctx.lock().unwrap().possibly_current_context() {
+            self.gl_surface.resize(
+                ctx,
+                NonZeroU32::new(size.0.max(1)).unwrap(),
+                NonZeroU32::new(size.1.max(1)).unwrap(),
+            )
+        }
+    }
+
+    #[inline]
+    pub fn swap_buffers(&self) {
+        if let Some(ctx) = self.gl_ctx.lock().unwrap().possibly_current_context() {
+            self.gl_surface.swap_buffers(ctx).unwrap()
+        }
+    }
+
+    #[inline]
+    pub fn gl_config(&self) -> &Config {
+        &self.gl_config
+    }
+
+    /// The negotiated API and version, the driver strings and the extensions, from any
+    /// thread. `None` until the context was loaded, on the `independent_ui` feature the
+    /// render thread loads it shortly after the backend was created.
+    #[inline]
+    pub fn info(&self) -> Option<GlInfo> {
+        self.info.get().cloned()
+    }
+
+    /// Query the info, `None` when the context is not current on the calling thread.
+    fn query_info(&self) -> Option<GlInfo> {
+        let gl_ctx = self.gl_ctx.lock().unwrap();
+        let ctx = gl_ctx
+            .possibly_current_context()
+            .filter(|ctx| ctx.is_current())?;
+        Some(GlInfo::query(
+            ctx.context_api(),
+            self.gl_config.display().supported_features(),
+        ))
+    }
+
+    /// Create a context sharing the textures and buffers of the rendering context, to be
+    /// made current on another thread.
+    pub fn create_shared_context(&self) -> glutin::error::Result<NotCurrentContext> {
+        let gl_ctx = self.gl_ctx.lock().unwrap();
+        let builder = |api| {
+            let builder = ContextAttributesBuilder::new().with_context_api(api);
+            let builder = match (
+                &gl_ctx.possibly_current_context,
+                &gl_ctx.not_current_context,
+            ) {
+                (Some(ctx), _) => builder.with_sharing(ctx),
+                (None, Some(ctx)) => builder.with_sharing(ctx),
+                (None, None) => builder,
+            };
+            builder.build(None)
+        };
+        let display = self.gl_config.display();
+        // The shared context must use the API the rendering context fell back to.
+        unsafe {
+            display
+                .create_context(&self.gl_config, &builder(ContextApi::OpenGl(None)))
+                .or_else(|_| {
+                    display.create_context(&self.gl_config, &builder(ContextApi::Gles(None)))
+                })
+        }
+    }
+}
+
+pub struct SkiaEnv {
+    gr_context: DirectContext,
+    fb_info: FramebufferInfo,
+    /// Resolved against the GL config, never `Auto`.
+    color_type: SurfaceColorType,
+    msaa_resolve: MsaaResolve,
+    surface: SkiaSurface,
+    size: (i32, i32),
+    /// The crate-managed framebuffer rendered into instead of the window framebuffer, it
+    /// is blitted to the window framebuffer on flush.
+    offscreen: Option<Framebuffer>,
+    offscreen_requested: bool,
+    render_scale: f32,
+    supersample: bool,
+    /// Render the frame into the window surface, past the render scale and the offscreen
+    /// framebuffer.
+    direct: bool,
+    /// Whether the offscreen framebuffer still holds the previous frame.
+    content_preserved: bool,
+}
+impl SkiaEnv {
+    pub fn canvas(&mut self) -> &mut Canvas {
+        self.target_surface().canvas()
+    }
+
+    /// The surface rendered into.
+    pub fn target_surface(&mut self) -> &mut SkiaSurface {
+        match self.offscreen.as_mut() {
+            Some(offscreen) if !self.direct => offscreen.surface(),
+            _ => &mut self.surface,
+        }
+    }
+
+    /// Render the next frames straight into the window surface at its resolution, e.g. the
+    /// diagnostic patterns testing what the compositor presents.
+    #[inline]
+    pub fn set_direct(&mut self, direct: bool) {
+        self.direct = direct;
+    }
+
+    pub fn resize(&mut self, size: (i32, i32), config: &Config) {
+        // Minimized windows report a zero size, there is no surface of that size, keep
+        // the old one until restored since nothing is rendered meanwhile.
+        if size.0 <= 0 || size.1 <= 0 {
+            return;
+        }
+        let num_samples = config.num_samples() as usize;
+        let stencil_size = config.num_samples() as usize;
+
+        self.size = size;
+        self.surface = create_surface(
+            size,
+            self.fb_info,
+            self.color_type,
+            &mut self.gr_context,
+            num_samples,
+            stencil_size,
+        );
+        self.update_offscreen();
+    }
+
+    /// Recreate the window surface with the color type, resolved against the GL config, and
+    /// the GPU context when the msaa resolve changed. Returns whether the GPU context was
+    /// recreated.
+    pub fn set_surface_config(&mut self, surface_config: SurfaceConfig, config: &Config) -> bool {
+        let color_type = surface_config.color_type.resolve(config);
+        if surface_config.msaa_resolve != self.msaa_resolve {
+            self.color_type = color_type;
+            self.msaa_resolve = surface_config.msaa_resolve;
+            self.recreate(config);
+            return true;
+        }
+        if color_type == self.color_type {
+            return false;
+        }
+        self.color_type = color_type;
+        self.fb_info.format = color_type.formats().1.into();
+        self.resize(self.size, config);
+        false
+    }
+
+    #[inline]
+    pub fn color_type(&self) -> SurfaceColorType {
+        self.color_type
+    }
+
+    /// Render into a crate-managed framebuffer, e.g. for post-processing, instead of the
+    /// window framebuffer.
+    pub fn set_offscreen(&mut self, offscreen: bool) {
+        self.offscreen_requested = offscreen;
+        self.update_offscreen();
+    }
+
+    #[inline]
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Render at `scale` times the window resolution, a scale other than `1` renders into
+    /// the offscreen framebuffer.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        if scale != self.render_scale {
+            self.render_scale = scale;
+            self.update_offscreen();
+        }
+    }
+
+    /// Supersampling renders into the offscreen framebuffer at twice the render scale, the
+    /// linear blit at exactly half the size averages each 2x2 block.
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        let supersample = antialiasing == Antialiasing::Supersample;
+        if supersample != self.supersample {
+            self.supersample = supersample;
+            self.update_offscreen();
+        }
+    }
+
+    /// The number of surface pixels per window pixel, the render scale including
+    /// supersampling, `1` when rendering directly.
+    #[inline]
+    pub fn pixel_scale(&self) -> f32 {
+        if self.direct {
+            1.
+        } else {
+            self.offscreen_scale()
+        }
+    }
+
+    /// The number of offscreen framebuffer pixels per window pixel.
+    #[inline]
+    fn offscreen_scale(&self) -> f32 {
+        if self.supersample {
+            self.render_scale * 2.
+        } else {
+            self.render_scale
+        }
+    }
+
+    /// Abandon the GPU context and create a new one, e.g. after a GPU reset. The GPU
+    /// resources of the old context must not be used anymore.
+    fn recreate(&mut self, config: &Config) {
+        self.offscreen = None;
+        self.gr_context.abandon();
+        let mut skia_env = create_skia_env(self.size, config, self.msaa_resolve);
+        skia_env.set_surface_config(
+            SurfaceConfig {
+                color_type: self.color_type,
+                msaa_resolve: self.msaa_resolve,
+            },
+            config,
+        );
+        skia_env.offscreen_requested = self.offscreen_requested;
+        skia_env.render_scale = self.render_scale;
+        skia_env.supersample = self.supersample;
+        skia_env.update_offscreen();
+        *self = skia_env;
+    }
+
+    fn update_offscreen(&mut self) {
+        self.offscreen = None;
+        self.content_preserved = false;
+        let scale = self.offscreen_scale();
+        if self.offscreen_requested || scale != 1. {
+            let size = (
+                (self.size.0 as f32 * scale).round() as i32,
+                (self.size.1 as f32 * scale).round() as i32,
+            );
+            self.offscreen = Framebuffer::new(&mut self.gr_context, size, self.color_type);
+        }
+    }
+
+    /// Whether the GPU context was lost, e.g. after a GPU reset or a driver update.
+    #[inline]
+    pub fn is_device_lost(&mut self) -> bool {
+        self.gr_context.abandoned()
+    }
+
+    /// Submit what was recorded and abandon the GPU context, so no GL calls are made after
+    /// the GL context is gone, e.g. when a frame panicked. The environment is unusable after.
+    pub fn teardown(&mut self) {
+        self.gr_context.flush_and_submit();
+        self.offscreen = None;
+        self.gr_context.abandon();
+    }
+
+    /// Render into a framebuffer of its own and snapshot it, the window surface and the
+    /// offscreen framebuffer are left untouched.
+    pub fn render_offscreen(
+        &mut self,
+        size: (i32, i32),
+        draw: impl FnOnce(&mut Canvas),
+    ) -> Option<Image> {
+        let mut framebuffer = Framebuffer::new(&mut self.gr_context, size, self.color_type)?;
+        draw(framebuffer.surface().canvas());
+        let image = framebuffer.surface().image_snapshot();
+        self.gr_context.flush_and_submit();
+        drop(framebuffer);
+        // Deleting the bound framebuffer changed the GL state behind Skia's back.
+        self.gr_context.reset(None);
+        Some(image)
+    }
+
+    /// Submit the rendering, blitting the offscreen framebuffer to the window framebuffer.
+    pub fn flush(&mut self) {
+        self.gr_context.flush_and_submit();
+        match &self.offscreen {
+            Some(offscreen) if !self.direct => {
+                offscreen.blit_to(self.fb_info.fboid, self.size, true);
+                self.gr_context.reset(None);
+            }
+            _ => {}
+        }
+        self.content_preserved = self.offscreen.is_some() && !self.direct;
+    }
+
+    /// A snapshot of the presented frame when the offscreen framebuffer still holds it, the
+    /// window framebuffer is undefined after a swap.
+    pub fn preserved_snapshot(&mut self) -> Option<Image> {
+        if !self.content_preserved {
+            return None;
+        }
+        self.offscreen
+            .as_mut()
+            .map(|offscreen| offscreen.surface().image_snapshot())
+    }
+
+    /// Clip the canvas to the damaged area when the offscreen framebuffer still holds the
+    /// previous frame, the window framebuffer is undefined after a swap and always redrawn
+    /// whole. Returns the save count to restore once drawn.
+    pub fn clip_to_damage(&mut self, damage: Damage) -> Option<usize> {
+        let Damage::Rect(rect) = damage else {
+            return None;
+        };
+        if !self.content_preserved {
+            return None;
+        }
+        let scale = self.pixel_scale();
+        let rect = Rect::new(
+            rect.left * scale,
+            rect.top * scale,
+            rect.right * scale,
+            rect.bottom * scale,
+        );
+        let canvas = self.canvas();
+        let count = canvas.save();
+        canvas.clip_rect(Rect::from_irect(rect.round_out()), None, false);
+        Some(count)
+    }
+}
+
+/// The state driving the renderer, shared by the inline and the `independent_ui` rendering.
+struct UiState {
+    input: InputState,
+    camera: Camera,
+    renderer: Box<dyn Renderer>,
+    last_frame: Instant,
+    clock: FrameClock,
+    skp_path: Option<PathBuf>,
+    print_options: Option<PrintOptions>,
+    /// Rasterized into the glyph atlas before the next frame.
+    glyph_sets: Vec<GlyphSet>,
+    slow_frames: Option<SlowFrameConfig>,
+    /// The picture of the last frame, recorded to be dumped if it turns out slow.
+    frame_picture: Option<Picture>,
+    capture: Option<(PathBuf, CaptureOptions)>,
+    screenshotter: Screenshotter,
+    screenshot_requested: bool,
+    /// Read the frames back asynchronously for the screenshots and the stream.
+    readback: Option<AsyncReadback>,
+    /// The frames whose readback is a screenshot.
+    readback_screenshots: Vec<usize>,
+    /// Keep a snapshot of every frame for the renderer to draw the next frame with.
+    keep_frame_snapshot: bool,
+    /// Keep the presented frame to cross-fade from, see `Backend::set_cross_fade`.
+    keep_fade_snapshot: bool,
+    /// Panic on GL errors after each frame, e.g. in the stress test.
+    gl_error_checks: bool,
+    frame_snapshot: Option<Image>,
+    /// Answered with the next frame, taken before its swap.
+    #[cfg(feature = "independent_ui")]
+    snapshot_replies: Vec<mpsc::Sender<Option<Image>>>,
+    /// The transient draw data of the frame, reset before each one.
+    arena: FrameArena,
+    uploader: Option<TextureUploader>,
+    cross_fade: CrossFade,
+    recovery: StateRecovery,
+    streamer: Option<FrameStreamer>,
+    /// Records or verifies the hash of every frame.
+    frame_hashes: Option<FrameHashes>,
+    profiler: DrawProfiler,
+    stats: Arc<Mutex<FrameStats>>,
+    /// Posted with `Backend::post_event`, handed to the renderer at the start of the frame.
+    user_events: Arc<Mutex<Vec<UserEvent>>>,
+    show_hud: bool,
+    ruler: Option<RulerOverlay>,
+    /// The locale of the overlay texts, see `Backend::set_locale`.
+    locale: Locale,
+    locale_typeface: Option<Typeface>,
+    /// Drawn instead of the content.
+    diagnostics: Option<Diagnostics>,
+    context_menu: Option<ContextMenu>,
+    /// Clear with a transparent color, the window must have been created transparent.
+    transparent: bool,
+    theme: ThemeAnimator,
+    system_theme: SystemTheme,
+    /// Switch to the light or dark theme with the system theme.
+    follow_system_theme: bool,
+    color_vision: ColorVision,
+    output_adjustment: OutputAdjustment,
+    /// The platform insets in window pixels.
+    insets: Insets,
+    dither: bool,
+    paint_antialias: bool,
+    quality: QualityGovernor,
+    focus: FocusManager,
+    #[cfg(feature = "profiling")]
+    puffin_overlay: Option<PuffinOverlay>,
+}
+impl UiState {
+    /// Created with the GL context of `gl_env` current.
+    fn new(
+        gl_env: &GlEnv,
+        renderer: Box<dyn Renderer>,
+        stats: Arc<Mutex<FrameStats>>,
+        user_events: Arc<Mutex<Vec<UserEvent>>>,
+        system_theme: SystemTheme,
+    ) -> Self {
+        let uploader = TextureUploader::new(gl_env)
+            .map_err(|err| eprintln!("Error creating the texture upload context: {err}"))
+            .ok();
+        Self {
+            uploader,
+            input: InputState::default(),
+            camera: Camera::default(),
+            renderer,
+            last_frame: Instant::now(),
+            clock: FrameClock::default(),
+            skp_path: None,
+            print_options: None,
+            glyph_sets: vec![],
+            slow_frames: None,
+            frame_picture: None,
+            capture: None,
+            screenshotter: Screenshotter::default(),
+            screenshot_requested: false,
+            readback: None,
+            readback_screenshots: vec![],
+            keep_frame_snapshot: false,
+            keep_fade_snapshot: false,
+            gl_error_checks: false,
+            frame_snapshot: None,
+            #[cfg(feature = "independent_ui")]
+            snapshot_replies: vec![],
+            arena: FrameArena::new(),
+            cross_fade: CrossFade::default(),
+            recovery: StateRecovery::default(),
+            streamer: None,
+            frame_hashes: None,
+            profiler: DrawProfiler::default(),
+            stats,
+            user_events,
+            show_hud: false,
+            ruler: None,
+            locale: Locale::default(),
+            locale_typeface: None,
+            diagnostics: None,
+            context_menu: None,
+            transparent: false,
+            theme: ThemeAnimator::default(),
+            system_theme,
+            follow_system_theme: false,
+            color_vision: ColorVision::Normal,
+            output_adjustment: OutputAdjustment::default(),
+            insets: Insets::ZERO,
+            dither: false,
+            paint_antialias: true,
+            quality: QualityGovernor::default(),
+            focus: FocusManager::default(),
+            #[cfg(feature = "profiling")]
+            puffin_overlay: None,
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        let _span = info_span!("handle_input").entered();
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+        self.input.handle(event);
+        if let Some(diagnostics) = &mut self.diagnostics {
+            if diagnostics.handle(event) {
+                return;
+            }
+        }
+        if matches!(event, InputEvent::Key { .. }) {
+            self.focus.set_nodes(self.renderer.focus_nodes());
+        }
+        match self.focus.handle(event, self.input.modifiers()) {
+            KeyRouting::Consumed => return,
+            KeyRouting::Focused(id) => {
+                if self.renderer.handle_focused_key(id, event) {
+                    return;
+                }
+            }
+            KeyRouting::Unfocused => {}
+        }
+        if !self.camera.handle_input(event, &self.input) {
+            let camera = &self.camera;
+            let event = event.map_position(|position| camera.to_content(position));
+            self.renderer.handle_input(&event);
+        }
+    }
+
+    /// Draw the frame at `scale` times the window resolution.
+    fn draw(&mut self, canvas: &mut Canvas, frame: usize, scale: f32) {
+        #[cfg(feature = "profiling")]
+        puffin::GlobalProfiler::lock().new_frame();
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let now = Instant::now();
+        let frame_time = now - self.last_frame;
+        info_span!("update").in_scope(|| self.camera.update(frame_time.as_secs_f32()));
+        self.last_frame = now;
+        self.clock.tick(frame_time);
+        self.arena.reset();
+        let user_events = std::mem::take(&mut *self.user_events.lock().unwrap());
+        for event in user_events {
+            self.renderer.handle_user_event(event);
+        }
+        // The renderers are animated with the clock, which stands still while paused.
+        let clock_frame = self.clock.frame();
+        self.screenshotter.poll();
+
+        if !self.glyph_sets.is_empty() {
+            let _span = info_span!("prewarm_glyphs").entered();
+            glyphs::prewarm(canvas, &std::mem::take(&mut self.glyph_sets), scale);
+        }
+
+        let print_options = self.print_options.take();
+        let skp_path = self.skp_path.take();
+        let keep_picture = self
+            .slow_frames
+            .as_ref()
+            .is_some_and(|config| config.picture_dir.is_some());
+        if print_options.is_some() || skp_path.is_some() || keep_picture {
+            let size = canvas.base_layer_size();
+            let bounds = Rect::from_wh(size.width as f32, size.height as f32);
+            let picture =
+                scene::record(bounds, |canvas| self.draw_frame(canvas, clock_frame, scale));
+            canvas.draw_picture(&picture, None, None);
+
+            if let Some(path) = skp_path {
+                match std::fs::write(&path, picture.serialize().as_bytes()) {
+                    Ok(()) => println!("Dumped frame {frame} to {}", path.display()),
+                    Err(err) => eprintln!("Error dumping skp to {}: {err}", path.display()),
+                }
+            }
+            // The printed frame is the one presented, without rendering the renderer again.
+            if let Some(options) = print_options {
+                let printed = print::print(
+                    picture.clone(),
+                    (size.width, size.height),
+                    options,
+                    move |outcome| match outcome {
+                        Ok(PrintOutcome::Printed) => println!("Sent frame {frame} to the printer"),
+                        Ok(PrintOutcome::SavedPdf(path)) => {
+                            println!("Printed frame {frame} to {}", path.display())
+                        }
+                        Err(err) => eprintln!("Error printing: {err}"),
+                    },
+                );
+                if let Err(err) = printed {
+                    eprintln!("Error starting the print job: {err}");
+                }
+            }
+            self.frame_picture = keep_picture.then_some(picture);
+        } else {
+            self.draw_frame(canvas, clock_frame, scale);
+        }
+        self.cross_fade.draw(canvas, now);
+
+        self.stats
+            .lock()
+            .unwrap()
+            .update(frame, frame_time, self.profiler.take());
+    }
+
+    /// The presented frame, kept before the swap or still held by the offscreen framebuffer.
+    fn presented_snapshot(&self, skia_env: &mut SkiaEnv) -> Option<Image> {
+        self.frame_snapshot
+            .clone()
+            .or_else(|| skia_env.preserved_snapshot())
+    }
+
+    /// Fade from the presented content to the next frames over `duration`. The renderer is
+    /// not rendered again, its animations would step twice.
+    fn start_cross_fade(
+        &mut self,
+        skia_env: &mut SkiaEnv,
+        duration: Duration,
+        invalidator: &Invalidator,
+    ) {
+        match self.presented_snapshot(skia_env) {
+            Some(image) => self.cross_fade.start(image, duration, invalidator.clone()),
+            None => eprintln!("No presented frame kept to cross-fade from, see set_cross_fade"),
+        }
+    }
+
+    /// Process the frame requests needing the surface, before the buffers are swapped.
+    fn finish_frame(&mut self, surface: &mut SkiaSurface, frame: usize, invalidator: &Invalidator) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+        if let Some((path, options)) = self.capture.take() {
+            match capture::save(&surface.image_snapshot(), &path, &options) {
+                Ok(()) => println!("Captured frame to {}", path.display()),
+                Err(err) => eprintln!("Error capturing frame to {}: {err}", path.display()),
+            }
+        }
+        if self.readback.is_some() {
+            self.read_back(surface, frame, invalidator);
+        } else {
+            if std::mem::take(&mut self.screenshot_requested) {
+                self.screenshotter.take(&surface.image_snapshot());
+            }
+            if let Some(streamer) = &self.streamer {
+                streamer.push_frame(surface);
+            }
+        }
+        if self.frame_hashes.is_some() {
+            self.hash_frame(surface, frame);
+        }
+        // Kept before the swap, the window framebuffer is undefined afterwards.
+        self.frame_snapshot =
+            (self.keep_frame_snapshot || self.keep_fade_snapshot).then(|| surface.image_snapshot());
+        #[cfg(feature = "independent_ui")]
+        if !self.snapshot_replies.is_empty() {
+            let snapshot = surface.image_snapshot().make_non_texture_image();
+            for reply in self.snapshot_replies.drain(..) {
+                reply.send(snapshot.clone()).ok();
+            }
+        }
+        // The animations of the backend request their frames like the renderers on demand.
+        if self.is_animating() {
+            invalidator.invalidate();
+        }
+    }
+
+    /// Whether the camera momentum, the theme transition, the pending clock steps or a
+    /// diagnostic pattern, which must be presented every frame, need another frame.
+    fn is_animating(&self) -> bool {
+        self.camera.is_moving()
+            || self.theme.is_animating()
+            || self.clock.is_stepping()
+            || self.diagnostics.is_some()
+    }
+
+    /// Record or verify the hash of the frame, the outcome of a finished verification is
+    /// reported through the stats and the hashing stops.
+    fn hash_frame(&mut self, surface: &mut SkiaSurface, frame: usize) {
+        let Some(frame_hashes) = self.frame_hashes.as_mut() else {
+            return;
+        };
+        let Some(hash) = frame_hash::hash_surface(surface) else {
+            eprintln!("Error reading back frame {frame} to hash");
+            return;
+        };
+        match frame_hashes.push(frame, hash) {
+            Ok(None) => {}
+            Ok(Some(verification)) => {
+                self.stats.lock().unwrap().set_verification(verification);
+                self.frame_hashes = None;
+            }
+            Err(err) => {
+                eprintln!("Error recording the frame hashes: {err}");
+                self.frame_hashes = None;
+            }
+        }
+    }
+
+    /// Hand the frames read back asynchronously to the screenshots and the stream, and
+    /// start reading this frame back if they want it.
+    fn read_back(&mut self, surface: &mut SkiaSurface, frame: usize, invalidator: &Invalidator) {
+        let Some(readback) = self.readback.as_mut() else {
+            return;
+        };
+        while let Some(pixels) = readback.poll(surface, frame) {
+            if let Some(index) = self
+                .readback_screenshots
+                .iter()
+                .position(|requested| *requested == pixels.frame)
+            {
+                self.readback_screenshots.swap_remove(index);
+                match pixels.to_image() {
+                    Some(image) => self.screenshotter.take(&image),
+                    None => eprintln!("Error reading back the screenshot"),
+                }
+            }
+            if let Some(streamer) = &self.streamer {
+                streamer.push_readback(&pixels);
+            }
+        }
+
+        let screenshot = std::mem::take(&mut self.screenshot_requested);
+        let streaming = self
+            .streamer
+            .as_ref()
+            .is_some_and(|streamer| streamer.has_clients());
+        if screenshot || streaming {
+            if readback.read(surface, frame) {
+                if screenshot {
+                    self.readback_screenshots.push(frame);
+                }
+            } else if screenshot {
+                // Both buffers are in flight, the screenshot is of the next frame.
+                self.screenshot_requested = true;
+            }
+        }
+        // The reads are mapped on the later frames, which on demand must be rendered.
+        if readback.is_pending() || self.screenshot_requested {
+            invalidator.invalidate();
+        }
+    }
+
+    /// Save the renderer state after a frame, or recover when the frame panicked or lost
+    /// the GPU, the panic propagates when it can't be recovered from.
+    fn end_frame(
+        &mut self,
+        result: std::thread::Result<()>,
+        skia_env: &mut SkiaEnv,
+        gl_env: &GlEnv,
+        frame: usize,
+    ) {
+        match result {
+            Err(payload) => {
+                if !self.recovery.can_recover() {
+                    panic::resume_unwind(payload);
+                }
+                eprintln!("Recovering from the panicked frame {frame}");
+                self.recover(skia_env, gl_env);
+            }
+            Ok(()) if skia_env.is_device_lost() => {
+                eprintln!("The GPU device was lost, recreating the GPU context");
+                self.recover(skia_env, gl_env);
+            }
+            Ok(()) => self.recovery.save(frame, self.renderer.as_ref()),
+        }
+    }
+
+    /// Drop the GPU resources of the abandoned context and tell the renderer.
+    fn context_lost(&mut self) {
+        self.frame_snapshot = None;
+        self.cross_fade.cancel();
+        self.renderer.context_lost();
+    }
+
+    /// Recreate the GPU context and hand the saved state back to the renderer.
+    fn recover(&mut self, skia_env: &mut SkiaEnv, gl_env: &GlEnv) {
+        gl_env.make_current();
+        skia_env.recreate(&gl_env.gl_config);
+        self.context_lost();
+        if self.recovery.config().is_some() {
+            match self.recovery.restore(self.renderer.as_mut()) {
+                Some(frame) => eprintln!("Restored the renderer state saved at frame {frame}"),
+                None => eprintln!("No renderer state was saved to restore"),
+            }
+        }
+    }
+
+    /// Render the thumbnail of `Backend::render_thumbnail`, laid out at `layout_size`.
+    fn render_thumbnail(
+        &self,
+        skia_env: &mut SkiaEnv,
+        renderer: &mut dyn Renderer,
+        size: (i32, i32),
+        layout_size: (i32, i32),
+    ) -> Option<Image> {
+        let fit = (size.0 as f32 / layout_size.0 as f32).min(size.1 as f32 / layout_size.1 as f32);
+        let theme = self.theme.current();
+        let input = InputState::default();
+        let camera = Camera::default();
+        let profiler = DrawProfiler::default();
+        let arena = FrameArena::new();
+
+        let image = skia_env.render_offscreen(size, |canvas| {
+            canvas.clear(theme.colors.background);
+            canvas.translate((
+                (size.0 as f32 - layout_size.0 as f32 * fit) / 2.,
+                (size.1 as f32 - layout_size.1 as f32 * fit) / 2.,
+            ));
+            canvas.scale((fit, fit));
+            canvas.clip_rect(
+                Rect::from_wh(layout_size.0 as f32, layout_size.1 as f32),
+                None,
+                true,
+            );
+            let info = FrameInfo {
+                frame: 0,
+                size: layout_size,
+                pixel_scale: fit,
+                safe_area: Rect::from_iwh(layout_size.0, layout_size.1),
+                input: &input,
+                camera: &camera,
+                profiler: &profiler,
+                theme: &theme,
+                previous_frame: None,
+                dt: 0.,
+                dither: self.dither,
+                anti_alias: self.paint_antialias,
+                arena: &arena,
+                uploader: self.uploader.as_ref(),
+            };
+            renderer.render(canvas, &info);
+            renderer.render_overlay(canvas, &info);
+        })?;
+        image.make_non_texture_image()
+    }
+
+    /// Panic with the pending GL errors after the frame, when the checks are enabled with
+    /// `Backend::set_gl_error_checks`.
+    fn check_gl_errors(&self, frame: usize) {
+        if !self.gl_error_checks {
+            return;
+        }
+        let mut errors = vec![];
+        loop {
+            let error = unsafe { gl::GetError() };
+            // A lost context reports errors forever.
+            if error == gl::NO_ERROR || errors.len() == 16 {
+                break;
+            }
+            errors.push(error);
+        }
+        assert!(
+            errors.is_empty(),
+            "GL errors after frame {frame}: {errors:#06x?}"
+        );
+    }
+
+    /// Log the frame with its timings if it was slow, after its buffers were swapped.
+    fn report_slow_frame(&mut self, frame: usize, timings: FrameTimings) {
+        let picture = self.frame_picture.take();
+        let Some(config) = &self.slow_frames else {
+            return;
+        };
+        let total = timings.total();
+        if total <= config.threshold {
+            return;
+        }
+        eprintln!(
+            "Slow frame {frame}: {:.2} ms ({timings})",
+            total.as_secs_f64() * 1000.
+        );
+        if let (Some(dir), Some(picture)) = (&config.picture_dir, picture) {
+            let path = dir.join(format!("slow-frame-{frame}.skp"));
+            match std::fs::write(&path, picture.serialize().as_bytes()) {
+                Ok(()) => eprintln!("Dumped slow frame {frame} to {}", path.display()),
+                Err(err) => eprintln!("Error dumping skp to {}: {err}", path.display()),
+            }
+        }
+    }
+
+    /// The paint antialiasing, unless given up under load.
+    fn anti_alias(&self) -> bool {
+        self.paint_antialias
+            && self
+                .quality
+                .quality()
+                .is_enabled(QualityFeature::Antialiasing)
+    }
+
+    /// The antialiasing of the renderer, without supersampling when given up under load.
+    fn antialiasing(&self) -> Antialiasing {
+        if self
+            .quality
+            .quality()
+            .is_enabled(QualityFeature::Antialiasing)
+        {
+            self.renderer.antialiasing()
+        } else {
+            Antialiasing::Msaa
+        }
+    }
+
+    /// Report the frame time to the quality governor, the renderer is told when the quality
+    /// changed. Returns whether it did, the render scale may have to be applied.
+    fn update_quality(&mut self, frame_time: Duration, budget: Duration) -> bool {
+        match self.quality.update(frame_time, budget) {
+            Some(quality) => {
+                self.quality_changed(quality);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn set_quality_policy(&mut self, policy: Option<QualityPolicy>) {
+        let quality = self.quality.quality();
+        self.quality.set_policy(policy);
+        if self.quality.quality() != quality {
+            self.quality_changed(self.quality.quality());
+        }
+    }
+
+    fn quality_changed(&mut self, quality: Quality) {
+        self.stats.lock().unwrap().quality = quality;
+        self.renderer.quality_changed(quality);
+    }
+
+    fn set_system_theme(&mut self, theme: SystemTheme) {
+        self.system_theme = theme;
+        self.renderer.system_theme_changed(theme);
+        if self.follow_system_theme {
+            self.theme
+                .set(Theme::for_system(theme), SYSTEM_THEME_TRANSITION);
+        }
+    }
+
+    fn set_follow_system_theme(&mut self, follow: bool) {
+        self.follow_system_theme = follow;
+        if follow {
+            self.theme.set(
+                Theme::for_system(self.system_theme),
+                SYSTEM_THEME_TRANSITION,
+            );
+        }
+    }
+
+    fn set_async_readback(&mut self, enabled: bool) {
+        if enabled == self.readback.is_some() {
+            return;
+        }
+        self.readback = enabled.then(AsyncReadback::new);
+        // The screenshots still in flight are taken from the next frame.
+        if !self.readback_screenshots.is_empty() {
+            self.readback_screenshots.clear();
+            self.screenshot_requested = true;
+        }
+    }
+
+    fn set_stream(&mut self, config: Option<StreamConfig>) {
+        self.streamer = config.and_then(|config| match FrameStreamer::bind(config) {
+            Ok(streamer) => {
+                println!("Streaming frames on {}", config.addr);
+                Some(streamer)
+            }
+            Err(err) => {
+                eprintln!("Error streaming frames on {}: {err}", config.addr);
+                None
+            }
+        });
+    }
+
+    fn set_locale(&mut self, locale: Locale) {
+        self.renderer.locale_changed(&locale);
+        self.locale_typeface = locale.typeface();
+        self.locale = locale;
+    }
+
+    fn set_frame_hashing(&mut self, config: Option<FrameHashConfig>) {
+        self.frame_hashes = config.and_then(|config| match FrameHashes::open(&config) {
+            Ok(frame_hashes) => Some(frame_hashes),
+            Err(err) => {
+                eprintln!(
+                    "Error opening the frame hashes {}: {err}",
+                    config.path.display()
+                );
+                None
+            }
+        });
+    }
+
+    fn draw_frame(&mut self, canvas: &mut Canvas, frame: usize, scale: f32) {
+        let _span = info_span!("record").entered();
+        #[cfg(feature = "profiling")]
+        puffin::profile_scope!("record");
+        let theme = self.theme.current();
+        // The whole frame goes through the filters, overlays included, the output
+        // adjustment after the simulated vision.
+        let filter = match (
+            self.output_adjustment.color_filter(),
+            self.color_vision.color_filter(),
+        ) {
+            (Some(adjustment), Some(vision)) => color_filters::compose(adjustment, vision),
+            (adjustment, vision) => adjustment.or(vision),
+        };
+        let filter_layer = filter.map(|filter| {
+            let mut paint = Paint::default();
+            paint.set_color_filter(filter);
+            canvas.save_layer(&SaveLayerRec::default().paint(&paint))
+        });
+        // The surface is premultiplied, like the compositors expect.
+        canvas.clear(if self.transparent {
+            Color::TRANSPARENT
+        } else {
+            theme.colors.background
+        });
+        canvas.save();
+        canvas.scale((scale, scale));
+        canvas.save();
+        canvas.concat(&self.camera.matrix());
+
+        let size = window_size(canvas, scale);
+        let info = FrameInfo {
+            frame,
+            size,
+            pixel_scale: scale,
+            safe_area: self.insets.safe_area(size),
+            input: &self.input,
+            camera: &self.camera,
+            profiler: &self.profiler,
+            theme: &theme,
+            previous_frame: self
+                .frame_snapshot
+                .as_ref()
+                .filter(|_| self.keep_frame_snapshot),
+            dt: self.clock.dt(),
+            dither: self.dither,
+            anti_alias: self.anti_alias(),
+            arena: &self.arena,
+            uploader: self.uploader.as_ref(),
+        };
+        // The patterns replace the content, the renderer is not drawn to leave its cost out
+        // of the measurements.
+        if self.diagnostics.is_none() {
+            self.renderer.render(canvas, &info);
+            self.focus.set_nodes(self.renderer.focus_nodes());
+            self.focus.draw_ring(canvas, &theme);
+        }
+        canvas.restore();
+
+        match &mut self.diagnostics {
+            Some(diagnostics) => diagnostics.draw(canvas, info.size, frame, scale),
+            None => self.renderer.render_overlay(canvas, &info),
+        }
+
+        if let Some(ruler) = &self.ruler {
+            ruler.draw(
+                canvas,
+                &self.camera,
+                info.size,
+                self.input.cursor_position(),
+                &theme,
+                &self.locale,
+                self.locale_typeface.as_ref(),
+            );
+        }
+        if let Some(menu) = &self.context_menu {
+            menu.draw(canvas, &theme, self.anti_alias());
+        }
+        if self.show_hud {
+            stats::draw_hud(canvas, &self.stats.lock().unwrap());
+        }
+        self.screenshotter.draw_confirmation(canvas, info.size);
+        #[cfg(feature = "profiling")]
+        if let Some(overlay) = &self.puffin_overlay {
+            overlay.draw(canvas, info.size);
+        }
+        canvas.restore();
+        if let Some(count) = filter_layer {
+            canvas.restore_to_count(count);
+        }
+    }
+}
+
+/// The size of the window a canvas rendering at `scale` times its resolution stands for.
+#[inline]
+fn window_size(canvas: &Canvas, scale: f32) -> (i32, i32) {
+    let size = canvas.base_layer_size();
+    (
+        (size.width as f32 / scale).round() as i32,
+        (size.height as f32 / scale).round() as i32,
+    )
+}
+
+/// The window geometry saved before entering fullscreen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WindowGeometry {
+    position: Option<PhysicalPosition<i32>>,
+    size: PhysicalSize<u32>,
+    maximized: bool,
+}
+
+pub struct Backend {
+    window: Option<Arc<Window>>,
+    /// The window title without the progress, see `set_title`.
+    title: String,
+    progress: Option<f32>,
+    saved_geometry: Option<WindowGeometry>,
+    /// Where the window geometry is saved on exit.
+    geometry_path: Option<PathBuf>,
+    recorder: Option<InputRecorder>,
+    pacer: FramePacer,
+    stats: Arc<Mutex<FrameStats>>,
+    user_events: Arc<Mutex<Vec<UserEvent>>>,
+    idle: Arc<Mutex<IdleQueue>>,
+    invalidator: Invalidator,
+    on_demand: bool,
+    hud_visible: bool,
+    diagnostics: Option<DiagnosticPattern>,
+    ruler_visible: bool,
+    clock_paused: bool,
+    time_scale: f32,
+    color_vision: ColorVision,
+    output_adjustment: OutputAdjustment,
+    safe_area: SafeAreaConfig,
+    insets: Insets,
+    scale_factor: f64,
+    system_theme: SystemTheme,
+    drag_regions: Option<DragRegions>,
+    cursor_position: Option<(f32, f32)>,
+    hovered_region: HitTest,
+    context_menu: Option<ContextMenu>,
+    /// The action chosen in the context menu, taken by the application.
+    menu_action: Option<Action>,
+    cross_fade: Option<CrossFadeConfig>,
+    follow_system_theme: bool,
+    locale: Locale,
+    occluded: bool,
+    minimized: bool,
+    #[cfg(feature = "profiling")]
+    puffin_overlay_visible: bool,
+
+    #[cfg(not(feature = "independent_ui"))]
+    gl_env: Arc<GlEnv>,
+    #[cfg(not(feature = "independent_ui"))]
+    skia_env: SkiaEnv,
+    #[cfg(not(feature = "independent_ui"))]
+    ui_state: UiState,
+    #[cfg(not(feature = "independent_ui"))]
+    scaler: ResolutionScaler,
+
+    /// Never blocks the event loop, the input is coalesced while the render thread lags.
+    #[cfg(feature = "independent_ui")]
+    sender: BufferedSender<Message>,
+    #[cfg(feature = "independent_ui")]
+    heartbeat: Arc<Heartbeat>,
+    #[cfg(feature = "independent_ui")]
+    watchdog: Option<Watchdog>,
+    #[cfg(feature = "independent_ui")]
+    render_thread: thread::JoinHandle<()>,
+}
+
+impl Backend {
+    pub fn new(window: Arc<Window>, gl_env: Arc<GlEnv>, renderer: Box<dyn Renderer>) -> Self {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            let mut renderer = renderer;
+            #[cfg(feature = "profiling")]
+            puffin::set_scopes_on(true);
+
+            gl_env.make_current();
+            gl_env.load();
+
+            let size = window.inner_size();
+            let size = (
+                size.width.try_into().expect("Could not convert width"),
+                size.height.try_into().expect("Could not convert height"),
+            );
+            let skia_env = create_skia_env(size, &gl_env.gl_config, MsaaResolve::default());
+            let stats = Arc::new(Mutex::new(FrameStats::default()));
+            let user_events = Arc::new(Mutex::new(vec![]));
+            let idle = Arc::new(Mutex::new(IdleQueue::default()));
+            let invalidator = Invalidator::default();
+            renderer.attach_invalidator(invalidator.clone());
+            let system_theme = window.theme().map(SystemTheme::from).unwrap_or_default();
+            let scale_factor = window.scale_factor();
+            let ui_state = UiState::new(
+                &gl_env,
+                renderer,
+                stats.clone(),
+                user_events.clone(),
+                system_theme,
+            );
+            let mut backend = Self {
+                title: window.title(),
+                progress: None,
+                saved_geometry: None,
+                geometry_path: None,
+                recorder: None,
+                pacer: FramePacer::default(),
+                window: Some(window),
+                gl_env,
+                skia_env,
+                ui_state,
+                scaler: ResolutionScaler::default(),
+                stats,
+                user_events,
+                idle,
+                invalidator,
+                on_demand: false,
+                hud_visible: false,
+                diagnostics: None,
+                ruler_visible: false,
+                clock_paused: false,
+                time_scale: 1.,
+                color_vision: ColorVision::Normal,
+                output_adjustment: OutputAdjustment::default(),
+                safe_area: SafeAreaConfig::default(),
+                insets: Insets::ZERO,
+                scale_factor,
+                system_theme,
+                drag_regions: None,
+                cursor_position: None,
+                hovered_region: HitTest::Client,
+                context_menu: None,
+                menu_action: None,
+                cross_fade: None,
+                follow_system_theme: false,
+                locale: Locale::default(),
+                occluded: false,
+                minimized: false,
+                #[cfg(feature = "profiling")]
+                puffin_overlay_visible: false,
+            };
+            backend.notify_moved();
+            backend
+        }
+
+        #[cfg(feature = "independent_ui")]
+        {
+            Self::with_render_thread_config(window, gl_env, renderer, Default::default())
+        }
+    }
+
+    /// Create the backend with the priority and core affinity of the render thread.
+    #[cfg(feature = "independent_ui")]
+    pub fn with_render_thread_config(
+        window: Arc<Window>,
+        gl_env: Arc<GlEnv>,
+        mut renderer: Box<dyn Renderer>,
+        thread_config: RenderThreadConfig,
+    ) -> Self {
+        #[cfg(feature = "profiling")]
+        puffin::set_scopes_on(true);
+
+        let size = window.inner_size();
+        let size = (
+            size.width.try_into().expect("Could not convert width"),
+            size.height.try_into().expect("Could not convert height"),
+        );
+        let (sender, receiver) = channel::bounded(MESSAGE_CAPACITY);
+        let stats = Arc::new(Mutex::new(FrameStats::default()));
+        let user_events = Arc::new(Mutex::new(vec![]));
+        let idle = Arc::new(Mutex::new(IdleQueue::default()));
+        let invalidator = Invalidator::default();
+        renderer.attach_invalidator(invalidator.clone());
+        let system_theme = window.theme().map(SystemTheme::from).unwrap_or_default();
+        let scale_factor = window.scale_factor();
+        let heartbeat = Arc::new(Heartbeat::default());
+
+        let render_thread = {
+            let stats = stats.clone();
+            let user_events = user_events.clone();
+            let idle = idle.clone();
+            let invalidator = invalidator.clone();
+            let heartbeat = heartbeat.clone();
+            // One render thread per window, each with its own GL and GPU contexts.
+            thread::Builder::new()
+                .name(format!("ui_runtime {:?}", window.id()))
+                .spawn(move || {
+                    thread_config.apply();
+                    ui_runtime(
+                        size,
+                        receiver,
+                        gl_env,
+                        renderer,
+                        stats,
+                        user_events,
+                        idle,
+                        invalidator,
+                        system_theme,
+                        heartbeat,
+                    )
+                })
+                .unwrap()
+        };
+
+        let mut backend = Self {
+            title: window.title(),
+            progress: None,
+            saved_geometry: None,
+            geometry_path: None,
+            recorder: None,
+            pacer: FramePacer::default(),
+            window: Some(window),
+            stats,
+            user_events,
+            idle,
+            invalidator,
+            on_demand: false,
+            hud_visible: false,
+            diagnostics: None,
+            ruler_visible: false,
+            clock_paused: false,
+            time_scale: 1.,
+            color_vision: ColorVision::Normal,
+            output_adjustment: OutputAdjustment::default(),
+            safe_area: SafeAreaConfig::default(),
+            insets: Insets::ZERO,
+            scale_factor,
+            system_theme,
+            drag_regions: None,
+            cursor_position: None,
+            hovered_region: HitTest::Client,
+            context_menu: None,
+            menu_action: None,
+            cross_fade: None,
+            follow_system_theme: false,
+            locale: Locale::default(),
+            occluded: false,
+            minimized: false,
+            #[cfg(feature = "profiling")]
+            puffin_overlay_visible: false,
+            sender: BufferedSender::new(sender),
+            heartbeat,
+            watchdog: None,
+            render_thread,
+        };
+        backend.notify_moved();
+        backend
+    }
+
+    /// The id of the window, `None` after `exit`.
+    #[inline]
+    pub fn window_id(&self) -> Option<WindowId> {
+        self.window.as_ref().map(|window| window.id())
+    }
+
+    /// Whether the render thread stopped, after `exit` or when a frame panicked.
+    #[cfg(feature = "independent_ui")]
+    #[inline]
+    pub fn is_render_thread_finished(&self) -> bool {
+        self.render_thread.is_finished()
+    }
+
+    /// Always `true`, the frames render on the thread of the event loop.
+    #[cfg(not(feature = "independent_ui"))]
+    #[inline]
+    pub fn is_render_thread_finished(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn exit(&mut self) {
+        self.save_geometry();
+        self.window.take();
+        #[cfg(feature = "independent_ui")]
+        self.sender.send(Message::Shutdown).ok();
+    }
+
+    #[inline]
+    pub fn request_redraw(&self) {
+        #[cfg(not(feature = "independent_ui"))]
+        if let Some(ref window) = self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Request the user attention, e.g. flashing the taskbar entry on Windows or bouncing the
+    /// dock icon on macOS, `None` cancels the request.
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        if let Some(ref window) = self.window {
+            window.request_user_attention(request_type);
+        }
+    }
+
+    /// Set the window title, the progress of `set_progress` stays appended to it.
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_owned();
+        self.update_title();
+    }
+
+    /// Show the progress of a long running task in `[0, 1]` after the window title, `None`
+    /// clears it. Only a title bar fallback, winit has no taskbar or dock progress
+    /// indicator, change the title with `set_title` so the progress is kept.
+    pub fn set_progress(&mut self, progress: Option<f32>) {
+        self.progress = progress.map(|progress| progress.clamp(0., 1.));
+        self.update_title();
+    }
+
+    fn update_title(&self) {
+        if let Some(ref window) = self.window {
+            match self.progress {
+                Some(progress) => {
+                    window.set_title(&format!("{} - {:.0}%", self.title, progress * 100.))
+                }
+                None => window.set_title(&self.title),
+            }
+        }
+    }
+
+    /// Keep the window always on top/bottom of the other windows, e.g. for overlay tools.
+    #[inline]
+    pub fn set_window_level(&self, level: WindowLevel) {
+        if let Some(ref window) = self.window {
+            window.set_window_level(level);
+        }
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.window
+            .as_ref()
+            .map_or(false, |window| window.fullscreen().is_some())
+    }
+
+    /// Switch to a borderless fullscreen window covering the current monitor without a video
+    /// mode switch, the prior window geometry is restored when toggled off.
+    pub fn set_borderless_fullscreen(&mut self, fullscreen: bool) {
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        if fullscreen == window.fullscreen().is_some() {
+            return;
+        }
+
+        if fullscreen {
+            let monitor = window.current_monitor();
+            let size = monitor.as_ref().map(|monitor| monitor.size());
+            self.enter_fullscreen(&window, Fullscreen::Borderless(monitor), size);
+        } else {
+            self.leave_fullscreen(&window);
+        }
+    }
+
+    /// Save the window geometry to `path` on exit, restore it with `SavedGeometry::apply`
+    /// when building the window. `None` disables the saving.
+    #[inline]
+    pub fn set_geometry_persistence(&mut self, path: Option<PathBuf>) {
+        self.geometry_path = path;
+    }
+
+    /// The window geometry to persist, the geometry before entering fullscreen when
+    /// fullscreen.
+    pub fn window_geometry(&self) -> Option<SavedGeometry> {
+        let window = self.window.as_ref()?;
+        let geometry = self.saved_geometry.unwrap_or_else(|| WindowGeometry {
+            position: window.outer_position().ok(),
+            size: window.inner_size(),
+            maximized: window.is_maximized(),
+        });
+        Some(SavedGeometry {
+            monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+            position: geometry.position.map(Into::into),
+            size: geometry.size.into(),
+            maximized: geometry.maximized,
+            fullscreen: window.fullscreen().is_some(),
+        })
+    }
+
+    fn save_geometry(&self) {
+        let (Some(path), Some(geometry)) = (&self.geometry_path, self.window_geometry()) else {
+            return;
+        };
+        if let Err(err) = geometry.save(path) {
+            eprintln!(
+                "Error saving the window geometry to {}: {err}",
+                path.display()
+            );
+        }
+    }
+
+    /// The video modes of the monitor the window is currently on.
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
+            .map(|monitor| monitor.video_modes().collect())
+            .unwrap_or_default()
+    }
+
+    /// Switch to exclusive fullscreen with the video mode, the frame pacer adopts the refresh
+    /// rate of the mode. `None` leaves fullscreen and restores the prior window geometry.
+    pub fn set_exclusive_fullscreen(&mut self, video_mode: Option<VideoMode>) {
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+
+        match video_mode {
+            Some(video_mode) => {
+                let size = video_mode.size();
+                self.set_refresh_rate_millihertz(video_mode.refresh_rate_millihertz());
+                self.enter_fullscreen(&window, Fullscreen::Exclusive(video_mode), Some(size));
+            }
+            None => {
+                if window.fullscreen().is_some() {
+                    self.leave_fullscreen(&window);
+                }
+            }
+        }
+    }
+
+    fn enter_fullscreen(
+        &mut self,
+        window: &Window,
+        fullscreen: Fullscreen,
+        size: Option<PhysicalSize<u32>>,
+    ) {
+        if self.saved_geometry.is_none() {
+            self.saved_geometry = Some(WindowGeometry {
+                position: window.outer_position().ok(),
+                size: window.inner_size(),
+                maximized: window.is_maximized(),
+            });
+        }
+        window.set_fullscreen(Some(fullscreen));
+
+        // The resized event follows, but resize right away to avoid presenting
+        // stretched frames in the meantime.
+        if let Some(size) = size {
+            self.notify_resize(size.into());
+        }
+    }
+
+    /// Leave any fullscreen, the frame pacing adopted for an exclusive video mode goes back
+    /// to the frame rate and the monitor refresh rate.
+    fn leave_fullscreen(&mut self, window: &Window) {
+        let exclusive = matches!(window.fullscreen(), Some(Fullscreen::Exclusive(_)));
+        window.set_fullscreen(None);
+        if exclusive {
+            self.set_refresh_rate_millihertz(0);
+            self.notify_moved();
+        }
+        if let Some(geometry) = self.saved_geometry.take() {
+            window.set_maximized(geometry.maximized);
+            if !geometry.maximized {
+                if let Some(position) = geometry.position {
+                    window.set_outer_position(position);
+                }
+                if let Some(size) = window.request_inner_size(geometry.size) {
+                    self.notify_resize(size.into());
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn frame_pacer(&mut self) -> &mut FramePacer {
+        &mut self.pacer
+    }
+
+    /// Align the frames with the refresh rate of the monitor the window is on, call it when
+    /// the window moved, e.g. on `WindowEvent::Moved`, it may be on another monitor.
+    pub fn notify_moved(&mut self) {
+        let millihertz = self
+            .window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .unwrap_or(0);
+        self.pacer.set_vblank_rate_millihertz(millihertz);
+
+        #[cfg(feature = "independent_ui")]
+        self.sender
+            .send(Message::VblankRate(millihertz))
+            .expect("Send vblank rate message failed.");
+    }
+
+    /// Adopt the refresh rate for the frame pacing, `0` restores the prior frame rate.
+    fn set_refresh_rate_millihertz(&mut self, millihertz: u32) {
+        if millihertz == 0 {
+            self.pacer.reset_refresh_rate();
+        } else {
+            self.pacer.set_refresh_rate_millihertz(millihertz);
+        }
+
+        #[cfg(feature = "independent_ui")]
+        self.sender
+            .send(Message::RefreshRate(millihertz))
+            .expect("Send refresh rate message failed.");
+    }
+
+    #[inline]
+    pub fn toggle_borderless_fullscreen(&mut self) {
+        self.set_borderless_fullscreen(!self.is_fullscreen())
+    }
+
+    /// The scale factor of the monitor the window is on.
+    #[inline]
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Notify the window moved to a monitor with another scale factor, e.g. on
+    /// `WindowEvent::ScaleFactorChanged`, the surfaces are recreated at the new physical size
+    /// and the renderer is told with `Renderer::scale_factor_changed`.
+    pub fn notify_scale_factor(&mut self, scale_factor: f64) {
+        if scale_factor == self.scale_factor {
+            return;
+        }
+        // The window keeps its logical size, the resize may only be reported after.
+        let size = self.window.as_ref().map(|window| {
+            let size: LogicalSize<f64> = window.inner_size().to_logical(self.scale_factor);
+            let size: PhysicalSize<u32> = size.to_physical(scale_factor);
+            (size.width, size.height)
+        });
+        self.scale_factor = scale_factor;
+        #[cfg(not(feature = "independent_ui"))]
+        self.ui_state.renderer.scale_factor_changed(scale_factor);
+        #[cfg(feature = "independent_ui")]
+        self.sender
+            .send(Message::ScaleFactor(scale_factor))
+            .expect("Send scale factor message failed.");
+        if let Some(size) = size {
+            self.notify_resize(size);
+        }
+        self.request_redraw();
+    }
+
+    pub fn notify_resize(&mut self, size: (u32, u32)) {
+        self.record(RecordedEvent::Resize(size));
+        // Minimizing resizes the window to zero on some platforms.
+        self.minimized = size.0 == 0 || size.1 == 0;
+        self.update_paused();
+        self.invalidator.invalidate();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.skia_env
+                .resize((size.0 as i32, size.1 as i32), &self.gl_env.gl_config);
+            self.gl_env.resize((size.0 as u32, size.1 as u32));
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Resize(size.0, size.1))
+                .expect("Send resize message failed.")
+        }
+        self.update_insets();
+    }
+
+    /// Declare what covers the window, the renderer keeps its controls inside
+    /// `FrameInfo::safe_area`.
+    pub fn set_safe_area_config(&mut self, config: SafeAreaConfig) {
+        self.safe_area = config;
+        self.update_insets();
+    }
+
+    #[inline]
+    pub fn safe_area_config(&self) -> SafeAreaConfig {
+        self.safe_area
+    }
+
+    /// The platform insets of the window in physical pixels.
+    #[inline]
+    pub fn insets(&self) -> Insets {
+        self.insets
+    }
+
+    fn update_insets(&mut self) {
+        let insets = self
+            .window
+            .as_ref()
+            .map_or(Insets::ZERO, |window| self.safe_area.insets(window));
+        if insets == self.insets {
+            return;
+        }
+        self.insets = insets;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.insets = insets;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Insets(insets))
+                .expect("Send insets message failed.")
+        }
+    }
+
+    /// Pause the rendering while the window is covered, e.g. on `WindowEvent::Occluded`.
+    pub fn notify_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+        self.update_paused();
+    }
+
+    fn update_paused(&mut self) {
+        let paused = self.occluded || self.minimized;
+        if paused == self.pacer.is_paused() {
+            return;
+        }
+        self.pacer.set_paused(paused);
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Paused(paused))
+                .expect("Send paused message failed.");
+            if let Some(watchdog) = &mut self.watchdog {
+                watchdog.rearm();
+            }
+        }
+        if !paused {
+            self.request_redraw();
+        }
+    }
+
+    /// Watch for the render thread to stop producing frames, checked with
+    /// `check_watchdog`.
+    #[cfg(feature = "independent_ui")]
+    pub fn set_watchdog(&mut self, config: Option<WatchdogConfig>) {
+        self.watchdog = config.map(Watchdog::new);
+    }
+
+    /// Call regularly from the event loop, logs the state and returns the action to take
+    /// when the render thread just stalled. The context recreation is requested here, the
+    /// shutdown is left to the caller.
+    #[cfg(feature = "independent_ui")]
+    pub fn check_watchdog(&mut self) -> Option<WatchdogAction> {
+        if self.pacer.is_paused() {
+            return None;
+        }
+        let watchdog = self.watchdog.as_mut()?;
+        let since = watchdog.check(&self.heartbeat, Instant::now())?;
+        let action = watchdog.config().action;
+        // The render thread may be stuck holding the stats.
+        let state = match self.stats.try_lock() {
+            Ok(stats) => format!(
+                "last frame {} ({:.1} fps, {} dropped)",
+                stats.frame, stats.fps, stats.dropped_frames
+            ),
+            Err(_) => "stats locked".to_string(),
+        };
+        eprintln!(
+            "The render thread produced no frame for {:.2} s, {state}, taking action: {action}",
+            since.as_secs_f64()
+        );
+        if action == WatchdogAction::RecreateContext {
+            self.sender
+                .send(Message::RecreateContext)
+                .expect("Send recreate context message failed.");
+        }
+        Some(action)
+    }
+
+    pub fn handle_input(&mut self, event: InputEvent) {
+        self.record(RecordedEvent::Input(event));
+        if let InputEvent::CursorMoved { position } = event {
+            self.cursor_position = Some(position);
+        }
+        if self.handle_context_menu(&event) || self.handle_drag_regions(&event) {
+            return;
+        }
+        self.invalidator.invalidate();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.handle_input(&event);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Input(event))
+                .expect("Send input message failed.")
+        }
+    }
+
+    /// Record the input and the resizes, e.g. to attach to a bug report, `None` stops the
+    /// recording.
+    #[inline]
+    pub fn set_input_recording(&mut self, recorder: Option<InputRecorder>) {
+        self.recorder = recorder;
+    }
+
+    fn record(&mut self, event: RecordedEvent) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if let Err(err) = recorder.record(event) {
+            eprintln!("Error recording the input, stopping the recording: {err}");
+            self.recorder = None;
+        }
+    }
+
+    /// Feed a recorded event back, a resize resizes the window.
+    pub fn replay(&mut self, event: RecordedEvent) {
+        match event {
+            RecordedEvent::Input(event) => self.handle_input(event),
+            RecordedEvent::Resize(size) => self.request_inner_size(size),
+        }
+    }
+
+    /// Resize the window to the inner size in physical pixels.
+    pub fn request_inner_size(&mut self, size: (u32, u32)) {
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        // Platforms resizing asynchronously report the size in a resized event.
+        if let Some(size) = window.request_inner_size(PhysicalSize::from(size)) {
+            self.notify_resize(size.into());
+        }
+    }
+
+    /// Minimize or restore the window, the rendering pauses while minimized.
+    pub fn set_minimized(&mut self, minimized: bool) {
+        if let Some(window) = &self.window {
+            window.set_minimized(minimized);
+        }
+        // Not every platform reports the minimizing with a zero size.
+        self.minimized = minimized;
+        self.update_paused();
+    }
+
+    /// Declare the title and resize regions of a window drawing its own decorations, a
+    /// left press in them moves or resizes the window instead of reaching the renderer.
+    #[inline]
+    pub fn set_drag_regions(&mut self, regions: Option<DragRegions>) {
+        self.drag_regions = regions;
+    }
+
+    /// Returns whether the event started a window move or resize.
+    fn handle_drag_regions(&mut self, event: &InputEvent) -> bool {
+        let (Some(regions), Some(window)) = (&self.drag_regions, &self.window) else {
+            return false;
+        };
+        let size = window.inner_size();
+        let size = (size.width as f32, size.height as f32);
+
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                let hit = regions.hit_test(position, size);
+                if hit.cursor_icon() != self.hovered_region.cursor_icon() {
+                    window.set_cursor_icon(hit.cursor_icon());
+                }
+                self.hovered_region = hit;
+                false
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            } => {
+                let Some(position) = self.cursor_position else {
+                    return false;
+                };
+                let result = match regions.hit_test(position, size) {
+                    HitTest::Client => return false,
+                    HitTest::Caption => window.drag_window(),
+                    HitTest::Resize(direction) => window.drag_resize_window(direction),
+                };
+                if let Err(err) = result {
+                    eprintln!("Error dragging the window: {err}");
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_camera_config(&mut self, config: CameraConfig) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.camera.set_config(config);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::CameraConfig(config))
+                .expect("Send camera config message failed.")
+        }
+    }
+
+    /// Record the next frame into an `SkPicture` and serialize it to `path`, the `.skp` file
+    /// can be inspected with Skia's debugger (https://debugger.skia.org).
+    pub fn dump_skp(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.skp_path = Some(path);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::DumpSkp(path))
+                .expect("Send dump skp message failed.")
+        }
+    }
+
+    /// Print the next frame, falls back to saving a PDF if no system printer is available.
+    pub fn print(&mut self, options: PrintOptions) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.print_options = Some(options);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Print(options))
+                .expect("Send print message failed.")
+        }
+    }
+
+    /// Capture the next frame into an image file encoded with the options.
+    pub fn capture(&mut self, path: impl Into<PathBuf>, options: CaptureOptions) {
+        let path = path.into();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.capture = Some((path, options));
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Capture(path, options))
+                .expect("Send capture message failed.")
+        }
+    }
+
+    /// Switch the theme, fading from the current one over `transition`.
+    pub fn set_theme(&mut self, theme: Theme, transition: Duration) {
+        self.notify_global_change(GlobalChange::Theme);
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.theme.set(theme, transition);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Theme(theme, transition))
+                .expect("Send theme message failed.")
+        }
+    }
+
+    /// The light or dark appearance of the OS.
+    #[inline]
+    pub fn system_theme(&self) -> SystemTheme {
+        self.system_theme
+    }
+
+    /// Notify the system theme changed, e.g. on `WindowEvent::ThemeChanged`, the renderer
+    /// is told with `Renderer::system_theme_changed`.
+    pub fn notify_system_theme(&mut self, theme: SystemTheme) {
+        if theme == self.system_theme {
+            return;
+        }
+        self.system_theme = theme;
+        if self.follow_system_theme {
+            self.notify_global_change(GlobalChange::Theme);
+        }
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_system_theme(theme);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::SystemTheme(theme))
+                .expect("Send system theme message failed.")
+        }
+    }
+
+    /// Switch between the light and the dark theme with the system theme.
+    pub fn set_follow_system_theme(&mut self, follow: bool) {
+        self.follow_system_theme = follow;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_follow_system_theme(follow);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::FollowSystemTheme(follow))
+                .expect("Send follow system theme message failed.")
+        }
+    }
+
+    #[inline]
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Switch the locale the texts are laid out for, the renderer is told with
+    /// `Renderer::locale_changed` to drop the paragraphs shaped for the previous one, the
+    /// ruler labels follow it. The switch is a `GlobalChange::Language`.
+    pub fn set_locale(&mut self, locale: Locale) {
+        if locale == self.locale {
+            return;
+        }
+        self.notify_global_change(GlobalChange::Language);
+        self.locale = locale.clone();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_locale(locale);
+            self.invalidator.invalidate();
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Locale(locale))
+                .expect("Send locale message failed.")
+        }
+    }
+
+    /// Cross-fade the window content on the flagged global changes, `None` switches them
+    /// without fading. Every presented frame is kept while set, to fade from.
+    pub fn set_cross_fade(&mut self, config: Option<CrossFadeConfig>) {
+        let keep = config.is_some();
+        self.cross_fade = config;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.keep_fade_snapshot = keep;
+            if !keep && !self.ui_state.keep_frame_snapshot {
+                self.ui_state.frame_snapshot = None;
+            }
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::KeepFadeSnapshot(keep))
+                .expect("Send keep fade snapshot message failed.")
+        }
+    }
+
+    /// Call right before applying a global change, e.g. switching the language, the
+    /// content is cross-faded when the change is flagged in the config.
+    pub fn notify_global_change(&mut self, change: GlobalChange) {
+        let Some(config) = &self.cross_fade else {
+            return;
+        };
+        if config.fades(change) {
+            self.cross_fade(config.duration);
+        }
+    }
+
+    /// Fade from the presented content to the next frames over `duration`, call right before
+    /// changing the content. The presented frame is only kept with `set_cross_fade` or
+    /// `set_keep_frame_snapshot`, or in the offscreen framebuffer, otherwise it just switches.
+    pub fn cross_fade(&mut self, duration: Duration) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state
+                .start_cross_fade(&mut self.skia_env, duration, &self.invalidator);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::CrossFade(duration))
+                .expect("Send cross fade message failed.")
+        }
+    }
+
+    /// Save the next frame to the screenshot directory, encoded on a worker thread.
+    pub fn screenshot(&mut self) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.screenshot_requested = true;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Screenshot)
+                .expect("Send screenshot message failed.")
+        }
+    }
+
+    /// Read the screenshots and the streamed frames back through pixel buffers, mapped two
+    /// frames later, instead of stalling the frame until the GPU finished it, see
+    /// `AsyncReadback`.
+    pub fn set_async_readback(&mut self, enabled: bool) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_async_readback(enabled);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::AsyncReadback(enabled))
+                .expect("Send async readback message failed.")
+        }
+    }
+
+    /// Run `task` on the render thread in the spare time before the next frame, e.g. to
+    /// warm caches or upload textures incrementally. A task returning `IdleStatus::Pending`
+    /// runs again in the next idle period, it should check `IdleContext::time_remaining` to
+    /// size its work since the events are not processed meanwhile.
+    pub fn schedule_idle(
+        &mut self,
+        task: impl FnMut(&mut IdleContext) -> IdleStatus + Send + 'static,
+    ) {
+        self.idle.lock().unwrap().push(Box::new(task));
+    }
+
+    /// Rasterize the glyphs before the next frame, e.g. at startup or during a loading
+    /// screen, so animations don't hitch on their first use.
+    pub fn prewarm_glyphs(&mut self, sets: Vec<GlyphSet>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.glyph_sets.extend(sets);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::PrewarmGlyphs(sets))
+                .expect("Send prewarm glyphs message failed.")
+        }
+    }
+
+    /// Log the frames slower than the threshold with the time of each phase, `None` stops
+    /// the logging.
+    pub fn set_slow_frame_logging(&mut self, config: Option<SlowFrameConfig>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.slow_frames = config;
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::SlowFrames(config))
+                .expect("Send slow frames message failed.")
+        }
+    }
+
+    /// Save the renderer state every few frames with `Renderer::save_state`, and recover
+    /// from a panicking frame or a GPU reset by recreating the GPU context and restoring
+    /// the last saved state. `None` lets the panics propagate.
+    pub fn set_state_recovery(&mut self, config: Option<RecoveryConfig>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.recovery.set_config(config);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::StateRecovery(config))
+                .expect("Send state recovery message failed.")
+        }
+    }
+
+    /// Check for GL errors after each frame and panic on them.
+    pub fn set_gl_error_checks(&mut self, enabled: bool) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.gl_error_checks = enabled;
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::GlErrorChecks(enabled))
+                .expect("Send gl error checks message failed.")
+        }
+    }
+
+    /// Pass the previous frame to the renderer in `FrameInfo::previous_frame`. Holding the
+    /// snapshot makes the surface copy its content before the next frame draws over it.
+    pub fn set_keep_frame_snapshot(&mut self, keep: bool) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.keep_frame_snapshot = keep;
+            if !keep && !self.ui_state.keep_fade_snapshot {
+                self.ui_state.frame_snapshot = None;
+            }
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::KeepFrameSnapshot(keep))
+                .expect("Send keep frame snapshot message failed.")
+        }
+    }
+
+    /// A snapshot of the last rendered frame, e.g. for crossfades or thumbnails, `None` when
+    /// the readback failed. The frame must be kept before its swap, the window framebuffer
+    /// is undefined afterwards: inline it is only there with `set_keep_frame_snapshot`,
+    /// `set_cross_fade` or the offscreen framebuffer, with `independent_ui` the next frame
+    /// is rendered and snapshot otherwise.
+    ///
+    /// The frame is read back from the GPU into a raster image, usable on any thread and
+    /// after the backend was dropped, at the cost of a stall. To draw the previous frame
+    /// every frame, e.g. for motion trails, keep it on the GPU with
+    /// `set_keep_frame_snapshot` and draw `FrameInfo::previous_frame` instead. With
+    /// `independent_ui` the call blocks until the render thread took the snapshot.
+    pub fn frame_snapshot(&mut self) -> Option<Image> {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state
+                .presented_snapshot(&mut self.skia_env)
+                .and_then(|image| image.make_non_texture_image())
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            let (reply, snapshot) = mpsc::channel();
+            // The reply is waited for, nothing would flush the request if it was kept.
+            self.sender
+                .send_blocking(Message::FrameSnapshot(reply))
+                .expect("Send frame snapshot message failed.");
+            // The render thread stopped when the reply was dropped.
+            snapshot.recv().ok().flatten()
+        }
+    }
+
+    /// Render `renderer` into a `size` image without disturbing the window surface, e.g. for
+    /// document previews or a scene switcher. The renderer is laid out at the window size
+    /// with an idle input and camera, then scaled down to fit and centered on the theme
+    /// background. The image is read back to the CPU, usable on any thread, `None` for an
+    /// empty size or when the rendering failed.
+    ///
+    /// With `independent_ui` the renderer is sent to the render thread and the call blocks
+    /// until the thumbnail was rendered there.
+    pub fn render_thumbnail(
+        &mut self,
+        renderer: Box<dyn Renderer>,
+        size: (i32, i32),
+    ) -> Option<Image> {
+        if size.0 <= 0 || size.1 <= 0 {
+            return None;
+        }
+        let window_size: LogicalSize<f64> = self
+            .window
+            .as_ref()?
+            .inner_size()
+            .to_logical(self.scale_factor);
+        let layout_size = (
+            (window_size.width.round() as i32).max(1),
+            (window_size.height.round() as i32).max(1),
+        );
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            let mut renderer = renderer;
+            self.ui_state
+                .render_thumbnail(&mut self.skia_env, renderer.as_mut(), size, layout_size)
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            let (reply, thumbnail) = mpsc::channel();
+            // The reply is waited for, nothing would flush the request if it was kept.
+            self.sender
+                .send_blocking(Message::RenderThumbnail(ThumbnailRequest {
+                    renderer,
+                    size,
+                    layout_size,
+                    reply,
+                }))
+                .expect("Send render thumbnail message failed.");
+            // The render thread stopped when the reply was dropped.
+            thumbnail.recv().ok().flatten()
+        }
+    }
+
+    pub fn set_screenshot_config(&mut self, config: ScreenshotConfig) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.screenshotter.set_config(config);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::ScreenshotConfig(config))
+                .expect("Send screenshot config message failed.")
+        }
+    }
+
+    /// Stream the rendered frames to the clients connecting to the address, `None` stops
+    /// streaming.
+    pub fn stream(&mut self, config: Option<StreamConfig>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_stream(config);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Stream(config))
+                .expect("Send stream message failed.")
+        }
+    }
+
+    /// Record the hash of every rendered frame, or compare it with a recording until its
+    /// last frame, see `FrameHashConfig` and `take_verification`. `None` stops hashing.
+    pub fn set_frame_hashing(&mut self, config: Option<FrameHashConfig>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_frame_hashing(config);
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::FrameHashing(config))
+                .expect("Send frame hashing message failed.")
+        }
+    }
+
+    /// The statistics of the last rendered frame, including the draw calls reported through
+    /// `ProfiledCanvas`.
+    #[inline]
+    pub fn stats(&self) -> FrameStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// The frames dropped since the previous call, e.g. to log jank or lower the quality.
+    #[inline]
+    pub fn take_dropped_frames(&self) -> Vec<DroppedFrame> {
+        self.stats.lock().unwrap().take_dropped()
+    }
+
+    /// The outcome of the frame hash verification once every recorded frame was compared,
+    /// returned once, e.g. to exit with a failure code when a frame differed.
+    #[inline]
+    pub fn take_verification(&self) -> Option<Verification> {
+        self.stats.lock().unwrap().take_verification()
+    }
+
+    #[inline]
+    pub fn is_hud_visible(&self) -> bool {
+        self.hud_visible
+    }
+
+    /// Let the desktop show through the frame where it is not opaque, the window must have
+    /// been created with `with_transparent(true)` from a config supporting transparency.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        if let Some(window) = &self.window {
+            window.set_transparent(transparent);
+        }
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.transparent = transparent;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Transparent(transparent))
+                .expect("Send transparent message failed.")
+        }
+    }
+
+    /// Blur the desktop behind the transparent parts of the frame, where the platform
+    /// supports it.
+    pub fn set_blur_behind(&mut self, enabled: bool) {
+        if let Some(window) = &self.window {
+            if let Err(err) = blur::set_blur_behind(window, enabled) {
+                eprintln!("Error setting the blur behind the window: {err}");
+            }
+        }
+    }
+
+    /// Show the frame stats on top of the frame.
+    pub fn set_hud_visible(&mut self, visible: bool) {
+        self.hud_visible = visible;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.show_hud = visible;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Hud(visible))
+                .expect("Send hud message failed.")
+        }
+    }
+
+    #[inline]
+    pub fn toggle_hud(&mut self) {
+        self.set_hud_visible(!self.hud_visible)
+    }
+
+    #[inline]
+    pub fn diagnostics(&self) -> Option<DiagnosticPattern> {
+        self.diagnostics
+    }
+
+    /// Draw a calibration pattern instead of the content, to check the vsync, the colors
+    /// and the latency of the driver and compositor, `None` draws the content again. The
+    /// pattern is drawn every frame straight into the window surface at its resolution,
+    /// past the render scale and the offscreen framebuffer.
+    pub fn set_diagnostics(&mut self, pattern: Option<DiagnosticPattern>) {
+        self.diagnostics = pattern;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.diagnostics = pattern.map(Diagnostics::new);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Diagnostics(pattern))
+                .expect("Send diagnostics message failed.")
+        }
+    }
+
+    /// Step through the diagnostic patterns, then back to the content.
+    pub fn cycle_diagnostics(&mut self) {
+        self.set_diagnostics(match self.diagnostics {
+            Some(pattern) => pattern.next(),
+            None => Some(DiagnosticPattern::ALL[0]),
+        })
+    }
+
+    #[inline]
+    pub fn is_ruler_visible(&self) -> bool {
+        self.ruler_visible
+    }
+
+    /// Show a grid, rulers and a crosshair following the camera, to position elements
+    /// while developing renderers.
+    pub fn set_ruler_visible(&mut self, visible: bool) {
+        self.ruler_visible = visible;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.ruler = visible.then(RulerOverlay::default);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Ruler(visible))
+                .expect("Send ruler message failed.")
+        }
+    }
+
+    #[inline]
+    pub fn toggle_ruler(&mut self) {
+        self.set_ruler_visible(!self.ruler_visible)
+    }
+
+    /// Open a menu drawn on top of the frame at the position in window pixels, at the
+    /// cursor for `None`. It takes the input until closed, the chosen action is returned
+    /// by `take_menu_action`.
+    pub fn open_context_menu(&mut self, position: Option<(f32, f32)>, entries: Vec<MenuEntry>) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size();
+        let position = position.or(self.cursor_position).unwrap_or_default();
+        self.context_menu = Some(ContextMenu::new(
+            entries,
+            position,
+            (size.width as f32, size.height as f32),
+            self.scale_factor as f32,
+        ));
+        self.sync_context_menu();
+    }
+
+    pub fn close_context_menu(&mut self) {
+        if self.context_menu.take().is_some() {
+            self.sync_context_menu();
+        }
+    }
+
+    #[inline]
+    pub fn is_context_menu_open(&self) -> bool {
+        self.context_menu.is_some()
+    }
+
+    /// The action chosen in the context menu since the last call, to run like the bound
+    /// actions after handling the input.
+    #[inline]
+    pub fn take_menu_action(&mut self) -> Option<Action> {
+        self.menu_action.take()
+    }
+
+    fn sync_context_menu(&mut self) {
+        self.invalidator.invalidate();
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.context_menu = self.context_menu.clone();
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::ContextMenu(self.context_menu.clone()))
+                .expect("Send context menu message failed.")
+        }
+    }
+
+    /// Returns whether the open context menu took the event, the releases and modifiers
+    /// still reach the renderer to keep its input state consistent.
+    fn handle_context_menu(&mut self, event: &InputEvent) -> bool {
+        let Some(menu) = self.context_menu.as_mut() else {
+            return false;
+        };
+        let hovered = menu.hovered();
+        let response = menu.handle(event);
+        let hover_changed = menu.hovered() != hovered;
+        match response {
+            MenuResponse::Open if hover_changed => self.sync_context_menu(),
+            MenuResponse::Open => {}
+            MenuResponse::Closed => self.close_context_menu(),
+            MenuResponse::Chosen(action) => {
+                self.menu_action = Some(action);
+                self.close_context_menu();
+            }
+        }
+        !matches!(
+            event,
+            InputEvent::Modifiers(_)
+                | InputEvent::MouseButton { pressed: false, .. }
+                | InputEvent::Key { pressed: false, .. }
+        )
+    }
+
+    #[inline]
+    pub fn is_clock_paused(&self) -> bool {
+        self.clock_paused
+    }
+
+    /// Freeze the frame clock the renderers are animated with, the window keeps rendering
+    /// the same frame and the camera stays interactive.
+    pub fn set_clock_paused(&mut self, paused: bool) {
+        self.clock_paused = paused;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.clock.set_paused(paused);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::ClockPaused(paused))
+                .expect("Send clock paused message failed.")
+        }
+    }
+
+    #[inline]
+    pub fn toggle_clock_pause(&mut self) {
+        self.set_clock_paused(!self.clock_paused)
+    }
+
+    /// Pause the frame clock and advance it by exactly one frame.
+    pub fn step_frame(&mut self) {
+        self.clock_paused = true;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.clock.step();
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::StepFrame)
+                .expect("Send step frame message failed.")
         }
     }
 
     #[inline]
-    pub fn possibly_current_context(&self) -> Option<&PossiblyCurrentContext> {
-        self.possibly_current_context.as_ref()
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
     }
-}
 
-pub struct GlEnv {
-    gl_surface: Surface<WindowSurface>,
-    gl_ctx: Mutex<GlCtx>,
-    gl_config: Config,
-}
-unsafe impl Sync for GlEnv {}
-unsafe impl Send for GlEnv {}
-impl GlEnv {
-    #[inline]
-    pub fn new(gl_surface: Surface<WindowSurface>, gl_ctx: GlCtx, gl_config: Config) -> Self {
-        Self {
-            gl_surface,
-            gl_ctx: Mutex::new(gl_ctx),
-            gl_config,
+    /// Run the frame clock in slow motion, clamped to `[0.1, 1]`.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        let time_scale = time_scale.clamp(clock::MIN_TIME_SCALE, clock::MAX_TIME_SCALE);
+        self.time_scale = time_scale;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.clock.set_time_scale(time_scale);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::TimeScale(time_scale))
+                .expect("Send time scale message failed.")
         }
     }
 
     #[inline]
-    pub fn set_vsync(&self) {
-        if let Err(res) = self.gl_surface.set_swap_interval(
-            self.gl_ctx
-                .lock()
-                .unwrap()
-                .possibly_current_context()
-                .unwrap(),
-            SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
-        ) {
-            eprintln!("Error setting vsync: {res:?}");
-        }
+    pub fn slow_down(&mut self) {
+        self.set_time_scale(clock::slower(self.time_scale))
     }
 
     #[inline]
-    pub fn make_current(&self) {
-        self.gl_ctx.lock().unwrap().make_current(&self.gl_surface)
+    pub fn speed_up(&mut self) {
+        self.set_time_scale(clock::faster(self.time_scale))
     }
 
     #[inline]
-    pub fn load(&self) {
-        gl::load_with(|s| {
-            self.gl_config
-                .display()
-                .get_proc_address(CString::new(s).unwrap().as_c_str())
-        });
+    pub fn color_vision(&self) -> ColorVision {
+        self.color_vision
     }
 
-    #[inline]
-    pub fn resize(&self, size: (u32, u32)) {
-        if let Some(ctx) = self.gl_ctx.lock().unwrap().possibly_current_context() {
-            self.gl_surface.resize(
-                ctx,
-                NonZeroU32::new(size.0.max(1)).unwrap(),
-                NonZeroU32::new(size.1.max(1)).unwrap(),
-            )
+    /// Simulate a color vision deficiency over the whole frame.
+    pub fn set_color_vision(&mut self, color_vision: ColorVision) {
+        self.color_vision = color_vision;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.color_vision = color_vision;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::ColorVision(color_vision))
+                .expect("Send color vision message failed.")
         }
     }
 
     #[inline]
-    pub fn swap_buffers(&self) {
-        if let Some(ctx) = self.gl_ctx.lock().unwrap().possibly_current_context() {
-            self.gl_surface.swap_buffers(ctx).unwrap()
-        }
+    pub fn cycle_color_vision(&mut self) {
+        self.set_color_vision(self.color_vision.next())
     }
-}
 
-pub struct SkiaEnv {
-    gr_context: DirectContext,
-    fb_info: FramebufferInfo,
-    surface: SkiaSurface,
-}
-impl SkiaEnv {
-    pub fn canvas(&mut self) -> &mut Canvas {
-        self.surface.canvas()
+    #[inline]
+    pub fn output_adjustment(&self) -> OutputAdjustment {
+        self.output_adjustment
     }
 
-    pub fn resize(&mut self, size: (i32, i32), config: &Config) {
-        let num_samples = config.num_samples() as usize;
-        let stencil_size = config.num_samples() as usize;
-
-        self.surface = create_surface(
-            size,
-            self.fb_info,
-            &mut self.gr_context,
-            num_samples,
-            stencil_size,
-        );
+    /// Correct the gamma, the brightness and the contrast of the whole frame, e.g. for a
+    /// display that can't be calibrated.
+    pub fn set_output_adjustment(&mut self, adjustment: OutputAdjustment) {
+        self.output_adjustment = adjustment;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.output_adjustment = adjustment;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::OutputAdjustment(adjustment))
+                .expect("Send output adjustment message failed.")
+        }
     }
-}
-
-pub struct Backend {
-    window: Option<Arc<Window>>,
 
-    #[cfg(not(feature = "independent_ui"))]
-    gl_env: Arc<GlEnv>,
-    #[cfg(not(feature = "independent_ui"))]
-    skia_env: SkiaEnv,
+    /// Show the flame view of the puffin scopes recorded in the previous frame.
+    #[cfg(feature = "profiling")]
+    pub fn set_puffin_overlay_visible(&mut self, visible: bool) {
+        self.puffin_overlay_visible = visible;
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.puffin_overlay = visible.then(PuffinOverlay::default);
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::PuffinOverlay(visible))
+                .expect("Send puffin overlay message failed.")
+        }
+    }
 
-    #[cfg(feature = "independent_ui")]
-    sender: Sender<Message>,
-}
+    #[cfg(feature = "profiling")]
+    #[inline]
+    pub fn toggle_puffin_overlay(&mut self) {
+        self.set_puffin_overlay_visible(!self.puffin_overlay_visible)
+    }
 
-impl Backend {
-    pub fn new(window: Arc<Window>, gl_env: Arc<GlEnv>) -> Self {
+    /// Render into a crate-managed framebuffer blitted to the window framebuffer before the
+    /// buffers are swapped, instead of rendering into the window framebuffer directly.
+    pub fn set_offscreen_rendering(&mut self, offscreen: bool) {
         #[cfg(not(feature = "independent_ui"))]
         {
-            gl_env.make_current();
-            gl_env.load();
-
-            let size = window.inner_size();
-            let size = (
-                size.width.try_into().expect("Could not convert width"),
-                size.height.try_into().expect("Could not convert height"),
-            );
-            let skia_env = create_skia_env(size, &gl_env.gl_config);
-            Self {
-                window: Some(window),
-                gl_env,
-                skia_env,
-            }
+            self.skia_env.set_offscreen(offscreen);
+            self.request_redraw();
         }
-
         #[cfg(feature = "independent_ui")]
         {
-            let size = window.inner_size();
-            let size = (
-                size.width.try_into().expect("Could not convert width"),
-                size.height.try_into().expect("Could not convert height"),
-            );
-            let (sender, receiver) = channel();
+            self.sender
+                .send(Message::Offscreen(offscreen))
+                .expect("Send offscreen message failed.")
+        }
+    }
 
-            thread::Builder::new()
-                .spawn(move || ui_runtime(size, receiver, gl_env))
-                .unwrap();
+    /// Dither the gradients against the banding on 8 bit displays, applied by the renderers
+    /// through `FrameInfo::dither`.
+    pub fn set_dithering(&mut self, dither: bool) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.dither = dither;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::Dither(dither))
+                .expect("Send dither message failed.")
+        }
+    }
 
-            Self {
-                window: Some(window),
-                sender,
-            }
+    /// Antialias the paints of the renderers, applied through `FrameInfo::anti_alias`.
+    /// Turned off with `MsaaResolve::PerFrame` on a multisampled config, the edges are still
+    /// smoothed by the multisampling at a fraction of the fill cost.
+    pub fn set_paint_antialiasing(&mut self, anti_alias: bool) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.paint_antialias = anti_alias;
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::PaintAntialiasing(anti_alias))
+                .expect("Send paint antialiasing message failed.")
         }
     }
 
-    #[inline]
-    pub fn exit(&mut self) {
-        self.window.take();
+    /// Give up the expensive features one by one while the frames stay over budget and
+    /// restore them once the headroom returns, the renderer is told with
+    /// `Renderer::quality_changed` and the quality is in `FrameStats::quality`. `None` turns
+    /// it off and restores every feature.
+    pub fn set_quality_policy(&mut self, policy: Option<QualityPolicy>) {
+        #[cfg(not(feature = "independent_ui"))]
+        {
+            self.ui_state.set_quality_policy(policy);
+            self.skia_env
+                .set_render_scale(self.ui_state.quality.render_scale(self.scaler.scale()));
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::QualityPolicy(policy))
+                .expect("Send quality policy message failed.")
+        }
     }
 
-    #[inline]
-    pub fn request_redraw(&self) {
+    /// The pixel format of the window surface, `SurfaceColorType::Auto` detects it from the
+    /// GL config. A color type the config has no matching color buffer for falls back to
+    /// the detected one. Changing the msaa resolve recreates the GPU context, like a GPU
+    /// reset.
+    pub fn set_surface_config(&mut self, surface_config: SurfaceConfig) {
         #[cfg(not(feature = "independent_ui"))]
-        if let Some(ref window) = self.window {
-            window.request_redraw();
+        {
+            if self
+                .skia_env
+                .set_surface_config(surface_config, &self.gl_env.gl_config)
+            {
+                self.ui_state.context_lost();
+            }
+            self.request_redraw();
+        }
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::SurfaceConfig(surface_config))
+                .expect("Send surface config message failed.")
         }
     }
 
-    pub fn notify_resize(&mut self, size: (u32, u32)) {
+    /// Render at a different resolution than the window, optionally adjusted automatically
+    /// when the frame time exceeds the frame duration of the pacer.
+    pub fn set_render_scale(&mut self, render_scale: RenderScale) {
         #[cfg(not(feature = "independent_ui"))]
         {
+            self.scaler = ResolutionScaler::new(render_scale);
             self.skia_env
-                .resize((size.0 as i32, size.1 as i32), &self.gl_env.gl_config);
-            self.gl_env.resize((size.0 as u32, size.1 as u32));
+                .set_render_scale(self.ui_state.quality.render_scale(render_scale.scale));
+            self.request_redraw();
         }
         #[cfg(feature = "independent_ui")]
         {
             self.sender
-                .send(Message::Resize(size.0, size.1))
-                .expect("Send resize message failed.")
+                .send(Message::RenderScale(render_scale))
+                .expect("Send render scale message failed.")
+        }
+    }
+
+    /// Hand the event to the renderer at the start of the next frame, with
+    /// `Renderer::handle_user_event`, instead of sharing state between the application
+    /// logic and the drawing code.
+    #[inline]
+    pub fn post_event<T: Any + Send>(&self, event: T) {
+        self.post_user_event(UserEvent::new(event))
+    }
+
+    /// Queue the event for the next frame, e.g. on `BackendEvent::User` posted by a
+    /// `BackendProxy`, keeping its timestamp.
+    pub fn post_user_event(&self, event: UserEvent) {
+        self.user_events.lock().unwrap().push(event);
+        self.invalidate(Damage::Full);
+    }
+
+    /// Redraw the damage, e.g. on `BackendEvent::Redraw` posted by a `BackendProxy`.
+    pub fn invalidate(&self, damage: Damage) {
+        match damage {
+            Damage::Full => self.invalidator.invalidate(),
+            Damage::Rect(rect) => self.invalidator.invalidate_rect(rect),
+        }
+        self.request_redraw();
+    }
+
+    /// Request the redraws, shared with the renderer through `Renderer::attach_invalidator`.
+    #[inline]
+    pub fn invalidator(&self) -> Invalidator {
+        self.invalidator.clone()
+    }
+
+    /// Render only the frames requested through the invalidator, the input and the resizes
+    /// invalidate the whole frame. Otherwise every frame of the pacer is rendered.
+    pub fn set_on_demand(&mut self, on_demand: bool) {
+        self.on_demand = on_demand;
+        #[cfg(not(feature = "independent_ui"))]
+        self.request_redraw();
+        #[cfg(feature = "independent_ui")]
+        {
+            self.sender
+                .send(Message::OnDemand(on_demand))
+                .expect("Send on demand message failed.")
         }
     }
 
+    #[inline]
+    pub fn is_on_demand(&self) -> bool {
+        self.on_demand
+    }
+
+    /// Whether the frame starting at `now` should be rendered, to gate `request_redraw` on
+    /// the frames of the pacer. Called every frame by the event loop, it also hands the
+    /// messages kept while the render thread lagged over.
+    #[inline]
+    pub fn wants_frame(&self, now: Instant) -> bool {
+        #[cfg(feature = "independent_ui")]
+        // The kept messages are moot once the render thread stopped.
+        self.sender.flush().ok();
+        !self.on_demand || self.invalidator.is_invalid(now)
+    }
+
     #[allow(unused_variables)]
     pub fn render(&mut self, frame: usize) {
         #[cfg(not(feature = "independent_ui"))]
         {
-            let canvas = self.skia_env.canvas();
-            canvas.clear(Color::WHITE);
-
-            renderer::render_frame(frame % 360, 12, 60, canvas);
+            let _span = info_span!("frame", frame).entered();
+            // The redraws requested by the system, e.g. on expose, are whole.
+            let damage = self
+                .invalidator
+                .take(Instant::now())
+                .filter(|_| self.on_demand)
+                .unwrap_or(Damage::Full);
+            let result = guard_frame(&mut self.skia_env, &self.gl_env, |skia_env| {
+                skia_env.set_antialiasing(self.ui_state.antialiasing());
+                skia_env.set_direct(self.ui_state.diagnostics.is_some());
+                let render_start = Instant::now();
+                let scale = skia_env.pixel_scale();
+                let clip = skia_env.clip_to_damage(damage);
+                // A clock step advances a paced frame.
+                self.ui_state
+                    .clock
+                    .set_step_duration(self.pacer.frame_duration());
+                self.ui_state.draw(skia_env.canvas(), frame, scale);
+                if let Some(count) = clip {
+                    skia_env.canvas().restore_to_count(count);
+                }
+                self.ui_state
+                    .finish_frame(skia_env.target_surface(), frame, &self.invalidator);
 
-            self.skia_env.gr_context.flush_and_submit();
-            self.gl_env.swap_buffers();
+                let flush_start = Instant::now();
+                info_span!("flush").in_scope(|| {
+                    #[cfg(feature = "profiling")]
+                    puffin::profile_scope!("flush");
+                    skia_env.flush()
+                });
+                let flush_end = Instant::now();
+                let frame_time = render_start.elapsed();
+                let budget = self.pacer.frame_duration();
+                let rescaled = self.scaler.update(frame_time, budget).is_some();
+                if self.ui_state.update_quality(frame_time, budget) || rescaled {
+                    skia_env
+                        .set_render_scale(self.ui_state.quality.render_scale(self.scaler.scale()));
+                }
+                let swap_start = Instant::now();
+                info_span!("swap").in_scope(|| {
+                    #[cfg(feature = "profiling")]
+                    puffin::profile_scope!("swap");
+                    self.gl_env.swap_buffers()
+                });
+                let swap_end = Instant::now();
+                self.stats.lock().unwrap().update_pacing(
+                    self.pacer.take_lateness(),
+                    swap_end - swap_start,
+                    self.pacer.frame_duration(),
+                );
+                self.pacer
+                    .notify_presented(render_start, flush_end, swap_end);
+                IdleQueue::run(
+                    &self.idle,
+                    &mut skia_env.gr_context,
+                    self.pacer.next_frame_start(),
+                );
+                self.ui_state.check_gl_errors(frame);
+                self.ui_state.report_slow_frame(
+                    frame,
+                    FrameTimings {
+                        events: Duration::ZERO,
+                        record: flush_start - render_start,
+                        flush: flush_end - flush_start,
+                        swap: swap_end - swap_start,
+                    },
+                );
+            });
+            self.ui_state
+                .end_frame(result, &mut self.skia_env, &self.gl_env, frame);
         }
         #[cfg(feature = "independent_ui")]
         {}
     }
 }
 
-fn create_skia_env(size: (i32, i32), gl_config: &Config) -> SkiaEnv {
+/// Render a frame, when it panics tear down the GPU context and release the GL context, so
+/// a panicking renderer doesn't leave the driver mid-frame. The panic is returned to be
+/// recovered from or propagated, see `UiState::end_frame`.
+fn guard_frame<R>(
+    skia_env: &mut SkiaEnv,
+    gl_env: &GlEnv,
+    frame: impl FnOnce(&mut SkiaEnv) -> R,
+) -> std::thread::Result<R> {
+    panic::catch_unwind(AssertUnwindSafe(|| frame(skia_env))).map_err(|payload| {
+        eprintln!("The frame panicked, tearing down the GPU context");
+        skia_env.teardown();
+        gl_env.release();
+        payload
+    })
+}
+
+fn create_skia_env(size: (i32, i32), gl_config: &Config, msaa_resolve: MsaaResolve) -> SkiaEnv {
     let interface = skia_safe::gpu::gl::Interface::new_load_with(|name| {
         if name == "eglGetCurrentDisplay" {
             return std::ptr::null();
@@ -246,16 +3092,19 @@ fn create_skia_env(size: (i32, i32), gl_config: &Config) -> SkiaEnv {
     })
     .expect("Could not create interface");
 
-    let mut gr_context = skia_safe::gpu::DirectContext::new_gl(interface, None)
+    let mut options = ContextOptions::default();
+    options.internal_multisample_count = msaa_resolve.internal_sample_count();
+    let mut gr_context = skia_safe::gpu::DirectContext::new_gl(interface, Some(&options))
         .expect("Could not create direct context");
 
+    let color_type = SurfaceColorType::detect(gl_config);
     let fb_info = {
         let mut fboid: GLint = 0;
         unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
 
         FramebufferInfo {
             fboid: fboid.try_into().unwrap(),
-            format: skia_safe::gpu::gl::Format::RGBA8.into(),
+            format: color_type.formats().1.into(),
             ..Default::default()
         }
     };
@@ -263,18 +3112,35 @@ fn create_skia_env(size: (i32, i32), gl_config: &Config) -> SkiaEnv {
     let num_samples = gl_config.num_samples() as usize;
     let stencil_size = gl_config.stencil_size() as usize;
 
-    let surface = create_surface(size, fb_info, &mut gr_context, num_samples, stencil_size);
+    let surface = create_surface(
+        size,
+        fb_info,
+        color_type,
+        &mut gr_context,
+        num_samples,
+        stencil_size,
+    );
 
     SkiaEnv {
         gr_context,
         fb_info,
+        color_type,
+        msaa_resolve,
         surface,
+        size,
+        offscreen: None,
+        offscreen_requested: false,
+        render_scale: 1.,
+        supersample: false,
+        direct: false,
+        content_preserved: false,
     }
 }
 
 fn create_surface(
     size: (i32, i32),
     fb_info: FramebufferInfo,
+    color_type: SurfaceColorType,
     gr_context: &mut skia_safe::gpu::DirectContext,
     num_samples: usize,
     stencil_size: usize,
@@ -286,40 +3152,183 @@ fn create_surface(
         gr_context,
         &backend_render_target,
         SurfaceOrigin::BottomLeft,
-        ColorType::RGBA8888,
+        color_type.formats().0,
         None,
         None,
     )
-    .expect("Could not create skia surface")
+    .unwrap_or_else(|| panic!("Could not create a {color_type} skia surface"))
+}
+
+/// A thumbnail to render on the render thread, see `Backend::render_thumbnail`.
+#[cfg(feature = "independent_ui")]
+pub struct ThumbnailRequest {
+    renderer: Box<dyn Renderer>,
+    size: (i32, i32),
+    layout_size: (i32, i32),
+    reply: mpsc::Sender<Option<Image>>,
+}
+
+#[cfg(feature = "independent_ui")]
+impl std::fmt::Debug for ThumbnailRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThumbnailRequest")
+            .field("size", &self.size)
+            .field("layout_size", &self.layout_size)
+            .finish_non_exhaustive()
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug)]
 pub enum Message {
     Resize(u32, u32),
+    Input(InputEvent),
+    CameraConfig(CameraConfig),
+    /// Refresh rate in millihertz, `0` restores the frame rate before it was adopted.
+    RefreshRate(u32),
+    /// The refresh rate of the monitor in millihertz, `0` when unknown.
+    VblankRate(u32),
+    /// Stop rendering until resumed, the render thread blocks on the messages meanwhile.
+    Paused(bool),
+    DumpSkp(PathBuf),
+    Print(PrintOptions),
+    PrewarmGlyphs(Vec<GlyphSet>),
+    SlowFrames(Option<SlowFrameConfig>),
+    Capture(PathBuf, CaptureOptions),
+    Screenshot,
+    AsyncReadback(bool),
+    ScreenshotConfig(ScreenshotConfig),
+    GlErrorChecks(bool),
+    StateRecovery(Option<RecoveryConfig>),
+    Dither(bool),
+    PaintAntialiasing(bool),
+    QualityPolicy(Option<QualityPolicy>),
+    KeepFrameSnapshot(bool),
+    KeepFadeSnapshot(bool),
+    Stream(Option<StreamConfig>),
+    FrameHashing(Option<FrameHashConfig>),
+    Hud(bool),
+    Ruler(bool),
+    Diagnostics(Option<DiagnosticPattern>),
+    ContextMenu(Option<ContextMenu>),
+    ClockPaused(bool),
+    StepFrame,
+    TimeScale(f32),
+    ColorVision(ColorVision),
+    OutputAdjustment(OutputAdjustment),
+    Insets(Insets),
+    Transparent(bool),
+    Theme(Theme, Duration),
+    CrossFade(Duration),
+    Locale(Locale),
+    SystemTheme(SystemTheme),
+    ScaleFactor(f64),
+    FollowSystemTheme(bool),
+    Offscreen(bool),
+    SurfaceConfig(SurfaceConfig),
+    RenderScale(RenderScale),
+    OnDemand(bool),
+    #[cfg(feature = "profiling")]
+    PuffinOverlay(bool),
+    #[cfg(feature = "independent_ui")]
+    FrameSnapshot(mpsc::Sender<Option<Image>>),
+    #[cfg(feature = "independent_ui")]
+    RenderThumbnail(ThumbnailRequest),
+    /// Abandon the GPU context and create a new one, e.g. when the watchdog found the
+    /// render thread stalled.
+    RecreateContext,
+    /// Stop the render thread.
+    Shutdown,
 }
 
 #[cfg(feature = "independent_ui")]
-pub fn ui_runtime(mut size: (i32, i32), receiver: Receiver<Message>, gl_env: Arc<GlEnv>) {
-    use std::{
-        sync::atomic::AtomicUsize,
-        time::{Duration, Instant},
-    };
+impl Prioritized for Message {
+    fn is_control(&self) -> bool {
+        matches!(
+            self,
+            Message::Resize(..)
+                | Message::RefreshRate(_)
+                | Message::VblankRate(_)
+                | Message::Paused(_)
+                | Message::RecreateContext
+                | Message::Shutdown
+        )
+    }
+
+    fn replaces(&self, queued: &Self) -> bool {
+        matches!(
+            (self, queued),
+            (Message::Resize(..), Message::Resize(..))
+                | (Message::RefreshRate(_), Message::RefreshRate(_))
+                | (Message::VblankRate(_), Message::VblankRate(_))
+                | (Message::Paused(_), Message::Paused(_))
+        )
+    }
+
+    fn coalesces(&self, queued: &Self) -> bool {
+        matches!(
+            (self, queued),
+            (
+                Message::Input(InputEvent::CursorMoved { .. }),
+                Message::Input(InputEvent::CursorMoved { .. })
+            )
+        )
+    }
+}
+
+/// The number of queued bulk messages, e.g. input events, before the cursor moves are
+/// coalesced and the other messages kept by the sender.
+#[cfg(feature = "independent_ui")]
+const MESSAGE_CAPACITY: usize = 256;
+
+#[cfg(feature = "independent_ui")]
+#[allow(clippy::too_many_arguments)]
+pub fn ui_runtime(
+    mut size: (i32, i32),
+    receiver: Receiver<Message>,
+    gl_env: Arc<GlEnv>,
+    renderer: Box<dyn Renderer>,
+    stats: Arc<Mutex<FrameStats>>,
+    user_events: Arc<Mutex<Vec<UserEvent>>>,
+    idle: Arc<Mutex<IdleQueue>>,
+    invalidator: Invalidator,
+    system_theme: SystemTheme,
+    heartbeat: Arc<Heartbeat>,
+) {
+    use std::sync::atomic::AtomicUsize;
 
     gl_env.make_current();
     gl_env.load();
     gl_env.set_vsync();
 
-    let mut skia_env = create_skia_env(size, &gl_env.gl_config);
+    let mut skia_env = create_skia_env(size, &gl_env.gl_config, MsaaResolve::default());
+    let mut ui_state = UiState::new(&gl_env, renderer, stats, user_events, system_theme);
 
     let mut frame = 0usize;
     let mut resized = false;
 
-    let mut previous_frame_start = Instant::now();
+    let mut pacer = FramePacer::default();
+    let mut scaler = ResolutionScaler::default();
+    let mut on_demand = false;
 
     loop {
         let frame_start = Instant::now();
 
-        if let Ok(msg) = receiver.try_recv() {
+        let events_span = info_span!("events").entered();
+        loop {
+            // Block instead of spinning while paused.
+            let msg = if pacer.is_paused() {
+                receiver.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                receiver.try_recv()
+            };
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(TryRecvError::Empty) => break,
+                // The backend is gone without shutting the thread down, e.g. it panicked.
+                Err(TryRecvError::Disconnected) => return,
+            };
+            // Whatever the message, it may change the frame.
+            invalidator.invalidate();
             match msg {
                 Message::Resize(width, height) => {
                     size = (width as i32, height as i32);
@@ -338,41 +3347,202 @@ pub fn ui_runtime(mut size: (i32, i32), receiver: Receiver<Message>, gl_env: Arc
                     // .unwrap();
                     // file.write_all(data.as_bytes()).unwrap();
                 }
+                Message::Input(event) => ui_state.handle_input(&event),
+                Message::CameraConfig(config) => ui_state.camera.set_config(config),
+                Message::RefreshRate(0) => pacer.reset_refresh_rate(),
+                Message::RefreshRate(millihertz) => pacer.set_refresh_rate_millihertz(millihertz),
+                Message::VblankRate(millihertz) => pacer.set_vblank_rate_millihertz(millihertz),
+                Message::Paused(paused) => pacer.set_paused(paused),
+                Message::DumpSkp(path) => ui_state.skp_path = Some(path),
+                Message::Print(options) => ui_state.print_options = Some(options),
+                Message::PrewarmGlyphs(sets) => ui_state.glyph_sets.extend(sets),
+                Message::FrameSnapshot(reply) => {
+                    match ui_state.presented_snapshot(&mut skia_env) {
+                        Some(image) => {
+                            reply.send(image.make_non_texture_image()).ok();
+                        }
+                        // The window framebuffer is undefined after the swap, the next
+                        // frame is snapshot before its own.
+                        None => {
+                            ui_state.snapshot_replies.push(reply);
+                            invalidator.invalidate();
+                        }
+                    }
+                }
+                Message::RenderThumbnail(mut request) => {
+                    let thumbnail = ui_state.render_thumbnail(
+                        &mut skia_env,
+                        request.renderer.as_mut(),
+                        request.size,
+                        request.layout_size,
+                    );
+                    request.reply.send(thumbnail).ok();
+                }
+                Message::SlowFrames(config) => ui_state.slow_frames = config,
+                Message::Capture(path, options) => ui_state.capture = Some((path, options)),
+                Message::Screenshot => ui_state.screenshot_requested = true,
+                Message::AsyncReadback(enabled) => ui_state.set_async_readback(enabled),
+                Message::ScreenshotConfig(config) => ui_state.screenshotter.set_config(config),
+                Message::GlErrorChecks(enabled) => ui_state.gl_error_checks = enabled,
+                Message::StateRecovery(config) => ui_state.recovery.set_config(config),
+                Message::Dither(dither) => ui_state.dither = dither,
+                Message::PaintAntialiasing(anti_alias) => ui_state.paint_antialias = anti_alias,
+                Message::QualityPolicy(policy) => {
+                    ui_state.set_quality_policy(policy);
+                    skia_env.set_render_scale(ui_state.quality.render_scale(scaler.scale()));
+                }
+                Message::KeepFrameSnapshot(keep) => {
+                    ui_state.keep_frame_snapshot = keep;
+                    if !keep && !ui_state.keep_fade_snapshot {
+                        ui_state.frame_snapshot = None;
+                    }
+                }
+                Message::KeepFadeSnapshot(keep) => {
+                    ui_state.keep_fade_snapshot = keep;
+                    if !keep && !ui_state.keep_frame_snapshot {
+                        ui_state.frame_snapshot = None;
+                    }
+                }
+                Message::Stream(config) => ui_state.set_stream(config),
+                Message::FrameHashing(config) => ui_state.set_frame_hashing(config),
+                Message::Hud(visible) => ui_state.show_hud = visible,
+                Message::Ruler(visible) => ui_state.ruler = visible.then(RulerOverlay::default),
+                Message::Diagnostics(pattern) => {
+                    ui_state.diagnostics = pattern.map(Diagnostics::new)
+                }
+                Message::ContextMenu(menu) => ui_state.context_menu = menu,
+                Message::ClockPaused(paused) => ui_state.clock.set_paused(paused),
+                Message::StepFrame => ui_state.clock.step(),
+                Message::TimeScale(time_scale) => ui_state.clock.set_time_scale(time_scale),
+                Message::ColorVision(color_vision) => ui_state.color_vision = color_vision,
+                Message::OutputAdjustment(adjustment) => ui_state.output_adjustment = adjustment,
+                Message::Insets(insets) => ui_state.insets = insets,
+                Message::Transparent(transparent) => ui_state.transparent = transparent,
+                Message::Theme(theme, transition) => ui_state.theme.set(theme, transition),
+                Message::CrossFade(duration) => {
+                    ui_state.start_cross_fade(&mut skia_env, duration, &invalidator)
+                }
+                Message::Locale(locale) => ui_state.set_locale(locale),
+                Message::SystemTheme(theme) => ui_state.set_system_theme(theme),
+                Message::ScaleFactor(scale_factor) => {
+                    ui_state.renderer.scale_factor_changed(scale_factor)
+                }
+                Message::FollowSystemTheme(follow) => ui_state.set_follow_system_theme(follow),
+                Message::Offscreen(offscreen) => skia_env.set_offscreen(offscreen),
+                Message::SurfaceConfig(surface_config) => {
+                    if skia_env.set_surface_config(surface_config, &gl_env.gl_config) {
+                        ui_state.context_lost();
+                    }
+                }
+                Message::RenderScale(render_scale) => {
+                    scaler = ResolutionScaler::new(render_scale);
+                    skia_env.set_render_scale(ui_state.quality.render_scale(render_scale.scale));
+                }
+                Message::OnDemand(enabled) => on_demand = enabled,
+                Message::RecreateContext => {
+                    eprintln!("Recreating the GPU context");
+                    skia_env.recreate(&gl_env.gl_config);
+                    ui_state.context_lost();
+                }
+                Message::Shutdown => return,
+                #[cfg(feature = "profiling")]
+                Message::PuffinOverlay(visible) => {
+                    ui_state.puffin_overlay = visible.then(PuffinOverlay::default)
+                }
             }
         }
+        drop(events_span);
+        let events_time = frame_start.elapsed();
 
-        let expected_frame_length_seconds = 1.0 / 20.0;
-        let frame_duration = Duration::from_secs_f32(expected_frame_length_seconds);
-
-        if frame_start - previous_frame_start > frame_duration {
-            if resized {
-                gl_env.resize((size.0 as u32, size.1 as u32));
-                skia_env.resize((size.0, size.1), &gl_env.gl_config);
-            }
+        // On demand the pacer keeps its schedule, the frames nothing invalidated are skipped.
+        if pacer.start_frame(frame_start) && (!on_demand || invalidator.is_invalid(frame_start)) {
+            let damage = invalidator
+                .take(frame_start)
+                .filter(|_| on_demand)
+                .unwrap_or(Damage::Full);
+            let _span = info_span!("frame", frame).entered();
+            let result = guard_frame(&mut skia_env, &gl_env, |skia_env| {
+                if resized {
+                    gl_env.resize((size.0 as u32, size.1 as u32));
+                    skia_env.resize((size.0, size.1), &gl_env.gl_config);
+                }
 
-            let canvas = skia_env.canvas();
-            canvas.clear(Color::WHITE);
+                skia_env.set_antialiasing(ui_state.antialiasing());
+                skia_env.set_direct(ui_state.diagnostics.is_some());
+                let render_start = Instant::now();
+                let scale = skia_env.pixel_scale();
+                let clip = skia_env.clip_to_damage(damage);
+                let canvas = skia_env.canvas();
 
-            // use skia_safe::{ClipOp, Paint, Rect};
-            // canvas.save();
-            // let rect = Rect::new(100., 100., 200., 200.);
-            // canvas.clip_rect(rect, ClipOp::Difference, false);
+                // use skia_safe::{ClipOp, Paint, Rect};
+                // canvas.save();
+                // let rect = Rect::new(100., 100., 200., 200.);
+                // canvas.clip_rect(rect, ClipOp::Difference, false);
 
-            // let rect = Rect::new(0., 0., size.0 as f32, size.1 as f32);
-            // let mut paint = Paint::default();
-            // paint.set_color(Color::GRAY);
-            // canvas.draw_rect(rect, &paint);
-            // canvas.restore();
+                // let rect = Rect::new(0., 0., size.0 as f32, size.1 as f32);
+                // let mut paint = Paint::default();
+                // paint.set_color(Color::GRAY);
+                // canvas.draw_rect(rect, &paint);
+                // canvas.restore();
 
-            renderer::render_frame(frame % 360, 12, 60, canvas);
-            // std::thread::sleep(std::time::Duration::from_millis(100));
+                // A clock step advances a paced frame.
+                ui_state.clock.set_step_duration(pacer.frame_duration());
+                ui_state.draw(canvas, frame, scale);
+                if let Some(count) = clip {
+                    canvas.restore_to_count(count);
+                }
+                // std::thread::sleep(std::time::Duration::from_millis(100));
+                ui_state.finish_frame(skia_env.target_surface(), frame, &invalidator);
 
-            skia_env.surface.flush_and_submit();
-            gl_env.swap_buffers();
+                let flush_start = Instant::now();
+                info_span!("flush").in_scope(|| {
+                    #[cfg(feature = "profiling")]
+                    puffin::profile_scope!("flush");
+                    skia_env.flush()
+                });
+                let flush_end = Instant::now();
+                let frame_time = render_start.elapsed();
+                let budget = pacer.frame_duration();
+                let rescaled = scaler.update(frame_time, budget).is_some();
+                if ui_state.update_quality(frame_time, budget) || rescaled {
+                    skia_env.set_render_scale(ui_state.quality.render_scale(scaler.scale()));
+                }
+                let swap_start = Instant::now();
+                info_span!("swap").in_scope(|| {
+                    #[cfg(feature = "profiling")]
+                    puffin::profile_scope!("swap");
+                    gl_env.swap_buffers()
+                });
+                let swap_end = Instant::now();
+                ui_state.stats.lock().unwrap().update_pacing(
+                    pacer.take_lateness(),
+                    swap_end - swap_start,
+                    pacer.frame_duration(),
+                );
+                pacer.notify_presented(render_start, flush_end, swap_end);
+                ui_state.check_gl_errors(frame);
+                heartbeat.beat();
+                ui_state.report_slow_frame(
+                    frame,
+                    FrameTimings {
+                        events: events_time,
+                        record: flush_start - render_start,
+                        flush: flush_end - flush_start,
+                        swap: swap_end - swap_start,
+                    },
+                );
+            });
+            ui_state.end_frame(result, &mut skia_env, &gl_env, frame);
 
-            previous_frame_start = frame_start;
             frame += 1;
             resized = false;
+        } else if !pacer.is_paused() {
+            let _span = info_span!("idle").entered();
+            IdleQueue::run(&idle, &mut skia_env.gr_context, pacer.next_frame_start());
+            // Sleep instead of spinning when nothing is to be drawn.
+            if on_demand && !invalidator.is_invalid(Instant::now()) {
+                receiver.wait_until(pacer.next_frame_start());
+            }
         }
     }
 }
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use skia_safe::{
+    canvas::PointMode, vertices::VertexMode, BlendMode, Canvas, Color, Paint, PaintCap, PaintStyle,
+    Point, Rect, Vertices,
+};
+
+/// The rects per vertices draw, their vertices must be indexable with `u16`.
+const RECTS_PER_DRAW: usize = (u16::MAX as usize + 1) / 4;
+
+/// Primitives sharing a paint, drawn with a single `draw_points` call.
+#[derive(Debug, Clone)]
+struct PointGroup {
+    color: Color,
+    /// The stroke width, twice the radius of the circles.
+    width: f32,
+    points: Vec<Point>,
+}
+
+/// Collects thousands of primitives, e.g. the marks of a plot, and draws them with a
+/// handful of draw calls instead of one per item: the rects with per-vertex colors in
+/// `draw_vertices` calls, the lines and circles grouped by color and size in `draw_points`
+/// calls. Within a batch the rects are drawn first, then the lines, then the circles, use
+/// several batches to layer them otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    rect_positions: Vec<Point>,
+    rect_colors: Vec<Color>,
+    lines: Vec<PointGroup>,
+    line_groups: HashMap<(u32, u32), usize>,
+    circles: Vec<PointGroup>,
+    circle_groups: HashMap<(u32, u32), usize>,
+    len: usize,
+}
+
+impl Batch {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A filled rect, not antialiased.
+    pub fn rect(&mut self, rect: Rect, color: Color) -> &mut Self {
+        self.rect_positions.extend([
+            Point::new(rect.left, rect.top),
+            Point::new(rect.right, rect.top),
+            Point::new(rect.right, rect.bottom),
+            Point::new(rect.left, rect.bottom),
+        ]);
+        self.rect_colors.extend([color; 4]);
+        self.len += 1;
+        self
+    }
+
+    /// A line with butt caps.
+    pub fn line(
+        &mut self,
+        from: impl Into<Point>,
+        to: impl Into<Point>,
+        width: f32,
+        color: Color,
+    ) -> &mut Self {
+        let group = group(&mut self.lines, &mut self.line_groups, color, width);
+        group.points.extend([from.into(), to.into()]);
+        self.len += 1;
+        self
+    }
+
+    /// The segments between the consecutive points.
+    pub fn polyline(&mut self, points: &[Point], width: f32, color: Color) -> &mut Self {
+        for segment in points.windows(2) {
+            self.line(segment[0], segment[1], width, color);
+        }
+        self
+    }
+
+    /// A filled circle.
+    pub fn circle(&mut self, center: impl Into<Point>, radius: f32, color: Color) -> &mut Self {
+        let group = group(
+            &mut self.circles,
+            &mut self.circle_groups,
+            color,
+            radius * 2.,
+        );
+        group.points.push(center.into());
+        self.len += 1;
+        self
+    }
+
+    /// The number of primitives.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove the primitives, keeping the allocations for the next frame.
+    pub fn clear(&mut self) {
+        self.rect_positions.clear();
+        self.rect_colors.clear();
+        self.lines.clear();
+        self.line_groups.clear();
+        self.circles.clear();
+        self.circle_groups.clear();
+        self.len = 0;
+    }
+
+    /// The number of draw calls `draw` issues.
+    pub fn draw_calls(&self) -> usize {
+        self.rect_colors.len().div_ceil(RECTS_PER_DRAW * 4) + self.lines.len() + self.circles.len()
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, anti_alias: bool) {
+        if !self.rect_positions.is_empty() {
+            let indices: Vec<u16> = (0..RECTS_PER_DRAW.min(self.rect_colors.len() / 4) as u16)
+                .flat_map(|rect| {
+                    let first = rect * 4;
+                    [first, first + 1, first + 2, first, first + 2, first + 3]
+                })
+                .collect();
+            let paint = Paint::default();
+            for (positions, colors) in self
+                .rect_positions
+                .chunks(RECTS_PER_DRAW * 4)
+                .zip(self.rect_colors.chunks(RECTS_PER_DRAW * 4))
+            {
+                let vertices = Vertices::new_copy(
+                    VertexMode::Triangles,
+                    positions,
+                    &[],
+                    colors,
+                    Some(&indices[..positions.len() / 4 * 6]),
+                );
+                // Without a shader the vertex colors are drawn as they are.
+                canvas.draw_vertices(&vertices, BlendMode::Dst, &paint);
+            }
+        }
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(anti_alias);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_cap(PaintCap::Butt);
+        for group in &self.lines {
+            paint.set_color(group.color);
+            paint.set_stroke_width(group.width);
+            canvas.draw_points(PointMode::Lines, &group.points, &paint);
+        }
+        // Round points are circles with the stroke width as diameter.
+        paint.set_stroke_cap(PaintCap::Round);
+        for group in &self.circles {
+            paint.set_color(group.color);
+            paint.set_stroke_width(group.width);
+            canvas.draw_points(PointMode::Points, &group.points, &paint);
+        }
+    }
+}
+
+fn group<'a>(
+    groups: &'a mut Vec<PointGroup>,
+    index: &mut HashMap<(u32, u32), usize>,
+    color: Color,
+    width: f32,
+) -> &'a mut PointGroup {
+    let index = *index
+        .entry((u32::from(color), width.to_bits()))
+        .or_insert_with(|| {
+            groups.push(PointGroup {
+                color,
+                width,
+                points: vec![],
+            });
+            groups.len() - 1
+        });
+    &mut groups[index]
+}
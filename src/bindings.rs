@@ -0,0 +1,353 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use serde::Deserialize;
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, ModifiersState, NamedKey},
+};
+
+/// An application action triggered by a binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    ToggleFullscreen,
+    ToggleHud,
+    /// The grid, rulers and crosshair debug overlay.
+    ToggleRuler,
+    /// Pause or resume the frame clock the renderers are animated with.
+    TogglePause,
+    /// Pause the frame clock and advance it by one frame.
+    StepFrame,
+    /// Step the slow motion down to 0.5x, 0.25x and 0.1x.
+    SlowDown,
+    SpeedUp,
+    /// Cycle through the simulated color vision deficiencies.
+    CycleColorVision,
+    /// Step through the calibration patterns drawn instead of the content.
+    CycleDiagnostics,
+    /// Only available on the `profiling` feature.
+    TogglePuffinOverlay,
+    Print,
+    Screenshot,
+    /// Open the menu of the actions at the cursor. Unbound by default, the right button
+    /// belongs to the renderers, e.g. bind `MouseRight` in the config.
+    ContextMenu,
+}
+
+impl Action {
+    /// The name of the action shown in the menus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::ToggleFullscreen => "Toggle Fullscreen",
+            Action::ToggleHud => "Toggle HUD",
+            Action::ToggleRuler => "Toggle Ruler",
+            Action::TogglePause => "Pause",
+            Action::StepFrame => "Step Frame",
+            Action::SlowDown => "Slow Down",
+            Action::SpeedUp => "Speed Up",
+            Action::CycleColorVision => "Cycle Color Vision",
+            Action::CycleDiagnostics => "Cycle Diagnostics",
+            Action::TogglePuffinOverlay => "Toggle Profiler",
+            Action::Print => "Print",
+            Action::Screenshot => "Screenshot",
+            Action::ContextMenu => "Context Menu",
+        }
+    }
+}
+
+/// The key or mouse button of a trigger.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+/// A key or mouse button pressed with exactly the modifiers, parsed from e.g. `Ctrl+P`,
+/// `F11`, `Shift+MouseMiddle` or `Ctrl++`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Trigger {
+    pub modifiers: ModifiersState,
+    pub input: Input,
+}
+
+impl Trigger {
+    #[inline]
+    pub fn new(mut modifiers: ModifiersState, input: Input) -> Self {
+        let input = normalize(input);
+        // Shift is how symbols like `+` are typed on many layouts, the character tells.
+        if matches!(&input, Input::Key(Key::Character(c)) if c.to_uppercase() == c.as_str()) {
+            modifiers.remove(ModifiersState::SHIFT);
+        }
+        Self { modifiers, input }
+    }
+
+    #[inline]
+    pub fn key(key: Key) -> Self {
+        Self::new(ModifiersState::empty(), Input::Key(key))
+    }
+}
+
+/// Characters are matched case-insensitively, the shift state is in the modifiers.
+fn normalize(input: Input) -> Input {
+    match input {
+        Input::Key(Key::Character(c)) => Input::Key(Key::Character(c.to_lowercase().into())),
+        input => input,
+    }
+}
+
+const NAMED_KEYS: &[(&str, NamedKey)] = &[
+    ("Escape", NamedKey::Escape),
+    ("Enter", NamedKey::Enter),
+    ("Tab", NamedKey::Tab),
+    ("Space", NamedKey::Space),
+    ("Backspace", NamedKey::Backspace),
+    ("Delete", NamedKey::Delete),
+    ("Insert", NamedKey::Insert),
+    ("Home", NamedKey::Home),
+    ("End", NamedKey::End),
+    ("PageUp", NamedKey::PageUp),
+    ("PageDown", NamedKey::PageDown),
+    ("Up", NamedKey::ArrowUp),
+    ("Down", NamedKey::ArrowDown),
+    ("Left", NamedKey::ArrowLeft),
+    ("Right", NamedKey::ArrowRight),
+    ("PrintScreen", NamedKey::PrintScreen),
+    ("F1", NamedKey::F1),
+    ("F2", NamedKey::F2),
+    ("F3", NamedKey::F3),
+    ("F4", NamedKey::F4),
+    ("F5", NamedKey::F5),
+    ("F6", NamedKey::F6),
+    ("F7", NamedKey::F7),
+    ("F8", NamedKey::F8),
+    ("F9", NamedKey::F9),
+    ("F10", NamedKey::F10),
+    ("F11", NamedKey::F11),
+    ("F12", NamedKey::F12),
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+    ("Mouse4", MouseButton::Back),
+    ("Mouse5", MouseButton::Forward),
+];
+
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // The `+` key itself leaves two empty parts at the end, e.g. `Ctrl++` or `+`.
+        let (parts, name) = match parts.as_slice() {
+            [parts @ .., "", ""] => (parts, "+"),
+            [parts @ .., name] => (parts, *name),
+            [] => unreachable!("Splitting yields a part"),
+        };
+        if name.is_empty() {
+            return Err(format!("Missing key in binding {s}"));
+        }
+
+        let mut modifiers = ModifiersState::empty();
+        for modifier in parts.iter().copied() {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" => ModifiersState::ALT,
+                "super" | "cmd" | "meta" => ModifiersState::SUPER,
+                _ => return Err(format!("Unknown modifier {modifier} in binding {s}")),
+            };
+        }
+
+        let input = if let Some((_, key)) = NAMED_KEYS
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        {
+            Input::Key(Key::Named(*key))
+        } else if let Some((_, button)) = MOUSE_BUTTONS
+            .iter()
+            .find(|(button_name, _)| button_name.eq_ignore_ascii_case(name))
+        {
+            Input::Mouse(*button)
+        } else if name.chars().count() == 1 {
+            Input::Key(Key::Character(name.into()))
+        } else {
+            return Err(format!("Unknown key {name} in binding {s}"));
+        };
+
+        Ok(Trigger::new(modifiers, input))
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.input {
+            Input::Key(Key::Character(c)) => write!(f, "{}", c.to_uppercase()),
+            Input::Key(key) => match NAMED_KEYS
+                .iter()
+                .find(|(_, named)| *key == Key::Named(*named))
+            {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{key:?}"),
+            },
+            Input::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(_, b)| b == button) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{button:?}"),
+            },
+        }
+    }
+}
+
+/// The triggers of the actions, rebindable at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bindings {
+    map: HashMap<Trigger, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+        bindings.bind(
+            Trigger::new(
+                ModifiersState::SUPER,
+                Input::Key(Key::Character("q".into())),
+            ),
+            Action::Quit,
+        );
+        bindings.bind(
+            Trigger::key(Key::Named(NamedKey::F11)),
+            Action::ToggleFullscreen,
+        );
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F3)), Action::ToggleHud);
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F2)), Action::ToggleRuler);
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F5)), Action::TogglePause);
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F6)), Action::StepFrame);
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F7)), Action::SlowDown);
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F8)), Action::SpeedUp);
+        bindings.bind(
+            Trigger::new(ModifiersState::SHIFT, Input::Key(Key::Named(NamedKey::F3))),
+            Action::CycleColorVision,
+        );
+        bindings.bind(
+            Trigger::new(ModifiersState::SHIFT, Input::Key(Key::Named(NamedKey::F2))),
+            Action::CycleDiagnostics,
+        );
+        #[cfg(feature = "profiling")]
+        bindings.bind(
+            Trigger::key(Key::Named(NamedKey::F4)),
+            Action::TogglePuffinOverlay,
+        );
+        bindings.bind(
+            Trigger::new(
+                ModifiersState::CONTROL,
+                Input::Key(Key::Character("p".into())),
+            ),
+            Action::Print,
+        );
+        bindings.bind(Trigger::key(Key::Named(NamedKey::F12)), Action::Screenshot);
+        bindings
+    }
+}
+
+impl Bindings {
+    #[inline]
+    pub fn empty() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Bind the trigger, replacing its previous action.
+    #[inline]
+    pub fn bind(&mut self, trigger: Trigger, action: Action) -> Option<Action> {
+        self.map.insert(trigger, action)
+    }
+
+    #[inline]
+    pub fn unbind(&mut self, trigger: &Trigger) -> Option<Action> {
+        self.map.remove(trigger)
+    }
+
+    pub fn unbind_action(&mut self, action: Action) {
+        self.map.retain(|_, bound| *bound != action);
+    }
+
+    /// Make the trigger the only one of the action.
+    pub fn rebind(&mut self, action: Action, trigger: Trigger) {
+        self.unbind_action(action);
+        self.bind(trigger, action);
+    }
+
+    pub fn triggers(&self, action: Action) -> impl Iterator<Item = &Trigger> {
+        self.map
+            .iter()
+            .filter(move |(_, bound)| **bound == action)
+            .map(|(trigger, _)| trigger)
+    }
+
+    #[inline]
+    pub fn action(&self, modifiers: ModifiersState, input: Input) -> Option<Action> {
+        self.map.get(&Trigger::new(modifiers, input)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(c: &str) -> Input {
+        Input::Key(Key::Character(c.into()))
+    }
+
+    #[test]
+    fn plus_key_parses_alone_and_with_modifiers() {
+        let trigger: Trigger = "Ctrl++".parse().unwrap();
+        assert_eq!(
+            trigger,
+            Trigger::new(ModifiersState::CONTROL, character("+"))
+        );
+        assert_eq!(trigger.to_string(), "Ctrl++");
+        let spaced: Trigger = "Ctrl + +".parse().unwrap();
+        assert_eq!(spaced, trigger);
+        let alone: Trigger = "+".parse().unwrap();
+        assert_eq!(alone, Trigger::new(ModifiersState::empty(), character("+")));
+        assert!("Ctrl+".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn symbols_match_whether_typed_with_shift() {
+        let mut bindings = Bindings::empty();
+        bindings.bind("Ctrl++".parse().unwrap(), Action::SpeedUp);
+        bindings.bind("Shift+P".parse().unwrap(), Action::Print);
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        assert_eq!(
+            bindings.action(ctrl_shift, character("+")),
+            Some(Action::SpeedUp)
+        );
+        assert_eq!(
+            bindings.action(ModifiersState::CONTROL, character("+")),
+            Some(Action::SpeedUp)
+        );
+        assert_eq!(
+            bindings.action(ModifiersState::SHIFT, character("P")),
+            Some(Action::Print)
+        );
+        assert_eq!(
+            bindings.action(ModifiersState::empty(), character("p")),
+            None
+        );
+    }
+}
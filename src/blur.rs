@@ -0,0 +1,20 @@
+use winit::window::Window;
+
+/// Blur the content behind the transparent parts of the window, with the acrylic effect on
+/// Windows and the compositor blur (e.g. the KDE blur hint) elsewhere.
+pub fn set_blur_behind(window: &Window, enabled: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        let result = if enabled {
+            window_vibrancy::apply_acrylic(window, None)
+        } else {
+            window_vibrancy::clear_acrylic(window)
+        };
+        result.map_err(|err| err.to_string())
+    }
+    #[cfg(not(windows))]
+    {
+        window.set_blur(enabled);
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use skia_safe::{Canvas, Color, Paint, PaintCap, PaintStyle};
+
+use crate::{
+    input::{InputEvent, PenState, TouchPhase},
+    renderer::{FrameInfo, Renderer},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrushSample {
+    pub position: (f32, f32),
+    pub pen: PenState,
+}
+
+impl BrushSample {
+    /// The stroke width of the sample, pressure scales the width linearly and tilting the
+    /// stylus widens the stroke like the side of a pencil.
+    pub fn width(&self, base_width: f32) -> f32 {
+        let pressure = self.pen.pressure.unwrap_or(1.);
+        let tilt = self.pen.tilt.map(|tilt| 1. + tilt.sin()).unwrap_or(1.);
+        (base_width * pressure * tilt).max(0.5)
+    }
+}
+
+/// A sample renderer drawing the touch/stylus strokes with variable stroke width.
+pub struct BrushRenderer {
+    color: Color,
+    base_width: f32,
+    strokes: Vec<Vec<BrushSample>>,
+    active: HashMap<u64, Vec<BrushSample>>,
+}
+
+impl Default for BrushRenderer {
+    fn default() -> Self {
+        Self::new(Color::BLACK, 8.)
+    }
+}
+
+impl BrushRenderer {
+    pub fn new(color: Color, base_width: f32) -> Self {
+        Self {
+            color,
+            base_width,
+            strokes: vec![],
+            active: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+        self.active.clear();
+    }
+
+    fn draw_stroke(&self, canvas: &mut Canvas, stroke: &[BrushSample], paint: &mut Paint) {
+        if let [sample] = stroke {
+            paint.set_style(PaintStyle::Fill);
+            canvas.draw_circle(sample.position, sample.width(self.base_width) / 2., paint);
+            return;
+        }
+
+        paint.set_style(PaintStyle::Stroke);
+        for segment in stroke.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            paint.set_stroke_width((from.width(self.base_width) + to.width(self.base_width)) / 2.);
+            canvas.draw_line(from.position, to.position, paint);
+        }
+    }
+}
+
+impl Renderer for BrushRenderer {
+    fn render(&mut self, canvas: &mut Canvas, _: &FrameInfo) {
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_color(self.color);
+        paint.set_stroke_cap(PaintCap::Round);
+
+        for stroke in self.strokes.iter().chain(self.active.values()) {
+            self.draw_stroke(canvas, stroke, &mut paint);
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        if let InputEvent::Touch {
+            id,
+            phase,
+            position,
+            pen,
+        } = *event
+        {
+            let sample = BrushSample { position, pen };
+            match phase {
+                TouchPhase::Started => {
+                    self.active.insert(id, vec![sample]);
+                }
+                TouchPhase::Moved => {
+                    if let Some(stroke) = self.active.get_mut(&id) {
+                        stroke.push(sample);
+                    }
+                }
+                TouchPhase::Ended => {
+                    if let Some(mut stroke) = self.active.remove(&id) {
+                        stroke.push(sample);
+                        self.strokes.push(stroke);
+                    }
+                }
+                TouchPhase::Cancelled => {
+                    self.active.remove(&id);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    f32::consts::{PI, TAU},
+    time::Instant,
+};
+
+use skia_safe::{Matrix, Rect};
+
+use crate::input::{InputEvent, InputState, MouseButton, TouchPhase};
+
+/// Remaining fraction of the momentum velocity after one second.
+const MOMENTUM_FRICTION: f32 = 0.02;
+/// Stop the momentum below this speed, in pixels per second.
+const MOMENTUM_MIN_SPEED: f32 = 5.;
+/// Zoom factor per scrolled pixel when zooming with `Ctrl` + wheel.
+const SCROLL_ZOOM_SPEED: f32 = 0.005;
+/// The rotation snaps to a multiple of `CameraConfig::snap_angle` within this many radians.
+const ROTATION_SNAP_THRESHOLD: f32 = 6. * PI / 180.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraConfig {
+    pub pan: bool,
+    pub zoom: bool,
+    /// Rotate the content with two finger touch gestures.
+    pub rotate: bool,
+    /// The step in radians the rotation snaps to when close, e.g. `PI / 2.` to make a
+    /// straight content easy to get back to, `None` rotates freely.
+    pub snap_angle: Option<f32>,
+    pub momentum: bool,
+    /// The mouse button dragging the content, `None` by default so the button reaches the
+    /// renderer, e.g. to draw with it.
+    pub drag_button: Option<MouseButton>,
+    pub min_scale: f32,
+    pub max_scale: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            pan: true,
+            zoom: true,
+            rotate: false,
+            snap_angle: Some(PI / 2.),
+            momentum: true,
+            drag_button: None,
+            min_scale: 0.1,
+            max_scale: 10.,
+        }
+    }
+}
+
+impl CameraConfig {
+    #[inline]
+    pub fn disabled() -> Self {
+        Self {
+            pan: false,
+            zoom: false,
+            rotate: false,
+            momentum: false,
+            drag_button: None,
+            ..Default::default()
+        }
+    }
+}
+
+/// The pan/zoom/rotate transform of the canvas content, driven by the mouse wheel, trackpad
+/// pinch, mouse drag and two finger touch gestures.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    config: CameraConfig,
+    offset: (f32, f32),
+    scale: f32,
+    /// Clockwise in radians, snapped from `free_rotation`.
+    rotation: f32,
+    /// The rotation of the gestures before snapping.
+    free_rotation: f32,
+    velocity: (f32, f32),
+    drag: Option<Drag>,
+    pinch: Option<Pinch>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    position: (f32, f32),
+    time: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pinch {
+    center: (f32, f32),
+    distance: f32,
+    /// The direction from the first to the second touch, in radians.
+    angle: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(CameraConfig::default())
+    }
+}
+
+impl Camera {
+    pub fn new(config: CameraConfig) -> Self {
+        Self {
+            config,
+            offset: (0., 0.),
+            scale: 1.,
+            rotation: 0.,
+            free_rotation: 0.,
+            velocity: (0., 0.),
+            drag: None,
+            pinch: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &CameraConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn set_config(&mut self, config: CameraConfig) {
+        self.config = config;
+        self.scale = self.scale.clamp(config.min_scale, config.max_scale);
+        if !config.rotate {
+            self.rotation = 0.;
+            self.free_rotation = 0.;
+        }
+        if !config.momentum {
+            self.velocity = (0., 0.);
+        }
+    }
+
+    #[inline]
+    pub fn offset(&self) -> (f32, f32) {
+        self.offset
+    }
+
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// The clockwise rotation in radians.
+    #[inline]
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// The matrix mapping the content space to the window space.
+    #[inline]
+    pub fn matrix(&self) -> Matrix {
+        let mut matrix = Matrix::scale((self.scale, self.scale));
+        matrix.post_rotate(self.rotation.to_degrees(), None);
+        matrix.post_translate(self.offset);
+        matrix
+    }
+
+    /// Map a window space position into the content space.
+    #[inline]
+    pub fn to_content(&self, position: (f32, f32)) -> (f32, f32) {
+        let (x, y) = (position.0 - self.offset.0, position.1 - self.offset.1);
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            (x * cos + y * sin) / self.scale,
+            (y * cos - x * sin) / self.scale,
+        )
+    }
+
+    /// Map a content space position into the window space, the inverse of `to_content`.
+    #[inline]
+    pub fn to_window(&self, position: (f32, f32)) -> (f32, f32) {
+        let (x, y) = (position.0 * self.scale, position.1 * self.scale);
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            x * cos - y * sin + self.offset.0,
+            x * sin + y * cos + self.offset.1,
+        )
+    }
+
+    /// The content space area visible in a window of `size`, the bounds of the window
+    /// corners while rotated.
+    pub fn visible_rect(&self, size: (i32, i32)) -> Rect {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        let corners = [(0., 0.), (width, 0.), (0., height), (width, height)]
+            .map(|corner| self.to_content(corner));
+        let mut rect = Rect::new(f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for (x, y) in corners {
+            rect.left = rect.left.min(x);
+            rect.top = rect.top.min(y);
+            rect.right = rect.right.max(x);
+            rect.bottom = rect.bottom.max(y);
+        }
+        rect
+    }
+
+    pub fn pan(&mut self, delta: (f32, f32)) {
+        if self.config.pan {
+            self.offset.0 += delta.0;
+            self.offset.1 += delta.1;
+        }
+    }
+
+    /// Zoom by `factor` keeping the window space `focus` point fixed.
+    pub fn zoom_at(&mut self, focus: (f32, f32), factor: f32) {
+        if !self.config.zoom {
+            return;
+        }
+        let scale = (self.scale * factor).clamp(self.config.min_scale, self.config.max_scale);
+        let factor = scale / self.scale;
+        self.offset.0 = focus.0 - (focus.0 - self.offset.0) * factor;
+        self.offset.1 = focus.1 - (focus.1 - self.offset.1) * factor;
+        self.scale = scale;
+    }
+
+    /// Rotate clockwise by `angle` radians keeping the window space `focus` point fixed,
+    /// snapping to `CameraConfig::snap_angle`.
+    pub fn rotate_at(&mut self, focus: (f32, f32), angle: f32) {
+        if !self.config.rotate {
+            return;
+        }
+        self.free_rotation = (self.free_rotation + angle).rem_euclid(TAU);
+        let rotation = match self.config.snap_angle {
+            Some(step) if step > 0. => {
+                let snapped = (self.free_rotation / step).round() * step;
+                if (self.free_rotation - snapped).abs() < ROTATION_SNAP_THRESHOLD {
+                    snapped.rem_euclid(TAU)
+                } else {
+                    self.free_rotation
+                }
+            }
+            _ => self.free_rotation,
+        };
+        let (sin, cos) = (rotation - self.rotation).sin_cos();
+        let (x, y) = (focus.0 - self.offset.0, focus.1 - self.offset.1);
+        self.offset = (focus.0 - (x * cos - y * sin), focus.1 - (x * sin + y * cos));
+        self.rotation = rotation;
+    }
+
+    /// Handle the input event, the `input` state should already contain the event.
+    ///
+    /// Returns `true` if the event was consumed by the camera.
+    pub fn handle_input(&mut self, event: &InputEvent, input: &InputState) -> bool {
+        match *event {
+            InputEvent::Scroll { delta } => {
+                if input.modifiers().control_key() {
+                    let focus = input.cursor_position().unwrap_or(self.offset);
+                    self.zoom_at(focus, (delta.1 * SCROLL_ZOOM_SPEED).exp());
+                    self.config.zoom
+                } else {
+                    self.pan(delta);
+                    self.config.pan
+                }
+            }
+            InputEvent::Magnify { delta } => {
+                let focus = input.cursor_position().unwrap_or(self.offset);
+                self.zoom_at(focus, 1. + delta);
+                self.config.zoom
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                if self.config.drag_button != Some(button) || !self.config.pan {
+                    return false;
+                }
+                if pressed {
+                    self.velocity = (0., 0.);
+                    self.drag = input.cursor_position().map(|position| Drag {
+                        position,
+                        time: Instant::now(),
+                    });
+                } else if let Some(drag) = self.drag.take() {
+                    // The pointer rested before release, don't fling.
+                    if drag.time.elapsed().as_secs_f32() > 0.05 {
+                        self.velocity = (0., 0.);
+                    }
+                }
+                true
+            }
+            InputEvent::CursorMoved { position } => {
+                let Some(drag) = self.drag.as_mut() else {
+                    return false;
+                };
+                let delta = (position.0 - drag.position.0, position.1 - drag.position.1);
+                let now = Instant::now();
+                let dt = (now - drag.time).as_secs_f32().max(1e-3);
+                drag.position = position;
+                drag.time = now;
+
+                self.velocity = (
+                    (self.velocity.0 + delta.0 / dt) / 2.,
+                    (self.velocity.1 + delta.1 / dt) / 2.,
+                );
+                self.pan(delta);
+                true
+            }
+            InputEvent::Touch { phase, .. } => {
+                let pinch = Self::pinch(input);
+                if let (Some(previous), Some(current)) = (self.pinch, pinch) {
+                    self.pan((
+                        current.center.0 - previous.center.0,
+                        current.center.1 - previous.center.1,
+                    ));
+                    if previous.distance > 0. {
+                        self.zoom_at(current.center, current.distance / previous.distance);
+                    }
+                    // The shortest turn, the angle wraps around between the events.
+                    let turn = (current.angle - previous.angle + PI).rem_euclid(TAU) - PI;
+                    self.rotate_at(current.center, turn);
+                }
+                // The ends always reach the renderer, so the strokes started before the
+                // pinch are finished.
+                let ends = matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled);
+                let consumed = !ends
+                    && (pinch.is_some() || (self.pinch.is_some() && phase != TouchPhase::Started));
+                self.pinch = pinch;
+                consumed
+            }
+            InputEvent::Modifiers(_) | InputEvent::Key { .. } => false,
+        }
+    }
+
+    /// Whether the momentum still moves the camera, see `update`.
+    #[inline]
+    pub fn is_moving(&self) -> bool {
+        self.velocity != (0., 0.)
+    }
+
+    /// Advance the momentum of the camera by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        if self.drag.is_some() || !self.config.momentum {
+            return;
+        }
+        let speed = (self.velocity.0.powi(2) + self.velocity.1.powi(2)).sqrt();
+        if speed < MOMENTUM_MIN_SPEED {
+            self.velocity = (0., 0.);
+            return;
+        }
+        self.pan((self.velocity.0 * dt, self.velocity.1 * dt));
+        let friction = MOMENTUM_FRICTION.powf(dt);
+        self.velocity = (self.velocity.0 * friction, self.velocity.1 * friction);
+    }
+
+    fn pinch(input: &InputState) -> Option<Pinch> {
+        if input.touch_count() != 2 {
+            return None;
+        }
+        // Ordered by id, the angle flips when the touches swap.
+        let mut touches: Vec<_> = input.touches().collect();
+        touches.sort_by_key(|touch| touch.id);
+        let (a, b) = (touches[0].position, touches[1].position);
+        Some(Pinch {
+            center: ((a.0 + b.0) / 2., (a.1 + b.1) / 2.),
+            distance: ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt(),
+            angle: (b.1 - a.1).atan2(b.0 - a.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    fn rotating_camera() -> Camera {
+        Camera::new(CameraConfig {
+            rotate: true,
+            snap_angle: None,
+            ..Default::default()
+        })
+    }
+
+    /// Panned, zoomed and rotated around other points.
+    fn moved_camera() -> Camera {
+        let mut camera = rotating_camera();
+        camera.pan((30., -12.));
+        camera.zoom_at((100., 50.), 2.5);
+        camera.rotate_at((40., 80.), 0.7);
+        camera
+    }
+
+    fn assert_near(position: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (position.0 - expected.0).abs() < 1e-3 && (position.1 - expected.1).abs() < 1e-3,
+            "{position:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn positions_round_trip_through_the_window_space() {
+        let camera = moved_camera();
+        for position in [(0., 0.), (123.5, -7.25), (-300., 640.)] {
+            assert_near(camera.to_content(camera.to_window(position)), position);
+            assert_near(camera.to_window(camera.to_content(position)), position);
+        }
+    }
+
+    #[test]
+    fn window_mapping_matches_the_matrix() {
+        let camera = moved_camera();
+        for position in [(0., 0.), (123.5, -7.25), (-300., 640.)] {
+            let mapped = camera.matrix().map_point(position);
+            assert_near((mapped.x, mapped.y), camera.to_window(position));
+        }
+    }
+
+    #[test]
+    fn rotated_visible_rect_bounds_the_window_corners() {
+        let mut camera = rotating_camera();
+        camera.rotate_at((0., 0.), PI / 4.);
+        let rect = camera.visible_rect((100, 100));
+        let half_diagonal = 100. * FRAC_1_SQRT_2;
+        assert_near((rect.left, rect.top), (0., -half_diagonal));
+        assert_near(
+            (rect.right, rect.bottom),
+            (2. * half_diagonal, half_diagonal),
+        );
+    }
+
+    #[test]
+    fn quarter_turn_swaps_the_visible_sides() {
+        let mut camera = Camera::new(CameraConfig {
+            rotate: true,
+            ..Default::default()
+        });
+        camera.zoom_at((0., 0.), 2.);
+        // Snapped to the quarter turn.
+        camera.rotate_at((0., 0.), PI / 2. - 0.05);
+        let rect = camera.visible_rect((200, 100));
+        assert_near((rect.left, rect.top), (0., -100.));
+        assert_near((rect.right, rect.bottom), (50., 0.));
+    }
+}
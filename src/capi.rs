@@ -0,0 +1,327 @@
+use std::{
+    ffi::{c_char, c_void, CStr},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use skia_safe::{prelude::NativeAccess, Canvas};
+use winit::{
+    dpi::LogicalSize,
+    event::{Event, WindowEvent},
+    event_loop::{EventLoop, EventLoopBuilder},
+    platform::pump_events::{EventLoopExtPumpEvents, PumpStatus},
+    window::WindowBuilder,
+};
+
+use crate::{
+    backend::Backend,
+    input::{InputEvent, MouseButton},
+    proxy::{BackendEvent, BackendProxy},
+    renderer::{FrameInfo, Renderer},
+    surface::ConfigRequest,
+    window::create_window,
+};
+
+/// Draws a frame on the `SkCanvas*` of the Skia the host was built against, the canvas is
+/// only valid during the call. The size is in pixels. With the default `independent_ui`
+/// feature it is called on the render thread, not on the thread that created the backend.
+pub type SkglRenderCallback = extern "C" fn(
+    canvas: *mut c_void,
+    width: i32,
+    height: i32,
+    frame: usize,
+    user_data: *mut c_void,
+);
+
+/// The opaque handle of the C API declared in `include/skia_gl.h`, a window with its GL
+/// context and the backend driving it. Embedded through the `cdylib` of the crate built
+/// with the `capi` feature, every function is called from the thread that created the
+/// backend, usually the main thread. A panic in an entry point is caught and reported as
+/// a failure, the backend should be destroyed then.
+pub struct SkglBackend {
+    event_loop: EventLoop<BackendEvent>,
+    backend: Backend,
+    callback: Arc<Mutex<Option<RenderCallback>>>,
+    frame: usize,
+}
+
+#[derive(Clone, Copy)]
+struct RenderCallback {
+    callback: SkglRenderCallback,
+    user_data: *mut c_void,
+}
+
+// The host owns the user data and is responsible for its use from the render thread.
+unsafe impl Send for RenderCallback {}
+
+/// Hands the frames to the callback set with `skgl_backend_set_render_callback`.
+struct CallbackRenderer {
+    callback: Arc<Mutex<Option<RenderCallback>>>,
+}
+
+impl Renderer for CallbackRenderer {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        let Some(callback) = self.callback.lock().ok().and_then(|callback| *callback) else {
+            return;
+        };
+        (callback.callback)(
+            canvas.native_mut() as *mut _ as *mut c_void,
+            info.size.0,
+            info.size.1,
+            info.frame,
+            callback.user_data,
+        );
+    }
+}
+
+/// Run the body of an entry point, a panic is reported and returns `failed` instead of
+/// unwinding into the host.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+        eprintln!("A skia_gl call panicked");
+        failed
+    })
+}
+
+/// Run the body with the backend, returns `false` for a null backend or a panic.
+unsafe fn with_backend(backend: *mut SkglBackend, body: impl FnOnce(&mut SkglBackend)) -> bool {
+    let Some(backend) = backend.as_mut() else {
+        return false;
+    };
+    guard(false, || {
+        body(backend);
+        true
+    })
+}
+
+/// Create a window of the logical size with the title, and the backend drawing into it.
+/// Returns null when the window or the GL context could not be created, the error is
+/// printed, or when an event loop was already created in the process.
+///
+/// # Safety
+///
+/// `title` is null or a nul terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_create(
+    width: u32,
+    height: u32,
+    title: *const c_char,
+) -> *mut SkglBackend {
+    let title = if title.is_null() {
+        String::from("rust-skia-gl-window")
+    } else {
+        CStr::from_ptr(title).to_string_lossy().into_owned()
+    };
+    let created = guard(None, || {
+        let event_loop = EventLoopBuilder::<BackendEvent>::with_user_event()
+            .build()
+            .ok()?;
+        let callback = Arc::new(Mutex::new(None));
+        let mut renderer: Box<dyn Renderer> = Box::new(CallbackRenderer {
+            callback: callback.clone(),
+        });
+        renderer.attach_proxy(BackendProxy::new(event_loop.create_proxy()));
+        let window_builder = WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(LogicalSize::new(width, height));
+        let (window, gl_env) =
+            match create_window(&event_loop, window_builder, ConfigRequest::default()) {
+                Ok(created) => created,
+                Err(err) => {
+                    eprintln!("{err}");
+                    return None;
+                }
+            };
+        let backend = Backend::new(window, gl_env, renderer);
+        Some(SkglBackend {
+            event_loop,
+            backend,
+            callback,
+            frame: 0,
+        })
+    });
+    match created {
+        Some(backend) => Box::into_raw(Box::new(backend)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Set the callback drawing the frames, a null callback draws nothing. Returns `false` on
+/// failure.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_set_render_callback(
+    backend: *mut SkglBackend,
+    callback: Option<SkglRenderCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    with_backend(backend, |backend| {
+        *backend
+            .callback
+            .lock()
+            .expect("The render callback lock is poisoned") =
+            callback.map(|callback| RenderCallback {
+                callback,
+                user_data,
+            });
+        backend.backend.request_redraw();
+    })
+}
+
+/// Resize the window to the size in pixels. Returns `false` on failure.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_resize(
+    backend: *mut SkglBackend,
+    width: u32,
+    height: u32,
+) -> bool {
+    with_backend(backend, |backend| {
+        backend.backend.request_inner_size((width, height))
+    })
+}
+
+/// Move the cursor to the position in pixels. Returns `false` on failure.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_send_cursor_moved(
+    backend: *mut SkglBackend,
+    x: f32,
+    y: f32,
+) -> bool {
+    with_backend(backend, |backend| {
+        backend
+            .backend
+            .handle_input(InputEvent::CursorMoved { position: (x, y) })
+    })
+}
+
+/// Press or release the mouse button, 0 is the left one, 1 the right one, 2 the middle one.
+/// Returns `false` on failure.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_send_mouse_button(
+    backend: *mut SkglBackend,
+    button: u32,
+    pressed: bool,
+) -> bool {
+    let button = match button {
+        0 => MouseButton::Left,
+        1 => MouseButton::Right,
+        2 => MouseButton::Middle,
+        other => MouseButton::Other(other as u16),
+    };
+    with_backend(backend, |backend| {
+        backend
+            .backend
+            .handle_input(InputEvent::MouseButton { button, pressed })
+    })
+}
+
+/// Scroll by the delta in pixels. Returns `false` on failure.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_send_scroll(
+    backend: *mut SkglBackend,
+    dx: f32,
+    dy: f32,
+) -> bool {
+    with_backend(backend, |backend| {
+        backend
+            .backend
+            .handle_input(InputEvent::Scroll { delta: (dx, dy) })
+    })
+}
+
+/// Handle the pending window events and draw the due frames, waiting at most the timeout
+/// for an event. Returns `false` once the window was closed or on failure, the backend
+/// should be destroyed then.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_pump(backend: *mut SkglBackend, timeout_ms: u32) -> bool {
+    let Some(SkglBackend {
+        event_loop,
+        backend,
+        frame,
+        ..
+    }) = backend.as_mut()
+    else {
+        return false;
+    };
+    let status = guard(None, || {
+        Some(event_loop.pump_events(
+            Some(Duration::from_millis(timeout_ms as u64)),
+            |event, window_target| {
+                if let Event::UserEvent(event) = event {
+                    match event {
+                        BackendEvent::Redraw(damage) => backend.invalidate(damage),
+                        BackendEvent::User(event) => backend.post_user_event(event),
+                    }
+                } else if let Event::WindowEvent { event, .. } = event {
+                    if let Some(input) = InputEvent::from_window_event(&event) {
+                        backend.handle_input(input);
+                    }
+                    match event {
+                        WindowEvent::CloseRequested => window_target.exit(),
+                        WindowEvent::Occluded(occluded) => backend.notify_occluded(occluded),
+                        WindowEvent::Moved(_) => backend.notify_moved(),
+                        WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                            backend.notify_scale_factor(scale_factor)
+                        }
+                        WindowEvent::Resized(physical_size) => {
+                            backend.notify_resize(physical_size.into())
+                        }
+                        WindowEvent::RedrawRequested => {
+                            *frame += 1;
+                            backend.render(*frame);
+                        }
+                        _ => (),
+                    }
+                }
+                let frame_start = Instant::now();
+                if backend.frame_pacer().start_frame(frame_start)
+                    && backend.wants_frame(frame_start)
+                {
+                    backend.request_redraw();
+                }
+            },
+        ))
+    });
+    matches!(status, Some(PumpStatus::Continue))
+}
+
+/// Stop the backend and close the window.
+///
+/// # Safety
+///
+/// `backend` is null or was returned by `skgl_backend_create` and not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn skgl_backend_destroy(backend: *mut SkglBackend) {
+    if backend.is_null() {
+        return;
+    }
+    let backend = Box::from_raw(backend);
+    guard((), move || {
+        let mut backend = backend;
+        backend.backend.exit();
+    });
+}
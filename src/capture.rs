@@ -0,0 +1,94 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use skia_safe::{
+    CubicResampler, Data, EncodedImageFormat, Image, Paint, Rect, SamplingOptions, Surface,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    Png,
+    /// Quality in `[0, 100]`, `100` encodes with the least error.
+    Jpeg {
+        quality: u32,
+    },
+    /// Quality in `[0, 100]`, `100` is lossless.
+    Webp {
+        quality: u32,
+    },
+}
+
+impl CaptureFormat {
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Jpeg { .. } => "jpg",
+            CaptureFormat::Webp { .. } => "webp",
+        }
+    }
+
+    #[inline]
+    fn encoded_format(&self) -> (EncodedImageFormat, u32) {
+        match *self {
+            CaptureFormat::Png => (EncodedImageFormat::PNG, 100),
+            CaptureFormat::Jpeg { quality } => (EncodedImageFormat::JPEG, quality.min(100)),
+            CaptureFormat::Webp { quality } => (EncodedImageFormat::WEBP, quality.min(100)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureOptions {
+    pub format: CaptureFormat,
+    /// Downscale factor applied before encoding, `1.0` keeps the frame size.
+    pub scale: f32,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            format: CaptureFormat::Png,
+            scale: 1.,
+        }
+    }
+}
+
+/// Encode the image with the capture options, GPU backed images are read back first.
+pub fn encode(image: &Image, options: &CaptureOptions) -> Option<Data> {
+    let mut image = image.to_raster_image(None)?;
+    if options.scale > 0. && options.scale < 1. {
+        image = downscale(&image, options.scale)?;
+    }
+
+    let (format, quality) = options.format.encoded_format();
+    image.encode_to_data_with_quality(format, quality)
+}
+
+/// Encode the image and write it to `path`.
+pub fn save(
+    image: &Image,
+    path: impl AsRef<Path>,
+    options: &CaptureOptions,
+) -> std::io::Result<()> {
+    let data = encode(image, options)
+        .ok_or_else(|| Error::new(ErrorKind::Other, "Could not encode the capture"))?;
+    std::fs::write(path, data.as_bytes())
+}
+
+fn downscale(image: &Image, scale: f32) -> Option<Image> {
+    let width = ((image.width() as f32 * scale).round() as i32).max(1);
+    let height = ((image.height() as f32 * scale).round() as i32).max(1);
+
+    let mut surface = Surface::new_raster_n32_premul((width, height))?;
+    surface.canvas().draw_image_rect_with_sampling_options(
+        image,
+        None,
+        Rect::from_wh(width as f32, height as f32),
+        SamplingOptions::from(CubicResampler::mitchell()),
+        &Paint::default(),
+    );
+    Some(surface.image_snapshot())
+}
@@ -0,0 +1,463 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Instant,
+};
+
+/// Splits the messages into control messages, which are always accepted and received
+/// first, and bulk messages, which are bounded.
+pub trait Prioritized {
+    fn is_control(&self) -> bool;
+
+    /// A queued control message replaced by `self` instead of queueing both, e.g. resizes.
+    fn replaces(&self, _queued: &Self) -> bool {
+        false
+    }
+
+    /// The last queued bulk message replaced by `self` when the queue is full, e.g. cursor
+    /// moves, only the latest position matters to a lagging receiver.
+    fn coalesces(&self, _queued: &Self) -> bool {
+        false
+    }
+}
+
+struct Queues<T> {
+    control: VecDeque<T>,
+    bulk: VecDeque<T>,
+    disconnected: bool,
+    /// The live senders, the receiver is disconnected once they are all dropped.
+    senders: usize,
+}
+
+struct Shared<T> {
+    queues: Mutex<Queues<T>>,
+    bulk_capacity: usize,
+    /// Signaled when a bulk message is received, waking up blocked senders.
+    space: Condvar,
+    /// Signaled when a message is sent, waking up a blocked receiver.
+    available: Condvar,
+}
+
+/// Create a channel holding at most `bulk_capacity` bulk messages, sending a bulk message
+/// blocks while the queue is full.
+pub fn bounded<T: Prioritized>(bulk_capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        queues: Mutex::new(Queues {
+            control: VecDeque::new(),
+            bulk: VecDeque::with_capacity(bulk_capacity),
+            disconnected: false,
+            senders: 1,
+        }),
+        bulk_capacity: bulk_capacity.max(1),
+        space: Condvar::new(),
+        available: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+/// The receiving side was dropped, the message is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The bulk queue is full and the message coalesces with none, it is returned.
+    Full(T),
+    /// The receiving side was dropped, the message is returned.
+    Disconnected(T),
+}
+
+/// Every sender was dropped and no message is left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No message is queued right now.
+    Empty,
+    /// Every sender was dropped and no message is left.
+    Disconnected,
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Prioritized> Sender<T> {
+    /// Queue the message, blocks while the bulk queue is full, see `try_send`.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let mut queues = self.shared.queues.lock().unwrap();
+        if message.is_control() {
+            return self.send_control(queues, message);
+        }
+
+        while queues.bulk.len() >= self.shared.bulk_capacity && !queues.disconnected {
+            queues = self.shared.space.wait(queues).unwrap();
+        }
+        if queues.disconnected {
+            return Err(SendError(message));
+        }
+        queues.bulk.push_back(message);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    /// Queue the message without blocking. A bulk message not fitting the full queue
+    /// replaces the last queued one it coalesces with, or is returned.
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        let mut queues = self.shared.queues.lock().unwrap();
+        if message.is_control() {
+            return self
+                .send_control(queues, message)
+                .map_err(|SendError(message)| TrySendError::Disconnected(message));
+        }
+        if queues.disconnected {
+            return Err(TrySendError::Disconnected(message));
+        }
+        if queues.bulk.len() < self.shared.bulk_capacity {
+            queues.bulk.push_back(message);
+            self.shared.available.notify_one();
+            return Ok(());
+        }
+        match queues.bulk.back_mut() {
+            Some(queued) if message.coalesces(queued) => {
+                *queued = message;
+                Ok(())
+            }
+            _ => Err(TrySendError::Full(message)),
+        }
+    }
+
+    /// Control messages are always accepted.
+    fn send_control(
+        &self,
+        mut queues: MutexGuard<Queues<T>>,
+        message: T,
+    ) -> Result<(), SendError<T>> {
+        if queues.disconnected {
+            return Err(SendError(message));
+        }
+        match queues
+            .control
+            .iter_mut()
+            .find(|queued| message.replaces(queued))
+        {
+            Some(queued) => *queued = message,
+            None => queues.control.push_back(message),
+        }
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    /// The number of queued messages.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let queues = self.shared.queues.lock().unwrap();
+        queues.control.len() + queues.bulk.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.queues.lock().unwrap().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut queues = self.shared.queues.lock().unwrap();
+        queues.senders -= 1;
+        if queues.senders == 0 {
+            self.shared.available.notify_all();
+        }
+    }
+}
+
+/// Sends without ever blocking, e.g. from the event loop. The bulk messages not fitting the
+/// channel are kept in order, coalesced, and handed over by the next sends or `flush` once
+/// the receiver caught up.
+pub struct BufferedSender<T> {
+    sender: Sender<T>,
+    overflow: RefCell<VecDeque<T>>,
+}
+
+impl<T: Prioritized> BufferedSender<T> {
+    #[inline]
+    pub fn new(sender: Sender<T>) -> Self {
+        Self {
+            sender,
+            overflow: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Fails once the receiving side was dropped, with a message that was not received.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        if message.is_control() {
+            return self.sender.send(message);
+        }
+        let mut overflow = self.overflow.borrow_mut();
+        match overflow.back_mut() {
+            Some(queued) if message.coalesces(queued) => *queued = message,
+            _ => overflow.push_back(message),
+        }
+        self.drain(&mut overflow)
+    }
+
+    /// Hand the kept messages over, then the message, blocking while the channel is full,
+    /// e.g. for a request the caller waits for the reply of. Sent with `send` it could stay
+    /// kept with nothing left to flush it while the caller waits.
+    pub fn send_blocking(&self, message: T) -> Result<(), SendError<T>> {
+        let mut overflow = self.overflow.borrow_mut();
+        while let Some(kept) = overflow.pop_front() {
+            self.sender.send(kept)?;
+        }
+        self.sender.send(message)
+    }
+
+    /// Hand the kept messages over as far as the channel takes them.
+    pub fn flush(&self) -> Result<(), SendError<T>> {
+        self.drain(&mut self.overflow.borrow_mut())
+    }
+
+    /// The number of messages kept until the channel takes them.
+    #[inline]
+    pub fn overflow_len(&self) -> usize {
+        self.overflow.borrow().len()
+    }
+
+    fn drain(&self, overflow: &mut VecDeque<T>) -> Result<(), SendError<T>> {
+        while let Some(message) = overflow.pop_front() {
+            match self.sender.try_send(message) {
+                Ok(()) => {}
+                Err(TrySendError::Full(message)) => {
+                    overflow.push_front(message);
+                    break;
+                }
+                Err(TrySendError::Disconnected(message)) => return Err(SendError(message)),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Take the next message without blocking, control messages come first.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut queues = self.shared.queues.lock().unwrap();
+        if let Some(message) = queues.control.pop_front() {
+            return Ok(message);
+        }
+        match queues.bulk.pop_front() {
+            Some(message) => {
+                self.shared.space.notify_one();
+                Ok(message)
+            }
+            None if queues.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Wait for the next message, control messages come first. Fails once every sender was
+    /// dropped and the queued messages were taken.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut queues = self.shared.queues.lock().unwrap();
+        loop {
+            if let Some(message) = queues.control.pop_front() {
+                return Ok(message);
+            }
+            if let Some(message) = queues.bulk.pop_front() {
+                self.shared.space.notify_one();
+                return Ok(message);
+            }
+            if queues.senders == 0 {
+                return Err(RecvError);
+            }
+            queues = self.shared.available.wait(queues).unwrap();
+        }
+    }
+
+    /// Wait until a message is queued, every sender was dropped or the deadline passed,
+    /// without taking a message.
+    pub fn wait_until(&self, deadline: Instant) {
+        let mut queues = self.shared.queues.lock().unwrap();
+        while queues.control.is_empty() && queues.bulk.is_empty() && queues.senders > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return;
+            }
+            queues = self
+                .shared
+                .available
+                .wait_timeout(queues, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.queues.lock().unwrap().disconnected = true;
+        self.shared.space.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Control(u32),
+        Bulk(u32),
+    }
+
+    impl Prioritized for Message {
+        fn is_control(&self) -> bool {
+            matches!(self, Message::Control(_))
+        }
+
+        fn replaces(&self, queued: &Self) -> bool {
+            matches!((self, queued), (Message::Control(_), Message::Control(_)))
+        }
+
+        fn coalesces(&self, queued: &Self) -> bool {
+            matches!((self, queued), (Message::Bulk(0), Message::Bulk(0)))
+        }
+    }
+
+    #[test]
+    fn control_messages_come_first_and_replace() {
+        let (sender, receiver) = bounded(4);
+        sender.send(Message::Bulk(1)).unwrap();
+        sender.send(Message::Control(1)).unwrap();
+        sender.send(Message::Control(2)).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(Message::Control(2)));
+        assert_eq!(receiver.try_recv(), Ok(Message::Bulk(1)));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn receiver_disconnects_once_every_sender_dropped() {
+        let (sender, receiver) = bounded(4);
+        let other = sender.clone();
+        sender.send(Message::Bulk(1)).unwrap();
+        drop(sender);
+        let waiting = std::thread::spawn(move || {
+            let first = receiver.recv();
+            (first, receiver.recv(), receiver.try_recv())
+        });
+        drop(other);
+        assert_eq!(
+            waiting.join().unwrap(),
+            (
+                Ok(Message::Bulk(1)),
+                Err(RecvError),
+                Err(TryRecvError::Disconnected)
+            )
+        );
+    }
+
+    #[test]
+    fn sending_fails_once_the_receiver_dropped() {
+        let (sender, receiver) = bounded(1);
+        drop(receiver);
+        assert_eq!(
+            sender.send(Message::Control(1)),
+            Err(SendError(Message::Control(1)))
+        );
+        assert_eq!(
+            sender.send(Message::Bulk(1)),
+            Err(SendError(Message::Bulk(1)))
+        );
+    }
+
+    #[test]
+    fn try_send_coalesces_with_the_last_message_when_full() {
+        let (sender, receiver) = bounded(2);
+        sender.try_send(Message::Bulk(1)).unwrap();
+        sender.try_send(Message::Bulk(0)).unwrap();
+        assert_eq!(sender.try_send(Message::Bulk(0)), Ok(()));
+        assert_eq!(
+            sender.try_send(Message::Bulk(2)),
+            Err(TrySendError::Full(Message::Bulk(2)))
+        );
+        assert_eq!(sender.try_send(Message::Control(1)), Ok(()));
+        assert_eq!(sender.len(), 3);
+        drop(receiver);
+        assert_eq!(
+            sender.try_send(Message::Bulk(2)),
+            Err(TrySendError::Disconnected(Message::Bulk(2)))
+        );
+    }
+
+    #[test]
+    fn buffered_sender_keeps_the_order_without_blocking() {
+        let (sender, receiver) = bounded(1);
+        let sender = BufferedSender::new(sender);
+        for message in [1, 0, 0, 2, 0] {
+            sender.send(Message::Bulk(message)).unwrap();
+        }
+        assert_eq!(sender.overflow_len(), 3);
+        let mut received = Vec::new();
+        while let Ok(message) = receiver.try_recv() {
+            received.push(message);
+            sender.flush().unwrap();
+        }
+        assert_eq!(
+            received,
+            [1, 0, 2, 0]
+                .map(Message::Bulk)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(sender.overflow_len(), 0);
+    }
+
+    #[test]
+    fn blocking_request_reaches_a_full_channel() {
+        let (sender, receiver) = bounded(1);
+        let sender = BufferedSender::new(sender);
+        for message in [1, 2, 3] {
+            sender.send(Message::Bulk(message)).unwrap();
+        }
+        assert_eq!(sender.overflow_len(), 2);
+        // The receiver answers the request like the render thread a snapshot request.
+        let (reply, replied) = std::sync::mpsc::channel();
+        let render_thread = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            while let Ok(message) = receiver.recv() {
+                if message == Message::Bulk(99) {
+                    reply.send(received).unwrap();
+                    return;
+                }
+                received.push(message);
+            }
+        });
+        sender.send_blocking(Message::Bulk(99)).unwrap();
+        assert_eq!(
+            replied.recv().unwrap(),
+            [1, 2, 3].map(Message::Bulk).into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(sender.overflow_len(), 0);
+        render_thread.join().unwrap();
+    }
+}
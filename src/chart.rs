@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+
+use skia_safe::{Canvas, Color, Paint, PaintStyle, Point, Rect};
+
+use crate::{
+    arena::FrameArena,
+    batch::Batch,
+    text_cache::{TextAppearance, TextCache},
+};
+
+/// The space around the plot area for the tick labels, in canvas units.
+const MARGIN_LEFT: f32 = 52.;
+const MARGIN_BOTTOM: f32 = 34.;
+const MARGIN: f32 = 10.;
+const TICK_LENGTH: f32 = 4.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeriesKind {
+    Line {
+        width: f32,
+    },
+    /// Bars from zero, `width` in data units.
+    Bar {
+        width: f32,
+    },
+    Scatter {
+        radius: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub kind: SeriesKind,
+    pub color: Color,
+    /// Sorted by x for the lines and the bars, the decimation depends on it.
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Series {
+    #[inline]
+    pub fn new(kind: SeriesKind, color: Color, points: Vec<(f32, f32)>) -> Self {
+        Self {
+            kind,
+            color,
+            points,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    pub label: Option<String>,
+    /// The visible range, `None` fits the data. Zooming and panning set it.
+    pub range: Option<(f32, f32)>,
+    /// The approximate number of ticks.
+    pub ticks: usize,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Self {
+            label: None,
+            range: None,
+            ticks: 6,
+        }
+    }
+}
+
+/// Line, bar and scatter plots with axes, ticks and labels. The marks are drawn through a
+/// `Batch` and the labels through the `TextCache`, the points outside the visible range
+/// are skipped and the lines are decimated to a few points per device pixel column, so
+/// series of millions of points plot at frame rate.
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub series: Vec<Series>,
+    pub x_axis: Axis,
+    pub y_axis: Axis,
+    pub text: TextAppearance,
+    pub axis_color: Color,
+    /// The grid lines at the ticks, `None` hides them.
+    pub grid_color: Option<Color>,
+    batch: Batch,
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self {
+            series: vec![],
+            x_axis: Axis::default(),
+            y_axis: Axis::default(),
+            text: TextAppearance::new(11., Color::from_rgb(0x44, 0x44, 0x44)),
+            axis_color: Color::from_rgb(0x44, 0x44, 0x44),
+            grid_color: Some(Color::from_argb(0x20, 0, 0, 0)),
+            batch: Batch::new(),
+        }
+    }
+}
+
+impl Chart {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_series(mut self, series: Series) -> Self {
+        self.series.push(series);
+        self
+    }
+
+    /// The visible x and y ranges, the axis ranges or the data bounds.
+    pub fn ranges(&self) -> ((f32, f32), (f32, f32)) {
+        let points = || self.series.iter().flat_map(|series| series.points.iter());
+        let bounds = |values: &mut dyn Iterator<Item = f32>| {
+            let (min, max) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+            if min > max {
+                (0., 1.)
+            } else if min == max {
+                (min - 0.5, max + 0.5)
+            } else {
+                (min, max)
+            }
+        };
+        let x = self
+            .x_axis
+            .range
+            .unwrap_or_else(|| bounds(&mut points().map(|point| point.0)));
+        let y = self.y_axis.range.unwrap_or_else(|| {
+            // The bars start at zero.
+            let zero = self
+                .series
+                .iter()
+                .any(|series| matches!(series.kind, SeriesKind::Bar { .. }))
+                .then_some(0.);
+            bounds(&mut points().map(|point| point.1).chain(zero))
+        });
+        (x, y)
+    }
+
+    /// The area the series are plotted in when drawn into `rect`.
+    pub fn plot_area(&self, rect: Rect) -> Rect {
+        Rect::new(
+            rect.left + MARGIN_LEFT,
+            rect.top + MARGIN,
+            rect.right - MARGIN,
+            rect.bottom - MARGIN_BOTTOM,
+        )
+    }
+
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas,
+        rect: Rect,
+        text: &mut TextCache,
+        arena: &FrameArena,
+        anti_alias: bool,
+    ) {
+        let area = self.plot_area(rect);
+        if area.is_empty() {
+            return;
+        }
+        let ((x_min, x_max), (y_min, y_max)) = self.ranges();
+        let to_canvas = |x: f32, y: f32| {
+            Point::new(
+                area.left + (x - x_min) / (x_max - x_min) * area.width(),
+                area.bottom - (y - y_min) / (y_max - y_min) * area.height(),
+            )
+        };
+        let x_ticks = nice_ticks(x_min, x_max, self.x_axis.ticks);
+        let y_ticks = nice_ticks(y_min, y_max, self.y_axis.ticks);
+
+        self.batch.clear();
+        if let Some(grid_color) = self.grid_color {
+            for x in &x_ticks {
+                let x = to_canvas(*x, y_min).x;
+                self.batch
+                    .line((x, area.top), (x, area.bottom), 1., grid_color);
+            }
+            for y in &y_ticks {
+                let y = to_canvas(x_min, *y).y;
+                self.batch
+                    .line((area.left, y), (area.right, y), 1., grid_color);
+            }
+        }
+        self.batch.draw(canvas, anti_alias);
+
+        // The device pixels per canvas unit, the lines keep a few points per pixel column.
+        let scale = canvas.local_to_device_as_3x3().scale_x().abs().max(1.);
+        let columns = (area.width() * scale).ceil() as usize;
+        canvas.save();
+        canvas.clip_rect(area, None, anti_alias);
+        self.batch.clear();
+        for series in &self.series {
+            match series.kind {
+                SeriesKind::Line { width } => {
+                    let decimated = decimate(arena, &series.points, (x_min, x_max), columns);
+                    let points = arena
+                        .alloc_slice_fill_iter(decimated.iter().map(|(x, y)| to_canvas(*x, *y)));
+                    self.batch.polyline(points, width, series.color);
+                }
+                SeriesKind::Bar { width } => {
+                    let base = to_canvas(x_min, 0f32.clamp(y_min, y_max)).y;
+                    for (x, y) in visible(&series.points, (x_min - width, x_max + width)) {
+                        let left = to_canvas(x - width / 2., *y);
+                        let right = to_canvas(x + width / 2., *y).x;
+                        let rect = Rect::new(left.x, left.y.min(base), right, left.y.max(base));
+                        self.batch.rect(rect, series.color);
+                    }
+                }
+                SeriesKind::Scatter { radius } => {
+                    // One mark per device pixel, the overdrawn ones aren't visible.
+                    let mut covered = HashSet::new();
+                    for (x, y) in series.points.iter() {
+                        if *x < x_min || *x > x_max || *y < y_min || *y > y_max {
+                            continue;
+                        }
+                        let point = to_canvas(*x, *y);
+                        let pixel = ((point.x * scale) as i32, (point.y * scale) as i32);
+                        if covered.insert(pixel) {
+                            self.batch.circle(point, radius, series.color);
+                        }
+                    }
+                }
+            }
+        }
+        self.batch.draw(canvas, anti_alias);
+        canvas.restore();
+
+        self.draw_axes(
+            canvas,
+            area,
+            text,
+            anti_alias,
+            (&x_ticks[..], &y_ticks[..]),
+            &to_canvas,
+        );
+    }
+
+    fn draw_axes(
+        &mut self,
+        canvas: &mut Canvas,
+        area: Rect,
+        text: &mut TextCache,
+        anti_alias: bool,
+        (x_ticks, y_ticks): (&[f32], &[f32]),
+        to_canvas: &impl Fn(f32, f32) -> Point,
+    ) {
+        let mut paint = Paint::default();
+        paint.set_anti_alias(anti_alias);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_color(self.axis_color);
+        canvas.draw_line((area.left, area.top), (area.left, area.bottom), &paint);
+        canvas.draw_line((area.left, area.bottom), (area.right, area.bottom), &paint);
+
+        let step = |ticks: &[f32]| ticks.get(1).zip(ticks.first()).map(|(b, a)| b - a);
+        let x_decimals = decimals(step(x_ticks));
+        for x in x_ticks {
+            let position = to_canvas(*x, 0.).x;
+            canvas.draw_line(
+                (position, area.bottom),
+                (position, area.bottom + TICK_LENGTH),
+                &paint,
+            );
+            let label = format!("{x:.x_decimals$}");
+            let paragraph = text.paragraph(&label, &self.text, f32::INFINITY);
+            let width = paragraph.max_intrinsic_width();
+            paragraph.paint(
+                canvas,
+                (position - width / 2., area.bottom + TICK_LENGTH + 2.),
+            );
+        }
+        let y_decimals = decimals(step(y_ticks));
+        for y in y_ticks {
+            let position = to_canvas(0., *y).y;
+            canvas.draw_line(
+                (area.left - TICK_LENGTH, position),
+                (area.left, position),
+                &paint,
+            );
+            let label = format!("{y:.y_decimals$}");
+            let paragraph = text.paragraph(&label, &self.text, f32::INFINITY);
+            let (width, height) = (paragraph.max_intrinsic_width(), paragraph.height());
+            paragraph.paint(
+                canvas,
+                (area.left - TICK_LENGTH - 3. - width, position - height / 2.),
+            );
+        }
+
+        if let Some(label) = &self.x_axis.label {
+            let paragraph = text.paragraph(label, &self.text, f32::INFINITY);
+            let width = paragraph.max_intrinsic_width();
+            paragraph.paint(
+                canvas,
+                (
+                    area.center_x() - width / 2.,
+                    area.bottom + MARGIN_BOTTOM - 14.,
+                ),
+            );
+        }
+        if let Some(label) = &self.y_axis.label {
+            let paragraph = text.paragraph(label, &self.text, f32::INFINITY);
+            let width = paragraph.max_intrinsic_width();
+            canvas.save();
+            canvas.translate((area.left - MARGIN_LEFT, area.center_y() + width / 2.));
+            canvas.rotate(-90., None);
+            paragraph.paint(canvas, (0., 0.));
+            canvas.restore();
+        }
+    }
+}
+
+/// About `target` ticks covering the range at 1, 2 or 5 times a power of ten.
+pub fn nice_ticks(min: f32, max: f32, target: usize) -> Vec<f32> {
+    if min.is_nan() || max.is_nan() || max <= min || target == 0 {
+        return vec![];
+    }
+    let rough = (max - min) / target as f32;
+    let magnitude = 10f32.powf(rough.log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(10. * magnitude);
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|tick| tick as f32 * step).collect()
+}
+
+/// The decimals to tell the ticks apart.
+fn decimals(step: Option<f32>) -> usize {
+    step.map_or(0, |step| (-step.log10().floor()).max(0.) as usize)
+}
+
+/// The points sorted by x within the range.
+fn visible(points: &[(f32, f32)], (min, max): (f32, f32)) -> &[(f32, f32)] {
+    let start = points.partition_point(|point| point.0 < min);
+    let end = points.partition_point(|point| point.0 <= max);
+    &points[start..end.max(start)]
+}
+
+/// The points sorted by x within the range, with one neighbour on each side so the line
+/// leaves the plot area, reduced to the first, last, lowest and highest point of each of
+/// the `columns`, which draws the same pixels as the full series. The reduced points are
+/// allocated in the frame arena.
+fn decimate<'a>(
+    arena: &'a FrameArena,
+    points: &'a [(f32, f32)],
+    (min, max): (f32, f32),
+    columns: usize,
+) -> &'a [(f32, f32)] {
+    let start = points
+        .partition_point(|point| point.0 < min)
+        .saturating_sub(1);
+    let end = (points.partition_point(|point| point.0 <= max) + 1).min(points.len());
+    let points = &points[start..end.max(start)];
+    if columns == 0 || points.len() <= columns * 4 {
+        return points;
+    }
+    let column = |x: f32| {
+        (((x - min) / (max - min) * columns as f32).floor() as i64).clamp(-1, columns as i64)
+    };
+    // Four points for each column and the neighbours outside of the range.
+    let decimated = arena.alloc_slice_fill((columns + 2) * 4, (0., 0.));
+    let mut len = 0;
+    let mut bucket = column(points[0].0);
+    let (mut first, mut low, mut high) = (0, 0, 0);
+    for (index, point) in points.iter().enumerate() {
+        let current = column(point.0);
+        if current != bucket {
+            push_bucket(decimated, &mut len, points, [first, low, high, index - 1]);
+            bucket = current;
+            (first, low, high) = (index, index, index);
+        }
+        if point.1 < points[low].1 {
+            low = index;
+        }
+        if point.1 > points[high].1 {
+            high = index;
+        }
+    }
+    push_bucket(
+        decimated,
+        &mut len,
+        points,
+        [first, low, high, points.len() - 1],
+    );
+    &decimated[..len]
+}
+
+fn push_bucket(
+    decimated: &mut [(f32, f32)],
+    len: &mut usize,
+    points: &[(f32, f32)],
+    mut indices: [usize; 4],
+) {
+    indices.sort_unstable();
+    let mut previous = None;
+    for index in indices {
+        if previous != Some(index) {
+            decimated[*len] = points[index];
+            *len += 1;
+            previous = Some(index);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+/// The slowest and the fastest time scales, slow motion only slows the clock down.
+pub const MIN_TIME_SCALE: f32 = 0.1;
+pub const MAX_TIME_SCALE: f32 = 1.;
+
+/// The time scales stepped through by `slower` and `faster`.
+const TIME_SCALE_STEPS: [f32; 4] = [0.1, 0.25, 0.5, 1.];
+
+/// The frame clock the renderers are animated with, through `FrameInfo::frame` and
+/// `FrameInfo::dt`. It can be paused, stepped one frame at a time and slowed down, e.g. to
+/// debug animations, while the window keeps rendering and the camera stays interactive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameClock {
+    paused: bool,
+    /// The frames to advance while paused.
+    pending_steps: usize,
+    time_scale: f32,
+    /// The fractional frame count, slow motion advances it by less than a frame per frame.
+    position: f64,
+    time: Duration,
+    dt: f32,
+    /// The time a single step advances.
+    step_duration: Duration,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            pending_steps: 0,
+            time_scale: 1.,
+            position: 0.,
+            time: Duration::ZERO,
+            dt: 0.,
+            step_duration: Duration::from_secs_f32(1. / 60.),
+        }
+    }
+}
+
+impl FrameClock {
+    /// Advance by the real time elapsed since the previous frame, scaled by the time scale.
+    /// While paused only the pending steps advance it, by the step duration each.
+    pub fn tick(&mut self, elapsed: Duration) {
+        if self.paused {
+            if self.pending_steps == 0 {
+                self.dt = 0.;
+                return;
+            }
+            self.pending_steps -= 1;
+            self.position = self.position.floor() + 1.;
+            self.dt = self.step_duration.as_secs_f32();
+        } else {
+            self.position += f64::from(self.time_scale);
+            self.dt = elapsed.as_secs_f32() * self.time_scale;
+        }
+        self.time += Duration::from_secs_f32(self.dt);
+    }
+
+    /// The frames the clock advanced.
+    #[inline]
+    pub fn frame(&self) -> usize {
+        self.position as usize
+    }
+
+    /// The scaled time the clock advanced.
+    #[inline]
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// The seconds the last tick advanced, zero while paused.
+    #[inline]
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.pending_steps = 0;
+    }
+
+    /// Whether steps are pending, each advancing a frame.
+    #[inline]
+    pub fn is_stepping(&self) -> bool {
+        self.pending_steps > 0
+    }
+
+    /// Pause and advance exactly one frame on the next tick.
+    #[inline]
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    #[inline]
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Clamped to `[MIN_TIME_SCALE, MAX_TIME_SCALE]`.
+    #[inline]
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    }
+
+    /// The time a single step advances, e.g. the frame duration of the pacer.
+    #[inline]
+    pub fn set_step_duration(&mut self, step_duration: Duration) {
+        self.step_duration = step_duration;
+    }
+}
+
+/// The next slower time scale of the steps, `time_scale` if none is slower.
+pub fn slower(time_scale: f32) -> f32 {
+    TIME_SCALE_STEPS
+        .iter()
+        .rev()
+        .copied()
+        .find(|step| *step < time_scale)
+        .unwrap_or(time_scale)
+}
+
+/// The next faster time scale of the steps, `time_scale` if none is faster.
+pub fn faster(time_scale: f32) -> f32 {
+    TIME_SCALE_STEPS
+        .iter()
+        .copied()
+        .find(|step| *step > time_scale)
+        .unwrap_or(time_scale)
+}
@@ -0,0 +1,361 @@
+use skia_safe::Color;
+
+/// A color by hue in degrees, saturation and lightness in `0..=1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+    pub alpha: f32,
+}
+
+/// A color by hue in degrees, saturation and value in `0..=1`, the space of the color
+/// picker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+    pub alpha: f32,
+}
+
+/// A color in the perceptual OkLab space, the equal steps of lightness look equal, which
+/// makes even shades and gradients without the muddy middle of the sRGB mixes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+    pub alpha: f32,
+}
+
+impl Hsl {
+    pub fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self {
+            hue,
+            saturation,
+            lightness,
+            alpha: 1.,
+        }
+    }
+
+    pub fn from_color(color: Color) -> Self {
+        let (r, g, b, alpha) = channels(color);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let lightness = (max + min) / 2.;
+        let delta = max - min;
+        let saturation = if delta == 0. {
+            0.
+        } else {
+            delta / (1. - (2. * lightness - 1.).abs())
+        };
+        Self {
+            hue: hue(r, g, b, max, delta),
+            saturation,
+            lightness,
+            alpha,
+        }
+    }
+
+    pub fn to_color(self) -> Color {
+        let lightness = self.lightness.clamp(0., 1.);
+        let chroma = (1. - (2. * lightness - 1.).abs()) * self.saturation.clamp(0., 1.);
+        from_hue_chroma(self.hue, chroma, lightness - chroma / 2., self.alpha)
+    }
+}
+
+impl Hsv {
+    pub fn new(hue: f32, saturation: f32, value: f32) -> Self {
+        Self {
+            hue,
+            saturation,
+            value,
+            alpha: 1.,
+        }
+    }
+
+    pub fn from_color(color: Color) -> Self {
+        let (r, g, b, alpha) = channels(color);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let delta = max - min;
+        Self {
+            hue: hue(r, g, b, max, delta),
+            saturation: if max == 0. { 0. } else { delta / max },
+            value: max,
+            alpha,
+        }
+    }
+
+    pub fn to_color(self) -> Color {
+        let value = self.value.clamp(0., 1.);
+        let chroma = value * self.saturation.clamp(0., 1.);
+        from_hue_chroma(self.hue, chroma, value - chroma, self.alpha)
+    }
+}
+
+impl Oklab {
+    pub fn from_color(color: Color) -> Self {
+        let (r, g, b, alpha) = channels(color);
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+            alpha,
+        }
+    }
+
+    /// The sRGB color, clipped to the gamut.
+    pub fn to_color(self) -> Color {
+        let l = (self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+        from_channels(from_linear(r), from_linear(g), from_linear(b), self.alpha)
+    }
+
+    /// The chroma, the distance from the gray of the same lightness.
+    #[inline]
+    pub fn chroma(&self) -> f32 {
+        self.a.hypot(self.b)
+    }
+
+    /// The hue angle in degrees.
+    #[inline]
+    pub fn hue(&self) -> f32 {
+        self.b.atan2(self.a).to_degrees().rem_euclid(360.)
+    }
+
+    #[inline]
+    pub fn lerp(&self, other: &Oklab, t: f32) -> Oklab {
+        let value = |a: f32, b: f32| a + (b - a) * t;
+        Oklab {
+            l: value(self.l, other.l),
+            a: value(self.a, other.a),
+            b: value(self.b, other.b),
+            alpha: value(self.alpha, other.alpha),
+        }
+    }
+
+    /// The color rotated around the lightness axis by `degrees`, keeping the lightness and
+    /// the chroma.
+    pub fn rotate_hue(&self, degrees: f32) -> Oklab {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Oklab {
+            a: self.a * cos - self.b * sin,
+            b: self.a * sin + self.b * cos,
+            ..*self
+        }
+    }
+}
+
+/// Mix the colors in OkLab, `t` of `0` is `a`.
+#[inline]
+pub fn mix(a: Color, b: Color, t: f32) -> Color {
+    Oklab::from_color(a)
+        .lerp(&Oklab::from_color(b), t)
+        .to_color()
+}
+
+/// `count` shades of the color evenly spaced in perceived lightness from dark to light,
+/// keeping its hue and chroma, e.g. the tints of a theme accent.
+pub fn shades(color: Color, count: usize) -> Vec<Color> {
+    let base = Oklab::from_color(color);
+    // The room for chroma shrinks towards black and white.
+    let room = |l: f32| 1. - (2. * l - 1.).abs();
+    (0..count)
+        .map(|index| {
+            let l = 0.15 + 0.8 * (index as f32 + 0.5) / count as f32;
+            let scale = (room(l) / room(base.l).max(f32::EPSILON)).min(1.);
+            Oklab {
+                l,
+                a: base.a * scale,
+                b: base.b * scale,
+                ..base
+            }
+            .to_color()
+        })
+        .collect()
+}
+
+/// The color on the opposite side of the hue circle.
+#[inline]
+pub fn complementary(color: Color) -> Color {
+    Oklab::from_color(color).rotate_hue(180.).to_color()
+}
+
+/// The color and its neighbours `spread` degrees apart on the hue circle.
+pub fn analogous(color: Color, spread: f32) -> [Color; 3] {
+    let base = Oklab::from_color(color);
+    [
+        base.rotate_hue(-spread).to_color(),
+        color,
+        base.rotate_hue(spread).to_color(),
+    ]
+}
+
+/// The color and the two colors a third of the hue circle away.
+pub fn triadic(color: Color) -> [Color; 3] {
+    let base = Oklab::from_color(color);
+    [
+        color,
+        base.rotate_hue(120.).to_color(),
+        base.rotate_hue(240.).to_color(),
+    ]
+}
+
+/// The hue in degrees of the normalized channels.
+fn hue(r: f32, g: f32, b: f32, max: f32, delta: f32) -> f32 {
+    if delta == 0. {
+        return 0.;
+    }
+    let sector = if max == r {
+        ((g - b) / delta).rem_euclid(6.)
+    } else if max == g {
+        (b - r) / delta + 2.
+    } else {
+        (r - g) / delta + 4.
+    };
+    sector * 60.
+}
+
+fn from_hue_chroma(hue: f32, chroma: f32, min: f32, alpha: f32) -> Color {
+    let sector = hue.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (sector % 2. - 1.).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    from_channels(r + min, g + min, b + min, alpha)
+}
+
+fn channels(color: Color) -> (f32, f32, f32, f32) {
+    let channel = |value: u8| value as f32 / 255.;
+    (
+        channel(color.r()),
+        channel(color.g()),
+        channel(color.b()),
+        channel(color.a()),
+    )
+}
+
+fn from_channels(r: f32, g: f32, b: f32, alpha: f32) -> Color {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    Color::from_argb(channel(alpha), channel(r), channel(g), channel(b))
+}
+
+/// The sRGB transfer function.
+fn to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> [Color; 6] {
+        [
+            Color::BLACK,
+            Color::WHITE,
+            Color::RED,
+            Color::from_rgb(30, 144, 255),
+            Color::from_rgb(128, 128, 128),
+            Color::from_argb(128, 200, 120, 40),
+        ]
+    }
+
+    /// Whether every channel is within `tolerance` steps.
+    fn close(a: Color, b: Color, tolerance: u8) -> bool {
+        [
+            (a.a(), b.a()),
+            (a.r(), b.r()),
+            (a.g(), b.g()),
+            (a.b(), b.b()),
+        ]
+        .iter()
+        .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        for color in samples() {
+            assert_eq!(Hsl::from_color(color).to_color(), color, "{color:?}");
+            assert_eq!(Hsv::from_color(color).to_color(), color, "{color:?}");
+        }
+        let red = Hsl::from_color(Color::RED);
+        assert_eq!((red.hue, red.saturation, red.lightness), (0., 1., 0.5));
+        assert_eq!(Hsv::new(240., 1., 1.).to_color(), Color::BLUE);
+        assert_eq!(Hsl::new(-120., 1., 0.5).to_color(), Color::BLUE);
+    }
+
+    #[test]
+    fn oklab_round_trips_and_has_gray_without_chroma() {
+        for color in samples() {
+            assert!(
+                close(Oklab::from_color(color).to_color(), color, 1),
+                "{color:?}"
+            );
+        }
+        assert!((Oklab::from_color(Color::WHITE).l - 1.).abs() < 1e-3);
+        assert!(Oklab::from_color(Color::BLACK).l.abs() < 1e-3);
+        assert!(Oklab::from_color(Color::from_rgb(128, 128, 128)).chroma() < 1e-3);
+    }
+
+    #[test]
+    fn mixes_keep_their_ends() {
+        let (a, b) = (Color::RED, Color::from_rgb(30, 144, 255));
+        assert!(close(mix(a, b, 0.), a, 1));
+        assert!(close(mix(a, b, 1.), b, 1));
+        let middle = Oklab::from_color(mix(Color::BLACK, Color::WHITE, 0.5));
+        assert!((middle.l - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn shades_grow_lighter_with_the_hue() {
+        let base = Color::from_rgb(30, 144, 255);
+        let shades = shades(base, 5);
+        assert_eq!(shades.len(), 5);
+        let lightness: Vec<f32> = shades
+            .iter()
+            .map(|shade| Oklab::from_color(*shade).l)
+            .collect();
+        assert!(lightness.windows(2).all(|pair| pair[0] < pair[1]));
+        let hue = Oklab::from_color(base).hue();
+        let middle = Oklab::from_color(shades[2]).hue();
+        assert!((middle - hue).abs() < 5., "{middle} != {hue}");
+    }
+
+    #[test]
+    fn harmonies_rotate_the_hue() {
+        let base = Color::from_rgb(200, 120, 40);
+        assert!(close(complementary(complementary(base)), base, 2));
+        let [left, middle, right] = analogous(base, 30.);
+        assert_eq!(middle, base);
+        let hue = Oklab::from_color(base).hue();
+        let offset = |color: Color| (Oklab::from_color(color).hue() - hue).rem_euclid(360.);
+        assert!((offset(left) - 330.).abs() < 3.);
+        assert!((offset(right) - 30.).abs() < 3.);
+        assert_eq!(triadic(base)[0], base);
+    }
+}
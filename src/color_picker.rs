@@ -0,0 +1,307 @@
+use skia_safe::{gradient_shader, Canvas, Color, Paint, PaintStyle, Point, RRect, Rect, TileMode};
+
+use crate::{
+    color::Hsv,
+    input::{InputEvent, KeyCode, MouseButton},
+    renderer::{FrameInfo, Renderer},
+    theme::Theme,
+};
+
+/// The sizes of the parts in canvas units.
+const HUE_BAR_WIDTH: f32 = 16.;
+const SWATCH_SIZE: f32 = 20.;
+const GAP: f32 = 8.;
+const MARKER_RADIUS: f32 = 6.;
+/// The fraction of the saturation, the value or the hue circle an arrow key moves by.
+const KEY_STEP: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    SaturationValue,
+    Hue,
+}
+
+/// A color picker drawn with Skia, a saturation and value square next to a hue bar, with
+/// the picked color and a row of swatches below, e.g. a palette made with
+/// `color::shades`. The arrows move the saturation and the value, Page Up and Page Down the
+/// hue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorPicker {
+    pub rect: Rect,
+    pub swatches: Vec<Color>,
+    /// Kept in HSV so the hue survives picking a gray.
+    hsv: Hsv,
+    dragging: Option<Part>,
+    cursor: Option<(f32, f32)>,
+    changed: bool,
+}
+
+impl ColorPicker {
+    pub fn new(rect: Rect, color: Color) -> Self {
+        Self {
+            rect,
+            swatches: vec![],
+            hsv: Hsv::from_color(color),
+            dragging: None,
+            cursor: None,
+            changed: false,
+        }
+    }
+
+    #[inline]
+    pub fn with_swatches(mut self, swatches: Vec<Color>) -> Self {
+        self.swatches = swatches;
+        self
+    }
+
+    #[inline]
+    pub fn color(&self) -> Color {
+        self.hsv.to_color()
+    }
+
+    #[inline]
+    pub fn hsv(&self) -> Hsv {
+        self.hsv
+    }
+
+    /// Set the color without reporting a change, the hue is kept for the grays.
+    pub fn set_color(&mut self, color: Color) {
+        let hsv = Hsv::from_color(color);
+        self.hsv = if hsv.saturation == 0. || hsv.value == 0. {
+            Hsv {
+                hue: self.hsv.hue,
+                ..hsv
+            }
+        } else {
+            hsv
+        };
+    }
+
+    /// The color picked by the user since the last call.
+    #[inline]
+    pub fn take_changed(&mut self) -> Option<Color> {
+        std::mem::take(&mut self.changed).then(|| self.color())
+    }
+
+    /// Handle the input event, returns whether the picker consumed it.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                self.cursor = Some(position);
+                match self.dragging {
+                    Some(part) => {
+                        self.drag(part, position);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            } => {
+                let Some(position) = self.cursor else {
+                    return false;
+                };
+                let point = Point::from(position);
+                if self.saturation_value_rect().contains(point) {
+                    self.dragging = Some(Part::SaturationValue);
+                    self.drag(Part::SaturationValue, position);
+                } else if self.hue_rect().contains(point) {
+                    self.dragging = Some(Part::Hue);
+                    self.drag(Part::Hue, position);
+                } else if let Some(index) =
+                    (0..self.swatches.len()).find(|index| self.swatch_rect(*index).contains(point))
+                {
+                    self.set_color(self.swatches[index]);
+                    self.changed = true;
+                } else {
+                    return false;
+                }
+                true
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            } => self.dragging.take().is_some(),
+            _ => false,
+        }
+    }
+
+    /// Handle a key while the picker has the focus, returns whether it consumed the key.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let mut hsv = self.hsv;
+        match code {
+            KeyCode::ArrowLeft => hsv.saturation -= KEY_STEP,
+            KeyCode::ArrowRight => hsv.saturation += KEY_STEP,
+            KeyCode::ArrowDown => hsv.value -= KEY_STEP,
+            KeyCode::ArrowUp => hsv.value += KEY_STEP,
+            KeyCode::PageDown => hsv.hue -= KEY_STEP * 360.,
+            KeyCode::PageUp => hsv.hue += KEY_STEP * 360.,
+            _ => return false,
+        }
+        hsv.saturation = hsv.saturation.clamp(0., 1.);
+        hsv.value = hsv.value.clamp(0., 1.);
+        hsv.hue = hsv.hue.rem_euclid(360.);
+        self.update(hsv);
+        true
+    }
+
+    fn drag(&mut self, part: Part, position: (f32, f32)) {
+        let mut hsv = self.hsv;
+        match part {
+            Part::SaturationValue => {
+                let rect = self.saturation_value_rect();
+                hsv.saturation = fraction(position.0, rect.left, rect.width());
+                hsv.value = 1. - fraction(position.1, rect.top, rect.height());
+            }
+            Part::Hue => {
+                let rect = self.hue_rect();
+                hsv.hue = fraction(position.1, rect.top, rect.height()) * 360.;
+            }
+        }
+        self.update(hsv);
+    }
+
+    fn update(&mut self, hsv: Hsv) {
+        if hsv != self.hsv {
+            self.hsv = hsv;
+            self.changed = true;
+        }
+    }
+
+    fn saturation_value_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.left,
+            self.rect.top,
+            self.rect.right - HUE_BAR_WIDTH - GAP,
+            self.rect.bottom - SWATCH_SIZE - GAP,
+        )
+    }
+
+    fn hue_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.right - HUE_BAR_WIDTH,
+            self.rect.top,
+            self.rect.right,
+            self.rect.bottom - SWATCH_SIZE - GAP,
+        )
+    }
+
+    /// The picked color at the start of the bottom row, twice as wide as a swatch.
+    fn preview_rect(&self) -> Rect {
+        Rect::from_xywh(
+            self.rect.left,
+            self.rect.bottom - SWATCH_SIZE,
+            SWATCH_SIZE * 2.,
+            SWATCH_SIZE,
+        )
+    }
+
+    fn swatch_rect(&self, index: usize) -> Rect {
+        let preview = self.preview_rect();
+        Rect::from_xywh(
+            preview.right + GAP + index as f32 * (SWATCH_SIZE + GAP / 2.),
+            preview.top,
+            SWATCH_SIZE,
+            SWATCH_SIZE,
+        )
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, theme: &Theme, anti_alias: bool) {
+        let colors = &theme.colors;
+        let radius = theme.radii.small;
+        let mut paint = Paint::default();
+        paint.set_anti_alias(anti_alias);
+        let mut outline = paint.clone();
+        outline.set_style(PaintStyle::Stroke);
+        outline.set_color(colors.border);
+
+        // The pure hue whitened to the left and darkened to the bottom.
+        let square = self.saturation_value_rect();
+        let rrect = RRect::new_rect_xy(square, radius, radius);
+        paint.set_color(Hsv::new(self.hsv.hue, 1., 1.).to_color());
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_shader(gradient_shader::linear(
+            ((square.left, square.top), (square.right, square.top)),
+            [Color::WHITE, Color::from_argb(0, 0xff, 0xff, 0xff)].as_ref(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        ));
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_shader(gradient_shader::linear(
+            ((square.left, square.top), (square.left, square.bottom)),
+            [Color::TRANSPARENT, Color::BLACK].as_ref(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        ));
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_shader(None);
+        canvas.draw_rrect(rrect, &outline);
+
+        let hue_bar = self.hue_rect();
+        let hues: Vec<Color> = (0..=6)
+            .map(|sector| Hsv::new(sector as f32 * 60., 1., 1.).to_color())
+            .collect();
+        paint.set_shader(gradient_shader::linear(
+            ((hue_bar.left, hue_bar.top), (hue_bar.left, hue_bar.bottom)),
+            hues.as_slice(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        ));
+        let rrect = RRect::new_rect_xy(hue_bar, radius, radius);
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_shader(None);
+        canvas.draw_rrect(rrect, &outline);
+
+        // The markers are ringed in both black and white to stand out on any color.
+        let mut ring = outline.clone();
+        ring.set_stroke_width(2.);
+        let marker = (
+            square.left + self.hsv.saturation * square.width(),
+            square.top + (1. - self.hsv.value) * square.height(),
+        );
+        ring.set_color(Color::WHITE);
+        canvas.draw_circle(marker, MARKER_RADIUS, &ring);
+        ring.set_color(Color::BLACK);
+        canvas.draw_circle(marker, MARKER_RADIUS + 1.5, &ring);
+        let y = hue_bar.top + self.hsv.hue / 360. * hue_bar.height();
+        let handle = Rect::new(hue_bar.left - 2., y - 2., hue_bar.right + 2., y + 2.);
+        ring.set_color(Color::WHITE);
+        canvas.draw_rect(handle, &ring);
+        ring.set_color(Color::BLACK);
+        canvas.draw_rect(handle.with_outset((1.5, 1.5)), &ring);
+
+        let preview = RRect::new_rect_xy(self.preview_rect(), radius, radius);
+        paint.set_color(self.color());
+        canvas.draw_rrect(preview, &paint);
+        canvas.draw_rrect(preview, &outline);
+        for (index, swatch) in self.swatches.iter().enumerate() {
+            let rrect = RRect::new_rect_xy(self.swatch_rect(index), radius, radius);
+            paint.set_color(*swatch);
+            canvas.draw_rrect(rrect, &paint);
+            canvas.draw_rrect(rrect, &outline);
+        }
+    }
+}
+
+/// The position along the span, clamped to `0..=1`.
+fn fraction(position: f32, start: f32, length: f32) -> f32 {
+    ((position - start) / length.max(f32::EPSILON)).clamp(0., 1.)
+}
+
+impl Renderer for ColorPicker {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        self.draw(canvas, info.theme, info.anti_alias);
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        self.handle(event);
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fmt, str::FromStr};
+
+use skia_safe::{color_filters, ColorFilter};
+
+/// The color vision deficiency simulated over the frame, a debug aid to check palettes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    /// No red cones.
+    Protanopia,
+    /// No green cones.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [
+        ColorVision::Normal,
+        ColorVision::Protanopia,
+        ColorVision::Deuteranopia,
+        ColorVision::Tritanopia,
+    ];
+
+    /// The next simulation, cycling back to normal vision.
+    #[inline]
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|vision| *vision == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The full severity matrices of Machado et al. 2009, applied to the RGB channels.
+    fn matrix(self) -> Option<[[f32; 3]; 3]> {
+        match self {
+            ColorVision::Normal => None,
+            ColorVision::Protanopia => Some([
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ]),
+            ColorVision::Deuteranopia => Some([
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ]),
+            ColorVision::Tritanopia => Some([
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ]),
+        }
+    }
+
+    /// The filter to draw the frame with, `None` for normal vision.
+    pub fn color_filter(self) -> Option<ColorFilter> {
+        let [r, g, b] = self.matrix()?;
+        #[rustfmt::skip]
+        let matrix = [
+            r[0], r[1], r[2], 0., 0.,
+            g[0], g[1], g[2], 0., 0.,
+            b[0], b[1], b[2], 0., 0.,
+            0.,   0.,   0.,   1., 0.,
+        ];
+        Some(color_filters::matrix_row_major(&matrix))
+    }
+}
+
+impl FromStr for ColorVision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|vision| vision.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown color vision `{s}`"))
+    }
+}
+
+impl fmt::Display for ColorVision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ColorVision::Normal => "normal",
+            ColorVision::Protanopia => "protanopia",
+            ColorVision::Deuteranopia => "deuteranopia",
+            ColorVision::Tritanopia => "tritanopia",
+        })
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::bindings::{Action, Bindings};
+
+/// The config file read when no `--config` is given, it is optional.
+pub const DEFAULT_CONFIG_PATH: &str = "skia_gl.toml";
+
+/// The user configuration, e.g.
+///
+/// ```toml
+/// [bindings]
+/// "Ctrl+S" = "screenshot"
+/// "Mouse4" = "toggle_hud"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Trigger to action, replacing the default triggers of the bound actions.
+    pub bindings: BTreeMap<String, Action>,
+}
+
+impl Config {
+    /// Read and validate the config file.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let config: Config =
+            toml::from_str(&text).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        config
+            .bindings()
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        Ok(config)
+    }
+
+    /// The default bindings with the configured ones applied.
+    pub fn bindings(&self) -> Result<Bindings, String> {
+        let mut bindings = Bindings::default();
+        for action in self.bindings.values() {
+            bindings.unbind_action(*action);
+        }
+        for (trigger, action) in self.bindings.iter() {
+            bindings.bind(trigger.parse()?, *action);
+        }
+        Ok(bindings)
+    }
+}
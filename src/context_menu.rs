@@ -0,0 +1,318 @@
+use skia_safe::{Canvas, Font, Paint, PaintStyle, Point, RRect, Rect};
+
+use crate::{
+    bindings::Action,
+    input::{InputEvent, KeyCode, MouseButton, TouchPhase},
+    theme::Theme,
+};
+
+/// The font size of the menu in logical pixels.
+const FONT_SIZE: f32 = 13.;
+/// The paddings and heights in logical pixels.
+const ITEM_HEIGHT: f32 = 24.;
+const SEPARATOR_HEIGHT: f32 = 9.;
+const PADDING: f32 = 4.;
+const TEXT_PADDING: f32 = 12.;
+const SHORTCUT_GAP: f32 = 32.;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuEntry {
+    Item {
+        label: String,
+        /// The trigger of the action shown on the right, e.g. `Ctrl+P`.
+        shortcut: Option<String>,
+        action: Action,
+        enabled: bool,
+    },
+    Separator,
+}
+
+impl MenuEntry {
+    #[inline]
+    pub fn item(label: impl Into<String>, action: Action) -> Self {
+        Self::Item {
+            label: label.into(),
+            shortcut: None,
+            action,
+            enabled: true,
+        }
+    }
+
+    #[inline]
+    pub fn with_shortcut(mut self, trigger: impl Into<String>) -> Self {
+        if let Self::Item { shortcut, .. } = &mut self {
+            *shortcut = Some(trigger.into());
+        }
+        self
+    }
+
+    #[inline]
+    pub fn with_enabled(mut self, is_enabled: bool) -> Self {
+        if let Self::Item { enabled, .. } = &mut self {
+            *enabled = is_enabled;
+        }
+        self
+    }
+
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        matches!(self, Self::Item { enabled: true, .. })
+    }
+}
+
+/// What the menu did with an input event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuResponse {
+    /// The menu stays open, the event was consumed.
+    Open,
+    /// The menu was dismissed, e.g. by a press outside of it or Escape.
+    Closed,
+    Chosen(Action),
+}
+
+/// A context menu drawn with Skia on top of the frame, opened at a position in window
+/// pixels and moved to stay inside the window. It is navigated with the mouse or the
+/// arrows, Enter and Escape, the chosen entry triggers its action like a binding does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextMenu {
+    entries: Vec<MenuEntry>,
+    rect: Rect,
+    /// The top of each entry relative to the menu.
+    tops: Vec<f32>,
+    scale_factor: f32,
+    hovered: Option<usize>,
+}
+
+impl ContextMenu {
+    pub fn new(
+        entries: Vec<MenuEntry>,
+        position: (f32, f32),
+        window_size: (f32, f32),
+        scale_factor: f32,
+    ) -> Self {
+        let font = menu_font(scale_factor);
+        let measure = |text: &str| font.measure_str(text, None).0;
+        let content_width = entries
+            .iter()
+            .map(|entry| match entry {
+                MenuEntry::Item {
+                    label, shortcut, ..
+                } => {
+                    measure(label)
+                        + shortcut.as_deref().map_or(0., |shortcut| {
+                            SHORTCUT_GAP * scale_factor + measure(shortcut)
+                        })
+                }
+                MenuEntry::Separator => 0.,
+            })
+            .fold(0., f32::max);
+        let mut tops = Vec::with_capacity(entries.len());
+        let mut height = PADDING * scale_factor;
+        for entry in &entries {
+            tops.push(height);
+            height += scale_factor
+                * match entry {
+                    MenuEntry::Item { .. } => ITEM_HEIGHT,
+                    MenuEntry::Separator => SEPARATOR_HEIGHT,
+                };
+        }
+        height += PADDING * scale_factor;
+        let width = content_width + 2. * TEXT_PADDING * scale_factor;
+
+        // Open towards the window center when there is no room below or right.
+        let mut left = position.0;
+        if left + width > window_size.0 {
+            left -= width;
+        }
+        let mut top = position.1;
+        if top + height > window_size.1 {
+            top -= height;
+        }
+        let left = left.min(window_size.0 - width).max(0.);
+        let top = top.min(window_size.1 - height).max(0.);
+        Self {
+            entries,
+            rect: Rect::from_xywh(left, top, width, height),
+            tops,
+            scale_factor,
+            hovered: None,
+        }
+    }
+
+    #[inline]
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[MenuEntry] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// The enabled entry under the position in window pixels.
+    pub fn entry_at(&self, position: (f32, f32)) -> Option<usize> {
+        if !self.rect.contains(Point::from(position)) {
+            return None;
+        }
+        let y = position.1 - self.rect.top;
+        let index = self.tops.iter().rposition(|top| *top <= y)?;
+        self.entries[index].is_enabled().then_some(index)
+    }
+
+    pub fn handle(&mut self, event: &InputEvent) -> MenuResponse {
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                self.hovered = self.entry_at(position);
+                MenuResponse::Open
+            }
+            // Chosen on release like native menus, a press outside dismisses the menu.
+            InputEvent::MouseButton { pressed: true, .. } => match self.hovered {
+                Some(_) => MenuResponse::Open,
+                None => MenuResponse::Closed,
+            },
+            InputEvent::Touch {
+                phase, position, ..
+            } => {
+                self.hovered = self.entry_at(position);
+                match (phase, self.hovered) {
+                    (TouchPhase::Ended, Some(index)) => self.choose(index),
+                    (TouchPhase::Started, None) if !self.rect.contains(Point::from(position)) => {
+                        MenuResponse::Closed
+                    }
+                    _ => MenuResponse::Open,
+                }
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left | MouseButton::Right,
+                pressed: false,
+            } => match self.hovered {
+                Some(index) => self.choose(index),
+                None => MenuResponse::Open,
+            },
+            InputEvent::Key {
+                code,
+                pressed: true,
+            } => match code {
+                KeyCode::Escape => MenuResponse::Closed,
+                KeyCode::ArrowDown => {
+                    self.hovered = self.step(1);
+                    MenuResponse::Open
+                }
+                KeyCode::ArrowUp => {
+                    self.hovered = self.step(-1);
+                    MenuResponse::Open
+                }
+                KeyCode::Enter | KeyCode::NumpadEnter | KeyCode::Space => match self.hovered {
+                    Some(index) => self.choose(index),
+                    None => MenuResponse::Open,
+                },
+                _ => MenuResponse::Open,
+            },
+            _ => MenuResponse::Open,
+        }
+    }
+
+    fn choose(&self, index: usize) -> MenuResponse {
+        match &self.entries[index] {
+            MenuEntry::Item {
+                action,
+                enabled: true,
+                ..
+            } => MenuResponse::Chosen(*action),
+            _ => MenuResponse::Open,
+        }
+    }
+
+    /// The next enabled entry in the direction, wrapping around.
+    fn step(&self, direction: isize) -> Option<usize> {
+        let count = self.entries.len() as isize;
+        let start = self
+            .hovered
+            .map_or(if direction > 0 { -1 } else { count }, |index| {
+                index as isize
+            });
+        (1..=count)
+            .map(|offset| (start + direction * offset).rem_euclid(count) as usize)
+            .find(|index| self.entries[*index].is_enabled())
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, theme: &Theme, anti_alias: bool) {
+        let colors = &theme.colors;
+        let scale = self.scale_factor;
+        let radius = theme.radii.small * scale;
+        let rrect = RRect::new_rect_xy(self.rect, radius, radius);
+        let mut paint = Paint::default();
+        paint.set_anti_alias(anti_alias);
+        paint.set_color(colors.surface);
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_color(colors.border);
+        canvas.draw_rrect(rrect, &paint);
+        paint.set_style(PaintStyle::Fill);
+
+        let font = menu_font(scale);
+        let (_, metrics) = font.metrics();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let top = self.rect.top + self.tops[index];
+            match entry {
+                MenuEntry::Item {
+                    label,
+                    shortcut,
+                    enabled,
+                    ..
+                } => {
+                    let row = Rect::from_xywh(
+                        self.rect.left + PADDING * scale,
+                        top,
+                        self.rect.width() - 2. * PADDING * scale,
+                        ITEM_HEIGHT * scale,
+                    );
+                    let hovered = self.hovered == Some(index);
+                    if hovered {
+                        paint.set_color(colors.accent);
+                        canvas.draw_rrect(RRect::new_rect_xy(row, radius, radius), &paint);
+                    }
+                    paint.set_color(if !enabled {
+                        colors.text_disabled
+                    } else if hovered {
+                        colors.on_accent
+                    } else {
+                        colors.text
+                    });
+                    let baseline = row.center_y() - (metrics.ascent + metrics.descent) / 2.;
+                    let left = self.rect.left + TEXT_PADDING * scale;
+                    canvas.draw_str(label, (left, baseline), &font, &paint);
+                    if let Some(shortcut) = shortcut {
+                        let width = font.measure_str(shortcut, None).0;
+                        let right = self.rect.right - TEXT_PADDING * scale;
+                        canvas.draw_str(shortcut, (right - width, baseline), &font, &paint);
+                    }
+                }
+                MenuEntry::Separator => {
+                    let y = top + SEPARATOR_HEIGHT * scale / 2.;
+                    paint.set_color(colors.border);
+                    canvas.draw_rect(
+                        Rect::from_xywh(
+                            self.rect.left + PADDING * scale,
+                            y.floor(),
+                            self.rect.width() - 2. * PADDING * scale,
+                            scale.max(1.).floor(),
+                        ),
+                        &paint,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn menu_font(scale_factor: f32) -> Font {
+    Font::default()
+        .with_size(FONT_SIZE * scale_factor)
+        .expect("Could not create font")
+}
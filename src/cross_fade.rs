@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::{Canvas, Image, Paint, Rect};
+
+use crate::invalidation::Invalidator;
+
+/// A change of the whole window content that can be cross-faded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlobalChange {
+    /// A theme switch, also when following the system theme.
+    Theme,
+    Language,
+    /// The application replaced what the renderer shows.
+    Scene,
+}
+
+/// Which global changes cross-fade the window content, and for how long.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossFadeConfig {
+    pub duration: Duration,
+    pub changes: Vec<GlobalChange>,
+}
+
+impl CrossFadeConfig {
+    #[inline]
+    pub fn new(duration: Duration, changes: impl Into<Vec<GlobalChange>>) -> Self {
+        Self {
+            duration,
+            changes: changes.into(),
+        }
+    }
+
+    #[inline]
+    pub fn fades(&self, change: GlobalChange) -> bool {
+        !self.duration.is_zero() && self.changes.contains(&change)
+    }
+}
+
+#[derive(Debug)]
+struct Fade {
+    image: Image,
+    start: Instant,
+    duration: Duration,
+}
+
+/// Fades from a snapshot of the previous content to the new one, composited over every
+/// frame until the fade ends. The snapshot covers the whole target surface, it is drawn in
+/// device space.
+#[derive(Debug, Default)]
+pub struct CrossFade {
+    fade: Option<Fade>,
+    invalidator: Option<Invalidator>,
+}
+
+impl CrossFade {
+    /// Fade from `image` over `duration`, the frames are requested from the invalidator
+    /// while fading.
+    pub fn start(&mut self, image: Image, duration: Duration, invalidator: Invalidator) {
+        invalidator.invalidate();
+        self.fade = Some(Fade {
+            image,
+            start: Instant::now(),
+            duration,
+        });
+        self.invalidator = Some(invalidator);
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Drop the snapshot, e.g. when the GPU context it lives in goes away.
+    #[inline]
+    pub fn cancel(&mut self) {
+        self.fade = None;
+    }
+
+    /// Draw the previous content over the frame at its remaining opacity, with the canvas
+    /// in device space.
+    pub fn draw(&mut self, canvas: &mut Canvas, now: Instant) {
+        let Some(fade) = &self.fade else {
+            return;
+        };
+        let t = now.saturating_duration_since(fade.start).as_secs_f32()
+            / fade.duration.as_secs_f32().max(f32::EPSILON);
+        if t >= 1. {
+            self.fade = None;
+            return;
+        }
+        // Smoothstep, the change doesn't pop at the start nor at the end.
+        let opacity = 1. - t * t * (3. - 2. * t);
+        let mut paint = Paint::default();
+        paint.set_alpha_f(opacity);
+        let size = canvas.base_layer_size();
+        canvas.draw_image_rect(
+            &fade.image,
+            None,
+            Rect::from_wh(size.width as f32, size.height as f32),
+            &paint,
+        );
+        if let Some(invalidator) = &self.invalidator {
+            invalidator.invalidate();
+        }
+    }
+}
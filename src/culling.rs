@@ -0,0 +1,252 @@
+use skia_safe::Rect;
+
+/// The items a node holds before it splits into quadrants.
+const NODE_CAPACITY: usize = 8;
+/// Deeper nodes keep their items instead of splitting, e.g. when many items overlap.
+const MAX_DEPTH: usize = 8;
+
+/// Whether the rects overlap, touching edges included so lines and points, whose bounds
+/// are empty, are not culled.
+#[inline]
+pub fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.left <= b.right && b.left <= a.right && a.top <= b.bottom && b.top <= a.bottom
+}
+
+#[inline]
+fn encloses(outer: &Rect, inner: &Rect) -> bool {
+    outer.left <= inner.left
+        && outer.top <= inner.top
+        && inner.right <= outer.right
+        && inner.bottom <= outer.bottom
+}
+
+/// The items whose bounds overlap the visible rect, e.g. `FrameInfo::visible_rect`, for a
+/// few hundred items, a `QuadTree` scales to large scenes.
+pub fn visible<'a, T>(
+    items: &'a [T],
+    visible: Rect,
+    bounds: impl Fn(&T) -> Rect + 'a,
+) -> impl Iterator<Item = &'a T> + 'a {
+    items
+        .iter()
+        .filter(move |item| overlaps(&bounds(item), &visible))
+}
+
+struct Node<T> {
+    bounds: Rect,
+    depth: usize,
+    /// The items not fitting a single quadrant, or all of them before the split.
+    items: Vec<(Rect, T)>,
+    children: Option<Box<[Node<T>; 4]>>,
+}
+
+impl<T> Node<T> {
+    fn new(bounds: Rect, depth: usize) -> Self {
+        Self {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn insert(&mut self, rect: Rect, item: T) {
+        if let Some(children) = self.children.as_mut() {
+            if let Some(child) = children
+                .iter_mut()
+                .find(|child| encloses(&child.bounds, &rect))
+            {
+                child.insert(rect, item);
+                return;
+            }
+        }
+        self.items.push((rect, item));
+        if self.children.is_none() && self.items.len() > NODE_CAPACITY && self.depth < MAX_DEPTH {
+            self.split();
+        }
+    }
+
+    fn split(&mut self) {
+        let Rect {
+            left,
+            top,
+            right,
+            bottom,
+        } = self.bounds;
+        let (x, y) = (self.bounds.center_x(), self.bounds.center_y());
+        let depth = self.depth + 1;
+        self.children = Some(Box::new([
+            Node::new(Rect::new(left, top, x, y), depth),
+            Node::new(Rect::new(x, top, right, y), depth),
+            Node::new(Rect::new(left, y, x, bottom), depth),
+            Node::new(Rect::new(x, y, right, bottom), depth),
+        ]));
+        for (rect, item) in std::mem::take(&mut self.items) {
+            self.insert(rect, item);
+        }
+    }
+
+    fn query<'a>(&'a self, area: &Rect, f: &mut impl FnMut(&Rect, &'a T)) {
+        for (rect, item) in &self.items {
+            if overlaps(rect, area) {
+                f(rect, item);
+            }
+        }
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                if overlaps(&child.bounds, area) {
+                    child.query(area, f);
+                }
+            }
+        }
+    }
+}
+
+/// A spatial index of the items of a scene by their bounds in content space, queried with
+/// the visible rect so the renderer only draws the items on screen. Rebuild it when the
+/// items move, the items outside the bounds of the tree are kept at the root and tested
+/// on every query.
+pub struct QuadTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+impl<T> QuadTree<T> {
+    /// An empty tree over the bounds of the scene.
+    #[inline]
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            root: Node::new(bounds, 0),
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        self.root.bounds
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, rect: Rect, item: T) {
+        self.root.insert(rect, item);
+        self.len += 1;
+    }
+
+    /// Remove every item, keeping the bounds.
+    pub fn clear(&mut self) {
+        self.root = Node::new(self.root.bounds, 0);
+        self.len = 0;
+    }
+
+    /// Call `f` with the bounds of every item overlapping the area, in no particular order.
+    pub fn for_each_in<'a>(&'a self, area: Rect, mut f: impl FnMut(&Rect, &'a T)) {
+        self.root.query(&area, &mut f);
+    }
+
+    /// The items overlapping the area, in no particular order.
+    pub fn query(&self, area: Rect) -> Vec<&T> {
+        let mut items = Vec::new();
+        self.for_each_in(area, |_, item| items.push(item));
+        items
+    }
+}
+
+impl<T> FromIterator<(Rect, T)> for QuadTree<T> {
+    /// A tree over the union of the bounds of the items.
+    fn from_iter<I: IntoIterator<Item = (Rect, T)>>(iter: I) -> Self {
+        let items: Vec<_> = iter.into_iter().collect();
+        let bounds = items
+            .iter()
+            .map(|(rect, _)| *rect)
+            .reduce(|a, b| {
+                Rect::new(
+                    a.left.min(b.left),
+                    a.top.min(b.top),
+                    a.right.max(b.right),
+                    a.bottom.max(b.bottom),
+                )
+            })
+            .unwrap_or_default();
+        let mut tree = QuadTree::new(bounds);
+        for (rect, item) in items {
+            tree.insert(rect, item);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(count: usize) -> Vec<Rect> {
+        (0..count * count)
+            .map(|index| {
+                let (x, y) = ((index % count) as f32 * 10., (index / count) as f32 * 10.);
+                Rect::from_xywh(x, y, 8., 8.)
+            })
+            .collect()
+    }
+
+    fn sorted(mut items: Vec<&usize>) -> Vec<usize> {
+        items.sort();
+        items.into_iter().copied().collect()
+    }
+
+    #[test]
+    fn overlap_includes_touching_edges_and_empty_bounds() {
+        let visible = Rect::from_xywh(0., 0., 10., 10.);
+        assert!(overlaps(&Rect::from_xywh(10., 0., 5., 5.), &visible));
+        assert!(overlaps(&Rect::from_xywh(5., 5., 0., 0.), &visible));
+        assert!(!overlaps(&Rect::from_xywh(10.5, 0., 5., 5.), &visible));
+    }
+
+    #[test]
+    fn tree_query_matches_the_linear_scan() {
+        let rects = grid(20);
+        let tree: QuadTree<usize> = rects.iter().copied().zip(0..).collect();
+        assert_eq!(tree.len(), rects.len());
+        for area in [
+            Rect::from_xywh(0., 0., 30., 30.),
+            Rect::from_xywh(55., 95., 40., 12.),
+            Rect::from_xywh(-10., -10., 5., 5.),
+            tree.bounds(),
+        ] {
+            let expected: Vec<usize> = visible(&rects, area, |rect| *rect)
+                .map(|rect| rects.iter().position(|other| other == rect).unwrap())
+                .collect();
+            assert_eq!(sorted(tree.query(area)), expected, "{area:?}");
+        }
+    }
+
+    #[test]
+    fn items_outside_the_bounds_are_still_found() {
+        let mut tree = QuadTree::new(Rect::from_wh(100., 100.));
+        for index in 0..(NODE_CAPACITY * 4) {
+            tree.insert(Rect::from_xywh(index as f32, 10., 1., 1.), index);
+        }
+        tree.insert(Rect::from_xywh(500., 500., 10., 10.), 1000);
+        assert_eq!(tree.query(Rect::from_xywh(505., 505., 1., 1.)), vec![&1000]);
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(tree.query(tree.bounds()).is_empty());
+    }
+
+    #[test]
+    fn overlapping_items_stop_splitting_at_the_max_depth() {
+        let mut tree = QuadTree::new(Rect::from_wh(100., 100.));
+        for index in 0..1000 {
+            tree.insert(Rect::from_xywh(50., 50., 0., 0.), index);
+        }
+        assert_eq!(tree.query(Rect::from_xywh(49., 49., 2., 2.)).len(), 1000);
+    }
+}
@@ -0,0 +1,117 @@
+use skia_safe::{Point, Rect};
+use winit::window::{CursorIcon, ResizeDirection};
+
+/// What a point of an undecorated window stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitTest {
+    /// The content, the input goes to the renderer.
+    Client,
+    /// A title region, dragging it moves the window.
+    Caption,
+    /// A resize edge, dragging it resizes the window.
+    Resize(ResizeDirection),
+}
+
+impl HitTest {
+    /// The cursor shown over the region.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        match self {
+            HitTest::Client | HitTest::Caption => CursorIcon::Default,
+            HitTest::Resize(direction) => match direction {
+                ResizeDirection::East => CursorIcon::EResize,
+                ResizeDirection::North => CursorIcon::NResize,
+                ResizeDirection::NorthEast => CursorIcon::NeResize,
+                ResizeDirection::NorthWest => CursorIcon::NwResize,
+                ResizeDirection::South => CursorIcon::SResize,
+                ResizeDirection::SouthEast => CursorIcon::SeResize,
+                ResizeDirection::SouthWest => CursorIcon::SwResize,
+                ResizeDirection::West => CursorIcon::WResize,
+            },
+        }
+    }
+}
+
+pub type HitTestFn = Box<dyn Fn((f32, f32), (f32, f32)) -> HitTest + Send>;
+
+/// The drag and resize regions of a window drawing its own decorations, in window pixels.
+#[derive(Default)]
+pub struct DragRegions {
+    /// The draggable title regions.
+    pub captions: Vec<Rect>,
+    /// The regions inside the captions still going to the renderer, e.g. the buttons.
+    pub exclusions: Vec<Rect>,
+    /// The width of the resize edges along the window border, `0` disables resizing.
+    pub border: f32,
+    /// Decides instead of the rects when set, called with the position and the window size.
+    pub hit_test: Option<HitTestFn>,
+}
+
+impl DragRegions {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_caption(mut self, rect: Rect) -> Self {
+        self.captions.push(rect);
+        self
+    }
+
+    #[inline]
+    pub fn with_exclusion(mut self, rect: Rect) -> Self {
+        self.exclusions.push(rect);
+        self
+    }
+
+    #[inline]
+    pub fn with_border(mut self, border: f32) -> Self {
+        self.border = border;
+        self
+    }
+
+    #[inline]
+    pub fn with_hit_test(
+        mut self,
+        hit_test: impl Fn((f32, f32), (f32, f32)) -> HitTest + Send + 'static,
+    ) -> Self {
+        self.hit_test = Some(Box::new(hit_test));
+        self
+    }
+
+    pub fn hit_test(&self, position: (f32, f32), size: (f32, f32)) -> HitTest {
+        if let Some(hit_test) = &self.hit_test {
+            return hit_test(position, size);
+        }
+
+        if self.border > 0. {
+            let west = position.0 < self.border;
+            let east = position.0 >= size.0 - self.border;
+            let north = position.1 < self.border;
+            let south = position.1 >= size.1 - self.border;
+            let direction = match (north, south, west, east) {
+                (true, _, true, _) => Some(ResizeDirection::NorthWest),
+                (true, _, _, true) => Some(ResizeDirection::NorthEast),
+                (_, true, true, _) => Some(ResizeDirection::SouthWest),
+                (_, true, _, true) => Some(ResizeDirection::SouthEast),
+                (true, ..) => Some(ResizeDirection::North),
+                (_, true, ..) => Some(ResizeDirection::South),
+                (_, _, true, _) => Some(ResizeDirection::West),
+                (_, _, _, true) => Some(ResizeDirection::East),
+                _ => None,
+            };
+            if let Some(direction) = direction {
+                return HitTest::Resize(direction);
+            }
+        }
+
+        let point = Point::from(position);
+        if self.captions.iter().any(|rect| rect.contains(point))
+            && !self.exclusions.iter().any(|rect| rect.contains(point))
+        {
+            HitTest::Caption
+        } else {
+            HitTest::Client
+        }
+    }
+}
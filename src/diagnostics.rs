@@ -0,0 +1,294 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use skia_safe::{
+    gradient_shader, AlphaType, Canvas, Color, ColorType, Data, Font, Image, ImageInfo, Paint,
+    Rect, SamplingOptions, Shader, TileMode,
+};
+
+use crate::input::{InputEvent, KeyCode, MouseButton};
+
+/// The pixels the bars of the tear test move per frame.
+const TEAR_SPEED: i64 = 24;
+const TEAR_PERIOD: i64 = 96;
+/// The steps of the stepped gradient bands, and of the near black and near white bands.
+const BAND_STEPS: usize = 16;
+
+/// A calibration pattern drawn instead of the content, to check the vsync, the colors and
+/// the latency of the driver and compositor the application runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticPattern {
+    /// Vertical bars moving fast sideways, a horizontal break across them is a tear.
+    TearTest,
+    /// Smooth and stepped gradients, banding, crushed blacks or clipped whites show a
+    /// lossy color pipeline.
+    GradientBands,
+    /// One and two pixel lines and checkerboards, blur or moiré shows the frame is scaled
+    /// on its way to the display.
+    PixelGrid,
+    /// Flashes white on a click or Space, to measure the input to photon latency with a
+    /// camera or a photodiode.
+    Latency,
+}
+
+impl DiagnosticPattern {
+    pub const ALL: [DiagnosticPattern; 4] = [
+        DiagnosticPattern::TearTest,
+        DiagnosticPattern::GradientBands,
+        DiagnosticPattern::PixelGrid,
+        DiagnosticPattern::Latency,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DiagnosticPattern::TearTest => "tear-test",
+            DiagnosticPattern::GradientBands => "gradient-bands",
+            DiagnosticPattern::PixelGrid => "pixel-grid",
+            DiagnosticPattern::Latency => "latency",
+        }
+    }
+
+    /// The next pattern, `None` after the last one.
+    pub fn next(self) -> Option<DiagnosticPattern> {
+        let index = Self::ALL.iter().position(|pattern| *pattern == self)?;
+        Self::ALL.get(index + 1).copied()
+    }
+}
+
+impl fmt::Display for DiagnosticPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DiagnosticPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|pattern| pattern.name() == s)
+            .ok_or_else(|| format!("Unknown diagnostic pattern {s}"))
+    }
+}
+
+/// Draws the diagnostic patterns in device pixels, bypassing the camera and the render
+/// scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    pub pattern: DiagnosticPattern,
+    /// When the flash was requested, it is drawn on the next frame.
+    flash: Option<Instant>,
+    /// From the input to the frame drawing the flash.
+    last_latency: Option<Duration>,
+}
+
+impl Diagnostics {
+    pub fn new(pattern: DiagnosticPattern) -> Self {
+        Self {
+            pattern,
+            flash: None,
+            last_latency: None,
+        }
+    }
+
+    /// Handle the input event, returns whether the patterns consumed it.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::Key {
+                code: KeyCode::Space,
+                pressed,
+            }
+            | InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed,
+            } if self.pattern == DiagnosticPattern::Latency => {
+                if pressed {
+                    self.flash = Some(Instant::now());
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Draw the pattern over the whole window of `size` in window pixels, on a canvas
+    /// scaled by `scale`.
+    pub fn draw(&mut self, canvas: &mut Canvas, size: (i32, i32), frame: usize, scale: f32) {
+        canvas.save();
+        canvas.scale((1. / scale, 1. / scale));
+        let size = (
+            (size.0 as f32 * scale).round(),
+            (size.1 as f32 * scale).round(),
+        );
+        let mut paint = Paint::default();
+        let mut label = String::from(self.pattern.name());
+        match self.pattern {
+            DiagnosticPattern::TearTest => draw_tear_test(canvas, size, frame, &mut paint),
+            DiagnosticPattern::GradientBands => draw_gradient_bands(canvas, size, &mut paint),
+            DiagnosticPattern::PixelGrid => draw_pixel_grid(canvas, size, &mut paint),
+            DiagnosticPattern::Latency => {
+                let flash = self.flash.take();
+                if let Some(requested) = flash {
+                    self.last_latency = Some(requested.elapsed());
+                }
+                canvas.clear(if flash.is_some() {
+                    Color::WHITE
+                } else {
+                    Color::from_rgb(0x20, 0x20, 0x20)
+                });
+                label.push_str("  click or press Space to flash");
+                if let Some(latency) = self.last_latency {
+                    label.push_str(&format!(
+                        "  input to frame {:.2} ms",
+                        latency.as_secs_f64() * 1000.
+                    ));
+                }
+            }
+        }
+        label.push_str("  frame ");
+        label.push_str(&frame.to_string());
+
+        let font = Font::default()
+            .with_size(14. * scale)
+            .expect("Could not create font");
+        let (width, _) = font.measure_str(&label, None);
+        let padding = 8. * scale;
+        let height = 14. * scale + padding * 2.;
+        paint.set_shader(None);
+        paint.set_color(Color::from_argb(0xc0, 0, 0, 0));
+        canvas.draw_rect(
+            Rect::from_xywh(0., size.1 - height, width + padding * 2., height),
+            &paint,
+        );
+        paint.set_anti_alias(true).set_color(Color::WHITE);
+        canvas.draw_str(
+            &label,
+            (padding, size.1 - padding - 2. * scale),
+            &font,
+            &paint,
+        );
+        canvas.restore();
+    }
+}
+
+fn draw_tear_test(canvas: &mut Canvas, size: (f32, f32), frame: usize, paint: &mut Paint) {
+    canvas.clear(Color::BLACK);
+    paint.set_color(Color::WHITE);
+    let offset = (frame as i64 * TEAR_SPEED).rem_euclid(TEAR_PERIOD) as f32;
+    let mut x = offset - TEAR_PERIOD as f32;
+    while x < size.0 {
+        canvas.draw_rect(
+            Rect::from_xywh(x, 0., TEAR_PERIOD as f32 / 2., size.1),
+            paint,
+        );
+        x += TEAR_PERIOD as f32;
+    }
+}
+
+fn draw_gradient_bands(canvas: &mut Canvas, size: (f32, f32), paint: &mut Paint) {
+    canvas.clear(Color::BLACK);
+    let colors = [Color::WHITE, Color::RED, Color::GREEN, Color::BLUE];
+    // A smooth and a stepped band per color, then the steps next to black and white.
+    let rows = colors.len() * 2 + 2;
+    let height = size.1 / rows as f32;
+    for (index, color) in colors.iter().enumerate() {
+        let top = index as f32 * 2. * height;
+        paint.set_shader(gradient_shader::linear(
+            ((0., 0.), (size.0, 0.)),
+            [Color::BLACK, *color].as_ref(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        ));
+        canvas.draw_rect(Rect::from_xywh(0., top, size.0, height), paint);
+        paint.set_shader(None);
+        draw_steps(canvas, size.0, top + height, height, paint, |t| {
+            Color::from_rgb(
+                (color.r() as f32 * t) as u8,
+                (color.g() as f32 * t) as u8,
+                (color.b() as f32 * t) as u8,
+            )
+        });
+    }
+    // The darkest and the brightest values, lost on a display or compositor that crushes
+    // or clips them.
+    let top = colors.len() as f32 * 2. * height;
+    draw_steps(canvas, size.0, top, height, paint, |t| {
+        let value = (t * (BAND_STEPS - 1) as f32).round() as u8;
+        Color::from_rgb(value, value, value)
+    });
+    draw_steps(canvas, size.0, top + height, height, paint, |t| {
+        let value = 255 - (BAND_STEPS - 1) as u8 + (t * (BAND_STEPS - 1) as f32).round() as u8;
+        Color::from_rgb(value, value, value)
+    });
+}
+
+/// A row of `BAND_STEPS` rects colored at evenly spaced `t` in `0..=1`.
+fn draw_steps(
+    canvas: &mut Canvas,
+    width: f32,
+    top: f32,
+    height: f32,
+    paint: &mut Paint,
+    color: impl Fn(f32) -> Color,
+) {
+    let step_width = width / BAND_STEPS as f32;
+    for step in 0..BAND_STEPS {
+        paint.set_color(color(step as f32 / (BAND_STEPS - 1) as f32));
+        canvas.draw_rect(
+            Rect::from_xywh(step as f32 * step_width, top, step_width.ceil(), height),
+            paint,
+        );
+    }
+}
+
+fn draw_pixel_grid(canvas: &mut Canvas, size: (f32, f32), paint: &mut Paint) {
+    canvas.clear(Color::BLACK);
+    let (half_width, half_height) = ((size.0 / 2.).floor(), (size.1 / 2.).floor());
+    // The vertical lines top left, the horizontal lines top right, one pixel apart, the one
+    // pixel checkerboard bottom left and the two pixel one bottom right.
+    let quarters = [
+        (Rect::new(0., 0., half_width, half_height), (2, 1), 1),
+        (Rect::new(half_width, 0., size.0, half_height), (1, 2), 1),
+        (Rect::new(0., half_height, half_width, size.1), (2, 2), 1),
+        (
+            Rect::new(half_width, half_height, size.0, size.1),
+            (4, 4),
+            2,
+        ),
+    ];
+    for (rect, tile, cell) in quarters {
+        let lines = tile.0 != tile.1;
+        paint.set_shader(tile_shader(tile, |x, y| {
+            if lines {
+                x + y == 0
+            } else {
+                (x / cell + y / cell) % 2 == 0
+            }
+        }));
+        canvas.draw_rect(rect, paint);
+    }
+    paint.set_shader(None);
+}
+
+/// A shader repeating the white and black pixels of the tile, sampled without filtering.
+fn tile_shader(size: (i32, i32), white: impl Fn(i32, i32) -> bool) -> Option<Shader> {
+    let mut pixels = Vec::with_capacity((size.0 * size.1 * 4) as usize);
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let value = if white(x, y) { 0xff } else { 0 };
+            pixels.extend_from_slice(&[value, value, value, 0xff]);
+        }
+    }
+    let info = ImageInfo::new(size, ColorType::RGBA8888, AlphaType::Opaque, None);
+    Image::from_raster_data(&info, Data::new_copy(&pixels), size.0 as usize * 4)?.to_shader(
+        (TileMode::Repeat, TileMode::Repeat),
+        SamplingOptions::default(),
+        None,
+    )
+}
@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use skia_safe::{pdf, Canvas, Data, Document, Rect};
+
+use crate::{
+    arena::FrameArena,
+    camera::Camera,
+    input::InputState,
+    profiler::DrawProfiler,
+    renderer::{FrameInfo, Renderer},
+    theme::Theme,
+};
+
+/// Page margins in points.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Margins {
+    #[inline]
+    pub fn uniform(margin: f32) -> Self {
+        Self {
+            top: margin,
+            right: margin,
+            bottom: margin,
+            left: margin,
+        }
+    }
+}
+
+/// The page size and margins in points (1/72 inch).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageConfig {
+    pub size: (f32, f32),
+    pub margins: Margins,
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self::A4
+    }
+}
+
+impl PageConfig {
+    pub const A4: PageConfig = PageConfig::new((595., 842.));
+    pub const LETTER: PageConfig = PageConfig::new((612., 792.));
+
+    /// A page with 1/2 inch margins.
+    pub const fn new(size: (f32, f32)) -> Self {
+        Self {
+            size,
+            margins: Margins {
+                top: 36.,
+                right: 36.,
+                bottom: 36.,
+                left: 36.,
+            },
+        }
+    }
+
+    #[inline]
+    pub fn with_margins(mut self, margins: Margins) -> Self {
+        self.margins = margins;
+        self
+    }
+
+    #[inline]
+    pub fn landscape(mut self) -> Self {
+        self.size = (self.size.1, self.size.0);
+        self
+    }
+
+    /// The area inside the margins.
+    #[inline]
+    pub fn content_rect(&self) -> Rect {
+        Rect::new(
+            self.margins.left,
+            self.margins.top,
+            self.size.0 - self.margins.right,
+            self.size.1 - self.margins.bottom,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageInfo {
+    /// Zero based page index.
+    pub index: usize,
+    /// The size of the content area, the canvas origin is the top left of the content area.
+    pub content_size: (f32, f32),
+}
+
+/// A multi-page PDF document, every page is rendered through a document-backed canvas.
+pub struct PdfDocument {
+    document: Option<Document>,
+    config: PageConfig,
+}
+
+impl PdfDocument {
+    pub fn new(config: PageConfig, metadata: Option<&pdf::Metadata>) -> Self {
+        Self {
+            document: Some(pdf::new_document(metadata)),
+            config,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &PageConfig {
+        &self.config
+    }
+
+    /// Change the page config for the following pages.
+    #[inline]
+    pub fn set_config(&mut self, config: PageConfig) {
+        self.config = config;
+    }
+
+    #[inline]
+    pub fn page_count(&self) -> usize {
+        self.document
+            .as_ref()
+            .map_or(0, |document| document.pages())
+    }
+
+    /// Render the next page, the canvas is translated and clipped to the content area.
+    pub fn render_page(&mut self, draw: impl FnOnce(&mut Canvas, &PageInfo)) {
+        let document = self.document.take().expect("Document is closed");
+        let content = self.config.content_rect();
+        let info = PageInfo {
+            index: document.pages(),
+            content_size: (content.width(), content.height()),
+        };
+
+        let mut page = document.begin_page(self.config.size, Some(&content));
+        draw(page.canvas(), &info);
+        self.document = Some(page.end_page());
+    }
+
+    /// Render the next page with the renderer used on screen.
+    pub fn render_renderer(&mut self, renderer: &mut dyn Renderer, frame: usize) {
+        let input = InputState::default();
+        let camera = Camera::default();
+        let profiler = DrawProfiler::default();
+        let theme = Theme::default();
+        let arena = FrameArena::new();
+        self.render_page(|canvas, page| {
+            let info = FrameInfo {
+                frame,
+                size: (page.content_size.0 as i32, page.content_size.1 as i32),
+                pixel_scale: 1.,
+                safe_area: Rect::from_size(page.content_size),
+                input: &input,
+                camera: &camera,
+                profiler: &profiler,
+                theme: &theme,
+                previous_frame: None,
+                dt: 0.,
+                dither: false,
+                anti_alias: true,
+                arena: &arena,
+                uploader: None,
+            };
+            renderer.render(canvas, &info);
+            renderer.render_overlay(canvas, &info);
+        });
+    }
+
+    /// Close the document and return the encoded PDF.
+    #[inline]
+    pub fn finish(mut self) -> Data {
+        self.document.take().expect("Document is closed").close()
+    }
+
+    #[inline]
+    pub fn save(self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.finish().as_bytes())
+    }
+}
+
+/// Render `pages` pages into a PDF file at `path`, calling `draw` for every page.
+pub fn render_pdf(
+    path: impl AsRef<Path>,
+    config: PageConfig,
+    pages: usize,
+    mut draw: impl FnMut(&mut Canvas, &PageInfo),
+) -> std::io::Result<()> {
+    let mut document = PdfDocument::new(config, None);
+    for _ in 0..pages {
+        document.render_page(&mut draw);
+    }
+    document.save(path)
+}
@@ -0,0 +1,167 @@
+use std::{
+    ffi::CString,
+    io::{Error, ErrorKind},
+    num::NonZeroU32,
+};
+
+use glutin::{
+    api::egl::{
+        context::PossiblyCurrentContext, device::Device, display::Display, surface::Surface,
+    },
+    config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+    context::ContextAttributesBuilder,
+    display::GetDisplayExtensions,
+    prelude::*,
+    surface::{PbufferSurface, SurfaceAttributesBuilder},
+};
+use skia_safe::gpu::DirectContext;
+
+use crate::{fbo::Framebuffer, surface::SurfaceColorType};
+
+/// An EGL device, e.g. a GPU, usable without a window system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EglDeviceInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub vendor: Option<String>,
+}
+
+/// Enumerate the EGL devices, empty if `EGL_EXT_device_enumeration` is not supported.
+pub fn devices() -> Vec<EglDeviceInfo> {
+    match Device::query_devices() {
+        Ok(devices) => devices
+            .enumerate()
+            .map(|(index, device)| EglDeviceInfo {
+                index,
+                name: device.name().map(str::to_string),
+                vendor: device.vendor().map(str::to_string),
+            })
+            .collect(),
+        Err(err) => {
+            eprintln!("Error enumerating EGL devices: {err}");
+            vec![]
+        }
+    }
+}
+
+/// How the context is made current, it renders into crate-managed framebuffers either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EglSurfaceKind {
+    /// No surface at all (`EGL_KHR_surfaceless_context`).
+    Surfaceless,
+    /// A 1x1 pbuffer surface, the fallback if surfaceless contexts aren't supported.
+    Pbuffer,
+}
+
+/// A GL context on an EGL display without a window, for batch rendering and tests.
+pub struct EglGpu {
+    // Declared first to be dropped while the GL context is still alive.
+    gr_context: DirectContext,
+    _context: PossiblyCurrentContext,
+    _pbuffer: Option<Surface<PbufferSurface>>,
+    _display: Display,
+    kind: EglSurfaceKind,
+}
+
+impl EglGpu {
+    /// Create the context on the device with the index from `devices`, `None` takes the
+    /// first device.
+    pub fn new(device_index: Option<usize>) -> std::io::Result<Self> {
+        let device = Device::query_devices()
+            .map_err(|err| Error::new(ErrorKind::Unsupported, err.to_string()))?
+            .nth(device_index.unwrap_or(0))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "No such EGL device"))?;
+        let display = unsafe { Display::with_device(&device, None) }
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+        Self::with_display(display)
+    }
+
+    /// Create a surfaceless context where supported, a pbuffer backed one otherwise.
+    pub fn with_display(display: Display) -> std::io::Result<Self> {
+        if supports_surfaceless(&display) {
+            Self::create(display, EglSurfaceKind::Surfaceless)
+        } else {
+            Self::create(display, EglSurfaceKind::Pbuffer)
+        }
+    }
+
+    pub fn surfaceless(display: Display) -> std::io::Result<Self> {
+        if !supports_surfaceless(&display) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "EGL_KHR_surfaceless_context is not supported",
+            ));
+        }
+        Self::create(display, EglSurfaceKind::Surfaceless)
+    }
+
+    #[inline]
+    pub fn pbuffer(display: Display) -> std::io::Result<Self> {
+        Self::create(display, EglSurfaceKind::Pbuffer)
+    }
+
+    fn create(display: Display, kind: EglSurfaceKind) -> std::io::Result<Self> {
+        let template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+            .build();
+        let config = unsafe { display.find_configs(template) }
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "No EGL config"))?;
+
+        let context = unsafe {
+            display.create_context(&config, &ContextAttributesBuilder::new().build(None))
+        }
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        let (context, pbuffer) = match kind {
+            EglSurfaceKind::Surfaceless => (context.make_current_surfaceless(), None),
+            EglSurfaceKind::Pbuffer => {
+                let one = NonZeroU32::new(1).unwrap();
+                let attrs = SurfaceAttributesBuilder::<PbufferSurface>::new().build(one, one);
+                let pbuffer = unsafe { display.create_pbuffer_surface(&config, &attrs) }
+                    .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+                (context.make_current(&pbuffer), Some(pbuffer))
+            }
+        };
+        let context = context.map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        gl::load_with(|name| display.get_proc_address(CString::new(name).unwrap().as_c_str()));
+        let interface = skia_safe::gpu::gl::Interface::new_load_with(|name| {
+            display.get_proc_address(CString::new(name).unwrap().as_c_str())
+        })
+        .ok_or_else(|| Error::new(ErrorKind::Other, "Could not create interface"))?;
+        let gr_context = DirectContext::new_gl(interface, None)
+            .ok_or_else(|| Error::new(ErrorKind::Other, "Could not create direct context"))?;
+
+        Ok(Self {
+            gr_context,
+            _context: context,
+            _pbuffer: pbuffer,
+            _display: display,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn kind(&self) -> EglSurfaceKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn direct_context(&mut self) -> &mut DirectContext {
+        &mut self.gr_context
+    }
+
+    /// Create an RGBA8888 framebuffer to render into, it must be dropped before the context.
+    #[inline]
+    pub fn create_framebuffer(&mut self, size: (i32, i32)) -> Option<Framebuffer> {
+        Framebuffer::new(&mut self.gr_context, size, SurfaceColorType::Rgba8888)
+    }
+}
+
+#[inline]
+fn supports_surfaceless(display: &Display) -> bool {
+    display.extensions().contains("EGL_KHR_surfaceless_context")
+}
@@ -0,0 +1,163 @@
+use std::mem::ManuallyDrop;
+
+use gl::types::{GLenum, GLuint};
+use skia_safe::gpu::{gl::FramebufferInfo, BackendRenderTarget, DirectContext, SurfaceOrigin};
+
+use crate::{surface::SurfaceColorType, SkiaSurface};
+
+/// A crate-managed GL framebuffer with a Skia surface drawing into it, the GL context it
+/// was created with must be current when it is dropped.
+pub struct Framebuffer {
+    fbo: GLuint,
+    /// The color and the depth/stencil renderbuffers.
+    renderbuffers: [GLuint; 2],
+    size: (i32, i32),
+    /// Dropped before the framebuffer it renders into is deleted.
+    surface: ManuallyDrop<SkiaSurface>,
+}
+
+impl Framebuffer {
+    /// A framebuffer storing the colors like the resolved `color_type`, e.g. in half floats
+    /// to keep the precision of an F16 window surface.
+    pub fn new(
+        gr_context: &mut DirectContext,
+        size: (i32, i32),
+        color_type: SurfaceColorType,
+    ) -> Option<Self> {
+        let size = (size.0.max(1), size.1.max(1));
+        let (internal_format, color_type) = renderbuffer_format(color_type);
+        let (skia_color_type, format) = color_type.formats();
+        let mut fbo = 0;
+        let mut renderbuffers = [0; 2];
+        let complete = unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::GenRenderbuffers(2, renderbuffers.as_mut_ptr());
+            attach_renderbuffer(
+                renderbuffers[0],
+                internal_format,
+                gl::COLOR_ATTACHMENT0,
+                size,
+            );
+            attach_renderbuffer(
+                renderbuffers[1],
+                gl::DEPTH24_STENCIL8,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                size,
+            );
+            let complete = gl::CheckFramebufferStatus(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            complete
+        };
+        // Skia caches the GL state, which was changed behind its back.
+        gr_context.reset(None);
+
+        let surface = if complete {
+            let fb_info = FramebufferInfo {
+                fboid: fbo,
+                format: format.into(),
+                ..Default::default()
+            };
+            let backend_render_target = BackendRenderTarget::new_gl(size, None, 8, fb_info);
+            SkiaSurface::from_backend_render_target(
+                gr_context,
+                &backend_render_target,
+                SurfaceOrigin::BottomLeft,
+                skia_color_type,
+                None,
+                None,
+            )
+        } else {
+            None
+        };
+
+        match surface {
+            Some(surface) => Some(Self {
+                fbo,
+                renderbuffers,
+                size,
+                surface: ManuallyDrop::new(surface),
+            }),
+            None => {
+                eprintln!("Error creating a {}x{} framebuffer", size.0, size.1);
+                unsafe {
+                    gl::DeleteRenderbuffers(2, renderbuffers.as_ptr());
+                    gl::DeleteFramebuffers(1, &fbo);
+                }
+                None
+            }
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> GLuint {
+        self.fbo
+    }
+
+    #[inline]
+    pub fn size(&self) -> (i32, i32) {
+        self.size
+    }
+
+    #[inline]
+    pub fn surface(&mut self) -> &mut SkiaSurface {
+        &mut self.surface
+    }
+
+    /// Copy the content into the framebuffer `target` scaled to `target_size`, the Skia
+    /// surface must be flushed first.
+    pub fn blit_to(&self, target: GLuint, target_size: (i32, i32), linear: bool) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.size.0,
+                self.size.1,
+                0,
+                0,
+                target_size.0,
+                target_size.1,
+                gl::COLOR_BUFFER_BIT,
+                if linear { gl::LINEAR } else { gl::NEAREST },
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            // The surface is not used afterwards, its render target references the FBO.
+            ManuallyDrop::drop(&mut self.surface);
+            gl::DeleteRenderbuffers(2, self.renderbuffers.as_ptr());
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+/// The renderbuffer internal format of a resolved color type, with the color type drawn in
+/// it. Renderbuffers don't store the blue channel first, the blit to the window swaps it.
+fn renderbuffer_format(color_type: SurfaceColorType) -> (GLenum, SurfaceColorType) {
+    match color_type {
+        SurfaceColorType::Auto | SurfaceColorType::Rgba8888 | SurfaceColorType::Bgra8888 => {
+            (gl::RGBA8, SurfaceColorType::Rgba8888)
+        }
+        SurfaceColorType::Rgba1010102 => (gl::RGB10_A2, color_type),
+        SurfaceColorType::RgbaF16 => (gl::RGBA16F, color_type),
+    }
+}
+
+unsafe fn attach_renderbuffer(
+    renderbuffer: GLuint,
+    format: GLenum,
+    attachment: GLenum,
+    size: (i32, i32),
+) {
+    gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+    gl::RenderbufferStorage(gl::RENDERBUFFER, format, size.0, size.1);
+    gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, renderbuffer);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+}
@@ -0,0 +1,167 @@
+use skia_safe::{Canvas, Paint, PaintStyle, RRect, Rect};
+
+use crate::{
+    input::{InputEvent, KeyCode, ModifiersState},
+    theme::Theme,
+};
+
+/// The gap between a node and its focus ring.
+const FOCUS_RING_OFFSET: f32 = 3.;
+const FOCUS_RING_WIDTH: f32 = 2.;
+
+/// Identifies a focusable node within its renderer, e.g. a widget index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FocusId(pub u64);
+
+/// A node taking the keyboard focus, reported by `Renderer::focus_nodes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusNode {
+    pub id: FocusId,
+    /// The bounds in the canvas space of the renderer, the ring is drawn around them.
+    pub rect: Rect,
+    pub radius: f32,
+}
+
+impl FocusNode {
+    #[inline]
+    pub fn new(id: FocusId, rect: Rect) -> Self {
+        Self {
+            id,
+            rect,
+            radius: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+}
+
+/// Where a key event goes after the focus handling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRouting {
+    /// The key moved the focus.
+    Consumed,
+    /// Route the key to the focused node.
+    Focused(FocusId),
+    /// No node is focused, the key goes to the renderer.
+    Unfocused,
+}
+
+/// Tracks the focused node of the renderer and moves the focus with Tab and Shift-Tab,
+/// in the order of the nodes.
+#[derive(Debug, Default, Clone)]
+pub struct FocusManager {
+    nodes: Vec<FocusNode>,
+    focused: Option<FocusId>,
+    /// The ring is only shown after keyboard navigation, a click hides it.
+    ring_visible: bool,
+}
+
+impl FocusManager {
+    /// Update the focusable nodes, the focus is dropped when its node is gone.
+    pub fn set_nodes(&mut self, nodes: Vec<FocusNode>) {
+        self.nodes = nodes;
+        if let Some(id) = self.focused {
+            if !self.nodes.iter().any(|node| node.id == id) {
+                self.focused = None;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn focused(&self) -> Option<FocusId> {
+        self.focused
+    }
+
+    #[inline]
+    pub fn focus(&mut self, id: Option<FocusId>) {
+        self.focused = id;
+    }
+
+    #[inline]
+    pub fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+
+    #[inline]
+    pub fn focus_previous(&mut self) {
+        self.move_focus(-1);
+    }
+
+    /// Move the focus `step` nodes, wrapping around, the first step from no focus lands on
+    /// the first or the last node.
+    fn move_focus(&mut self, step: isize) {
+        let len = self.nodes.len() as isize;
+        if len == 0 {
+            self.focused = None;
+            return;
+        }
+        let index = match self.focused_index() {
+            Some(index) => (index as isize + step).rem_euclid(len),
+            None if step > 0 => 0,
+            None => len - 1,
+        };
+        self.focused = Some(self.nodes[index as usize].id);
+        self.ring_visible = true;
+    }
+
+    fn focused_index(&self) -> Option<usize> {
+        let id = self.focused?;
+        self.nodes.iter().position(|node| node.id == id)
+    }
+
+    /// Handle the input event, Tab and Shift-Tab move the focus and the other keys are
+    /// routed to the focused node.
+    pub fn handle(&mut self, event: &InputEvent, modifiers: ModifiersState) -> KeyRouting {
+        match *event {
+            InputEvent::Key {
+                code: KeyCode::Tab,
+                pressed,
+            } => {
+                if pressed {
+                    if modifiers.shift_key() {
+                        self.focus_previous();
+                    } else {
+                        self.focus_next();
+                    }
+                }
+                KeyRouting::Consumed
+            }
+            InputEvent::Key { .. } => match self.focused {
+                Some(id) => {
+                    self.ring_visible = true;
+                    KeyRouting::Focused(id)
+                }
+                None => KeyRouting::Unfocused,
+            },
+            InputEvent::MouseButton { pressed: true, .. } | InputEvent::Touch { .. } => {
+                self.ring_visible = false;
+                KeyRouting::Unfocused
+            }
+            _ => KeyRouting::Unfocused,
+        }
+    }
+
+    /// Draw the ring around the focused node, in the canvas space of the renderer.
+    pub fn draw_ring(&self, canvas: &mut Canvas, theme: &Theme) {
+        if !self.ring_visible {
+            return;
+        }
+        let Some(node) = self.focused_index().map(|index| self.nodes[index]) else {
+            return;
+        };
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(FOCUS_RING_WIDTH);
+        paint.set_color(theme.colors.accent);
+        let rect = node
+            .rect
+            .with_outset((FOCUS_RING_OFFSET, FOCUS_RING_OFFSET));
+        let radius = node.radius + FOCUS_RING_OFFSET;
+        canvas.draw_rrect(RRect::new_rect_xy(rect, radius, radius), &paint);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use skia_safe::{AlphaType, ColorType, ImageInfo};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::SkiaSurface;
+
+/// Whether the hashes of the frames are written or checked against the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameHashMode {
+    Record,
+    Verify,
+}
+
+/// Hashes the pixels of every rendered frame, to validate a change of the backend, e.g. of
+/// the resize path or the color type, renders identically across runs. The frames must be
+/// deterministic, e.g. driven by an input replay with a fixed step, and the readback stalls
+/// every frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameHashConfig {
+    pub mode: FrameHashMode,
+    /// The JSON lines of the hashes.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameHash {
+    pub frame: usize,
+    pub hash: u64,
+}
+
+/// The outcome of a verification, once the frames of the recording were all compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verification {
+    pub frames: usize,
+    pub mismatches: usize,
+}
+
+/// The XXH3 hash of the size and the pixels, stable across builds and platforms.
+pub fn hash_pixels(size: (i32, i32), pixels: &[u8]) -> u64 {
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.0.to_le_bytes());
+    hasher.update(&size.1.to_le_bytes());
+    hasher.update(pixels);
+    hasher.digest()
+}
+
+/// Read the surface back as RGBA8 and hash it, the pixels are converted from the color type
+/// of the surface so surfaces of another type drawing the same colors hash the same.
+pub fn hash_surface(surface: &mut SkiaSurface) -> Option<u64> {
+    let size = (surface.width(), surface.height());
+    let info = ImageInfo::new(size, ColorType::RGBA8888, AlphaType::Premul, None);
+    let row_bytes = info.min_row_bytes();
+    let mut pixels = vec![0u8; info.compute_min_byte_size()];
+    surface
+        .read_pixels(&info, &mut pixels, row_bytes, (0, 0))
+        .then(|| hash_pixels(size, &pixels))
+}
+
+/// Writes the hashes of the frames, or compares them with the hashes recorded for the same
+/// frame numbers, the frames missing from the recording are skipped.
+pub enum FrameHashes {
+    Recording(BufWriter<File>),
+    Verifying {
+        /// The recorded hashes by frame, removed once compared.
+        expected: HashMap<usize, u64>,
+        compared: usize,
+        mismatches: usize,
+    },
+}
+
+impl FrameHashes {
+    pub fn open(config: &FrameHashConfig) -> io::Result<Self> {
+        Ok(match config.mode {
+            FrameHashMode::Record => Self::Recording(BufWriter::new(File::create(&config.path)?)),
+            FrameHashMode::Verify => Self::Verifying {
+                expected: BufReader::new(File::open(&config.path)?)
+                    .lines()
+                    .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+                    .map(|line| {
+                        let FrameHash { frame, hash } = serde_json::from_str(&line?)?;
+                        Ok((frame, hash))
+                    })
+                    .collect::<io::Result<_>>()?,
+                compared: 0,
+                mismatches: 0,
+            },
+        })
+    }
+
+    /// Record or compare the hash of the frame, returns the outcome of the verification once
+    /// every recorded frame was compared.
+    pub fn push(&mut self, frame: usize, hash: u64) -> io::Result<Option<Verification>> {
+        match self {
+            Self::Recording(writer) => {
+                serde_json::to_writer(&mut *writer, &FrameHash { frame, hash })?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+                Ok(None)
+            }
+            Self::Verifying {
+                expected,
+                compared,
+                mismatches,
+            } => {
+                let Some(recorded) = expected.remove(&frame) else {
+                    return Ok(None);
+                };
+                if recorded != hash {
+                    eprintln!(
+                        "Frame {frame} differs from the recording: {hash:016x} != {recorded:016x}"
+                    );
+                    *mismatches += 1;
+                }
+                *compared += 1;
+                Ok(expected.is_empty().then_some(Verification {
+                    frames: *compared,
+                    mismatches: *mismatches,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verifying(recorded: &[(usize, u64)]) -> FrameHashes {
+        FrameHashes::Verifying {
+            expected: recorded.iter().copied().collect(),
+            compared: 0,
+            mismatches: 0,
+        }
+    }
+
+    #[test]
+    fn hash_covers_size_and_pixels() {
+        let pixels = [1u8, 2, 3, 4, 5, 6, 7, 8];
+        let hash = hash_pixels((2, 1), &pixels);
+        assert_eq!(hash, hash_pixels((2, 1), &pixels));
+        assert_ne!(hash, hash_pixels((1, 2), &pixels));
+        assert_ne!(hash, hash_pixels((2, 1), &[1, 2, 3, 4, 5, 6, 7, 9]));
+    }
+
+    #[test]
+    fn verification_matches_frames_by_number() {
+        let mut hashes = verifying(&[(1, 10), (2, 20), (3, 30)]);
+        assert_eq!(hashes.push(2, 20).unwrap(), None);
+        assert_eq!(hashes.push(1, 10).unwrap(), None);
+        assert_eq!(
+            hashes.push(3, 30).unwrap(),
+            Some(Verification {
+                frames: 3,
+                mismatches: 0
+            })
+        );
+    }
+
+    #[test]
+    fn verification_skips_unrecorded_frames() {
+        let mut hashes = verifying(&[(2, 20), (4, 40)]);
+        assert_eq!(hashes.push(1, 99).unwrap(), None);
+        assert_eq!(hashes.push(2, 20).unwrap(), None);
+        assert_eq!(hashes.push(3, 99).unwrap(), None);
+        assert_eq!(
+            hashes.push(4, 41).unwrap(),
+            Some(Verification {
+                frames: 2,
+                mismatches: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+    window::{Fullscreen, WindowBuilder},
+};
+
+/// The window placement persisted across runs, e.g.
+///
+/// ```toml
+/// monitor = "DP-1"
+/// position = [120, 80]
+/// size = [1280, 800]
+/// maximized = false
+/// fullscreen = false
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedGeometry {
+    /// The name of the monitor the window was on.
+    pub monitor: Option<String>,
+    /// The outer position in physical pixels, `None` when the platform doesn't report it.
+    pub position: Option<(i32, i32)>,
+    /// The inner size in physical pixels, the size before entering fullscreen when fullscreen.
+    pub size: (u32, u32),
+    pub maximized: bool,
+    /// Restored as borderless fullscreen, also when the window was in exclusive fullscreen.
+    pub fullscreen: bool,
+}
+
+impl SavedGeometry {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let text = toml::to_string(self).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        std::fs::write(path, text)
+    }
+
+    /// Apply the geometry to the window about to be built. When the saved monitor is gone,
+    /// or the position is off the monitor after a layout change, the platform places the
+    /// window and fullscreen uses the current monitor.
+    pub fn apply(
+        &self,
+        builder: WindowBuilder,
+        monitors: impl IntoIterator<Item = MonitorHandle>,
+    ) -> WindowBuilder {
+        let monitor = self.monitor.as_ref().and_then(|name| {
+            monitors
+                .into_iter()
+                .find(|monitor| monitor.name().as_ref() == Some(name))
+        });
+        if monitor.is_none() && self.monitor.is_some() {
+            eprintln!("The saved monitor is gone, using the default window placement");
+        }
+
+        let mut builder = builder
+            .with_inner_size(PhysicalSize::from(self.size))
+            .with_maximized(self.maximized);
+        if let (Some(monitor), Some(position)) = (&monitor, self.position) {
+            if contains(monitor, position) {
+                builder = builder.with_position(PhysicalPosition::from(position));
+            }
+        }
+        if self.fullscreen {
+            builder = builder.with_fullscreen(Some(Fullscreen::Borderless(monitor)));
+        }
+        builder
+    }
+}
+
+fn contains(monitor: &MonitorHandle, (x, y): (i32, i32)) -> bool {
+    let origin = monitor.position();
+    let size = monitor.size();
+    x >= origin.x
+        && y >= origin.y
+        && x < origin.x + size.width as i32
+        && y < origin.y + size.height as i32
+}
@@ -0,0 +1,138 @@
+use std::{collections::BTreeSet, ffi::CStr, fmt};
+
+use gl::types::{GLenum, GLint};
+use glutin::{context::ContextApi, display::DisplayFeatures};
+
+/// What the GL context was negotiated to, to gate the code paths depending on optional
+/// features at runtime, e.g. `has_extension("GL_KHR_debug")` before enabling the debug
+/// output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlInfo {
+    /// OpenGL or OpenGL ES, the context falls back to ES where desktop GL is missing.
+    pub api: ContextApi,
+    /// The major and minor version of the context, `(0, 0)` if it couldn't be parsed.
+    pub version: (u32, u32),
+    pub version_string: String,
+    pub vendor: String,
+    pub renderer: String,
+    pub shading_language_version: String,
+    pub extensions: BTreeSet<String>,
+    /// The features of the display, e.g. `DisplayFeatures::SWAP_CONTROL` for tear control.
+    pub display_features: DisplayFeatures,
+}
+
+impl GlInfo {
+    /// Query the context current on the calling thread, the GL functions must be loaded.
+    pub fn query(api: ContextApi, display_features: DisplayFeatures) -> Self {
+        let version_string = get_string(gl::VERSION);
+        let version = {
+            let (mut major, mut minor): (GLint, GLint) = (0, 0);
+            unsafe {
+                gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+                gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+                // The version queries are missing before GL 3 and ES 3.
+                gl::GetError();
+            }
+            if major > 0 {
+                (major as u32, minor as u32)
+            } else {
+                parse_version(&version_string)
+            }
+        };
+        Self {
+            api,
+            version,
+            version_string,
+            vendor: get_string(gl::VENDOR),
+            renderer: get_string(gl::RENDERER),
+            shading_language_version: get_string(gl::SHADING_LANGUAGE_VERSION),
+            extensions: query_extensions(),
+            display_features,
+        }
+    }
+
+    #[inline]
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    #[inline]
+    pub fn is_gles(&self) -> bool {
+        matches!(self.api, ContextApi::Gles(_))
+    }
+
+    /// Whether the context is at least `major.minor`.
+    #[inline]
+    pub fn is_at_least(&self, major: u32, minor: u32) -> bool {
+        self.version >= (major, minor)
+    }
+}
+
+impl fmt::Display for GlInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {}.{} ({})",
+            if self.is_gles() {
+                "OpenGL ES"
+            } else {
+                "OpenGL"
+            },
+            self.version.0,
+            self.version.1,
+            self.version_string
+        )?;
+        writeln!(f, "Vendor: {}", self.vendor)?;
+        writeln!(f, "Renderer: {}", self.renderer)?;
+        writeln!(f, "GLSL: {}", self.shading_language_version)?;
+        writeln!(f, "Display features: {:?}", self.display_features)?;
+        write!(f, "{} extensions", self.extensions.len())
+    }
+}
+
+fn get_string(name: GLenum) -> String {
+    let ptr = unsafe { gl::GetString(name) };
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr.cast()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn query_extensions() -> BTreeSet<String> {
+    // Core profiles removed the extension string, they are queried by index.
+    if gl::GetStringi::is_loaded() {
+        let mut count: GLint = 0;
+        unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count) };
+        if count > 0 {
+            return (0..count as u32)
+                .filter_map(|index| {
+                    let ptr = unsafe { gl::GetStringi(gl::EXTENSIONS, index) };
+                    (!ptr.is_null()).then(|| {
+                        unsafe { CStr::from_ptr(ptr.cast()) }
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                })
+                .collect();
+        }
+    }
+    get_string(gl::EXTENSIONS)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// The version from e.g. `4.6.0 NVIDIA 535.54` or `OpenGL ES 3.2 Mesa 23.1`.
+fn parse_version(version: &str) -> (u32, u32) {
+    version
+        .split_whitespace()
+        .find_map(|word| {
+            let mut parts = word.split('.');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            Some((major, minor))
+        })
+        .unwrap_or((0, 0))
+}
@@ -0,0 +1,76 @@
+use skia_safe::{AlphaType, Canvas, Color, ColorType, Font, FontMgr, FontStyle, ImageInfo, Paint};
+
+/// The printable ASCII characters.
+pub const ASCII: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+/// Characters to rasterize into the glyph atlas ahead of their first use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphSet {
+    pub text: String,
+    /// The font family, `None` for the default font.
+    pub family: Option<String>,
+    pub style: FontStyle,
+    /// The font sizes in pixels, the glyphs are rasterized once per size.
+    pub sizes: Vec<f32>,
+}
+
+impl GlyphSet {
+    #[inline]
+    pub fn new(text: impl Into<String>, sizes: impl Into<Vec<f32>>) -> Self {
+        Self {
+            text: text.into(),
+            family: None,
+            style: FontStyle::normal(),
+            sizes: sizes.into(),
+        }
+    }
+
+    #[inline]
+    pub fn ascii(sizes: impl Into<Vec<f32>>) -> Self {
+        Self::new(ASCII, sizes)
+    }
+
+    #[inline]
+    pub fn with_family(mut self, family: impl Into<String>, style: FontStyle) -> Self {
+        self.family = Some(family.into());
+        self.style = style;
+        self
+    }
+}
+
+/// Draw the glyphs into a scratch surface of the canvas GPU context, so they are in the
+/// atlas before the frames needing them, e.g. during a loading screen. `scale` is the scale
+/// the text will be drawn at.
+pub fn prewarm(canvas: &mut Canvas, sets: &[GlyphSet], scale: f32) {
+    let info = ImageInfo::new((256, 256), ColorType::RGBA8888, AlphaType::Premul, None);
+    let Some(mut surface) = canvas.new_surface(&info, None) else {
+        eprintln!("Error creating the glyph prewarm surface");
+        return;
+    };
+    let font_mgr = FontMgr::new();
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_color(Color::BLACK);
+
+    for set in sets {
+        let typeface = set
+            .family
+            .as_ref()
+            .and_then(|family| font_mgr.match_family_style(family, set.style));
+        for &size in set.sizes.iter() {
+            let size = size * scale;
+            let font = match &typeface {
+                Some(typeface) => Font::from_typeface(typeface, size),
+                None => Font::default()
+                    .with_size(size)
+                    .expect("Could not create font"),
+            };
+            let canvas = surface.canvas();
+            canvas.clear(Color::TRANSPARENT);
+            canvas.draw_str(&set.text, (0., size), &font, &paint);
+        }
+    }
+    if let Some(mut context) = canvas.direct_context() {
+        context.flush_and_submit();
+    }
+}
@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+/// Tell the NVIDIA Optimus and AMD PowerXpress drivers to run the process on the discrete
+/// GPU, the drivers read the exports when the process starts so it can't be a runtime choice.
+#[cfg(all(windows, feature = "high_performance_gpu"))]
+#[no_mangle]
+pub static NvOptimusEnablement: u32 = 1;
+#[cfg(all(windows, feature = "high_performance_gpu"))]
+#[no_mangle]
+pub static AmdPowerXpressRequestHighPerformance: u32 = 1;
+
+/// Which GPU of a hybrid graphics system to render on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GpuPreference {
+    /// Let the system decide.
+    #[default]
+    Default,
+    /// The integrated GPU, saving power.
+    LowPower,
+    /// The discrete GPU.
+    HighPerformance,
+}
+
+impl FromStr for GpuPreference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(GpuPreference::Default),
+            "low-power" => Ok(GpuPreference::LowPower),
+            "high-performance" => Ok(GpuPreference::HighPerformance),
+            _ => Err(format!("Unknown gpu preference {s}")),
+        }
+    }
+}
+
+impl GpuPreference {
+    /// Set the hints read when the GL display is created, call it before creating the
+    /// display. On Windows the preference is a build time choice, see the
+    /// `high_performance_gpu` feature.
+    pub fn apply(&self) {
+        // Mesa selects the PRIME offload GPU with `DRI_PRIME`.
+        #[cfg(all(unix, not(target_os = "macos")))]
+        match self {
+            GpuPreference::Default => {}
+            GpuPreference::LowPower => std::env::set_var("DRI_PRIME", "0"),
+            GpuPreference::HighPerformance => std::env::set_var("DRI_PRIME", "1"),
+        }
+    }
+
+    /// Whether hardware accelerated configs are preferred over the others.
+    #[inline]
+    pub fn prefers_hardware_acceleration(&self) -> bool {
+        *self == GpuPreference::HighPerformance
+    }
+}
@@ -0,0 +1,128 @@
+use std::{io, path::PathBuf};
+
+use skia_safe::{Color, Rect, Surface};
+
+use crate::{
+    arena::FrameArena,
+    camera::Camera,
+    capture::{self, CaptureOptions},
+    input::InputState,
+    profiler::DrawProfiler,
+    renderer::{FrameInfo, Renderer},
+    stream::{FrameStreamer, StreamConfig},
+    theme::Theme,
+};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::egl::EglGpu;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessOptions {
+    pub size: (i32, i32),
+    pub frames: usize,
+    /// The directory the numbered frames are written to, `None` only renders (and streams).
+    pub out_dir: Option<PathBuf>,
+    pub capture: CaptureOptions,
+    /// The fixed time step in seconds between two frames.
+    pub dt: f32,
+    pub stream: Option<StreamConfig>,
+    /// Render on the GPU of the EGL device with this index, see `egl::devices`. `None`
+    /// renders on the CPU.
+    pub gpu_device: Option<usize>,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            size: (800, 800),
+            frames: 1,
+            out_dir: None,
+            capture: CaptureOptions::default(),
+            dt: 1. / 60.,
+            stream: None,
+            gpu_device: None,
+        }
+    }
+}
+
+/// Render the frames into an offscreen surface without a window, the output is deterministic
+/// since every frame advances by the fixed time step.
+pub fn run(renderer: &mut dyn Renderer, options: &HeadlessOptions) -> io::Result<()> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut gpu = options
+        .gpu_device
+        .map(|index| EglGpu::new(Some(index)))
+        .transpose()?;
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    if options.gpu_device.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "EGL devices are not supported on this platform",
+        ));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut framebuffer = gpu
+        .as_mut()
+        .and_then(|gpu| gpu.create_framebuffer(options.size));
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let surface = match framebuffer.as_mut() {
+        Some(framebuffer) => Some(framebuffer.surface().clone()),
+        None if gpu.is_some() => None,
+        None => Surface::new_raster_n32_premul(options.size),
+    };
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let surface = Surface::new_raster_n32_premul(options.size);
+    let mut surface = surface
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Could not create the surface"))?;
+    if let Some(out_dir) = &options.out_dir {
+        std::fs::create_dir_all(out_dir)?;
+    }
+    let streamer = options.stream.map(FrameStreamer::bind).transpose()?;
+
+    let input = InputState::default();
+    let mut camera = Camera::default();
+    let profiler = DrawProfiler::default();
+    let theme = Theme::default();
+    let mut arena = FrameArena::new();
+    for frame in 0..options.frames {
+        camera.update(options.dt);
+        arena.reset();
+
+        let canvas = surface.canvas();
+        canvas.clear(Color::WHITE);
+        canvas.save();
+        canvas.concat(&camera.matrix());
+        let info = FrameInfo {
+            frame,
+            size: options.size,
+            pixel_scale: 1.,
+            safe_area: Rect::from_iwh(options.size.0, options.size.1),
+            input: &input,
+            camera: &camera,
+            profiler: &profiler,
+            theme: &theme,
+            previous_frame: None,
+            dt: options.dt,
+            dither: false,
+            anti_alias: true,
+            arena: &arena,
+            uploader: None,
+        };
+        renderer.render(canvas, &info);
+        canvas.restore();
+        renderer.render_overlay(canvas, &info);
+
+        if let Some(out_dir) = &options.out_dir {
+            let path = out_dir.join(format!(
+                "frame_{frame:05}.{}",
+                options.capture.format.extension()
+            ));
+            capture::save(&surface.image_snapshot(), path, &options.capture)?;
+        }
+        if let Some(streamer) = &streamer {
+            streamer.push_frame(&mut surface);
+        }
+    }
+    Ok(())
+}
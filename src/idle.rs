@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use skia_safe::gpu::DirectContext;
+
+/// The time kept free before the next frame, a task overrunning its deadline by a little
+/// doesn't delay the frame.
+pub const IDLE_MARGIN: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStatus {
+    Finished,
+    /// Run the task again in a later idle period, e.g. after uploading one chunk of a
+    /// texture.
+    Pending,
+}
+
+/// What an idle task runs with, on the render thread.
+pub struct IdleContext<'a> {
+    /// The GPU context of the frames, e.g. to upload textures.
+    pub gr_context: &'a mut DirectContext,
+    deadline: Instant,
+}
+
+impl IdleContext<'_> {
+    /// When the task should have returned, to size its chunks of work.
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    #[inline]
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+pub type IdleTask = Box<dyn FnMut(&mut IdleContext) -> IdleStatus + Send>;
+
+/// Low priority work run on the render thread only in the spare time between the end of
+/// a frame and the start of the next one, as scheduled by the frame pacer, e.g. cache
+/// warming or incremental texture uploads. Nothing runs while the pacer is paused.
+#[derive(Default)]
+pub struct IdleQueue {
+    tasks: VecDeque<IdleTask>,
+}
+
+impl IdleQueue {
+    #[inline]
+    pub fn push(&mut self, task: IdleTask) {
+        self.tasks.push_back(task);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Run the tasks in order while there is time left before `next_frame_start`, each at
+    /// most once, the pending ones are queued again at the end. Returns the tasks run. The
+    /// queue is only locked to take the next task, the tasks run unlocked so scheduling
+    /// doesn't wait for them.
+    pub fn run(
+        queue: &Mutex<IdleQueue>,
+        gr_context: &mut DirectContext,
+        next_frame_start: Instant,
+    ) -> usize {
+        let deadline = next_frame_start - IDLE_MARGIN;
+        let (run, pending) = run_tasks(
+            deadline,
+            || queue.lock().unwrap().tasks.pop_front(),
+            |task| {
+                task(&mut IdleContext {
+                    gr_context: &mut *gr_context,
+                    deadline,
+                })
+            },
+        );
+        queue.lock().unwrap().tasks.extend(pending);
+        run
+    }
+}
+
+/// Run the tasks taken from `next` in order until `deadline`, returns how many ran and the
+/// pending ones.
+fn run_tasks<T>(
+    deadline: Instant,
+    mut next: impl FnMut() -> Option<T>,
+    mut run_task: impl FnMut(&mut T) -> IdleStatus,
+) -> (usize, Vec<T>) {
+    let mut run = 0;
+    let mut pending = vec![];
+    while Instant::now() < deadline {
+        let Some(mut task) = next() else {
+            break;
+        };
+        if run_task(&mut task) == IdleStatus::Pending {
+            pending.push(task);
+        }
+        run += 1;
+    }
+    (run, pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_run_in_order_and_pending_ones_are_returned() {
+        let mut tasks = VecDeque::from([0, 1, 2, 3]);
+        let mut order = vec![];
+        let (run, pending) = run_tasks(
+            Instant::now() + Duration::from_secs(10),
+            || tasks.pop_front(),
+            |task| {
+                order.push(*task);
+                if *task == 1 {
+                    IdleStatus::Pending
+                } else {
+                    IdleStatus::Finished
+                }
+            },
+        );
+        assert_eq!(run, 4);
+        assert_eq!(order, [0, 1, 2, 3]);
+        assert_eq!(pending, [1]);
+    }
+
+    #[test]
+    fn tasks_stop_once_the_budget_is_spent() {
+        let mut tasks: VecDeque<_> = (0..10).collect();
+        let (run, pending) = run_tasks(
+            Instant::now() + Duration::from_millis(20),
+            || tasks.pop_front(),
+            |_| {
+                std::thread::sleep(Duration::from_millis(8));
+                IdleStatus::Pending
+            },
+        );
+        assert!((1..=3).contains(&run), "{run} tasks run");
+        assert_eq!(pending, (0..run).collect::<Vec<_>>());
+        // The tasks not taken stay queued in order.
+        assert!(tasks.iter().copied().eq(run..10));
+    }
+
+    #[test]
+    fn nothing_runs_past_the_deadline() {
+        let mut tasks = VecDeque::from([0]);
+        let (run, pending) = run_tasks(Instant::now(), || tasks.pop_front(), |_| unreachable!());
+        assert_eq!(run, 0);
+        assert!(pending.is_empty());
+        assert_eq!(tasks.len(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    f64::consts::FRAC_PI_2,
+};
+use winit::{
+    event::{ElementState, Force, MouseScrollDelta, WindowEvent},
+    keyboard::PhysicalKey,
+};
+
+pub use winit::{
+    event::{MouseButton, TouchPhase},
+    keyboard::{KeyCode, ModifiersState},
+};
+
+/// Pixels scrolled per line for the line based scroll deltas.
+const SCROLL_LINE_HEIGHT: f32 = 20.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: (f32, f32),
+        pen: PenState,
+    },
+    CursorMoved {
+        position: (f32, f32),
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// Scroll delta in pixels.
+    Scroll {
+        delta: (f32, f32),
+    },
+    /// Trackpad pinch, positive values zoom in.
+    Magnify {
+        delta: f32,
+    },
+    Modifiers(ModifiersState),
+    /// A key by its physical position, key repeats are reported as presses.
+    Key {
+        code: KeyCode,
+        pressed: bool,
+    },
+}
+
+/// Pressure and tilt reported by the platform for a touch, both are `None` when the
+/// device is not pressure sensitive (e.g. fingers on most touch screens).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PenState {
+    /// Normalized pressure in `[0, 1]`.
+    pub pressure: Option<f32>,
+    /// Angle between the stylus and the surface normal in radians, `0` when the stylus is
+    /// perpendicular to the surface.
+    pub tilt: Option<f32>,
+}
+
+impl From<Force> for PenState {
+    fn from(force: Force) -> Self {
+        match force {
+            Force::Calibrated {
+                force,
+                max_possible_force,
+                altitude_angle,
+            } => PenState {
+                pressure: Some((force / max_possible_force).clamp(0., 1.) as f32),
+                tilt: altitude_angle.map(|altitude| (FRAC_PI_2 - altitude) as f32),
+            },
+            Force::Normalized(force) => PenState {
+                pressure: Some(force.clamp(0., 1.) as f32),
+                tilt: None,
+            },
+        }
+    }
+}
+
+impl InputEvent {
+    /// Translate the winit window event into an input event, returns `None` if the event
+    /// was not an input event.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::Touch(touch) => Some(InputEvent::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                position: (touch.location.x as f32, touch.location.y as f32),
+                pen: touch.force.map(PenState::from).unwrap_or_default(),
+            }),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved {
+                position: (position.x as f32, position.y as f32),
+            }),
+            WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::MouseButton {
+                button: *button,
+                pressed: *state == ElementState::Pressed,
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match *delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        (x * SCROLL_LINE_HEIGHT, y * SCROLL_LINE_HEIGHT)
+                    }
+                    MouseScrollDelta::PixelDelta(position) => {
+                        (position.x as f32, position.y as f32)
+                    }
+                };
+                Some(InputEvent::Scroll { delta })
+            }
+            WindowEvent::TouchpadMagnify { delta, .. } => Some(InputEvent::Magnify {
+                delta: *delta as f32,
+            }),
+            WindowEvent::ModifiersChanged(modifiers) => {
+                Some(InputEvent::Modifiers(modifiers.state()))
+            }
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
+                PhysicalKey::Code(code) => Some(InputEvent::Key {
+                    code,
+                    pressed: event.state == ElementState::Pressed,
+                }),
+                PhysicalKey::Unidentified(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Map the window space positions carried by the event, e.g. into content space.
+    pub fn map_position(self, f: impl Fn((f32, f32)) -> (f32, f32)) -> Self {
+        match self {
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+                pen,
+            } => InputEvent::Touch {
+                id,
+                phase,
+                position: f(position),
+                pen,
+            },
+            InputEvent::CursorMoved { position } => InputEvent::CursorMoved {
+                position: f(position),
+            },
+            event => event,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub position: (f32, f32),
+    pub start_position: (f32, f32),
+    pub pen: PenState,
+}
+
+/// Tracks the state accumulated from the [`InputEvent`]s, e.g. the active touch points.
+#[derive(Debug, Default, Clone)]
+pub struct InputState {
+    touches: HashMap<u64, TouchPoint>,
+    cursor_position: Option<(f32, f32)>,
+    pressed_buttons: HashSet<MouseButton>,
+    modifiers: ModifiersState,
+}
+
+impl InputState {
+    pub fn handle(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+                pen,
+            } => match phase {
+                TouchPhase::Started => {
+                    self.touches.insert(
+                        id,
+                        TouchPoint {
+                            id,
+                            phase,
+                            position,
+                            start_position: position,
+                            pen,
+                        },
+                    );
+                }
+                TouchPhase::Moved => {
+                    if let Some(touch) = self.touches.get_mut(&id) {
+                        touch.phase = phase;
+                        touch.position = position;
+                        touch.pen = pen;
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.touches.remove(&id);
+                }
+            },
+            InputEvent::CursorMoved { position } => self.cursor_position = Some(position),
+            InputEvent::MouseButton { button, pressed } => {
+                if pressed {
+                    self.pressed_buttons.insert(button);
+                } else {
+                    self.pressed_buttons.remove(&button);
+                }
+            }
+            InputEvent::Modifiers(modifiers) => self.modifiers = modifiers,
+            InputEvent::Scroll { .. } | InputEvent::Magnify { .. } | InputEvent::Key { .. } => {}
+        }
+    }
+
+    #[inline]
+    pub fn touches(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.touches.values()
+    }
+
+    #[inline]
+    pub fn touch(&self, id: u64) -> Option<&TouchPoint> {
+        self.touches.get(&id)
+    }
+
+    #[inline]
+    pub fn touch_count(&self) -> usize {
+        self.touches.len()
+    }
+
+    #[inline]
+    pub fn cursor_position(&self) -> Option<(f32, f32)> {
+        self.cursor_position
+    }
+
+    #[inline]
+    pub fn is_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+}
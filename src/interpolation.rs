@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    f32::consts::PI,
+    time::{Duration, Instant},
+};
+
+use skia_safe::Matrix;
+
+use crate::scene::{NodeId, Scene};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterpolationMode {
+    /// Present one update behind, moving from the previous to the latest transform over
+    /// the interval between them, smooth at the cost of an update of latency.
+    Interpolate,
+    /// Present the latest transform moved on at the velocity of the last interval, without
+    /// latency but overshooting when the motion changes, for at most `max` past the latest
+    /// update.
+    Extrapolate { max: Duration },
+}
+
+/// The last two committed transforms of a node.
+#[derive(Debug, Clone, Copy)]
+struct History {
+    previous: Option<(Instant, Matrix)>,
+    latest: (Instant, Matrix),
+}
+
+impl History {
+    /// The progress from the previous to the latest transform at `now`, `None` when there
+    /// is nothing to interpolate.
+    fn progress(&self, mode: InterpolationMode, now: Instant) -> Option<f32> {
+        let (previous_time, previous) = self.previous?;
+        let (latest_time, latest) = self.latest;
+        if previous == latest {
+            return None;
+        }
+        let interval = latest_time.saturating_duration_since(previous_time);
+        if interval.is_zero() {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(latest_time);
+        Some(match mode {
+            InterpolationMode::Interpolate => {
+                (elapsed.as_secs_f32() / interval.as_secs_f32()).min(1.)
+            }
+            InterpolationMode::Extrapolate { max } => {
+                1. + elapsed.min(max).as_secs_f32() / interval.as_secs_f32()
+            }
+        })
+    }
+}
+
+/// Smooths the transforms of the scene nodes between the updates of content changing at
+/// a low logical rate, e.g. data arriving at 5 Hz presented at 60 fps. The transforms are
+/// committed after each update, the frames in between present them interpolated or
+/// extrapolated. Only the presentation is affected, the hit-testing and the bounds use the
+/// committed transforms.
+#[derive(Debug, Clone)]
+pub struct TransformInterpolator {
+    mode: InterpolationMode,
+    history: HashMap<NodeId, History>,
+}
+
+impl TransformInterpolator {
+    pub fn new(mode: InterpolationMode) -> Self {
+        Self {
+            mode,
+            history: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn mode(&self) -> InterpolationMode {
+        self.mode
+    }
+
+    #[inline]
+    pub fn set_mode(&mut self, mode: InterpolationMode) {
+        self.mode = mode;
+    }
+
+    /// Record the transforms of the nodes after an update at `now`, the removed nodes are
+    /// forgotten.
+    pub fn commit(&mut self, scene: &Scene, now: Instant) {
+        self.history.retain(|id, _| scene.node(*id).is_some());
+        for id in scene.node_ids() {
+            let transform = scene.node(id).expect("Node does not exist").transform;
+            self.history
+                .entry(id)
+                .and_modify(|history| {
+                    history.previous = Some(history.latest);
+                    history.latest = (now, transform);
+                })
+                .or_insert(History {
+                    previous: None,
+                    latest: (now, transform),
+                });
+        }
+    }
+
+    /// Jump to the committed transforms, e.g. after a teleport that shouldn't be smoothed.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// The transform to present the node with at `now`, `transform` for the nodes added
+    /// since the last commit.
+    pub fn transform(&self, id: NodeId, transform: &Matrix, now: Instant) -> Matrix {
+        let Some(history) = self.history.get(&id) else {
+            return *transform;
+        };
+        match (history.previous, history.progress(self.mode, now)) {
+            (Some((_, previous)), Some(t)) => lerp_transform(&previous, &history.latest.1, t),
+            _ => history.latest.1,
+        }
+    }
+
+    /// Whether a node is still moving at `now`, the frames need to be rendered until then.
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.history.values().any(|history| {
+            let Some(t) = history.progress(self.mode, now) else {
+                return false;
+            };
+            match self.mode {
+                InterpolationMode::Interpolate => t < 1.,
+                InterpolationMode::Extrapolate { max } => {
+                    now.saturating_duration_since(history.latest.0) < max
+                }
+            }
+        })
+    }
+}
+
+/// The affine transform decomposed into a translation, a rotation, a scale and a shear.
+#[derive(Debug, Clone, Copy)]
+struct Decomposed {
+    translate: (f32, f32),
+    angle: f32,
+    scale: (f32, f32),
+    shear: f32,
+}
+
+impl Decomposed {
+    fn new(matrix: &Matrix) -> Self {
+        let (a, b) = (matrix.scale_x(), matrix.skew_y());
+        let (c, d) = (matrix.skew_x(), matrix.scale_y());
+        let angle = b.atan2(a);
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            translate: (matrix.translate_x(), matrix.translate_y()),
+            angle,
+            scale: (a.hypot(b), d * cos - c * sin),
+            shear: c * cos + d * sin,
+        }
+    }
+
+    fn to_matrix(self) -> Matrix {
+        let (sin, cos) = self.angle.sin_cos();
+        let (scale_x, scale_y) = self.scale;
+        Matrix::new_all(
+            cos * scale_x,
+            cos * self.shear - sin * scale_y,
+            self.translate.0,
+            sin * scale_x,
+            sin * self.shear + cos * scale_y,
+            self.translate.1,
+            0.,
+            0.,
+            1.,
+        )
+    }
+}
+
+/// Interpolate the transforms component wise, the rotation along the shortest arc. `t`
+/// past `1` extrapolates. Perspective transforms aren't interpolated, they switch at the
+/// half.
+pub fn lerp_transform(from: &Matrix, to: &Matrix, t: f32) -> Matrix {
+    if from.has_perspective() || to.has_perspective() {
+        return if t < 0.5 { *from } else { *to };
+    }
+    let lerp = |from: f32, to: f32| from + (to - from) * t;
+    let (from, to) = (Decomposed::new(from), Decomposed::new(to));
+    let mut turn = to.angle - from.angle;
+    if turn > PI {
+        turn -= 2. * PI;
+    } else if turn < -PI {
+        turn += 2. * PI;
+    }
+    Decomposed {
+        translate: (
+            lerp(from.translate.0, to.translate.0),
+            lerp(from.translate.1, to.translate.1),
+        ),
+        angle: from.angle + turn * t,
+        scale: (
+            lerp(from.scale.0, to.scale.0),
+            lerp(from.scale.1, to.scale.1),
+        ),
+        shear: lerp(from.shear, to.shear),
+    }
+    .to_matrix()
+}
@@ -0,0 +1,88 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use skia_safe::Rect;
+
+/// What the next frame must redraw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Damage {
+    Full,
+    /// Only the area in window pixels changed, the rest of the previous frame is kept when
+    /// its content is preserved, see `Backend::set_on_demand`.
+    Rect(Rect),
+}
+
+#[derive(Debug, Default)]
+struct Invalidation {
+    full: bool,
+    rect: Option<Rect>,
+    /// The scheduled redraws, unordered.
+    deadlines: Vec<Instant>,
+}
+
+/// Requests redraws in on-demand rendering, cloned into the renderers and widgets so they
+/// request exactly the frames they need, e.g. `invalidate_after` for the next step of an
+/// animation, instead of the application polling `request_redraw`.
+#[derive(Debug, Clone, Default)]
+pub struct Invalidator {
+    state: Arc<Mutex<Invalidation>>,
+}
+
+impl Invalidator {
+    /// Redraw the whole frame.
+    #[inline]
+    pub fn invalidate(&self) {
+        self.state.lock().unwrap().full = true;
+    }
+
+    /// Redraw the area in window pixels, merged with the other invalidated areas.
+    pub fn invalidate_rect(&self, rect: Rect) {
+        let mut state = self.state.lock().unwrap();
+        state.rect = Some(match state.rect {
+            Some(mut invalidated) => {
+                invalidated.join(rect);
+                invalidated
+            }
+            None => rect,
+        });
+    }
+
+    /// Redraw the whole frame once `delay` passed.
+    #[inline]
+    pub fn invalidate_after(&self, delay: Duration) {
+        self.state
+            .lock()
+            .unwrap()
+            .deadlines
+            .push(Instant::now() + delay);
+    }
+
+    /// Whether a frame is needed at `now`.
+    pub fn is_invalid(&self, now: Instant) -> bool {
+        let state = self.state.lock().unwrap();
+        state.full || state.rect.is_some() || state.deadlines.iter().any(|due| *due <= now)
+    }
+
+    /// The earliest scheduled redraw still to come.
+    #[inline]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.state.lock().unwrap().deadlines.iter().min().copied()
+    }
+
+    /// Take what the frame starting at `now` must redraw, the due deadlines redraw the
+    /// whole frame. `None` when nothing is invalid.
+    pub fn take(&self, now: Instant) -> Option<Damage> {
+        let mut state = self.state.lock().unwrap();
+        let due = state.deadlines.len();
+        state.deadlines.retain(|deadline| *deadline > now);
+        let full = std::mem::take(&mut state.full) || state.deadlines.len() < due;
+        let rect = state.rect.take();
+        if full {
+            Some(Damage::Full)
+        } else {
+            rect.map(Damage::Rect)
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs::Permissions,
+    io::{Error, ErrorKind, Read},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use skia_safe::{Canvas, Picture};
+
+use crate::{
+    proxy::BackendProxy,
+    renderer::{FrameInfo, Renderer},
+};
+
+/// The largest picture accepted from a client, a longer message closes the connection
+/// instead of allocating the length the client claims.
+pub const MAX_MESSAGE_LEN: usize = 64 * 1024 * 1024;
+
+/// The latest frame received from a client, with a sequence number to detect updates.
+#[derive(Default)]
+struct SharedFrame {
+    sequence: u64,
+    bytes: Option<Vec<u8>>,
+    /// Wakes the event loop on a new frame.
+    proxy: Option<BackendProxy>,
+}
+
+/// Renders the draw commands sent by other processes over a Unix socket.
+///
+/// Every message is a little-endian `u32` length followed by a serialized `SkPicture`,
+/// which replaces the displayed frame. A zero length clears the frame.
+///
+/// The clients are trusted: Skia doesn't harden the picture deserialization against
+/// malicious input, so the socket is only accessible to the user running the renderer.
+/// Don't bind it in a directory other users can write to.
+pub struct IpcRenderer {
+    path: PathBuf,
+    frame: Arc<Mutex<SharedFrame>>,
+    sequence: u64,
+    picture: Option<Picture>,
+}
+
+impl IpcRenderer {
+    /// Bind the socket at `path`, only connectable by the current user. A stale socket is
+    /// removed first, any other file at the path is an error.
+    pub fn bind(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(&path)?,
+            Ok(_) => {
+                return Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+
+        let frame = Arc::new(Mutex::new(SharedFrame::default()));
+        {
+            let frame = frame.clone();
+            std::thread::Builder::new()
+                .name("ipc_server".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        match stream {
+                            Ok(stream) => {
+                                let frame = frame.clone();
+                                std::thread::spawn(move || serve_client(stream, frame));
+                            }
+                            Err(err) => eprintln!("Error accepting ipc client: {err}"),
+                        }
+                    }
+                })
+                .expect("Failed to spawn ipc server thread");
+        }
+
+        Ok(Self {
+            path,
+            frame,
+            sequence: 0,
+            picture: None,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Deserialize the latest received frame if it changed since the last call.
+    fn update(&mut self) {
+        let bytes = {
+            let mut frame = self.frame.lock().unwrap();
+            if frame.sequence == self.sequence {
+                return;
+            }
+            self.sequence = frame.sequence;
+            frame.bytes.take()
+        };
+
+        self.picture = match bytes {
+            Some(bytes) => {
+                let picture = Picture::from_bytes(&bytes);
+                if picture.is_none() {
+                    eprintln!("Error deserializing ipc frame {}", self.sequence);
+                }
+                picture
+            }
+            None => None,
+        };
+    }
+}
+
+impl Drop for IpcRenderer {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+impl Renderer for IpcRenderer {
+    fn render(&mut self, canvas: &mut Canvas, _: &FrameInfo) {
+        self.update();
+        if let Some(picture) = &self.picture {
+            canvas.draw_picture(picture, None, None);
+        }
+    }
+
+    fn attach_proxy(&mut self, proxy: BackendProxy) {
+        self.frame.lock().unwrap().proxy = Some(proxy);
+    }
+}
+
+fn serve_client(mut stream: UnixStream, frame: Arc<Mutex<SharedFrame>>) {
+    loop {
+        let mut len = [0u8; 4];
+        match stream.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return,
+            Err(err) => {
+                eprintln!("Error reading ipc message: {err}");
+                return;
+            }
+        }
+
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_MESSAGE_LEN {
+            eprintln!("Closing the ipc client sending a message of {len} bytes");
+            return;
+        }
+        let bytes = if len == 0 {
+            None
+        } else {
+            let mut bytes = vec![0u8; len];
+            if let Err(err) = stream.read_exact(&mut bytes) {
+                eprintln!("Error reading ipc message: {err}");
+                return;
+            }
+            Some(bytes)
+        };
+
+        let mut frame = frame.lock().unwrap();
+        frame.sequence += 1;
+        frame.bytes = bytes;
+        if let Some(proxy) = &frame.proxy {
+            proxy.request_redraw();
+        }
+    }
+}
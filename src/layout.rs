@@ -0,0 +1,240 @@
+use skia_safe::{Matrix, Rect};
+use taffy::prelude::*;
+
+use crate::{
+    locale::Locale,
+    scene::{self, Scene},
+    text_cache::{TextAppearance, TextCache},
+    widget::{WidgetId, WidgetPanel},
+};
+
+pub use taffy::{NodeId as LayoutNodeId, Style as LayoutStyle};
+
+/// The content measured by the layout, e.g. text wrapping to the available width.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutContent {
+    Text { text: String, font_size: f32 },
+}
+
+/// A flexbox/grid layout tree, the rects are resolved when the styles or the available
+/// size changed and applied to widgets and scene nodes.
+pub struct LayoutTree {
+    taffy: TaffyTree<LayoutContent>,
+    texts: TextCache,
+    /// The leaves measured by their content.
+    text_nodes: Vec<NodeId>,
+    root: Option<NodeId>,
+    size: (f32, f32),
+    widgets: Vec<(WidgetId, NodeId)>,
+    scene_nodes: Vec<(scene::NodeId, NodeId)>,
+}
+
+impl Default for LayoutTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LayoutTree {
+    pub fn new() -> Self {
+        Self {
+            taffy: TaffyTree::new(),
+            texts: TextCache::default(),
+            text_nodes: vec![],
+            root: None,
+            size: (0., 0.),
+            widgets: vec![],
+            scene_nodes: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, style: LayoutStyle, children: &[NodeId]) -> NodeId {
+        self.taffy
+            .new_with_children(style, children)
+            .expect("Could not add layout node")
+    }
+
+    /// Add a leaf sized by the text it holds.
+    pub fn add_text(
+        &mut self,
+        style: LayoutStyle,
+        text: impl Into<String>,
+        font_size: f32,
+    ) -> NodeId {
+        let content = LayoutContent::Text {
+            text: text.into(),
+            font_size,
+        };
+        let node = self
+            .taffy
+            .new_leaf_with_context(style, content)
+            .expect("Could not add layout node");
+        self.text_nodes.push(node);
+        node
+    }
+
+    #[inline]
+    pub fn add_child(&mut self, parent: NodeId, child: NodeId) {
+        self.taffy
+            .add_child(parent, child)
+            .expect("Could not add layout child");
+    }
+
+    /// The node laid out in the available size.
+    #[inline]
+    pub fn set_root(&mut self, root: NodeId) {
+        self.root = Some(root);
+        self.taffy.mark_dirty(root).ok();
+    }
+
+    #[inline]
+    pub fn set_style(&mut self, node: NodeId, style: LayoutStyle) {
+        self.taffy
+            .set_style(node, style)
+            .expect("Could not set layout style");
+    }
+
+    /// Replace the measured content, e.g. when the text changed.
+    #[inline]
+    pub fn set_content(&mut self, node: NodeId, content: Option<LayoutContent>) {
+        if content.is_some() && !self.text_nodes.contains(&node) {
+            self.text_nodes.push(node);
+        }
+        self.taffy
+            .set_node_context(node, content)
+            .expect("Could not set layout content");
+    }
+
+    #[inline]
+    pub fn remove(&mut self, node: NodeId) {
+        self.taffy.remove(node).ok();
+        self.text_nodes.retain(|text_node| *text_node != node);
+        self.widgets.retain(|(_, bound)| *bound != node);
+        self.scene_nodes.retain(|(_, bound)| *bound != node);
+        if self.root == Some(node) {
+            self.root = None;
+        }
+    }
+
+    /// Measure the texts for the locale, the layout is resolved again on the next `compute`.
+    pub fn set_locale(&mut self, locale: Locale) {
+        if self.texts.set_locale(locale) {
+            for node in &self.text_nodes {
+                self.taffy.mark_dirty(*node).ok();
+            }
+        }
+    }
+
+    /// Position the widget at the absolute rect of the node.
+    #[inline]
+    pub fn bind_widget(&mut self, widget: WidgetId, node: NodeId) {
+        self.widgets.push((widget, node));
+    }
+
+    /// Translate the scene node to the node location, relative to its parent like the
+    /// scene node transforms.
+    #[inline]
+    pub fn bind_scene_node(&mut self, scene_node: scene::NodeId, node: NodeId) {
+        self.scene_nodes.push((scene_node, node));
+    }
+
+    /// Resolve the layout if a style, a content or the available size changed, returns
+    /// whether it was resolved.
+    pub fn compute(&mut self, size: (f32, f32)) -> bool {
+        let Some(root) = self.root else {
+            return false;
+        };
+        if size == self.size && !self.taffy.dirty(root).unwrap_or(true) {
+            return false;
+        }
+        self.size = size;
+
+        let texts = &mut self.texts;
+        let available = Size {
+            width: AvailableSpace::Definite(size.0),
+            height: AvailableSpace::Definite(size.1),
+        };
+        self.taffy
+            .compute_layout_with_measure(root, available, |known, available, _, content| {
+                match content {
+                    Some(LayoutContent::Text { text, font_size }) => {
+                        measure_text(texts, text, *font_size, known, available)
+                    }
+                    None => Size::ZERO,
+                }
+            })
+            .expect("Could not compute layout");
+        true
+    }
+
+    /// The rect relative to the parent node.
+    pub fn rect(&self, node: NodeId) -> Rect {
+        let layout = self.taffy.layout(node).expect("No such layout node");
+        Rect::from_xywh(
+            layout.location.x,
+            layout.location.y,
+            layout.size.width,
+            layout.size.height,
+        )
+    }
+
+    pub fn absolute_rect(&self, node: NodeId) -> Rect {
+        let mut rect = self.rect(node);
+        let mut parent = self.taffy.parent(node);
+        while let Some(node) = parent {
+            let parent_rect = self.rect(node);
+            rect.offset((parent_rect.left, parent_rect.top));
+            parent = self.taffy.parent(node);
+        }
+        rect
+    }
+
+    pub fn apply_to_widgets(&self, panel: &mut WidgetPanel) {
+        for (widget, node) in self.widgets.iter() {
+            panel.widget_mut(*widget).rect = self.absolute_rect(*node);
+        }
+    }
+
+    pub fn apply_to_scene(&self, scene: &mut Scene) {
+        for (scene_node, node) in self.scene_nodes.iter() {
+            let rect = self.rect(*node);
+            if let Some(scene_node) = scene.node_mut(*scene_node) {
+                scene_node.transform = Matrix::translate((rect.left, rect.top));
+            }
+        }
+    }
+}
+
+fn measure_text(
+    texts: &mut TextCache,
+    text: &str,
+    font_size: f32,
+    known: Size<Option<f32>>,
+    available: Size<AvailableSpace>,
+) -> Size<f32> {
+    let appearance = TextAppearance {
+        font_size,
+        ..Default::default()
+    };
+
+    // The intrinsic widths are only known after a first layout.
+    let paragraph = texts.paragraph(text, &appearance, f32::INFINITY);
+    let width = known
+        .width
+        .unwrap_or(match available.width {
+            AvailableSpace::Definite(width) => paragraph
+                .max_intrinsic_width()
+                .min(width)
+                .max(paragraph.min_intrinsic_width()),
+            AvailableSpace::MinContent => paragraph.min_intrinsic_width(),
+            AvailableSpace::MaxContent => paragraph.max_intrinsic_width(),
+        })
+        .ceil();
+    Size {
+        width,
+        height: known
+            .height
+            .unwrap_or_else(|| texts.paragraph(text, &appearance, width).height().ceil()),
+    }
+}
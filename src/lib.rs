@@ -0,0 +1,94 @@
+pub mod adjustment;
+pub mod arena;
+pub mod backend;
+pub mod batch;
+pub mod bindings;
+pub mod blur;
+pub mod brush;
+pub mod camera;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod capture;
+#[cfg(feature = "independent_ui")]
+pub mod channel;
+pub mod chart;
+pub mod clock;
+pub mod color;
+pub mod color_picker;
+pub mod color_vision;
+pub mod config;
+pub mod context_menu;
+pub mod cross_fade;
+pub mod culling;
+pub mod decorations;
+pub mod diagnostics;
+pub mod document;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod egl;
+pub mod fbo;
+pub mod focus;
+pub mod frame_hash;
+pub mod geometry;
+pub mod gl_info;
+pub mod glyphs;
+pub mod gpu;
+pub mod headless;
+pub mod idle;
+pub mod input;
+pub mod interpolation;
+pub mod invalidation;
+#[cfg(unix)]
+pub mod ipc;
+pub mod layout;
+pub mod locale;
+pub mod minimap;
+pub mod pacer;
+pub mod path_cache;
+pub mod path_ops;
+pub mod print;
+pub mod profiler;
+pub mod proxy;
+#[cfg(feature = "profiling")]
+pub mod puffin_overlay;
+pub mod quality;
+pub mod readback;
+pub mod record;
+pub mod recovery;
+pub mod renderer;
+pub mod resolution;
+pub mod ruler;
+pub mod safe_area;
+pub mod scene;
+pub mod scene_manager;
+pub mod screenshot;
+pub mod skp;
+pub mod snap;
+pub mod spring;
+pub mod stats;
+pub mod stream;
+pub mod stress;
+pub mod supervisor;
+pub mod surface;
+pub mod text_cache;
+pub mod text_effects;
+pub mod text_metrics;
+pub mod theme;
+#[cfg(feature = "independent_ui")]
+pub mod thread;
+pub mod timeline;
+pub mod tooltip;
+pub mod transition;
+pub mod triple_buffer;
+pub mod upload;
+pub mod user_event;
+#[cfg(feature = "video")]
+pub mod video;
+pub mod viewport;
+#[cfg(feature = "independent_ui")]
+pub mod watchdog;
+#[cfg(feature = "webcam")]
+pub mod webcam;
+pub mod widget;
+pub mod window;
+
+pub type SkiaSurface = skia_safe::Surface;
@@ -0,0 +1,289 @@
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use skia_safe::{FontMgr, FontStyle, Typeface};
+
+/// The languages written right to left.
+const RTL_LANGUAGES: &[&str] = &[
+    "ar", "dv", "fa", "he", "iw", "ku", "ps", "sd", "ug", "ur", "yi",
+];
+
+/// The language and region of the text, as a BCP 47 tag like `en-US`, `zh-Hant-TW` or
+/// `ar-EG`. It is passed to the paragraph layout, which picks the line-breaking rules and
+/// the fallback fonts for it, e.g. the Japanese or the Chinese shapes of the Han
+/// characters, and decides the default direction and digits of the text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locale {
+    tag: String,
+    /// Replace the ASCII digits with the native digits of the script, if it has any.
+    pub native_digits: bool,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            tag: "en-US".to_string(),
+            native_digits: false,
+        }
+    }
+}
+
+impl Locale {
+    /// The locale of the environment, from the `LC_ALL`, `LC_MESSAGES` and `LANG`
+    /// variables, e.g. `de_DE.UTF-8`, the default one otherwise.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| {
+                // Drop the encoding and the modifier, `C` and `POSIX` aren't languages.
+                let tag = value.split(['.', '@']).next().unwrap_or_default();
+                tag.replace('_', "-").parse().ok()
+            })
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn with_native_digits(mut self, native_digits: bool) -> Self {
+        self.native_digits = native_digits;
+        self
+    }
+
+    #[inline]
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// The lowercase language subtag, e.g. `zh`.
+    #[inline]
+    pub fn language(&self) -> &str {
+        self.subtags().next().unwrap_or_default()
+    }
+
+    /// The script subtag, e.g. `Hant`.
+    pub fn script(&self) -> Option<&str> {
+        self.subtags().nth(1).filter(|subtag| is_script(subtag))
+    }
+
+    /// The uppercase region subtag, e.g. `TW`, right after the language or the script.
+    pub fn region(&self) -> Option<&str> {
+        let position = if self.script().is_some() { 2 } else { 1 };
+        self.subtags()
+            .nth(position)
+            .filter(|subtag| is_region(subtag))
+    }
+
+    /// Whether the paragraphs run right to left by default.
+    #[inline]
+    pub fn is_rtl(&self) -> bool {
+        RTL_LANGUAGES.contains(&self.language())
+    }
+
+    /// The zero of the native digits, `None` for the scripts using the ASCII digits.
+    pub fn native_zero(&self) -> Option<char> {
+        match self.language() {
+            // The Maghreb writes the ASCII digits.
+            "ar" if matches!(self.region(), Some("DZ" | "MA" | "TN" | "LY" | "EH")) => None,
+            "ar" => Some('\u{0660}'),
+            "fa" | "ur" | "ps" => Some('\u{06F0}'),
+            "bn" | "as" => Some('\u{09E6}'),
+            "mr" | "ne" => Some('\u{0966}'),
+            "my" => Some('\u{1040}'),
+            "km" => Some('\u{17E0}'),
+            "lo" => Some('\u{0ED0}'),
+            "dz" => Some('\u{0F20}'),
+            _ => None,
+        }
+    }
+
+    /// The text with its ASCII digits replaced by the native ones when `native_digits` is
+    /// set. The native digits are single UTF-16 units like the ASCII ones, so the layout
+    /// offsets of the shaped text match the original.
+    pub fn shape_digits<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let zero = match self.native_zero() {
+            Some(zero) if self.native_digits && text.contains(|c: char| c.is_ascii_digit()) => zero,
+            _ => return Cow::Borrowed(text),
+        };
+        Cow::Owned(
+            text.chars()
+                .map(|c| {
+                    if c.is_ascii_digit() {
+                        char::from_u32(zero as u32 + (c as u32 - '0' as u32)).unwrap_or(c)
+                    } else {
+                        c
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The font families tried before the default ones, for the scripts whose characters
+    /// are shared between languages or often missing from the default font.
+    pub fn font_families(&self) -> &'static [&'static str] {
+        match (self.language(), self.script(), self.region()) {
+            ("ja", ..) => &["Noto Sans CJK JP", "Hiragino Sans", "Yu Gothic", "Meiryo"],
+            ("ko", ..) => &["Noto Sans CJK KR", "Apple SD Gothic Neo", "Malgun Gothic"],
+            ("zh", Some("Hant"), _) | ("zh", None, Some("TW" | "HK" | "MO")) => {
+                &["Noto Sans CJK TC", "PingFang TC", "Microsoft JhengHei"]
+            }
+            ("zh", ..) => &["Noto Sans CJK SC", "PingFang SC", "Microsoft YaHei"],
+            ("ar" | "fa" | "ur" | "ps", ..) => &["Noto Sans Arabic", "Geeza Pro", "Segoe UI"],
+            ("he" | "iw" | "yi", ..) => &["Noto Sans Hebrew", "Arial Hebrew", "Segoe UI"],
+            ("th", ..) => &["Noto Sans Thai", "Thonburi", "Leelawadee UI"],
+            ("hi" | "mr" | "ne", ..) => {
+                &["Noto Sans Devanagari", "Kohinoor Devanagari", "Nirmala UI"]
+            }
+            ("bn" | "as", ..) => &["Noto Sans Bengali", "Kohinoor Bangla", "Nirmala UI"],
+            _ => &[],
+        }
+    }
+
+    /// The typeface of the short texts drawn with `Canvas::draw_str`, which doesn't fall back
+    /// on other fonts like the paragraphs do. It covers the native digits when they are on
+    /// and prefers the font families of the locale, `None` for the default typeface.
+    pub fn typeface(&self) -> Option<Typeface> {
+        let fonts = FontMgr::new();
+        if let Some(zero) = self.native_zero().filter(|_| self.native_digits) {
+            return fonts.match_family_style_character(
+                "",
+                FontStyle::normal(),
+                &[self.tag()],
+                zero as i32,
+            );
+        }
+        self.font_families()
+            .iter()
+            .find_map(|family| fonts.match_family_style(family, FontStyle::normal()))
+    }
+
+    fn subtags(&self) -> impl Iterator<Item = &str> {
+        self.tag.split('-')
+    }
+}
+
+#[inline]
+fn is_script(subtag: &str) -> bool {
+    subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+#[inline]
+fn is_region(subtag: &str) -> bool {
+    (subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+        || (subtag.len() == 3 && subtag.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Parse a language tag, normalizing the case of its subtags, e.g. `zh_hant_tw` to
+    /// `zh-Hant-TW`. Only the script and the region right after the language are title and
+    /// upper cased, the variants and the extensions are lower cased, e.g. `-u-nu-arab`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut subtags = s.split(['-', '_']);
+        let language = subtags.next().unwrap_or_default();
+        if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return Err(format!("Invalid language in locale `{s}`"));
+        }
+        let mut tag = language.to_ascii_lowercase();
+        let (mut script_allowed, mut region_allowed) = (true, true);
+        for subtag in subtags {
+            if subtag.is_empty()
+                || subtag.len() > 8
+                || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(format!("Invalid subtag `{subtag}` in locale `{s}`"));
+            }
+            tag.push('-');
+            if script_allowed && is_script(subtag) {
+                tag.push_str(&subtag[..1].to_ascii_uppercase());
+                tag.push_str(&subtag[1..].to_ascii_lowercase());
+                script_allowed = false;
+            } else if region_allowed && is_region(subtag) {
+                tag.push_str(&subtag.to_ascii_uppercase());
+                (script_allowed, region_allowed) = (false, false);
+            } else {
+                tag.push_str(&subtag.to_ascii_lowercase());
+                (script_allowed, region_allowed) = (false, false);
+            }
+        }
+        Ok(Self {
+            tag,
+            native_digits: false,
+        })
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(tag: &str) -> Locale {
+        tag.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_normalizes_the_case() {
+        assert_eq!(locale("zh_hant_tw").tag(), "zh-Hant-TW");
+        assert_eq!(locale("EN-us").tag(), "en-US");
+        assert_eq!(locale("es-419").tag(), "es-419");
+    }
+
+    #[test]
+    fn parse_cases_the_region_only_in_its_position() {
+        assert_eq!(locale("ar-EG-u-NU-ARAB").tag(), "ar-EG-u-nu-arab");
+        assert_eq!(locale("de-ch-1996").tag(), "de-CH-1996");
+        assert_eq!(locale("sl-rozaj-biske").tag(), "sl-rozaj-biske");
+        assert_eq!(locale("en-x-ab").tag(), "en-x-ab");
+    }
+
+    #[test]
+    fn parse_rejects_invalid_tags() {
+        assert!("".parse::<Locale>().is_err());
+        assert!("e".parse::<Locale>().is_err());
+        assert!("en--US".parse::<Locale>().is_err());
+        assert!("en-US!".parse::<Locale>().is_err());
+        assert!("en-abcdefghi".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn subtags() {
+        let traditional = locale("zh-Hant-TW");
+        assert_eq!(traditional.language(), "zh");
+        assert_eq!(traditional.script(), Some("Hant"));
+        assert_eq!(traditional.region(), Some("TW"));
+
+        let extended = locale("en-u-nu-latn");
+        assert_eq!(extended.script(), None);
+        assert_eq!(extended.region(), None);
+    }
+
+    #[test]
+    fn direction_and_fonts() {
+        assert!(locale("he-IL").is_rtl());
+        assert!(!locale("en-US").is_rtl());
+        assert_eq!(locale("zh-HK").font_families()[0], "Noto Sans CJK TC");
+        assert_eq!(locale("zh-CN").font_families()[0], "Noto Sans CJK SC");
+    }
+
+    #[test]
+    fn native_digits() {
+        let arabic = locale("ar-EG").with_native_digits(true);
+        assert_eq!(arabic.shape_digits("v1.20"), "v\u{0661}.\u{0662}\u{0660}");
+        assert_eq!(
+            locale("ar-MA").with_native_digits(true).shape_digits("12"),
+            "12"
+        );
+        assert_eq!(locale("ar-EG").shape_digits("12"), "12");
+        assert!(matches!(
+            locale("en-US").with_native_digits(true).shape_digits("12"),
+            Cow::Borrowed(_)
+        ));
+    }
+}
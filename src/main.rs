@@ -1,152 +1,472 @@
-pub mod backend;
-pub mod renderer;
-
-pub type SkiaSurface = skia_safe::Surface;
-
 use std::{
-    num::NonZeroU32,
-    sync::Arc,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-use glutin::{
-    config::{ConfigTemplateBuilder, GlConfig},
-    context::{ContextApi, ContextAttributesBuilder, Version},
-    display::{GetGlDisplay, GlDisplay},
-    surface::{SurfaceAttributesBuilder, WindowSurface},
-};
-use glutin_winit::DisplayBuilder;
-use raw_window_handle::HasRawWindowHandle;
+use skia_safe::{Color, Rect};
 use winit::{
     dpi::LogicalSize,
-    event::{Event, KeyEvent, Modifiers, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
+    event::{ElementState, Event, KeyEvent, Modifiers, WindowEvent},
+    event_loop::{ControlFlow, EventLoopBuilder},
     window::WindowBuilder,
 };
 
-use crate::backend::{Backend, GlCtx, GlEnv};
+#[cfg(feature = "independent_ui")]
+use skia_gl::watchdog::{WatchdogAction, WatchdogConfig};
+use skia_gl::{
+    adjustment::OutputAdjustment,
+    backend::Backend,
+    bindings::{Action, Bindings, Input},
+    brush::BrushRenderer,
+    camera::CameraConfig,
+    config::{Config, DEFAULT_CONFIG_PATH},
+    context_menu::MenuEntry,
+    cross_fade::{CrossFadeConfig, GlobalChange},
+    decorations::DragRegions,
+    frame_hash::{FrameHashConfig, FrameHashMode},
+    geometry::SavedGeometry,
+    glyphs::GlyphSet,
+    gpu::GpuPreference,
+    headless::HeadlessOptions,
+    input::{InputEvent, MouseButton},
+    locale::Locale,
+    print::PrintOptions,
+    proxy::{BackendEvent, BackendProxy},
+    quality::QualityPolicy,
+    record::{InputRecorder, InputReplayer, ReplayTiming},
+    recovery::RecoveryConfig,
+    renderer::{DemoRenderer, Renderer},
+    resolution::{RenderScale, MIN_RENDER_SCALE},
+    safe_area::{Insets, SafeAreaConfig},
+    screenshot::ScreenshotConfig,
+    skp::SkpRenderer,
+    stats::SlowFrameConfig,
+    stream::{StreamConfig, StreamEncoding},
+    stress::{StressConfig, StressTest},
+    supervisor::Supervisor,
+    surface::{ConfigRequest, SurfaceConfig},
+    theme::Theme,
+    viewport::{Viewport, ViewportLayout},
+    window::create_window,
+};
 
 fn main() {
-    let el = EventLoop::new().expect("Failed to create event loop");
-    let winit_window_builder = WindowBuilder::new()
-        .with_title("rust-skia-gl-window")
-        .with_inner_size(LogicalSize::new(800, 800));
-
-    let template = ConfigTemplateBuilder::new()
-        .with_alpha_size(8)
-        .with_transparency(true);
-
-    let display_builder = DisplayBuilder::new().with_window_builder(Some(winit_window_builder));
-    let (window, gl_config) = display_builder
-        .build(&el, template, |configs| {
-            // Find the config with the minimum number of samples. Usually Skia takes care of
-            // anti-aliasing and may not be able to create appropriate Surfaces for samples > 0.
-            // See https://github.com/rust-skia/rust-skia/issues/782
-            // And https://github.com/rust-skia/rust-skia/issues/764
-            configs
-                .reduce(|accum, config| {
-                    let transparency_check = config.supports_transparency().unwrap_or(false)
-                        & !accum.supports_transparency().unwrap_or(false);
-
-                    if transparency_check || config.num_samples() < accum.num_samples() {
-                        config
-                    } else {
-                        accum
-                    }
-                })
-                .unwrap()
-        })
-        .unwrap();
-    println!("Picked a config with {} samples", gl_config.num_samples());
-    let window = Arc::new(window.expect("Could not create window with OpenGL context"));
-    let raw_window_handle = window.raw_window_handle();
-
-    // The context creation part. It can be created before surface and that's how
-    // it's expected in multithreaded + multiwindow operation mode, since you
-    // can send NotCurrentContext, but not Surface.
-    let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
-
-    // Since glutin by default tries to create OpenGL core context, which may not be
-    // present we should try gles.
-    let fallback_context_attributes = ContextAttributesBuilder::new()
-        .with_context_api(ContextApi::Gles(None))
-        .build(Some(raw_window_handle));
-
-    // There are also some old devices that support neither modern OpenGL nor GLES.
-    // To support these we can try and create a 2.1 context.
-    let legacy_context_attributes = ContextAttributesBuilder::new()
-        .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 1))))
-        .build(Some(raw_window_handle));
-
-    let not_current_gl_context = unsafe {
-        gl_config
-            .display()
-            .create_context(&gl_config, &context_attributes)
-            .unwrap_or_else(|_| {
-                gl_config
-                    .display()
-                    .create_context(&gl_config, &fallback_context_attributes)
-                    .unwrap_or_else(|_| {
-                        gl_config
-                            .display()
-                            .create_context(&gl_config, &legacy_context_attributes)
-                            .expect("failed to create context")
-                    })
-            })
+    let args: Vec<String> = std::env::args().collect();
+    let has_arg = |name: &str| args.iter().any(|arg| arg == name);
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
     };
 
-    let (width, height): (u32, u32) = window.inner_size().into();
+    let config = match arg_value("--config") {
+        Some(path) => Config::load(path).expect("Invalid config file"),
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+            Config::load(DEFAULT_CONFIG_PATH).expect("Invalid config file")
+        }
+        None => Config::default(),
+    };
+    let mut bindings = config.bindings().expect("Invalid bindings");
+    if has_arg("--context-menu") {
+        bindings.bind(
+            "MouseRight".parse().expect("Invalid trigger"),
+            Action::ContextMenu,
+        );
+    }
 
-    let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-        raw_window_handle,
-        NonZeroU32::new(width).unwrap(),
-        NonZeroU32::new(height).unwrap(),
-    );
+    let split = has_arg("--split");
+    let renderer: Box<dyn Renderer> = if let Some(path) = arg_value("--skp") {
+        Box::new(SkpRenderer::open(path).expect("Could not load skp file"))
+    } else if has_arg("--brush") {
+        Box::<BrushRenderer>::default()
+    } else if split {
+        Box::new(
+            ViewportLayout::new()
+                .with_viewport(Viewport::new(
+                    Rect::new(0., 0., 0.7, 1.),
+                    Box::<DemoRenderer>::default(),
+                ))
+                .with_viewport(
+                    Viewport::new(Rect::new(0.7, 0., 1., 0.5), Box::<DemoRenderer>::default())
+                        .with_background(Color::from(0xff_eeeeee))
+                        .with_border(Color::GRAY),
+                ),
+        )
+    } else {
+        Box::<DemoRenderer>::default()
+    };
+    #[cfg(unix)]
+    let renderer: Box<dyn Renderer> = match arg_value("--ipc") {
+        Some(path) => {
+            Box::new(skia_gl::ipc::IpcRenderer::bind(path).expect("Could not bind ipc socket"))
+        }
+        None => renderer,
+    };
+    #[cfg(feature = "webcam")]
+    let (renderer, _webcam) = if has_arg("--webcam") {
+        let webcam = skia_gl::webcam::Webcam::open(0, 4).expect("Could not open webcam");
+        let renderer: Box<dyn Renderer> = Box::new(skia_gl::video::VideoRenderer::new(
+            webcam.sink().clone(),
+            Some(renderer),
+        ));
+        (renderer, Some(webcam))
+    } else {
+        (renderer, None)
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if has_arg("--list-gpus") {
+        for device in skia_gl::egl::devices() {
+            println!(
+                "{}: {} ({})",
+                device.index,
+                device.name.as_deref().unwrap_or("unknown"),
+                device.vendor.as_deref().unwrap_or("unknown vendor")
+            );
+        }
+        return;
+    }
+    if has_arg("--headless") {
+        let mut renderer = renderer;
+        let options = HeadlessOptions {
+            frames: arg_value("--frames").map_or(1, |frames| {
+                frames.parse().expect("Invalid number of frames")
+            }),
+            out_dir: arg_value("--out").map(PathBuf::from),
+            stream: arg_value("--stream").map(|addr| StreamConfig {
+                addr: addr.parse().expect("Invalid stream address"),
+                encoding: StreamEncoding::default(),
+            }),
+            gpu_device: arg_value("--gpu-device")
+                .map(|index| index.parse().expect("Invalid gpu device index")),
+            ..Default::default()
+        };
+        skia_gl::headless::run(renderer.as_mut(), &options).expect("Headless rendering failed");
+        return;
+    }
 
-    let gl_surface = unsafe {
-        gl_config
-            .display()
-            .create_window_surface(&gl_config, &attrs)
-            .expect("Could not create gl window surface")
+    let gpu_preference: GpuPreference = arg_value("--gpu")
+        .map(|gpu| gpu.parse().expect("Invalid gpu preference"))
+        .unwrap_or_default();
+    gpu_preference.apply();
+
+    let el = EventLoopBuilder::<BackendEvent>::with_user_event()
+        .build()
+        .expect("Failed to create event loop");
+    let mut renderer = renderer;
+    renderer.attach_proxy(BackendProxy::new(el.create_proxy()));
+    let undecorated = has_arg("--undecorated");
+    let transparent = has_arg("--transparent");
+    let winit_window_builder = WindowBuilder::new()
+        .with_title("rust-skia-gl-window")
+        .with_inner_size(LogicalSize::new(800, 800))
+        .with_decorations(!undecorated)
+        .with_transparent(transparent);
+    let transparent_titlebar = has_arg("--transparent-titlebar");
+    #[cfg(target_os = "macos")]
+    let winit_window_builder = {
+        use winit::platform::macos::WindowBuilderExtMacOS;
+        winit_window_builder
+            .with_titlebar_transparent(transparent_titlebar)
+            .with_fullsize_content_view(transparent_titlebar)
+            .with_title_hidden(transparent_titlebar)
+    };
+    let geometry_path = arg_value("--geometry").map(PathBuf::from);
+    let winit_window_builder = match geometry_path.as_ref().filter(|path| path.exists()) {
+        Some(path) => match SavedGeometry::load(path) {
+            Ok(geometry) => geometry.apply(winit_window_builder, el.available_monitors()),
+            Err(err) => {
+                eprintln!("Error loading the window geometry: {err}");
+                winit_window_builder
+            }
+        },
+        None => winit_window_builder,
     };
 
-    let gl_env = Arc::new(GlEnv::new(
-        gl_surface,
-        GlCtx::new(not_current_gl_context),
-        gl_config,
-    ));
-    let mut backend = Backend::new(window, gl_env);
+    let color_type = arg_value("--color-type");
+    let config_request = ConfigRequest {
+        samples: arg_value("--samples").map_or(0, |samples| {
+            samples.parse().expect("Invalid number of samples")
+        }),
+        srgb: has_arg("--srgb"),
+        color_type: color_type
+            .map(|color_type| color_type.parse().expect("Invalid surface color type"))
+            .unwrap_or_default(),
+        hardware_accelerated: gpu_preference.prefers_hardware_acceleration(),
+        strict: has_arg("--strict-config"),
+        ..Default::default()
+    };
+    let (window, gl_env) =
+        create_window(&el, winit_window_builder, config_request).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(1);
+        });
+    let mut backend = Backend::new(window, gl_env.clone(), renderer);
+    // The extra windows render the demo, each on a backend of its own.
+    let mut supervisor = Supervisor::new();
+    let windows = arg_value("--windows").map_or(1, |windows| {
+        windows.parse().expect("Invalid number of windows")
+    });
+    for index in 1..windows {
+        let window_builder = WindowBuilder::new()
+            .with_title(format!("rust-skia-gl-window {}", index + 1))
+            .with_inner_size(LogicalSize::new(800, 800));
+        match create_window(&el, window_builder, config_request) {
+            Ok((window, gl_env)) => {
+                supervisor.insert(Backend::new(window, gl_env, Box::<DemoRenderer>::default()));
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    // The info is queried once the context was loaded, on the render thread by default.
+    let mut print_gl_info = has_arg("--gl-info");
+    if split {
+        backend.set_camera_config(CameraConfig::disabled());
+    } else {
+        backend.set_camera_config(CameraConfig {
+            rotate: has_arg("--rotate"),
+            // The demo pans with the left button, the brush draws with it.
+            drag_button: (!has_arg("--brush")).then_some(MouseButton::Left),
+            ..Default::default()
+        });
+    }
+    let typography = Theme::default().typography;
+    backend.prewarm_glyphs(vec![GlyphSet::ascii([
+        typography.small_font_size,
+        typography.font_size,
+        typography.heading_font_size,
+    ])]);
+    backend.set_geometry_persistence(geometry_path);
+    let msaa_resolve = arg_value("--msaa-resolve");
+    if color_type.is_some() || msaa_resolve.is_some() {
+        backend.set_surface_config(SurfaceConfig {
+            color_type: color_type
+                .map(|color_type| color_type.parse().expect("Invalid surface color type"))
+                .unwrap_or_default(),
+            msaa_resolve: msaa_resolve
+                .map(|resolve| resolve.parse().expect("Invalid msaa resolve"))
+                .unwrap_or_default(),
+        });
+    }
+    if has_arg("--dither") {
+        backend.set_dithering(true);
+    }
+    if has_arg("--no-paint-aa") {
+        backend.set_paint_antialiasing(false);
+    }
+    if has_arg("--degrade-quality") {
+        backend.set_quality_policy(Some(QualityPolicy::default()));
+    }
+    if has_arg("--offscreen") {
+        backend.set_offscreen_rendering(true);
+    }
+    if has_arg("--on-demand") {
+        backend.set_on_demand(true);
+    }
+    if let Some(scale) = arg_value("--render-scale") {
+        backend.set_render_scale(match scale.as_str() {
+            "auto" => RenderScale::auto(MIN_RENDER_SCALE, 1.),
+            scale => RenderScale::fixed(scale.parse().expect("Invalid render scale")),
+        });
+    }
+    let taskbar_inset = arg_value("--taskbar-inset")
+        .map_or(0., |inset| inset.parse().expect("Invalid taskbar inset"));
+    backend.set_safe_area_config(SafeAreaConfig {
+        transparent_titlebar,
+        taskbar: Insets::new(0., 0., taskbar_inset, 0.),
+        ..Default::default()
+    });
+    if transparent {
+        backend.set_transparent(true);
+        backend.set_blur_behind(has_arg("--blur"));
+    }
+    if undecorated {
+        backend.set_drag_regions(Some(
+            DragRegions::new()
+                .with_caption(Rect::from_xywh(0., 0., f32::MAX, 32.))
+                .with_border(6.),
+        ));
+    }
+    if let Some(color_vision) = arg_value("--color-vision") {
+        backend.set_color_vision(color_vision.parse().expect("Invalid color vision"));
+    }
+    let parse_adjustment = |name: &str, default: f32| {
+        arg_value(name).map_or(default, |value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid {name} value"))
+        })
+    };
+    backend.set_output_adjustment(OutputAdjustment {
+        gamma: parse_adjustment("--gamma", 1.),
+        brightness: parse_adjustment("--brightness", 0.),
+        contrast: parse_adjustment("--contrast", 1.),
+    });
+    if let Some(pattern) = arg_value("--diagnostics") {
+        backend.set_diagnostics(Some(pattern.parse().expect("Invalid diagnostic pattern")));
+    }
+    if has_arg("--system-theme") {
+        backend.set_follow_system_theme(true);
+    }
+    backend.set_locale(
+        arg_value("--locale")
+            .map(|locale| locale.parse().expect("Invalid locale"))
+            .unwrap_or_else(Locale::from_env)
+            .with_native_digits(has_arg("--native-digits")),
+    );
+    if let Some(millis) = arg_value("--cross-fade-ms") {
+        backend.set_cross_fade(Some(CrossFadeConfig::new(
+            Duration::from_millis(millis.parse().expect("Invalid cross fade duration")),
+            [GlobalChange::Theme],
+        )));
+    }
+    if let Some(dir) = arg_value("--screenshot-dir") {
+        backend.set_screenshot_config(ScreenshotConfig {
+            dir: PathBuf::from(dir),
+            ..Default::default()
+        });
+    }
+    if let Some(threshold) = arg_value("--slow-frame-ms") {
+        backend.set_slow_frame_logging(Some(SlowFrameConfig {
+            threshold: Duration::from_millis(
+                threshold.parse().expect("Invalid slow frame threshold"),
+            ),
+            picture_dir: arg_value("--slow-frame-dir").map(PathBuf::from),
+        }));
+    }
+    if let Some(interval) = arg_value("--save-state-every") {
+        backend.set_state_recovery(Some(RecoveryConfig {
+            interval: interval.parse().expect("Invalid state saving interval"),
+            ..Default::default()
+        }));
+    }
+    #[cfg(feature = "independent_ui")]
+    if let Some(timeout) = arg_value("--watchdog") {
+        backend.set_watchdog(Some(WatchdogConfig {
+            timeout: Duration::from_secs_f32(timeout.parse().expect("Invalid watchdog timeout")),
+            action: arg_value("--watchdog-action")
+                .map(|action| action.parse().expect("Invalid watchdog action"))
+                .unwrap_or_default(),
+        }));
+    }
+    if let Some(path) = arg_value("--record") {
+        backend.set_input_recording(Some(
+            InputRecorder::create(path).expect("Could not create the input recording"),
+        ));
+    }
+    let mut replayer = arg_value("--replay").map(|path| {
+        let timing = match arg_value("--replay-step-ms") {
+            Some(step) => ReplayTiming::FixedStep(Duration::from_millis(
+                step.parse().expect("Invalid replay step"),
+            )),
+            None => ReplayTiming::Realtime,
+        };
+        InputReplayer::open(path, timing).expect("Could not load the input recording")
+    });
+    let mut stress = arg_value("--stress").map(|seed| {
+        let mut config = StressConfig {
+            seed: seed.parse().expect("Invalid stress seed"),
+            ..Default::default()
+        };
+        if let Some(actions) = arg_value("--stress-actions") {
+            config.actions = match actions.as_str() {
+                "forever" => None,
+                actions => Some(actions.parse().expect("Invalid number of stress actions")),
+            };
+        }
+        println!("Running the stress test with seed {}", config.seed);
+        StressTest::new(config)
+    });
+    if let Some(addr) = arg_value("--stream") {
+        backend.stream(Some(StreamConfig {
+            addr: addr.parse().expect("Invalid stream address"),
+            encoding: StreamEncoding::default(),
+        }));
+    }
+    if has_arg("--async-readback") {
+        backend.set_async_readback(true);
+    }
+    if let Some(path) = arg_value("--record-hashes") {
+        backend.set_frame_hashing(Some(FrameHashConfig {
+            mode: FrameHashMode::Record,
+            path: PathBuf::from(path),
+        }));
+    } else if let Some(path) = arg_value("--verify-hashes") {
+        backend.set_frame_hashing(Some(FrameHashConfig {
+            mode: FrameHashMode::Verify,
+            path: PathBuf::from(path),
+        }));
+    }
 
     let mut frame = 0usize;
 
-    let mut previous_frame_start = Instant::now();
     let mut modifiers = Modifiers::default();
 
     el.run(move |event, window_target| {
         let frame_start = Instant::now();
 
-        if let Event::WindowEvent { event, .. } = event {
+        if let Event::UserEvent(event) = event {
+            match event {
+                BackendEvent::Redraw(damage) => backend.invalidate(damage),
+                BackendEvent::User(event) => backend.post_user_event(event),
+            }
+        } else if let Event::LoopExiting = event {
+            supervisor.exit_all(Duration::from_secs(1));
+        } else if let Event::WindowEvent { window_id, event } = event {
+            if supervisor.handle_window_event(window_id, &event) {
+                return;
+            }
+            // The open context menu takes the presses instead of the bindings.
+            let menu_open = backend.is_context_menu_open();
+            if let Some(input) = InputEvent::from_window_event(&event) {
+                backend.handle_input(input);
+            }
+            if let Some(action) = backend.take_menu_action() {
+                run_action(&mut backend, &bindings, action);
+            }
+
             match event {
                 WindowEvent::CloseRequested => {
                     backend.exit();
+                    supervisor.exit_all(Duration::from_secs(1));
                     std::process::exit(0);
                 }
+                WindowEvent::Occluded(occluded) => backend.notify_occluded(occluded),
+                WindowEvent::Moved(_) => backend.notify_moved(),
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    backend.notify_scale_factor(scale_factor)
+                }
+                WindowEvent::ThemeChanged(theme) => backend.notify_system_theme(theme.into()),
                 WindowEvent::Resized(physical_size) => {
                     let size: (u32, u32) = physical_size.into();
                     backend.notify_resize(size);
                 }
                 WindowEvent::ModifiersChanged(new_modifiers) => modifiers = new_modifiers,
                 WindowEvent::KeyboardInput {
-                    event: KeyEvent { logical_key, .. },
+                    event:
+                        KeyEvent {
+                            logical_key, state, ..
+                        },
                     ..
                 } => {
-                    if modifiers.state().super_key() && logical_key == "q" {
-                        backend.exit();
-                        std::process::exit(0);
+                    if state == ElementState::Pressed && !menu_open {
+                        if let Some(action) =
+                            bindings.action(modifiers.state(), Input::Key(logical_key))
+                        {
+                            run_action(&mut backend, &bindings, action);
+                        }
                     }
                     frame = frame.saturating_sub(10);
                     backend.request_redraw();
                 }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                } if !menu_open => {
+                    if let Some(action) = bindings.action(modifiers.state(), Input::Mouse(button)) {
+                        run_action(&mut backend, &bindings, action);
+                    }
+                }
                 WindowEvent::RedrawRequested => {
                     frame += 1;
                     backend.render(frame);
@@ -154,17 +474,117 @@ fn main() {
                 _ => (),
             }
         }
-        let expected_frame_length_seconds = 1.0 / 20.0;
-        let frame_duration = Duration::from_secs_f32(expected_frame_length_seconds);
-
-        if frame_start - previous_frame_start > frame_duration {
-            backend.request_redraw();
-            previous_frame_start = frame_start;
+        if let Some(verification) = backend.take_verification() {
+            println!(
+                "Verified {} frames, {} differed",
+                verification.frames, verification.mismatches
+            );
+            backend.exit();
+            supervisor.exit_all(Duration::from_secs(1));
+            std::process::exit(if verification.mismatches == 0 { 0 } else { 1 });
+        }
+        if print_gl_info {
+            if let Some(info) = gl_env.info() {
+                println!("{info}");
+                print_gl_info = false;
+            }
+        }
+        if backend.frame_pacer().start_frame(frame_start) {
+            if let Some(replayer) = replayer.as_mut() {
+                for event in replayer.advance(frame_start) {
+                    backend.replay(event.event);
+                }
+            }
+            if backend.wants_frame(frame_start) {
+                backend.request_redraw();
+            }
+        }
+        let supervised_frame = supervisor.request_frames(frame_start);
+        #[cfg(feature = "independent_ui")]
+        supervisor.check_render_threads();
+        #[cfg(feature = "independent_ui")]
+        if backend.check_watchdog() == Some(WatchdogAction::Shutdown) {
+            backend.exit();
+            window_target.exit();
+        }
+        if let Some(stress) = stress.as_mut() {
+            stress.step(&mut backend, frame_start);
+            if stress.is_finished() {
+                println!("Passed the {}", stress.describe());
+                backend.exit();
+                window_target.exit();
+            }
         }
 
-        window_target.set_control_flow(ControlFlow::WaitUntil(
-            previous_frame_start + frame_duration,
-        ))
+        // Keep stressing a minimized window, it is restored by a later action, and keep
+        // pacing the other windows.
+        let wake_up = [
+            stress.as_ref().and_then(StressTest::next_action),
+            supervised_frame,
+            (!backend.frame_pacer().is_paused()).then(|| backend.frame_pacer().next_frame_start()),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        window_target.set_control_flow(wake_up.map_or(ControlFlow::Wait, ControlFlow::WaitUntil))
     })
     .expect("run() failed");
 }
+
+fn run_action(backend: &mut Backend, bindings: &Bindings, action: Action) {
+    match action {
+        Action::Quit => {
+            backend.exit();
+            std::process::exit(0);
+        }
+        Action::ToggleFullscreen => backend.toggle_borderless_fullscreen(),
+        Action::ToggleHud => backend.toggle_hud(),
+        Action::ToggleRuler => backend.toggle_ruler(),
+        Action::TogglePause => backend.toggle_clock_pause(),
+        Action::StepFrame => backend.step_frame(),
+        Action::SlowDown => backend.slow_down(),
+        Action::SpeedUp => backend.speed_up(),
+        Action::CycleColorVision => backend.cycle_color_vision(),
+        Action::CycleDiagnostics => backend.cycle_diagnostics(),
+        #[cfg(feature = "profiling")]
+        Action::TogglePuffinOverlay => backend.toggle_puffin_overlay(),
+        #[cfg(not(feature = "profiling"))]
+        Action::TogglePuffinOverlay => {}
+        Action::Print => backend.print(PrintOptions::default()),
+        Action::Screenshot => backend.screenshot(),
+        Action::ContextMenu => backend.open_context_menu(None, action_menu(bindings)),
+    }
+}
+
+/// The actions with their first trigger, grouped like the default bindings.
+fn action_menu(bindings: &Bindings) -> Vec<MenuEntry> {
+    let entry = |action: Action| {
+        let entry = MenuEntry::item(action.label(), action);
+        match bindings.triggers(action).map(ToString::to_string).min() {
+            Some(trigger) => entry.with_shortcut(trigger),
+            None => entry,
+        }
+    };
+    let mut entries = vec![
+        entry(Action::ToggleFullscreen),
+        entry(Action::ToggleHud),
+        entry(Action::ToggleRuler),
+        entry(Action::CycleColorVision),
+        entry(Action::CycleDiagnostics),
+    ];
+    #[cfg(feature = "profiling")]
+    entries.push(entry(Action::TogglePuffinOverlay));
+    entries.extend([
+        MenuEntry::Separator,
+        entry(Action::TogglePause),
+        entry(Action::StepFrame),
+        entry(Action::SlowDown),
+        entry(Action::SpeedUp),
+        MenuEntry::Separator,
+        entry(Action::Print),
+        entry(Action::Screenshot),
+        MenuEntry::Separator,
+        entry(Action::Quit),
+    ]);
+    entries
+}
@@ -0,0 +1,105 @@
+use skia_safe::{Canvas, ClipOp, Color, Paint, PaintStyle, Rect};
+
+use crate::{
+    renderer::{FrameInfo, Renderer},
+    scene::Scene,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Renders the scene, plus the whole scene downscaled into a corner of the window with a
+/// rectangle showing the area visible through the camera.
+pub struct Minimap {
+    scene: Scene,
+    pub corner: Corner,
+    /// The maximum width/height of the minimap in pixels.
+    pub size: f32,
+    pub margin: f32,
+    pub background: Color,
+    pub camera_color: Color,
+}
+
+impl Minimap {
+    pub fn new(scene: Scene) -> Self {
+        Self {
+            scene,
+            corner: Corner::BottomRight,
+            size: 160.,
+            margin: 10.,
+            background: Color::from(0xcc_f0f0f0),
+            camera_color: Color::RED,
+        }
+    }
+
+    #[inline]
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    #[inline]
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// The minimap rect in window pixels for the mapped `area` of the scene.
+    fn rect(&self, area: Rect, size: (i32, i32)) -> Rect {
+        let scale = self.size / area.width().max(area.height());
+        let (width, height) = (area.width() * scale, area.height() * scale);
+        let (window_width, window_height) = (size.0 as f32, size.1 as f32);
+
+        let left = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => self.margin,
+            Corner::TopRight | Corner::BottomRight => window_width - self.margin - width,
+        };
+        let top = match self.corner {
+            Corner::TopLeft | Corner::TopRight => self.margin,
+            Corner::BottomLeft | Corner::BottomRight => window_height - self.margin - height,
+        };
+        Rect::from_xywh(left, top, width, height)
+    }
+}
+
+impl Renderer for Minimap {
+    fn render(&mut self, canvas: &mut Canvas, _: &FrameInfo) {
+        self.scene.draw(canvas);
+    }
+
+    fn render_overlay(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        let visible = info.camera.visible_rect(info.size);
+        let mut area = self.scene.bounds();
+        area.join(visible);
+        if area.is_empty() {
+            return;
+        }
+
+        let rect = self.rect(area, info.size);
+        let scale = rect.width() / area.width();
+
+        canvas.save();
+        canvas.clip_rect(rect, ClipOp::Intersect, true);
+        canvas.clear(self.background);
+        canvas.translate((rect.left, rect.top));
+        canvas.scale((scale, scale));
+        canvas.translate((-area.left, -area.top));
+
+        self.scene.draw(canvas);
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(1.5 / scale);
+        paint.set_color(self.camera_color);
+        canvas.draw_rect(visible, &paint);
+        canvas.restore();
+
+        paint.set_stroke_width(1.);
+        paint.set_color(Color::GRAY);
+        canvas.draw_rect(rect, &paint);
+    }
+}
@@ -0,0 +1,263 @@
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_FRAME_RATE: f32 = 20.;
+
+/// The slack left between the predicted end of the rendering and the vblank.
+const PRESENT_MARGIN: Duration = Duration::from_millis(2);
+
+/// Decides when the next frame starts, at a fixed frame rate. Once presentations are
+/// reported the frames are scheduled to finish just before the next vblank, the vblanks
+/// follow the refresh rate of the monitor, see `set_vblank_rate_millihertz`.
+#[derive(Debug, Clone, Copy)]
+pub struct FramePacer {
+    frame_duration: Duration,
+    /// The frame duration before a refresh rate was adopted, restored on reset.
+    own_frame_duration: Option<Duration>,
+    previous_frame_start: Instant,
+    /// The last presentation, the vblanks follow it every vblank period.
+    vblank: Option<Instant>,
+    /// The refresh period of the monitor, the frame duration when unknown.
+    vblank_period: Option<Duration>,
+    /// The predicted time from the frame start to its presentation.
+    render_time: Duration,
+    /// How late the previous frame started past its deadline.
+    lateness: Duration,
+    paused: bool,
+    /// The first frame after a pause is not late.
+    resumed: bool,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAME_RATE)
+    }
+}
+
+impl FramePacer {
+    pub fn new(frame_rate: f32) -> Self {
+        Self {
+            frame_duration: Duration::from_secs_f32(1. / frame_rate),
+            own_frame_duration: None,
+            previous_frame_start: Instant::now(),
+            vblank: None,
+            vblank_period: None,
+            render_time: Duration::ZERO,
+            lateness: Duration::ZERO,
+            paused: false,
+            resumed: false,
+        }
+    }
+
+    #[inline]
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    /// Replaces an adopted refresh rate for good.
+    #[inline]
+    pub fn set_frame_rate(&mut self, frame_rate: f32) {
+        self.frame_duration = Duration::from_secs_f32(1. / frame_rate);
+        self.own_frame_duration = None;
+    }
+
+    /// Adopt the refresh rate of a monitor or video mode as the frame rate and the vblank
+    /// rate, ignores unknown (zero) rates.
+    #[inline]
+    pub fn set_refresh_rate_millihertz(&mut self, millihertz: u32) {
+        if millihertz > 0 {
+            self.own_frame_duration.get_or_insert(self.frame_duration);
+            self.frame_duration = Duration::from_secs_f32(1000. / millihertz as f32);
+            self.set_vblank_rate_millihertz(millihertz);
+        }
+    }
+
+    /// Go back to the frame rate before the refresh rate was adopted, e.g. when leaving
+    /// exclusive fullscreen. The pause is kept, the vblank rate is the one of the video
+    /// mode until the monitor rate is set again.
+    #[inline]
+    pub fn reset_refresh_rate(&mut self) {
+        if let Some(frame_duration) = self.own_frame_duration.take() {
+            self.frame_duration = frame_duration;
+        }
+    }
+
+    /// The refresh rate of the monitor the presentations follow, independent of the frame
+    /// rate, e.g. 30 frames per second on a 60 Hz monitor. `0` when unknown, the vblanks
+    /// are assumed every frame duration then.
+    #[inline]
+    pub fn set_vblank_rate_millihertz(&mut self, millihertz: u32) {
+        self.vblank_period =
+            (millihertz > 0).then(|| Duration::from_secs_f64(1000. / millihertz as f64));
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop starting frames, e.g. while the window is occluded.
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.resumed |= self.paused && !paused;
+        self.paused = paused;
+    }
+
+    /// How late the previous frame started past its deadline, a whole frame duration or
+    /// more means a frame was dropped. Reset once taken, redraws outside of the pacing
+    /// are not late.
+    #[inline]
+    pub fn take_lateness(&mut self) -> Duration {
+        std::mem::take(&mut self.lateness)
+    }
+
+    /// Report the frame started at `frame_start` finished rendering at `rendered`, e.g. after
+    /// the flush, and its buffers were presented at `presented`, e.g. when the swap returned
+    /// with vsync, to align the next frames with the vblanks. The swap waiting for the
+    /// vblank is not part of the render time.
+    pub fn notify_presented(
+        &mut self,
+        frame_start: Instant,
+        rendered: Instant,
+        presented: Instant,
+    ) {
+        self.vblank = Some(presented);
+        let render_time = rendered.saturating_duration_since(frame_start);
+        // Follow slower frames at once and faster ones gradually, missing a vblank is
+        // worse than presenting a bit early.
+        self.render_time = if render_time > self.render_time {
+            render_time
+        } else {
+            (self.render_time * 7 + render_time) / 8
+        };
+    }
+
+    /// Returns `true` and starts a new frame if its scheduled start passed.
+    #[inline]
+    pub fn start_frame(&mut self, now: Instant) -> bool {
+        if !self.paused && now > self.next_frame_start() {
+            self.lateness = if std::mem::take(&mut self.resumed) {
+                Duration::ZERO
+            } else {
+                now.saturating_duration_since(self.next_frame_start())
+            };
+            self.previous_frame_start = now;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The start of the next frame, the previous start plus the frame duration until a
+    /// presentation is reported, then the first vblank the rendering can make a frame
+    /// duration after the previous start minus the predicted render time. A frame
+    /// presented a vblank late is followed by one for the vblank after it.
+    pub fn next_frame_start(&self) -> Instant {
+        let Some(vblank) = self.vblank else {
+            return self.previous_frame_start + self.frame_duration;
+        };
+        let lead = (self.render_time + PRESENT_MARGIN).min(self.frame_duration);
+        let vblank_period = self.vblank_period.unwrap_or(self.frame_duration);
+        let period = vblank_period.as_secs_f64();
+        // One vblank after the previous presentation at the refresh rate, as many as fit
+        // in the frame duration at a lower frame rate.
+        let after =
+            self.previous_frame_start + self.frame_duration.saturating_sub(vblank_period) + lead;
+        // The presented vblank is gone, even when the previous frame missed its own.
+        let periods = if after >= vblank {
+            ((after - vblank).as_secs_f64() / period).floor() + 1.
+        } else {
+            1.
+        };
+        vblank + Duration::from_secs_f64(periods * period) - lead
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// Report a frame started at `start`, rendered and presented that long after it.
+    fn present(pacer: &mut FramePacer, start: Instant, rendered: Duration, presented: Duration) {
+        pacer.previous_frame_start = start;
+        pacer.notify_presented(start, start + rendered, start + presented);
+    }
+
+    fn assert_near(instant: Instant, expected: Instant) {
+        let error = if instant > expected {
+            instant - expected
+        } else {
+            expected - instant
+        };
+        assert!(error < Duration::from_micros(50), "off by {error:?}");
+    }
+
+    #[test]
+    fn next_frame_finishes_before_the_next_vblank() {
+        let mut pacer = FramePacer::new(30.);
+        pacer.set_refresh_rate_millihertz(60_000);
+        let period = Duration::from_secs_f64(1. / 60.);
+        let start = Instant::now();
+        // Rendered in 4ms, started with the margin before the vblank.
+        present(&mut pacer, start, 4 * MS, 6 * MS);
+        let vblank = start + 6 * MS;
+        assert_near(pacer.next_frame_start(), vblank + period - 6 * MS);
+
+        let start = pacer.next_frame_start();
+        present(&mut pacer, start, 4 * MS, 6 * MS);
+        assert_near(pacer.next_frame_start(), vblank + 2 * period - 6 * MS);
+    }
+
+    #[test]
+    fn missed_vblank_skips_to_the_next_one() {
+        let mut pacer = FramePacer::new(30.);
+        pacer.set_refresh_rate_millihertz(60_000);
+        let period = Duration::from_secs_f64(1. / 60.);
+        let start = Instant::now();
+        present(&mut pacer, start, 4 * MS, 6 * MS);
+        // Rendered for longer than a period, presented a vblank late.
+        let start = pacer.next_frame_start();
+        let vblank = start + 6 * MS + period;
+        present(&mut pacer, start, 20 * MS, 6 * MS + period);
+        // The render time covers the whole period now.
+        assert_near(pacer.next_frame_start(), vblank);
+    }
+
+    #[test]
+    fn refresh_rate_change_follows_the_new_vblanks() {
+        let mut pacer = FramePacer::new(30.);
+        pacer.set_refresh_rate_millihertz(60_000);
+        let start = Instant::now();
+        present(&mut pacer, start, 4 * MS, 6 * MS);
+        let vblank = start + 6 * MS;
+        pacer.set_refresh_rate_millihertz(120_000);
+        let period = Duration::from_secs_f64(1. / 120.);
+        assert_near(pacer.next_frame_start(), vblank + period - 6 * MS);
+
+        let start = pacer.next_frame_start();
+        present(&mut pacer, start, 4 * MS, 6 * MS);
+        assert_near(pacer.next_frame_start(), vblank + 2 * period - 6 * MS);
+    }
+
+    #[test]
+    fn reset_restores_the_frame_rate_before_the_refresh_rate() {
+        let mut pacer = FramePacer::new(30.);
+        pacer.set_paused(true);
+        pacer.set_refresh_rate_millihertz(144_000);
+        pacer.set_refresh_rate_millihertz(60_000);
+        assert_eq!(pacer.frame_duration(), Duration::from_secs_f32(1. / 60.));
+        pacer.reset_refresh_rate();
+        assert_eq!(pacer.frame_duration(), Duration::from_secs_f32(1. / 30.));
+        assert!(pacer.is_paused());
+    }
+
+    #[test]
+    fn frame_rate_set_meanwhile_survives_the_reset() {
+        let mut pacer = FramePacer::new(30.);
+        pacer.set_refresh_rate_millihertz(60_000);
+        pacer.set_frame_rate(45.);
+        pacer.reset_refresh_rate();
+        assert_eq!(pacer.frame_duration(), Duration::from_secs_f32(1. / 45.));
+    }
+}
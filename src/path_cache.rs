@@ -0,0 +1,243 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use skia_safe::{
+    Canvas, Color, FilterMode, Image, ImageInfo, MipmapMode, Paint, PaintStyle, Path, Rect,
+    SamplingOptions,
+};
+
+pub const DEFAULT_PATH_CACHE_CAPACITY: usize = 512;
+
+/// The range of the scale buckets, as powers of two.
+const MIN_BUCKET: i32 = -4;
+const MAX_BUCKET: i32 = 6;
+
+/// Identifies a mask by the hash of the path, checked against the path of the mask on a
+/// hit, and by the paint settings changing the coverage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MaskKey {
+    path: u64,
+    /// The style, width, miter limit, join and cap bits, `0` for fills.
+    stroke: (u32, u32, u32, u32, u32),
+    /// The hash of the serialized path effect and mask filter.
+    effects: u64,
+    anti_alias: bool,
+    bucket: i32,
+}
+
+impl MaskKey {
+    fn new(path: &Path, paint: &Paint, bucket: i32) -> Self {
+        let mut hasher = DefaultHasher::new();
+        path.serialize().as_bytes().hash(&mut hasher);
+        let path_hash = hasher.finish();
+        let stroke = match paint.style() {
+            PaintStyle::Fill => (0, 0, 0, 0, 0),
+            style => (
+                style as u32,
+                paint.stroke_width().to_bits(),
+                paint.stroke_miter().to_bits(),
+                paint.stroke_join() as u32,
+                paint.stroke_cap() as u32,
+            ),
+        };
+        let mut hasher = DefaultHasher::new();
+        paint
+            .path_effect()
+            .map(|effect| effect.serialize().as_bytes().to_vec())
+            .hash(&mut hasher);
+        paint
+            .mask_filter()
+            .map(|filter| filter.serialize().as_bytes().to_vec())
+            .hash(&mut hasher);
+        Self {
+            path: path_hash,
+            stroke,
+            effects: hasher.finish(),
+            anti_alias: paint.is_anti_alias(),
+            bucket,
+        }
+    }
+}
+
+struct Mask {
+    /// The path rasterized, a path with a colliding hash is rasterized again.
+    path: Path,
+    /// The coverage of the path, rendered at the bucket scale.
+    image: Image,
+    /// The area covered by the image in path space.
+    bounds: Rect,
+    last_used: u64,
+}
+
+/// Caches the coverage of complex paths as alpha masks rasterized at power of two scale
+/// buckets, so redrawing thousands of paths, e.g. zooming a map, draws textures instead of
+/// rasterizing every path each frame. The masks are drawn with the color or the shader of
+/// the paint and are scaled down to the current scale, at most by half.
+///
+/// The masks belong to the GPU context of the canvas, clear the cache when the context is
+/// recreated.
+pub struct PathCache {
+    masks: HashMap<MaskKey, Mask>,
+    capacity: usize,
+    /// Paths with fewer verbs are drawn directly, rasterizing them is cheaper than a cache
+    /// lookup.
+    min_verbs: usize,
+    /// Paths with larger masks are drawn directly.
+    max_mask_size: i32,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for PathCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PATH_CACHE_CAPACITY)
+    }
+}
+
+impl PathCache {
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            masks: HashMap::new(),
+            capacity: capacity.max(1),
+            min_verbs: 16,
+            max_mask_size: 2048,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    #[inline]
+    pub fn with_min_verbs(mut self, min_verbs: usize) -> Self {
+        self.min_verbs = min_verbs;
+        self
+    }
+
+    #[inline]
+    pub fn with_max_mask_size(mut self, max_mask_size: i32) -> Self {
+        self.max_mask_size = max_mask_size;
+        self
+    }
+
+    /// Draw the path like `Canvas::draw_path`, from a cached mask when the path is complex
+    /// enough. Path effects and mask filters are applied when rasterizing the mask, the
+    /// color, shader and blending when drawing it.
+    pub fn draw_path(&mut self, canvas: &mut Canvas, path: &Path, paint: &Paint) {
+        let scale = canvas.local_to_device_as_3x3().max_scale();
+        if path.count_verbs() < self.min_verbs || !scale.is_finite() || scale <= 0. {
+            canvas.draw_path(path, paint);
+            return;
+        }
+        let bucket = (scale.log2().ceil() as i32).clamp(MIN_BUCKET, MAX_BUCKET);
+        let key = MaskKey::new(path, paint, bucket);
+
+        self.clock += 1;
+        if self.masks.get(&key).is_some_and(|mask| mask.path == *path) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            let Some(mask) = self.rasterize(canvas, path, paint, bucket) else {
+                canvas.draw_path(path, paint);
+                return;
+            };
+            if !self.masks.contains_key(&key) && self.masks.len() >= self.capacity {
+                self.evict();
+            }
+            self.masks.insert(key, mask);
+        }
+        let mask = self.masks.get_mut(&key).unwrap();
+        mask.last_used = self.clock;
+
+        let mut image_paint = paint.clone();
+        image_paint.set_style(PaintStyle::Fill);
+        image_paint.set_path_effect(None);
+        image_paint.set_mask_filter(None);
+        canvas.draw_image_rect_with_sampling_options(
+            &mask.image,
+            None,
+            mask.bounds,
+            SamplingOptions::new(FilterMode::Linear, MipmapMode::None),
+            &image_paint,
+        );
+    }
+
+    fn rasterize(
+        &self,
+        canvas: &mut Canvas,
+        path: &Path,
+        paint: &Paint,
+        bucket: i32,
+    ) -> Option<Mask> {
+        let bucket_scale = 2f32.powi(bucket);
+        // One pixel of padding keeps the antialiased edges off the texture border.
+        let padding = 1. / bucket_scale;
+        let bounds = paint
+            .compute_fast_bounds(path.bounds())
+            .with_outset((padding, padding));
+        let size = (
+            (bounds.width() * bucket_scale).ceil() as i32,
+            (bounds.height() * bucket_scale).ceil() as i32,
+        );
+        if size.0 <= 0 || size.1 <= 0 || size.0.max(size.1) > self.max_mask_size {
+            return None;
+        }
+        let mut surface = canvas.new_surface(&ImageInfo::new_a8(size), None)?;
+        let mask_canvas = surface.canvas();
+        mask_canvas.clear(Color::TRANSPARENT);
+        mask_canvas.scale((bucket_scale, bucket_scale));
+        mask_canvas.translate((-bounds.left, -bounds.top));
+        let mut coverage = paint.clone();
+        coverage.set_shader(None);
+        coverage.set_color_filter(None);
+        coverage.set_color(Color::BLACK);
+        mask_canvas.draw_path(path, &coverage);
+        Some(Mask {
+            path: path.clone(),
+            image: surface.image_snapshot(),
+            bounds: Rect::from_xywh(
+                bounds.left,
+                bounds.top,
+                size.0 as f32 / bucket_scale,
+                size.1 as f32 / bucket_scale,
+            ),
+            last_used: 0,
+        })
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .masks
+            .iter()
+            .min_by_key(|(_, mask)| mask.last_used)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.masks.remove(&key);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.masks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    /// Drop the masks, e.g. after the GPU context was recreated.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.masks.clear();
+    }
+
+    /// The draws served from a mask and the ones rasterizing a mask.
+    #[inline]
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use skia_safe::{OpBuilder, Path, PathOp};
+
+pub const DEFAULT_PATH_OP_CACHE_CAPACITY: usize = 128;
+
+/// Boolean operations on paths, `None` when Skia fails to compute the result, e.g. on
+/// paths with non-finite points.
+pub trait PathOps {
+    fn union(&self, other: &Path) -> Option<Path>;
+    fn intersect(&self, other: &Path) -> Option<Path>;
+    /// This path with `other` cut out.
+    fn difference(&self, other: &Path) -> Option<Path>;
+    fn xor(&self, other: &Path) -> Option<Path>;
+}
+
+impl PathOps for Path {
+    #[inline]
+    fn union(&self, other: &Path) -> Option<Path> {
+        self.op(other, PathOp::Union)
+    }
+
+    #[inline]
+    fn intersect(&self, other: &Path) -> Option<Path> {
+        self.op(other, PathOp::Intersect)
+    }
+
+    #[inline]
+    fn difference(&self, other: &Path) -> Option<Path> {
+        self.op(other, PathOp::Difference)
+    }
+
+    #[inline]
+    fn xor(&self, other: &Path) -> Option<Path> {
+        self.op(other, PathOp::XOR)
+    }
+}
+
+/// The union of all the paths, faster than chaining `PathOps::union` since the paths are
+/// resolved at once. An empty path for no paths.
+pub fn union_all<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<Path> {
+    let mut builder = OpBuilder::default();
+    for path in paths {
+        builder.add(path, PathOp::Union);
+    }
+    builder.resolve()
+}
+
+/// Hashes the geometry and the fill type, so equal paths built separately hash the same,
+/// unlike their generation ids.
+fn path_hash(path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    path.serialize().as_bytes().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct OpKey {
+    a: u64,
+    b: u64,
+    op: i32,
+}
+
+struct Entry {
+    /// The inputs, compared on a hit in case of a hash collision.
+    a: Path,
+    b: Path,
+    result: Option<Path>,
+    last_used: u64,
+}
+
+/// Computed path operations keyed by their inputs, the least recently used ones are evicted
+/// past the capacity, e.g. for a vector editor redrawing the same boolean shapes every
+/// frame while only one of them is edited.
+pub struct PathOpCache {
+    entries: HashMap<OpKey, Entry>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for PathOpCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PATH_OP_CACHE_CAPACITY)
+    }
+}
+
+impl PathOpCache {
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// The result of `a op b`, computed on a miss.
+    pub fn op(&mut self, a: &Path, b: &Path, op: PathOp) -> Option<&Path> {
+        self.clock += 1;
+        let key = OpKey {
+            a: path_hash(a),
+            b: path_hash(b),
+            op: op as i32,
+        };
+        let hit = self
+            .entries
+            .get(&key)
+            .is_some_and(|entry| entry.a == *a && entry.b == *b);
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+                self.evict();
+            }
+            self.entries.insert(
+                key,
+                Entry {
+                    a: a.clone(),
+                    b: b.clone(),
+                    result: a.op(b, op),
+                    last_used: 0,
+                },
+            );
+        }
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.clock;
+        entry.result.as_ref()
+    }
+
+    #[inline]
+    pub fn union(&mut self, a: &Path, b: &Path) -> Option<&Path> {
+        self.op(a, b, PathOp::Union)
+    }
+
+    #[inline]
+    pub fn intersect(&mut self, a: &Path, b: &Path) -> Option<&Path> {
+        self.op(a, b, PathOp::Intersect)
+    }
+
+    #[inline]
+    pub fn difference(&mut self, a: &Path, b: &Path) -> Option<&Path> {
+        self.op(a, b, PathOp::Difference)
+    }
+
+    #[inline]
+    pub fn xor(&mut self, a: &Path, b: &Path) -> Option<&Path> {
+        self.op(a, b, PathOp::XOR)
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The lookups served from the cache and the ones computing the operation.
+    #[inline]
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
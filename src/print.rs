@@ -0,0 +1,103 @@
+use std::{io, path::PathBuf, thread};
+
+use skia_safe::{pdf, Picture};
+
+use crate::document::{PageConfig, PdfDocument};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintOptions {
+    pub title: String,
+    pub page: PageConfig,
+    /// The resolution used for the content Skia has to rasterize, e.g. image filters.
+    pub dpi: f32,
+    /// Where the PDF is written, also the fallback if no system printer is available.
+    pub output: PathBuf,
+    /// Send the PDF to the system printer (CUPS `lp` on unix platforms).
+    pub system_printer: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            title: "skia_gl".to_string(),
+            page: PageConfig::default(),
+            dpi: 300.,
+            output: std::env::temp_dir().join("skia_gl-print.pdf"),
+            system_printer: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrintOutcome {
+    /// The document was sent to the system printer.
+    Printed,
+    /// No system printer was available, the document was saved as PDF.
+    SavedPdf(PathBuf),
+}
+
+/// Print the picture of a frame of `size` pixels on a worker thread, scaled to fit the page
+/// content area, `done` is called there with the outcome. Writing the PDF and waiting for
+/// `lp` can take seconds, the render thread only records the picture.
+pub fn print(
+    picture: Picture,
+    size: (i32, i32),
+    options: PrintOptions,
+    done: impl FnOnce(io::Result<PrintOutcome>) + Send + 'static,
+) -> io::Result<()> {
+    thread::Builder::new()
+        .name("print".to_string())
+        .spawn(move || done(print_picture(&picture, size, &options)))?;
+    Ok(())
+}
+
+fn print_picture(
+    picture: &Picture,
+    size: (i32, i32),
+    options: &PrintOptions,
+) -> io::Result<PrintOutcome> {
+    let mut metadata = pdf::Metadata::default();
+    metadata.title = options.title.clone();
+    metadata.creator = "skia_gl".to_string();
+    metadata.raster_dpi = Some(options.dpi);
+
+    let mut document = PdfDocument::new(options.page, Some(&metadata));
+    document.render_page(|canvas, page| {
+        let scale = (page.content_size.0 / size.0.max(1) as f32)
+            .min(page.content_size.1 / size.1.max(1) as f32);
+        canvas.scale((scale, scale));
+        canvas.draw_picture(picture, None, None);
+    });
+    document.save(&options.output)?;
+
+    if options.system_printer && send_to_printer(options)? {
+        Ok(PrintOutcome::Printed)
+    } else {
+        Ok(PrintOutcome::SavedPdf(options.output.clone()))
+    }
+}
+
+/// Returns `false` if there is no system printer to send the document to.
+#[cfg(unix)]
+fn send_to_printer(options: &PrintOptions) -> io::Result<bool> {
+    match std::process::Command::new("lp")
+        .arg("-t")
+        .arg(&options.title)
+        .arg(&options.output)
+        .status()
+    {
+        Ok(status) if status.success() => Ok(true),
+        Ok(status) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("lp exited with {status}"),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns `false` if there is no system printer to send the document to.
+#[cfg(not(unix))]
+fn send_to_printer(_: &PrintOptions) -> io::Result<bool> {
+    Ok(false)
+}
@@ -0,0 +1,288 @@
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+    time::{Duration, Instant},
+};
+
+use skia_safe::{
+    canvas::{PointMode, SrcRectConstraint},
+    Canvas, Font, Image, Matrix, Paint, Path, Picture, Point, RRect, Rect, TextBlob,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawCategory {
+    Rect,
+    RRect,
+    Oval,
+    Path,
+    Line,
+    Points,
+    Image,
+    Text,
+    Picture,
+    Paint,
+    Other,
+}
+
+impl DrawCategory {
+    pub const ALL: [DrawCategory; 11] = [
+        DrawCategory::Rect,
+        DrawCategory::RRect,
+        DrawCategory::Oval,
+        DrawCategory::Path,
+        DrawCategory::Line,
+        DrawCategory::Points,
+        DrawCategory::Image,
+        DrawCategory::Text,
+        DrawCategory::Picture,
+        DrawCategory::Paint,
+        DrawCategory::Other,
+    ];
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrawCategory::Rect => "rect",
+            DrawCategory::RRect => "rrect",
+            DrawCategory::Oval => "oval",
+            DrawCategory::Path => "path",
+            DrawCategory::Line => "line",
+            DrawCategory::Points => "points",
+            DrawCategory::Image => "image",
+            DrawCategory::Text => "text",
+            DrawCategory::Picture => "picture",
+            DrawCategory::Paint => "paint",
+            DrawCategory::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DrawCallStats {
+    pub count: usize,
+    /// The CPU time spent recording the calls, the GPU work happens on flush.
+    pub time: Duration,
+}
+
+/// The draw calls of a frame by category.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DrawStats {
+    calls: [DrawCallStats; DrawCategory::ALL.len()],
+}
+
+impl DrawStats {
+    #[inline]
+    pub fn get(&self, category: DrawCategory) -> DrawCallStats {
+        self.calls[category as usize]
+    }
+
+    #[inline]
+    pub fn add(&mut self, category: DrawCategory, time: Duration) {
+        let calls = &mut self.calls[category as usize];
+        calls.count += 1;
+        calls.time += time;
+    }
+
+    /// The categories with at least one draw call.
+    pub fn iter(&self) -> impl Iterator<Item = (DrawCategory, DrawCallStats)> + '_ {
+        DrawCategory::ALL
+            .iter()
+            .map(|category| (*category, self.get(*category)))
+            .filter(|(_, calls)| calls.count > 0)
+    }
+
+    pub fn total(&self) -> DrawCallStats {
+        self.calls
+            .iter()
+            .fold(DrawCallStats::default(), |total, calls| DrawCallStats {
+                count: total.count + calls.count,
+                time: total.time + calls.time,
+            })
+    }
+}
+
+/// Collects the draw calls made through `ProfiledCanvas` during a frame.
+#[derive(Debug, Default)]
+pub struct DrawProfiler {
+    stats: RefCell<DrawStats>,
+}
+
+impl DrawProfiler {
+    #[inline]
+    pub fn canvas<'a>(&'a self, canvas: &'a mut Canvas) -> ProfiledCanvas<'a> {
+        ProfiledCanvas {
+            canvas,
+            profiler: self,
+        }
+    }
+
+    #[inline]
+    pub fn add(&self, category: DrawCategory, time: Duration) {
+        self.stats.borrow_mut().add(category, time);
+    }
+
+    /// Return the collected stats and start collecting the next frame.
+    #[inline]
+    pub fn take(&self) -> DrawStats {
+        self.stats.take()
+    }
+}
+
+/// A canvas wrapper counting and timing the draw calls by category, the other canvas
+/// methods are reachable through `Deref`.
+pub struct ProfiledCanvas<'a> {
+    canvas: &'a mut Canvas,
+    profiler: &'a DrawProfiler,
+}
+
+impl<'a> ProfiledCanvas<'a> {
+    /// Attribute the draw calls made in `draw` to the category.
+    pub fn profile<R>(&mut self, category: DrawCategory, draw: impl FnOnce(&mut Canvas) -> R) -> R {
+        let start = Instant::now();
+        let result = draw(self.canvas);
+        self.profiler.add(category, start.elapsed());
+        result
+    }
+
+    pub fn draw_paint(&mut self, paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::Paint, |canvas| {
+            canvas.draw_paint(paint);
+        });
+        self
+    }
+
+    pub fn draw_rect(&mut self, rect: impl AsRef<Rect>, paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::Rect, |canvas| {
+            canvas.draw_rect(rect, paint);
+        });
+        self
+    }
+
+    pub fn draw_rrect(&mut self, rrect: impl AsRef<RRect>, paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::RRect, |canvas| {
+            canvas.draw_rrect(rrect, paint);
+        });
+        self
+    }
+
+    pub fn draw_oval(&mut self, oval: impl AsRef<Rect>, paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::Oval, |canvas| {
+            canvas.draw_oval(oval, paint);
+        });
+        self
+    }
+
+    pub fn draw_circle(
+        &mut self,
+        center: impl Into<Point>,
+        radius: f32,
+        paint: &Paint,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Oval, |canvas| {
+            canvas.draw_circle(center, radius, paint);
+        });
+        self
+    }
+
+    pub fn draw_path(&mut self, path: &Path, paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::Path, |canvas| {
+            canvas.draw_path(path, paint);
+        });
+        self
+    }
+
+    pub fn draw_line(
+        &mut self,
+        p1: impl Into<Point>,
+        p2: impl Into<Point>,
+        paint: &Paint,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Line, |canvas| {
+            canvas.draw_line(p1, p2, paint);
+        });
+        self
+    }
+
+    pub fn draw_points(&mut self, mode: PointMode, points: &[Point], paint: &Paint) -> &mut Self {
+        self.profile(DrawCategory::Points, |canvas| {
+            canvas.draw_points(mode, points, paint);
+        });
+        self
+    }
+
+    pub fn draw_image(
+        &mut self,
+        image: impl AsRef<Image>,
+        left_top: impl Into<Point>,
+        paint: Option<&Paint>,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Image, |canvas| {
+            canvas.draw_image(image, left_top, paint);
+        });
+        self
+    }
+
+    pub fn draw_image_rect(
+        &mut self,
+        image: impl AsRef<Image>,
+        src: Option<(&Rect, SrcRectConstraint)>,
+        dst: impl AsRef<Rect>,
+        paint: &Paint,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Image, |canvas| {
+            canvas.draw_image_rect(image, src, dst, paint);
+        });
+        self
+    }
+
+    pub fn draw_text_blob(
+        &mut self,
+        blob: impl AsRef<TextBlob>,
+        origin: impl Into<Point>,
+        paint: &Paint,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Text, |canvas| {
+            canvas.draw_text_blob(blob, origin, paint);
+        });
+        self
+    }
+
+    pub fn draw_str(
+        &mut self,
+        text: impl AsRef<str>,
+        origin: impl Into<Point>,
+        font: &Font,
+        paint: &Paint,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Text, |canvas| {
+            canvas.draw_str(text, origin, font, paint);
+        });
+        self
+    }
+
+    pub fn draw_picture(
+        &mut self,
+        picture: impl AsRef<Picture>,
+        matrix: Option<&Matrix>,
+        paint: Option<&Paint>,
+    ) -> &mut Self {
+        self.profile(DrawCategory::Picture, |canvas| {
+            canvas.draw_picture(picture, matrix, paint);
+        });
+        self
+    }
+}
+
+impl<'a> Deref for ProfiledCanvas<'a> {
+    type Target = Canvas;
+
+    fn deref(&self) -> &Self::Target {
+        self.canvas
+    }
+}
+
+impl<'a> DerefMut for ProfiledCanvas<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.canvas
+    }
+}
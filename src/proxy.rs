@@ -0,0 +1,54 @@
+use std::any::Any;
+
+use skia_safe::Rect;
+use winit::event_loop::EventLoopProxy;
+
+use crate::{invalidation::Damage, user_event::UserEvent};
+
+/// The events posted to the event loop by a `BackendProxy`, the user event type of the
+/// event loop driving the backend.
+#[derive(Debug)]
+pub enum BackendEvent {
+    /// Redraw the damage, handled with `Backend::invalidate`.
+    Redraw(Damage),
+    /// An event of the application, handed to the renderer with `Backend::post_user_event`.
+    User(UserEvent),
+}
+
+/// Wakes the event loop from other threads, e.g. when an asset finished loading or network
+/// data arrived, so the rendering is driven by the data instead of polling for it. Cheap to
+/// clone into every worker, given to the renderer with `Renderer::attach_proxy`.
+#[derive(Debug, Clone)]
+pub struct BackendProxy {
+    proxy: EventLoopProxy<BackendEvent>,
+}
+
+impl BackendProxy {
+    #[inline]
+    pub fn new(proxy: EventLoopProxy<BackendEvent>) -> Self {
+        Self { proxy }
+    }
+
+    /// Redraw the whole frame, returns `false` once the event loop exited.
+    #[inline]
+    pub fn request_redraw(&self) -> bool {
+        self.send(BackendEvent::Redraw(Damage::Full))
+    }
+
+    /// Redraw the area in window pixels, returns `false` once the event loop exited.
+    #[inline]
+    pub fn request_redraw_rect(&self, rect: Rect) -> bool {
+        self.send(BackendEvent::Redraw(Damage::Rect(rect)))
+    }
+
+    /// Post an event of the application to the renderer, timestamped now, returns `false`
+    /// once the event loop exited.
+    #[inline]
+    pub fn post_event<T: Any + Send>(&self, event: T) -> bool {
+        self.send(BackendEvent::User(UserEvent::new(event)))
+    }
+
+    fn send(&self, event: BackendEvent) -> bool {
+        self.proxy.send_event(event).is_ok()
+    }
+}
@@ -0,0 +1,131 @@
+use puffin::{GlobalFrameView, Reader, Scope, Stream};
+use skia_safe::{Canvas, Color, Font, Paint, Rect};
+
+const ROW_HEIGHT: f32 = 14.;
+const MAX_DEPTH: usize = 8;
+
+/// Draws a flame view of the latest puffin frame, the frames are collected while the
+/// overlay exists.
+#[derive(Default)]
+pub struct PuffinOverlay {
+    view: GlobalFrameView,
+}
+
+impl PuffinOverlay {
+    /// Draw the overlay along the bottom edge of a `size` sized frame.
+    pub fn draw(&self, canvas: &mut Canvas, size: (i32, i32)) {
+        let Some(frame) = self.view.lock().latest_frame() else {
+            return;
+        };
+        let frame = match frame.unpacked() {
+            Ok(frame) => frame,
+            Err(err) => {
+                eprintln!("Error unpacking puffin frame: {err:?}");
+                return;
+            }
+        };
+
+        let (start_ns, end_ns) = frame.meta.range_ns;
+        let ns_to_x = size.0 as f32 / (end_ns - start_ns).max(1) as f32;
+        let rows = frame.thread_streams.len() * (MAX_DEPTH + 1);
+        let top = size.1 as f32 - rows as f32 * ROW_HEIGHT;
+
+        let mut background = Paint::default();
+        background.set_color(Color::from_argb(0xc0, 0, 0, 0));
+        canvas.draw_rect(
+            Rect::new(0., top, size.0 as f32, size.1 as f32),
+            &background,
+        );
+
+        let font = Font::default()
+            .with_size(11.)
+            .expect("Could not create font");
+        let mut text = Paint::default();
+        text.set_anti_alias(true).set_color(Color::WHITE);
+
+        let mut y = top;
+        for (thread, stream) in frame.thread_streams.iter() {
+            canvas.draw_str(&thread.name, (4., y + ROW_HEIGHT - 3.), &font, &text);
+            y += ROW_HEIGHT;
+
+            let mut painter = FlamePainter {
+                canvas: &mut *canvas,
+                font: &font,
+                text: &text,
+                start_ns,
+                ns_to_x,
+                top: y,
+            };
+            match Reader::from_start(&stream.stream).read_top_scopes() {
+                Ok(scopes) => painter.draw_scopes(&stream.stream, &scopes, 0),
+                Err(err) => eprintln!("Error reading puffin stream: {err:?}"),
+            }
+            y += MAX_DEPTH as f32 * ROW_HEIGHT;
+        }
+    }
+}
+
+struct FlamePainter<'a> {
+    canvas: &'a mut Canvas,
+    font: &'a Font,
+    text: &'a Paint,
+    start_ns: i64,
+    ns_to_x: f32,
+    top: f32,
+}
+
+impl<'a> FlamePainter<'a> {
+    fn draw_scopes(&mut self, stream: &Stream, scopes: &[Scope], depth: usize) {
+        if depth >= MAX_DEPTH {
+            return;
+        }
+
+        for scope in scopes {
+            let left = (scope.record.start_ns - self.start_ns) as f32 * self.ns_to_x;
+            let width = (scope.record.duration_ns as f32 * self.ns_to_x).max(1.);
+            let rect = Rect::from_xywh(
+                left,
+                self.top + depth as f32 * ROW_HEIGHT,
+                width,
+                ROW_HEIGHT - 1.,
+            );
+
+            let mut paint = Paint::default();
+            paint.set_color(scope_color(scope.record.id));
+            self.canvas.draw_rect(rect, &paint);
+            if width > 40. {
+                self.canvas.save();
+                self.canvas.clip_rect(rect, None, None);
+                self.canvas.draw_str(
+                    format!(
+                        "{} {:.2} ms",
+                        scope.record.id,
+                        scope.record.duration_ns as f64 / 1e6
+                    ),
+                    (rect.left + 2., rect.bottom - 3.),
+                    self.font,
+                    self.text,
+                );
+                self.canvas.restore();
+            }
+
+            if let Ok(children) = Reader::with_offset(stream, scope.child_begin_position)
+                .and_then(|reader| reader.read_top_scopes())
+            {
+                self.draw_scopes(stream, &children, depth + 1);
+            }
+        }
+    }
+}
+
+/// A stable color per scope name.
+fn scope_color(id: &str) -> Color {
+    let hash = id.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    Color::from_rgb(
+        0x60 + (hash & 0x7f) as u8,
+        0x60 + ((hash >> 8) & 0x7f) as u8,
+        0x60 + ((hash >> 16) & 0x7f) as u8,
+    )
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use crate::resolution::MIN_RENDER_SCALE;
+
+/// An expensive feature given up under load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QualityFeature {
+    /// The post effects of the renderer, e.g. blurs and color grading.
+    PostEffects,
+    /// The shadows of the renderer.
+    Shadows,
+    /// The supersampling and the paint antialiasing, the multisampling of the window surface
+    /// is fixed by the GL config.
+    Antialiasing,
+    /// Rendering below the window resolution, see `QualityPolicy::reduced_render_scale`.
+    RenderScale,
+}
+
+impl QualityFeature {
+    pub const ALL: [QualityFeature; 4] = [
+        QualityFeature::PostEffects,
+        QualityFeature::Shadows,
+        QualityFeature::Antialiasing,
+        QualityFeature::RenderScale,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QualityFeature::PostEffects => "post effects",
+            QualityFeature::Shadows => "shadows",
+            QualityFeature::Antialiasing => "antialiasing",
+            QualityFeature::RenderScale => "render scale",
+        }
+    }
+}
+
+/// The features currently given up, the renderer skips the disabled ones it draws itself,
+/// e.g. the post effects and the shadows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Quality {
+    disabled: [bool; QualityFeature::ALL.len()],
+}
+
+impl Quality {
+    #[inline]
+    pub fn is_enabled(&self, feature: QualityFeature) -> bool {
+        !self.disabled[feature as usize]
+    }
+
+    /// Whether nothing is given up.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        !self.disabled.contains(&true)
+    }
+
+    pub fn disabled(&self) -> impl Iterator<Item = QualityFeature> + '_ {
+        QualityFeature::ALL
+            .into_iter()
+            .filter(|feature| !self.is_enabled(*feature))
+    }
+}
+
+/// When the features are given up and restored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityPolicy {
+    /// Given up in this order, the first one restored last.
+    pub features: Vec<QualityFeature>,
+    /// The over-budget frames in a row before the next feature is given up.
+    pub degrade_after: usize,
+    /// The frames in a row with headroom before the last given up feature is restored.
+    pub restore_after: usize,
+    /// A frame over this fraction of the budget is over budget.
+    pub over_budget: f32,
+    /// A frame under this fraction of the budget has headroom, well below `over_budget` so
+    /// a restored feature doesn't push the frame right back over.
+    pub headroom: f32,
+    /// The factor of the render scale while `QualityFeature::RenderScale` is given up.
+    pub reduced_render_scale: f32,
+}
+
+impl Default for QualityPolicy {
+    fn default() -> Self {
+        Self {
+            features: QualityFeature::ALL.to_vec(),
+            degrade_after: 30,
+            restore_after: 180,
+            over_budget: 0.95,
+            headroom: 0.6,
+            reduced_render_scale: 0.75,
+        }
+    }
+}
+
+/// Gives up the expensive features one by one while the frames stay over budget, and
+/// restores them in reverse once the headroom returns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QualityGovernor {
+    policy: Option<QualityPolicy>,
+    /// The number of `policy.features` given up.
+    degraded: usize,
+    over_budget: usize,
+    headroom: usize,
+}
+
+impl QualityGovernor {
+    /// `None` turns the degradation off and restores every feature.
+    pub fn set_policy(&mut self, policy: Option<QualityPolicy>) {
+        *self = Self {
+            policy,
+            ..Default::default()
+        };
+    }
+
+    #[inline]
+    pub fn policy(&self) -> Option<&QualityPolicy> {
+        self.policy.as_ref()
+    }
+
+    pub fn quality(&self) -> Quality {
+        let mut quality = Quality::default();
+        if let Some(policy) = &self.policy {
+            for feature in &policy.features[..self.degraded] {
+                quality.disabled[*feature as usize] = true;
+            }
+        }
+        quality
+    }
+
+    /// The render scale to use instead of `scale`.
+    pub fn render_scale(&self, scale: f32) -> f32 {
+        match &self.policy {
+            Some(policy) if !self.quality().is_enabled(QualityFeature::RenderScale) => {
+                (scale * policy.reduced_render_scale).max(MIN_RENDER_SCALE)
+            }
+            _ => scale,
+        }
+    }
+
+    /// Report the time spent rendering the frame, returns the new quality if it changed.
+    pub fn update(&mut self, frame_time: Duration, budget: Duration) -> Option<Quality> {
+        let policy = self.policy.as_ref()?;
+        let load = frame_time.as_secs_f32() / budget.as_secs_f32().max(f32::EPSILON);
+        if load > policy.over_budget {
+            self.over_budget += 1;
+            self.headroom = 0;
+        } else if load < policy.headroom {
+            self.headroom += 1;
+            self.over_budget = 0;
+        } else {
+            self.over_budget = 0;
+            self.headroom = 0;
+        }
+
+        if self.over_budget >= policy.degrade_after && self.degraded < policy.features.len() {
+            self.degraded += 1;
+        } else if self.headroom >= policy.restore_after && self.degraded > 0 {
+            self.degraded -= 1;
+        } else {
+            return None;
+        }
+        self.over_budget = 0;
+        self.headroom = 0;
+        Some(self.quality())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(10);
+    const OVER: Duration = Duration::from_millis(12);
+    const IDLE: Duration = Duration::from_millis(2);
+
+    fn governor() -> QualityGovernor {
+        let mut governor = QualityGovernor::default();
+        governor.set_policy(Some(QualityPolicy {
+            features: vec![QualityFeature::PostEffects, QualityFeature::RenderScale],
+            degrade_after: 3,
+            restore_after: 2,
+            ..Default::default()
+        }));
+        governor
+    }
+
+    fn run(governor: &mut QualityGovernor, frame_time: Duration, frames: usize) -> Option<Quality> {
+        (0..frames)
+            .filter_map(|_| governor.update(frame_time, BUDGET))
+            .last()
+    }
+
+    #[test]
+    fn degrades_in_order_after_sustained_load() {
+        let mut governor = governor();
+        assert_eq!(run(&mut governor, OVER, 2), None);
+        let quality = run(&mut governor, OVER, 1).unwrap();
+        assert!(!quality.is_enabled(QualityFeature::PostEffects));
+        assert!(quality.is_enabled(QualityFeature::RenderScale));
+
+        let quality = run(&mut governor, OVER, 3).unwrap();
+        assert_eq!(
+            quality.disabled().collect::<Vec<_>>(),
+            [QualityFeature::PostEffects, QualityFeature::RenderScale]
+        );
+        assert_eq!(run(&mut governor, OVER, 10), None);
+    }
+
+    #[test]
+    fn restores_in_reverse_with_headroom() {
+        let mut governor = governor();
+        run(&mut governor, OVER, 6);
+        let quality = run(&mut governor, IDLE, 2).unwrap();
+        assert!(!quality.is_enabled(QualityFeature::PostEffects));
+        assert!(quality.is_enabled(QualityFeature::RenderScale));
+        assert!(run(&mut governor, IDLE, 2).unwrap().is_full());
+    }
+
+    #[test]
+    fn frames_between_thresholds_reset_the_counts() {
+        let mut governor = governor();
+        run(&mut governor, OVER, 2);
+        run(&mut governor, Duration::from_millis(8), 1);
+        assert_eq!(run(&mut governor, OVER, 2), None);
+        assert!(governor.quality().is_full());
+    }
+
+    #[test]
+    fn reduced_render_scale_is_clamped() {
+        let mut governor = governor();
+        assert_eq!(governor.render_scale(1.), 1.);
+        run(&mut governor, OVER, 6);
+        assert_eq!(governor.render_scale(1.), 0.75);
+        assert_eq!(governor.render_scale(0.), MIN_RENDER_SCALE);
+    }
+
+    #[test]
+    fn no_policy_keeps_full_quality() {
+        let mut governor = QualityGovernor::default();
+        assert_eq!(run(&mut governor, OVER, 100), None);
+        assert!(governor.quality().is_full());
+    }
+}
@@ -0,0 +1,220 @@
+use std::ptr;
+
+use gl::types::{GLsync, GLuint};
+use skia_safe::{
+    gpu::gl::BackendState, surface::BackendHandleAccess, AlphaType, ColorType, Data, Image,
+    ImageInfo,
+};
+
+use crate::SkiaSurface;
+
+/// The frames between requesting a read and mapping its buffer, the GPU has finished the
+/// frame by then without the CPU waiting for it.
+pub const READBACK_LATENCY: usize = 2;
+
+/// The pixels of a frame read back asynchronously.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Readback {
+    /// The frame the read was requested in.
+    pub frame: usize,
+    pub size: (i32, i32),
+    /// The RGBA8 pixels, premultiplied, top row first.
+    pub pixels: Vec<u8>,
+}
+
+impl Readback {
+    #[inline]
+    pub fn row_bytes(&self) -> usize {
+        self.size.0 as usize * 4
+    }
+
+    /// A raster image of the pixels, e.g. to encode.
+    pub fn to_image(&self) -> Option<Image> {
+        let info = ImageInfo::new(self.size, ColorType::RGBA8888, AlphaType::Premul, None);
+        Image::from_raster_data(&info, Data::new_copy(&self.pixels), self.row_bytes())
+    }
+}
+
+struct Pending {
+    frame: usize,
+    size: (i32, i32),
+    fence: GLsync,
+}
+
+#[derive(Default)]
+struct PixelBuffer {
+    id: GLuint,
+    capacity: usize,
+    pending: Option<Pending>,
+}
+
+/// Reads the frames back through two pixel buffer objects, so continuous captures, e.g.
+/// streaming or recording, don't stall the pipeline: the read requested in frame N copies
+/// the pixels on the GPU and its buffer is mapped from frame N + 2 on, once the GPU is
+/// done with it, while the other buffer takes the read of frame N + 1. A frame requested
+/// while both buffers are in flight is dropped. The GL context must be current when it is
+/// dropped.
+#[derive(Default)]
+pub struct AsyncReadback {
+    buffers: [PixelBuffer; 2],
+}
+
+// The GL objects are only used on the thread the GL context is current on.
+unsafe impl Send for AsyncReadback {}
+
+impl AsyncReadback {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a read is still to be mapped.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.buffers.iter().any(|buffer| buffer.pending.is_some())
+    }
+
+    /// Start reading the surface back, after the frame was drawn into it. Returns `false`
+    /// when both buffers are in flight or the surface is not a GL framebuffer.
+    pub fn read(&mut self, surface: &mut SkiaSurface, frame: usize) -> bool {
+        let Some(buffer) = self
+            .buffers
+            .iter_mut()
+            .find(|buffer| buffer.pending.is_none())
+        else {
+            return false;
+        };
+        let Some(framebuffer) = surface
+            .get_backend_render_target(BackendHandleAccess::FlushRead)
+            .and_then(|target| target.gl_framebuffer_info())
+        else {
+            eprintln!("Error reading back the frame, the surface is not a GL framebuffer");
+            return false;
+        };
+        // The frame must have reached GL before copying it.
+        if let Some(mut context) = surface.canvas().direct_context() {
+            context.flush_and_submit();
+        }
+        let size = (surface.width(), surface.height());
+        let len = size.0 as usize * size.1 as usize * 4;
+        let fence = unsafe {
+            if buffer.id == 0 {
+                gl::GenBuffers(1, &mut buffer.id);
+            }
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer.fboid);
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, buffer.id);
+            if buffer.capacity != len {
+                gl::BufferData(
+                    gl::PIXEL_PACK_BUFFER,
+                    len as isize,
+                    ptr::null(),
+                    gl::STREAM_READ,
+                );
+                buffer.capacity = len;
+            }
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+            gl::ReadPixels(
+                0,
+                0,
+                size.0,
+                size.1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                ptr::null_mut(),
+            );
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+            gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0)
+        };
+        buffer.pending = Some(Pending { frame, size, fence });
+        reset_context(surface);
+        true
+    }
+
+    /// Map the oldest read requested at least `READBACK_LATENCY` frames before `frame`,
+    /// if the GPU finished it, call until `None` to drain the reads.
+    pub fn poll(&mut self, surface: &mut SkiaSurface, frame: usize) -> Option<Readback> {
+        let buffer = self
+            .buffers
+            .iter_mut()
+            .filter(|buffer| {
+                buffer
+                    .pending
+                    .as_ref()
+                    .is_some_and(|pending| pending.frame + READBACK_LATENCY <= frame)
+            })
+            .min_by_key(|buffer| buffer.pending.as_ref().map(|pending| pending.frame))?;
+        let pending = buffer.pending.as_ref()?;
+        // Never wait on the GPU, a late read is mapped on a later frame.
+        let status = unsafe { gl::ClientWaitSync(pending.fence, gl::SYNC_FLUSH_COMMANDS_BIT, 0) };
+        if status == gl::TIMEOUT_EXPIRED {
+            return None;
+        }
+        let pending = buffer.pending.take()?;
+        let readback = unsafe {
+            gl::DeleteSync(pending.fence);
+            if status == gl::WAIT_FAILED {
+                eprintln!("Error waiting for the readback of frame {}", pending.frame);
+                None
+            } else {
+                map_buffer(buffer.id, &pending)
+            }
+        };
+        reset_context(surface);
+        readback
+    }
+}
+
+impl Drop for AsyncReadback {
+    fn drop(&mut self) {
+        for buffer in self.buffers.iter_mut() {
+            unsafe {
+                if let Some(pending) = buffer.pending.take() {
+                    gl::DeleteSync(pending.fence);
+                }
+                if buffer.id != 0 {
+                    gl::DeleteBuffers(1, &buffer.id);
+                }
+            }
+        }
+    }
+}
+
+/// Copy the pixels out of the buffer, flipping the rows of the bottom-up GL framebuffer.
+unsafe fn map_buffer(id: GLuint, pending: &Pending) -> Option<Readback> {
+    let row_bytes = pending.size.0 as usize * 4;
+    let rows = pending.size.1 as usize;
+    gl::BindBuffer(gl::PIXEL_PACK_BUFFER, id);
+    let mapped = gl::MapBufferRange(
+        gl::PIXEL_PACK_BUFFER,
+        0,
+        (row_bytes * rows) as isize,
+        gl::MAP_READ_BIT,
+    ) as *const u8;
+    let readback = if mapped.is_null() {
+        eprintln!("Error mapping the readback of frame {}", pending.frame);
+        None
+    } else {
+        let mapped = std::slice::from_raw_parts(mapped, row_bytes * rows);
+        let mut pixels = Vec::with_capacity(row_bytes * rows);
+        for row in mapped.chunks_exact(row_bytes).rev() {
+            pixels.extend_from_slice(row);
+        }
+        gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER);
+        Some(Readback {
+            frame: pending.frame,
+            size: pending.size,
+            pixels,
+        })
+    };
+    gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+    readback
+}
+
+/// Skia caches the GL state, invalidate the parts changed behind its back: the framebuffer
+/// binding, the pack alignment and the buffer bindings, which Skia counts as misc state.
+fn reset_context(surface: &mut SkiaSurface) {
+    if let Some(mut context) = surface.canvas().direct_context() {
+        let touched = BackendState::RENDER_TARGET | BackendState::PIXEL_STORE | BackendState::MISC;
+        context.reset(Some(touched.bits()));
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Error, ErrorKind, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::InputEvent;
+
+/// An event fed to the backend, recorded to reproduce an interactive session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Input(InputEvent),
+    /// The new inner size of the window in physical pixels.
+    Resize((u32, u32)),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimedEvent {
+    /// The time since the recording started.
+    pub time: Duration,
+    pub event: RecordedEvent,
+}
+
+/// Writes the events as JSON lines, flushed per event so a crash keeps the recording.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl InputRecorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, event: RecordedEvent) -> io::Result<()> {
+        let event = TimedEvent {
+            time: self.start.elapsed(),
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+/// How the replayer advances through the recording.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayTiming {
+    /// At the recorded times relative to the start of the replay.
+    #[default]
+    Realtime,
+    /// By a fixed step per frame regardless of the wall time, so a replay runs as fast as
+    /// the frames are rendered and is independent of the machine speed.
+    FixedStep(Duration),
+}
+
+/// Feeds a recording back, poll it once per frame.
+#[derive(Debug, Clone)]
+pub struct InputReplayer {
+    events: Vec<TimedEvent>,
+    next: usize,
+    timing: ReplayTiming,
+    start: Option<Instant>,
+    /// The time reached in the recording.
+    time: Duration,
+}
+
+impl InputReplayer {
+    pub fn open(path: impl AsRef<Path>, timing: ReplayTiming) -> io::Result<Self> {
+        let mut events = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: TimedEvent = serde_json::from_str(&line)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            events.push(event);
+        }
+        Ok(Self::new(events, timing))
+    }
+
+    #[inline]
+    pub fn new(events: Vec<TimedEvent>, timing: ReplayTiming) -> Self {
+        Self {
+            events,
+            next: 0,
+            timing,
+            start: None,
+            time: Duration::ZERO,
+        }
+    }
+
+    /// Advance the replay to the frame starting at `now`, returns the events due. The
+    /// replay starts on the first call.
+    pub fn advance(&mut self, now: Instant) -> &[TimedEvent] {
+        self.time = match self.timing {
+            ReplayTiming::Realtime => now - *self.start.get_or_insert(now),
+            ReplayTiming::FixedStep(step) => {
+                if self.start.replace(now).is_some() {
+                    self.time + step
+                } else {
+                    Duration::ZERO
+                }
+            }
+        };
+        let first = self.next;
+        while self
+            .events
+            .get(self.next)
+            .is_some_and(|event| event.time <= self.time)
+        {
+            self.next += 1;
+        }
+        &self.events[first..self.next]
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.next == self.events.len()
+    }
+}
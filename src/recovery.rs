@@ -0,0 +1,209 @@
+use crate::renderer::Renderer;
+
+/// The bytes of the length prefixing each state in `join_states`.
+const LEN_BYTES: usize = std::mem::size_of::<u64>();
+
+/// How often the renderer state is saved, and how many times a crash is recovered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryConfig {
+    /// Save the state every `interval` frames.
+    pub interval: usize,
+    /// Let the panic propagate after this many recoveries, a renderer panicking on every
+    /// frame is not recovered forever.
+    pub max_recoveries: usize,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            interval: 60,
+            max_recoveries: 3,
+        }
+    }
+}
+
+/// Keeps the last state saved by the renderer with `Renderer::save_state`, handed back with
+/// `Renderer::restore_state` after a frame panicked or the GPU reset, once the backend
+/// recreated the GPU context, so the application resumes where it was instead of
+/// crashing or restarting from scratch.
+#[derive(Debug, Default)]
+pub struct StateRecovery {
+    config: Option<RecoveryConfig>,
+    /// The frame the state was saved at, and the state.
+    state: Option<(usize, Vec<u8>)>,
+    recoveries: usize,
+}
+
+impl StateRecovery {
+    /// `None` turns the recovery off, the saved state is dropped.
+    pub fn set_config(&mut self, config: Option<RecoveryConfig>) {
+        self.config = config;
+        if config.is_none() {
+            self.state = None;
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> Option<RecoveryConfig> {
+        self.config
+    }
+
+    /// Whether a crash can still be recovered from.
+    #[inline]
+    pub fn can_recover(&self) -> bool {
+        self.config
+            .is_some_and(|config| self.recoveries < config.max_recoveries)
+    }
+
+    #[inline]
+    pub fn recoveries(&self) -> usize {
+        self.recoveries
+    }
+
+    /// Save the renderer state if the frame is due, after it was drawn successfully.
+    pub fn save(&mut self, frame: usize, renderer: &dyn Renderer) {
+        let Some(config) = self.config else {
+            return;
+        };
+        if frame % config.interval.max(1) != 0 {
+            return;
+        }
+        if let Some(state) = renderer.save_state() {
+            self.state = Some((frame, state));
+        }
+    }
+
+    /// Hand the last saved state back to the renderer, returns the frame it was saved at.
+    pub fn restore(&mut self, renderer: &mut dyn Renderer) -> Option<usize> {
+        self.recoveries += 1;
+        let (frame, state) = self.state.as_ref()?;
+        renderer.restore_state(state);
+        Some(*frame)
+    }
+}
+
+/// Pack the states of the renderers a renderer is composed of, e.g. the scenes of a stack,
+/// into its state for `Renderer::save_state`, `None` when none of them has a state.
+pub fn join_states(states: impl IntoIterator<Item = Option<Vec<u8>>>) -> Option<Vec<u8>> {
+    let states: Vec<_> = states.into_iter().collect();
+    if states.iter().all(Option::is_none) {
+        return None;
+    }
+    let mut joined = Vec::new();
+    for state in states {
+        // The length plus one, `0` for a renderer without a state.
+        let len = state.as_ref().map_or(0, |state| state.len() as u64 + 1);
+        joined.extend_from_slice(&len.to_le_bytes());
+        joined.extend(state.unwrap_or_default());
+    }
+    Some(joined)
+}
+
+/// Unpack the states packed with `join_states`, in the same order, a truncated state ends
+/// the states early.
+pub fn split_states(mut joined: &[u8]) -> Vec<Option<&[u8]>> {
+    let mut states = Vec::new();
+    while joined.len() >= LEN_BYTES {
+        let (len, rest) = joined.split_at(LEN_BYTES);
+        let len = u64::from_le_bytes(len.try_into().unwrap()) as usize;
+        if len == 0 {
+            states.push(None);
+            joined = rest;
+            continue;
+        }
+        let Some(state) = rest.get(..len - 1) else {
+            break;
+        };
+        states.push(Some(state));
+        joined = &rest[len - 1..];
+    }
+    states
+}
+
+#[cfg(test)]
+mod tests {
+    use skia_safe::Canvas;
+
+    use super::*;
+    use crate::renderer::FrameInfo;
+
+    /// Counts its frames, the count is its state.
+    #[derive(Default)]
+    struct Counter {
+        count: u8,
+        restored: Option<Vec<u8>>,
+    }
+
+    impl Renderer for Counter {
+        fn render(&mut self, _canvas: &mut Canvas, _info: &FrameInfo) {}
+
+        fn save_state(&self) -> Option<Vec<u8>> {
+            Some(vec![self.count])
+        }
+
+        fn restore_state(&mut self, state: &[u8]) {
+            self.restored = Some(state.to_vec());
+        }
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let states = vec![Some(vec![1, 2, 3]), None, Some(vec![]), Some(vec![4])];
+        let joined = join_states(states.clone()).unwrap();
+        let split: Vec<_> = split_states(&joined)
+            .into_iter()
+            .map(|state| state.map(<[u8]>::to_vec))
+            .collect();
+        assert_eq!(split, states);
+    }
+
+    #[test]
+    fn no_states_join_to_none() {
+        assert_eq!(join_states([None, None]), None);
+        assert_eq!(join_states([]), None);
+    }
+
+    #[test]
+    fn truncated_states_end_early() {
+        let joined = join_states([Some(vec![1]), Some(vec![2, 3])]).unwrap();
+        let split = split_states(&joined[..joined.len() - 1]);
+        assert_eq!(split, vec![Some(&[1][..])]);
+        assert!(split_states(&[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn saves_on_the_interval_and_restores_the_last_state() {
+        let mut recovery = StateRecovery::default();
+        let mut renderer = Counter::default();
+        recovery.save(0, &renderer);
+        assert_eq!(recovery.restore(&mut renderer), None);
+
+        recovery.set_config(Some(RecoveryConfig {
+            interval: 2,
+            max_recoveries: 2,
+        }));
+        for frame in 1..=5 {
+            renderer.count = frame as u8;
+            recovery.save(frame, &renderer);
+        }
+        assert_eq!(recovery.restore(&mut renderer), Some(4));
+        assert_eq!(renderer.restored, Some(vec![4]));
+    }
+
+    #[test]
+    fn stops_recovering_after_the_maximum() {
+        let mut recovery = StateRecovery::default();
+        let mut renderer = Counter::default();
+        assert!(!recovery.can_recover());
+        recovery.set_config(Some(RecoveryConfig {
+            interval: 1,
+            max_recoveries: 2,
+        }));
+        assert!(recovery.can_recover());
+        recovery.restore(&mut renderer);
+        assert!(recovery.can_recover());
+        recovery.restore(&mut renderer);
+        assert!(!recovery.can_recover());
+        assert_eq!(recovery.recoveries(), 2);
+    }
+}
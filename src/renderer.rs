@@ -4,14 +4,195 @@
 #![allow(unknown_lints)]
 #![allow(clippy::unusual_byte_groupings)]
 use skia_safe::{
-    gradient_shader, Color, Matrix, Paint, PaintJoin, PaintStyle, Path, Point, TileMode,
+    gradient_shader, Canvas, Color, Image, Matrix, Paint, PaintJoin, PaintStyle, Path, Point, Rect,
+    TileMode,
 };
 use std::cmp::min;
 
+use crate::{
+    arena::FrameArena,
+    camera::Camera,
+    culling,
+    focus::{FocusId, FocusNode},
+    input::{InputEvent, InputState},
+    invalidation::Invalidator,
+    locale::Locale,
+    profiler::{DrawCategory, DrawProfiler},
+    proxy::BackendProxy,
+    quality::Quality,
+    theme::{SystemTheme, Theme},
+    upload::TextureUploader,
+    user_event::UserEvent,
+};
+
 const PI: f32 = std::f32::consts::PI;
 const DEGREES_IN_RADIANS: f32 = PI / 180.0;
 const PEN_SIZE: f32 = 1.0;
 
+pub struct FrameInfo<'a> {
+    pub frame: usize,
+    /// The size of the drawing area in pixels.
+    pub size: (i32, i32),
+    /// The surface pixels per window pixel, the canvas is scaled by it before the camera,
+    /// e.g. to map the canvas transform back to window pixels.
+    pub pixel_scale: f32,
+    /// The part of the drawing area not covered by the platform, e.g. a notch or a
+    /// transparent title bar, keep the controls inside, see `Backend::set_safe_area_config`.
+    pub safe_area: Rect,
+    pub input: &'a InputState,
+    pub camera: &'a Camera,
+    /// Wrap the canvas with `profiler.canvas(canvas)` to report the draw calls in the stats.
+    pub profiler: &'a DrawProfiler,
+    pub theme: &'a Theme,
+    /// The previous frame, kept with `Backend::set_keep_frame_snapshot`, e.g. for motion
+    /// trails. It is sized like the render target, which differs from `size` when the
+    /// render scale is not `1`.
+    pub previous_frame: Option<&'a Image>,
+    /// The seconds the frame clock advanced since the previous frame, scaled in slow motion
+    /// and zero while the clock is paused, see `Backend::set_clock_paused`.
+    pub dt: f32,
+    /// Dither the gradients with `Paint::set_dither`, set with `Backend::set_dithering`
+    /// against banding on 8 bit displays.
+    pub dither: bool,
+    /// Antialias the paints with `Paint::set_anti_alias`, turned off with
+    /// `Backend::set_paint_antialiasing` to save fill-rate on multisampled surfaces.
+    pub anti_alias: bool,
+    /// Allocate the transient draw data of the frame here, e.g. the points of a path, it is
+    /// reset after the frame instead of freeing every allocation.
+    pub arena: &'a FrameArena,
+    /// Uploads the large images on a worker thread, e.g. the decoded assets, `None` when
+    /// the platform has no shared context for it or outside of a window.
+    pub uploader: Option<&'a TextureUploader>,
+}
+
+impl FrameInfo<'_> {
+    /// The content space area on screen, after the camera transform, skip the items
+    /// outside it, see `culling::QuadTree`.
+    #[inline]
+    pub fn visible_rect(&self) -> Rect {
+        self.camera.visible_rect(self.size)
+    }
+
+    /// Whether an item with the bounds in content space is on screen.
+    #[inline]
+    pub fn is_visible(&self, bounds: &Rect) -> bool {
+        culling::overlaps(bounds, &self.visible_rect())
+    }
+}
+
+/// How the frame edges are antialiased.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Antialiasing {
+    /// Multisampling of the window surface, if the config has samples.
+    #[default]
+    Msaa,
+    /// Render at twice the resolution and downscale, smoother on path-heavy content at
+    /// four times the fill cost.
+    Supersample,
+}
+
+/// The drawing callback driven by the backend, on the `independent_ui` feature it is
+/// called from the ui thread.
+pub trait Renderer: Send {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo);
+
+    /// Draw on top of the frame in window space, after the camera transform was removed.
+    fn render_overlay(&mut self, _canvas: &mut Canvas, _info: &FrameInfo) {}
+
+    fn handle_input(&mut self, _event: &InputEvent) {}
+
+    /// The nodes taking the keyboard focus in the Tab order, the backend draws the ring
+    /// around the focused one.
+    fn focus_nodes(&self) -> Vec<FocusNode> {
+        Vec::new()
+    }
+
+    /// A key event for the focused node, the keys not consumed go to `handle_input`.
+    fn handle_focused_key(&mut self, _id: FocusId, _event: &InputEvent) -> bool {
+        false
+    }
+
+    /// Called when the OS switches between the light and the dark appearance.
+    fn system_theme_changed(&mut self, _theme: SystemTheme) {}
+
+    /// Called when the window moved to a monitor with another scale factor, the frame is
+    /// resized to the new physical size.
+    fn scale_factor_changed(&mut self, _scale_factor: f64) {}
+
+    /// Called when the application switches the locale, see `Backend::set_locale`, the
+    /// text caches should be given the new locale.
+    fn locale_changed(&mut self, _locale: &Locale) {}
+
+    /// The minimal state to resume from after a crash, e.g. the open document and the
+    /// scroll position, saved every few frames when the recovery is on, see
+    /// `Backend::set_state_recovery`.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Called with the last saved state after a frame panicked or the GPU reset, once the
+    /// GPU context was recreated, the GPU resources must be created again.
+    fn restore_state(&mut self, _state: &[u8]) {}
+
+    /// Called when the GPU context was abandoned and created again, e.g. after a GPU reset
+    /// or when the msaa resolve changed, the images and surfaces of the old context must
+    /// be dropped and created again.
+    fn context_lost(&mut self) {}
+
+    /// Called once the renderer is given to a backend, keep the invalidator to request the
+    /// frames in on-demand rendering, see `Backend::set_on_demand`.
+    fn attach_invalidator(&mut self, _invalidator: Invalidator) {}
+
+    /// Called before the renderer is given to a backend, hand the proxy to the threads
+    /// producing the content, e.g. an asset loader, to wake the event loop when it changed.
+    fn attach_proxy(&mut self, _proxy: BackendProxy) {}
+
+    /// Called at the start of the frame with the events posted since the previous frame,
+    /// e.g. with `Backend::post_event`, downcast to the posted types.
+    fn handle_user_event(&mut self, _event: UserEvent) {}
+
+    /// Called when features were given up under load or restored, see
+    /// `Backend::set_quality_policy`, the renderer skips the disabled effects it draws
+    /// itself, e.g. the post effects and the shadows.
+    fn quality_changed(&mut self, _quality: Quality) {}
+
+    /// Queried every frame, so a renderer can switch when its content changes.
+    fn antialiasing(&self) -> Antialiasing {
+        Antialiasing::Msaa
+    }
+}
+
+/// Spins a chain ring with the frames, requesting every frame on demand.
+#[derive(Debug, Default, Clone)]
+pub struct DemoRenderer {
+    invalidator: Option<Invalidator>,
+}
+
+impl Renderer for DemoRenderer {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        if let Some(invalidator) = &self.invalidator {
+            invalidator.invalidate();
+        }
+        info.profiler
+            .canvas(canvas)
+            .profile(DrawCategory::Path, |canvas| {
+                render_frame(
+                    info.frame % 360,
+                    12,
+                    60,
+                    info.dither,
+                    info.anti_alias,
+                    info.size,
+                    canvas,
+                )
+            });
+    }
+
+    fn attach_invalidator(&mut self, invalidator: Invalidator) {
+        self.invalidator = Some(invalidator);
+    }
+}
+
 fn point_in_circle(center: (f32, f32), radius: f32, radians: f32) -> (f32, f32) {
     (
         center.0 + radius * radians.cos(),
@@ -19,26 +200,39 @@ fn point_in_circle(center: (f32, f32), radius: f32, radians: f32) -> (f32, f32)
     )
 }
 
+/// Draw the frame into the `size` drawing area, the canvas may be scaled to more surface
+/// pixels, e.g. by the render scale or the supersampling.
+#[allow(clippy::too_many_arguments)]
 pub fn render_frame(
     frame: usize,
     fps: usize,
     bpm: usize,
+    dither: bool,
+    anti_alias: bool,
+    size: (i32, i32),
     canvas: &mut skia_safe::canvas::Canvas,
 ) -> usize {
     let step = 12.0 * bpm as f32 / 60.0 / fps as f32;
     let frame_count = (360.0 / step) as usize;
 
-    let size = {
-        let dim = canvas.image_info().dimensions();
-        min(dim.width, dim.height)
-    };
+    let stroke_width = PEN_SIZE.max(size.0 as f32 / 360.0);
+    let size = min(size.0, size.1);
 
     let center = (size / 2, size / 2);
     let chain_ring_radius = size / 2 * 100 / 100;
     let triangle_radius = size / 2 * 53 / 100;
 
     let rotation = frame as f32 * step;
-    chain_ring(canvas, center, chain_ring_radius, rotation, 32);
+    chain_ring(
+        canvas,
+        center,
+        chain_ring_radius,
+        rotation,
+        32,
+        stroke_width,
+        dither,
+        anti_alias,
+    );
 
     let triangle_rotation = 60.0 + rotation;
     triangle(
@@ -49,6 +243,9 @@ pub fn render_frame(
         Some(0),
         Color::GREEN,
         true,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -58,6 +255,9 @@ pub fn render_frame(
         Some(1),
         Color::BLUE,
         true,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -67,6 +267,9 @@ pub fn render_frame(
         Some(2),
         Color::RED,
         true,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -76,6 +279,9 @@ pub fn render_frame(
         Some(0),
         Color::YELLOW,
         false,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -85,6 +291,9 @@ pub fn render_frame(
         Some(1),
         Color::CYAN,
         false,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -94,6 +303,9 @@ pub fn render_frame(
         Some(2),
         Color::MAGENTA,
         false,
+        stroke_width,
+        dither,
+        anti_alias,
     );
 
     triangle(
@@ -104,6 +316,9 @@ pub fn render_frame(
         None,
         Color::from(0x77_222222),
         true,
+        stroke_width,
+        dither,
+        anti_alias,
     );
     triangle(
         canvas,
@@ -113,17 +328,24 @@ pub fn render_frame(
         None,
         Color::from(0x77_222222),
         false,
+        stroke_width,
+        dither,
+        anti_alias,
     );
 
     frame_count - (frame + 1)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn chain_ring(
     canvas: &mut skia_safe::canvas::Canvas,
     center: (i32, i32),
     radius: i32,
     rotation: f32,
     teeth_count: i32,
+    stroke_width: f32,
+    dither: bool,
+    anti_alias: bool,
 ) {
     canvas.save();
     canvas.translate(Point::from(center));
@@ -131,8 +353,9 @@ fn chain_ring(
     canvas.rotate(rotation, None);
 
     let mut paint = Paint::default();
-    paint.set_anti_alias(true);
-    paint.set_stroke_width(PEN_SIZE.max(canvas.image_info().dimensions().width as f32 / 360.0));
+    paint.set_anti_alias(anti_alias);
+    paint.set_dither(dither);
+    paint.set_stroke_width(stroke_width);
 
     let center = (0, 0);
     let c = (center.0 as f32, center.1 as f32);
@@ -251,7 +474,7 @@ fn chain_ring(
     canvas.restore();
 }
 
-#[allow(clippy::many_single_char_names)]
+#[allow(clippy::many_single_char_names, clippy::too_many_arguments)]
 fn triangle(
     canvas: &mut skia_safe::canvas::Canvas,
     center: (i32, i32),
@@ -260,6 +483,9 @@ fn triangle(
     vertex: Option<i32>,
     color: Color,
     wankel: bool,
+    stroke_width: f32,
+    dither: bool,
+    anti_alias: bool,
 ) {
     let c = (center.0 as f32, center.1 as f32);
     let r = radius as f32;
@@ -270,6 +496,7 @@ fn triangle(
     let mut alpha = degrees * DEGREES_IN_RADIANS;
     let mut path = Path::new();
     let mut paint = Paint::default();
+    paint.set_dither(dither);
     match vertex {
         Some(index) => {
             let a = (degrees + (120 * index) as f32) * DEGREES_IN_RADIANS;
@@ -294,10 +521,8 @@ fn triangle(
             gradient(&mut paint, center, radii, (color, Color::from(0x00_0000ff)))
         }
         None => {
-            paint.set_anti_alias(true);
-            paint.set_stroke_width(
-                PEN_SIZE.max(canvas.image_info().dimensions().width as f32 / 360.0),
-            );
+            paint.set_anti_alias(anti_alias);
+            paint.set_stroke_width(stroke_width);
             paint.set_style(PaintStyle::Stroke);
             paint.set_stroke_join(PaintJoin::Bevel);
             // Highlight reflection on the top triangle edge:
@@ -350,3 +575,71 @@ fn gradient(paint: &mut Paint, center: (f32, f32), radii: (f32, f32), colors: (C
         &matrix,
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use skia_safe::{
+        AlphaType, ColorType, FilterMode, ImageInfo, MipmapMode, SamplingOptions, Surface,
+    };
+
+    /// Render the demo frame at `scale` surface pixels per drawing area pixel.
+    fn render(size: (i32, i32), scale: i32) -> Surface {
+        let mut surface = Surface::new_raster_n32_premul((size.0 * scale, size.1 * scale)).unwrap();
+        let canvas = surface.canvas();
+        canvas.scale((scale as f32, scale as f32));
+        render_frame(30, 12, 60, false, true, size, canvas);
+        surface
+    }
+
+    /// The left, top, right and bottom of the mostly opaque pixels.
+    fn drawn_bounds(surface: &mut Surface) -> (i32, i32, i32, i32) {
+        let size = (surface.width(), surface.height());
+        let info = ImageInfo::new(size, ColorType::RGBA8888, AlphaType::Premul, None);
+        let mut pixels = vec![0u8; info.compute_min_byte_size()];
+        assert!(surface.read_pixels(&info, &mut pixels, info.min_row_bytes(), (0, 0)));
+        let mut bounds = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+        for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+            if pixel[3] > 0x80 {
+                let (x, y) = (index as i32 % size.0, index as i32 / size.0);
+                bounds = (
+                    bounds.0.min(x),
+                    bounds.1.min(y),
+                    bounds.2.max(x),
+                    bounds.3.max(y),
+                );
+            }
+        }
+        bounds
+    }
+
+    #[test]
+    fn supersampled_frame_matches_msaa_geometry() {
+        let size = (240, 200);
+        let mut msaa = render(size, 1);
+        // Downscaled by the linear blit at half the size, like the offscreen framebuffer.
+        let supersampled = render(size, 2).image_snapshot();
+        let mut resolved = Surface::new_raster_n32_premul(size).unwrap();
+        resolved.canvas().draw_image_rect_with_sampling_options(
+            &supersampled,
+            None,
+            Rect::from_iwh(size.0, size.1),
+            SamplingOptions::new(FilterMode::Linear, MipmapMode::None),
+            &Paint::default(),
+        );
+
+        let expected = drawn_bounds(&mut msaa);
+        let bounds = drawn_bounds(&mut resolved);
+        assert!(expected.2 > expected.0 && expected.3 > expected.1);
+        let offsets = [
+            bounds.0 - expected.0,
+            bounds.1 - expected.1,
+            bounds.2 - expected.2,
+            bounds.3 - expected.3,
+        ];
+        assert!(
+            offsets.iter().all(|offset| offset.abs() <= 1),
+            "{bounds:?} != {expected:?}"
+        );
+    }
+}
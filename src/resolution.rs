@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+pub const MIN_RENDER_SCALE: f32 = 0.5;
+pub const MAX_RENDER_SCALE: f32 = 2.;
+
+/// The number of frames to wait after a scale change before adjusting again, recreating
+/// the framebuffer every frame would cost more than it saves.
+const ADJUST_INTERVAL: usize = 30;
+const SCALE_STEP: f32 = 0.05;
+
+/// The resolution the frame is rendered at relative to the window size, the frame is
+/// scaled to the window size when it is blitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderScale {
+    pub scale: f32,
+    /// Adjust the scale within `(min, max)` to keep the frame time within the budget.
+    pub auto: Option<(f32, f32)>,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self::fixed(1.)
+    }
+}
+
+impl RenderScale {
+    #[inline]
+    pub fn fixed(scale: f32) -> Self {
+        Self {
+            scale: scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE),
+            auto: None,
+        }
+    }
+
+    #[inline]
+    pub fn auto(min: f32, max: f32) -> Self {
+        let min = min.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        let max = max.clamp(min, MAX_RENDER_SCALE);
+        Self {
+            scale: max,
+            auto: Some((min, max)),
+        }
+    }
+}
+
+/// Drives the render scale from the measured frame times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolutionScaler {
+    config: RenderScale,
+    scale: f32,
+    frames_since_change: usize,
+}
+
+impl Default for ResolutionScaler {
+    fn default() -> Self {
+        Self::new(RenderScale::default())
+    }
+}
+
+impl ResolutionScaler {
+    pub fn new(config: RenderScale) -> Self {
+        Self {
+            config,
+            scale: config.scale,
+            frames_since_change: 0,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &RenderScale {
+        &self.config
+    }
+
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Report the time spent rendering the frame, returns the new scale if it changed.
+    pub fn update(&mut self, frame_time: Duration, budget: Duration) -> Option<f32> {
+        let (min, max) = self.config.auto?;
+        self.frames_since_change += 1;
+        if self.frames_since_change < ADJUST_INTERVAL {
+            return None;
+        }
+
+        let load = frame_time.as_secs_f32() / budget.as_secs_f32().max(f32::EPSILON);
+        let scale = if load > 0.9 {
+            // The cost is roughly proportional to the pixel count, i.e. the squared scale.
+            (self.scale * (0.9 / load).sqrt()).min(self.scale - SCALE_STEP)
+        } else if load < 0.6 {
+            self.scale + SCALE_STEP
+        } else {
+            self.scale
+        };
+        let scale = ((scale / SCALE_STEP).round() * SCALE_STEP).clamp(min, max);
+
+        if scale != self.scale {
+            self.scale = scale;
+            self.frames_since_change = 0;
+            Some(scale)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use skia_safe::{Canvas, Font, Paint, PaintStyle, Rect, Typeface};
+
+use crate::{camera::Camera, locale::Locale, snap::PixelGrid, theme::Theme};
+
+/// The thickness of the rulers along the top and left window edges.
+const RULER_SIZE: f32 = 18.;
+
+/// A debug overlay with a grid, rulers and a crosshair following the camera, to position
+/// elements while developing renderers. The lines are snapped to the device pixels so they
+/// stay crisp at any zoom and render scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RulerOverlay {
+    /// The minimum distance in window pixels between two grid lines, the grid spacing
+    /// steps through 1, 2 and 5 times the powers of ten while zooming.
+    pub min_spacing: f32,
+    pub grid: bool,
+    pub rulers: bool,
+    /// A crosshair at the cursor labeled with its content coordinates.
+    pub crosshair: bool,
+}
+
+impl Default for RulerOverlay {
+    fn default() -> Self {
+        Self {
+            min_spacing: 8.,
+            grid: true,
+            rulers: true,
+            crosshair: true,
+        }
+    }
+}
+
+impl RulerOverlay {
+    /// The content space distance between two grid lines at the camera `scale`, and the
+    /// number of lines between two major lines.
+    pub fn grid_spacing(&self, scale: f32) -> (f32, i64) {
+        let min = (self.min_spacing / scale).max(f32::MIN_POSITIVE);
+        let magnitude = 10f32.powf(min.log10().floor());
+        for (step, major_every) in [(1., 10), (2., 5), (5., 10)] {
+            if magnitude * step >= min {
+                return (magnitude * step, major_every);
+            }
+        }
+        (magnitude * 10., 10)
+    }
+
+    /// Draw in window space, `cursor` is the cursor position in the window. The rulers
+    /// measure along the window edges, they are hidden while the camera is rotated. The
+    /// labels are drawn with the digits of the locale and its typeface, see
+    /// `Locale::typeface`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        canvas: &mut Canvas,
+        camera: &Camera,
+        size: (i32, i32),
+        cursor: Option<(f32, f32)>,
+        theme: &Theme,
+        locale: &Locale,
+        typeface: Option<&Typeface>,
+    ) {
+        let (spacing, major_every) = self.grid_spacing(camera.scale());
+        let visible = camera.visible_rect(size);
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        // Hairlines are one device pixel wide, centered on a pixel they cover it exactly.
+        let grid = PixelGrid::of(canvas);
+        let snap_x = |x: f32| grid.x(x, 0.5);
+        let snap_y = |y: f32| grid.y(y, 0.5);
+        let lines = |start: f32, end: f32| {
+            let first = (start / spacing).floor() as i64;
+            let last = (end / spacing).ceil() as i64;
+            first..=last
+        };
+        let matrix = camera.matrix();
+        let rotated = camera.rotation() != 0.;
+        // Only while the camera isn't rotated, a content line is a window row or column.
+        let to_window_x = |x: f32| matrix.map_point((x, 0.)).x;
+        let to_window_y = |y: f32| matrix.map_point((0., y)).y;
+
+        let mut minor = Paint::default();
+        minor
+            .set_style(PaintStyle::Stroke)
+            .set_stroke_width(0.)
+            .set_color(theme.colors.text.with_a(0x18));
+        let mut major = minor.clone();
+        major.set_color(theme.colors.text.with_a(0x40));
+        let grid_paint = |index: i64| {
+            if index % major_every == 0 {
+                &major
+            } else {
+                &minor
+            }
+        };
+
+        if self.grid && rotated {
+            // The lines cross the visible rect, which bounds the window in content space.
+            for index in lines(visible.left, visible.right) {
+                let x = index as f32 * spacing;
+                canvas.draw_line(
+                    matrix.map_point((x, visible.top)),
+                    matrix.map_point((x, visible.bottom)),
+                    grid_paint(index),
+                );
+            }
+            for index in lines(visible.top, visible.bottom) {
+                let y = index as f32 * spacing;
+                canvas.draw_line(
+                    matrix.map_point((visible.left, y)),
+                    matrix.map_point((visible.right, y)),
+                    grid_paint(index),
+                );
+            }
+        } else if self.grid {
+            for index in lines(visible.left, visible.right) {
+                let x = snap_x(to_window_x(index as f32 * spacing));
+                canvas.draw_line((x, 0.), (x, height), grid_paint(index));
+            }
+            for index in lines(visible.top, visible.bottom) {
+                let y = snap_y(to_window_y(index as f32 * spacing));
+                canvas.draw_line((0., y), (width, y), grid_paint(index));
+            }
+        }
+
+        let font = match typeface {
+            Some(typeface) => Font::from_typeface(typeface, theme.typography.small_font_size),
+            None => Font::default()
+                .with_size(theme.typography.small_font_size)
+                .expect("Could not create font"),
+        };
+        let mut text = Paint::default();
+        text.set_anti_alias(true).set_color(theme.colors.text);
+        let mut tick = major.clone();
+        tick.set_color(theme.colors.text);
+
+        if self.rulers && !rotated {
+            let mut background = Paint::default();
+            background.set_color(theme.colors.surface.with_a(0xe0));
+            canvas.draw_rect(Rect::from_wh(width, RULER_SIZE), &background);
+            canvas.draw_rect(
+                Rect::from_xywh(0., RULER_SIZE, RULER_SIZE, height - RULER_SIZE),
+                &background,
+            );
+            for index in lines(visible.left, visible.right) {
+                let x = snap_x(to_window_x(index as f32 * spacing));
+                if x < RULER_SIZE {
+                    continue;
+                }
+                let is_major = index % major_every == 0;
+                let length = if is_major {
+                    RULER_SIZE
+                } else {
+                    RULER_SIZE / 4.
+                };
+                canvas.draw_line((x, RULER_SIZE - length), (x, RULER_SIZE), &tick);
+                if is_major {
+                    let label = format_coordinate(index as f32 * spacing, spacing);
+                    let label = locale.shape_digits(&label);
+                    canvas.draw_str(label, (x + 3., RULER_SIZE - 5.), &font, &text);
+                }
+            }
+            for index in lines(visible.top, visible.bottom) {
+                let y = snap_y(to_window_y(index as f32 * spacing));
+                if y < RULER_SIZE {
+                    continue;
+                }
+                let is_major = index % major_every == 0;
+                let length = if is_major {
+                    RULER_SIZE
+                } else {
+                    RULER_SIZE / 4.
+                };
+                canvas.draw_line((RULER_SIZE - length, y), (RULER_SIZE, y), &tick);
+                if is_major {
+                    // Vertical labels, read bottom to top.
+                    let label = format_coordinate(index as f32 * spacing, spacing);
+                    let label = locale.shape_digits(&label);
+                    canvas.save();
+                    canvas.translate((RULER_SIZE - 5., y - 3.));
+                    canvas.rotate(-90., None);
+                    canvas.draw_str(label, (0., 0.), &font, &text);
+                    canvas.restore();
+                }
+            }
+        }
+
+        if let Some(cursor) = cursor.filter(|_| self.crosshair) {
+            let mut crosshair = major.clone();
+            crosshair.set_color(theme.colors.accent);
+            let (x, y) = (snap_x(cursor.0), snap_y(cursor.1));
+            canvas.draw_line((x, 0.), (x, height), &crosshair);
+            canvas.draw_line((0., y), (width, y), &crosshair);
+            let position = camera.to_content(cursor);
+            let label = format!(
+                "{}, {}",
+                format_coordinate(position.0, spacing / 10.),
+                format_coordinate(position.1, spacing / 10.)
+            );
+            canvas.draw_str(locale.shape_digits(&label), (x + 6., y - 6.), &font, &text);
+        }
+    }
+}
+
+/// The coordinate with as many decimals as the precision has.
+fn format_coordinate(value: f32, precision: f32) -> String {
+    let decimals = (-precision.log10().floor()).max(0.) as usize;
+    format!("{value:.decimals$}")
+}
@@ -0,0 +1,109 @@
+use skia_safe::Rect;
+use winit::window::{Fullscreen, Window};
+
+/// The height of the macOS title bar in points, the content is drawn under it with a
+/// transparent title bar.
+pub const TITLE_BAR_HEIGHT: f32 = 28.;
+
+/// The distances from the window edges covered by the platform, e.g. a notch, a title bar
+/// or a taskbar.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Insets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Insets {
+    pub const ZERO: Insets = Insets {
+        top: 0.,
+        right: 0.,
+        bottom: 0.,
+        left: 0.,
+    };
+
+    #[inline]
+    pub fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// The larger inset of every edge, the insets covering the same edge overlap.
+    #[inline]
+    pub fn max(&self, other: &Insets) -> Insets {
+        Insets {
+            top: self.top.max(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+            left: self.left.max(other.left),
+        }
+    }
+
+    #[inline]
+    pub fn scaled(&self, scale: f32) -> Insets {
+        Insets {
+            top: self.top * scale,
+            right: self.right * scale,
+            bottom: self.bottom * scale,
+            left: self.left * scale,
+        }
+    }
+
+    /// The part of an area of the size not covered, empty when the insets overlap.
+    pub fn safe_area(&self, size: (i32, i32)) -> Rect {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        let left = self.left.clamp(0., width);
+        let top = self.top.clamp(0., height);
+        Rect::new(
+            left,
+            top,
+            (width - self.right.max(0.)).max(left),
+            (height - self.bottom.max(0.)).max(top),
+        )
+    }
+}
+
+/// What covers the window, the insets are in logical points.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SafeAreaConfig {
+    /// The window was built with a transparent title bar and a full size content view on
+    /// macOS, the title bar covers the top of the content outside fullscreen.
+    pub transparent_titlebar: bool,
+    /// Kept clear in borderless fullscreen, where the taskbar of the monitor may show over
+    /// the window, e.g. an auto-hiding taskbar. winit doesn't report the work area.
+    pub taskbar: Insets,
+    /// The display cutouts in fullscreen, e.g. a notch.
+    pub cutout: Insets,
+}
+
+impl SafeAreaConfig {
+    /// The insets of the window in physical pixels.
+    pub fn insets(&self, window: &Window) -> Insets {
+        let scale = window.scale_factor() as f32;
+        let mut insets = Insets::ZERO;
+        match window.fullscreen() {
+            Some(fullscreen) => {
+                insets = insets.max(&self.cutout);
+                if matches!(fullscreen, Fullscreen::Borderless(_)) {
+                    insets = insets.max(&self.taskbar);
+                }
+            }
+            None => {
+                if cfg!(target_os = "macos") && self.transparent_titlebar && window.is_decorated() {
+                    insets.top = TITLE_BAR_HEIGHT;
+                }
+            }
+        }
+        insets.scaled(scale)
+    }
+}
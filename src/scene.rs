@@ -0,0 +1,588 @@
+use std::{collections::HashMap, time::Instant};
+
+use skia_safe::{Canvas, Color, Image, Matrix, Picture, PictureRecorder, Rect, Surface};
+
+use crate::{
+    interpolation::{InterpolationMode, TransformInterpolator},
+    invalidation::Invalidator,
+    renderer::{Antialiasing, FrameInfo, Renderer},
+};
+
+/// The largest side of an exported node image in pixels.
+pub const MAX_EXPORT_SIZE: i32 = 16384;
+
+/// Record the drawing into a picture culled to `bounds`.
+pub fn record(bounds: Rect, draw: impl FnOnce(&mut Canvas)) -> Picture {
+    let mut recorder = PictureRecorder::new();
+    draw(recorder.begin_recording(bounds, None));
+    recorder
+        .finish_recording_as_picture(Some(&bounds))
+        .expect("Could not record picture")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A node of the scene graph, the content is a recorded picture in the node space.
+#[derive(Clone)]
+pub struct Node {
+    pub transform: Matrix,
+    pub visible: bool,
+    content: Option<Picture>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix::new_identity(),
+            visible: true,
+            content: None,
+            parent: None,
+            children: vec![],
+        }
+    }
+
+    /// A node with the drawing recorded as its content.
+    #[inline]
+    pub fn record(bounds: Rect, draw: impl FnOnce(&mut Canvas)) -> Self {
+        Self::new().with_content(record(bounds, draw))
+    }
+
+    #[inline]
+    pub fn with_transform(mut self, transform: Matrix) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    #[inline]
+    pub fn with_content(mut self, content: Picture) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    #[inline]
+    pub fn content(&self) -> Option<&Picture> {
+        self.content.as_ref()
+    }
+
+    #[inline]
+    pub fn set_content(&mut self, content: Option<Picture>) {
+        self.content = content;
+    }
+
+    #[inline]
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    #[inline]
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// The bounds of the content in the node space.
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        self.content
+            .as_ref()
+            .map(|content| content.cull_rect())
+            .unwrap_or_default()
+    }
+}
+
+/// What a visible node with content drew at the last commit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DrawnNode {
+    content: u32,
+    transform: Matrix,
+    /// The bounds in the scene space.
+    bounds: Rect,
+}
+
+pub struct Scene {
+    pub antialiasing: Antialiasing,
+    nodes: Vec<Option<Node>>,
+    root: NodeId,
+    interpolator: Option<TransformInterpolator>,
+    invalidator: Option<Invalidator>,
+    /// The nodes as of the last commit, diffed against on the next one.
+    drawn: HashMap<NodeId, DrawnNode>,
+    /// The transform from the scene space to window pixels in the last frame.
+    view: Option<Matrix>,
+    /// The area the interpolated nodes move over until they settle, in the scene space.
+    animated_damage: Option<Rect>,
+    /// Whether an interpolated node rotates, sweeping outside its committed bounds.
+    animated_rotation: bool,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            antialiasing: Antialiasing::Msaa,
+            nodes: vec![Some(Node::new())],
+            root: NodeId(0),
+            interpolator: None,
+            invalidator: None,
+            drawn: HashMap::new(),
+            view: None,
+            animated_damage: None,
+            animated_rotation: false,
+        }
+    }
+
+    #[inline]
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Add the node as the last child of `parent`.
+    pub fn add(&mut self, parent: NodeId, mut node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        node.parent = Some(parent);
+        node.children.clear();
+        self.nodes.push(Some(node));
+        self.node_mut(parent)
+            .expect("Parent node does not exist")
+            .children
+            .push(id);
+        id
+    }
+
+    /// Remove the node and its descendants, the root node can not be removed.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        if id == self.root {
+            return None;
+        }
+        let node = self.nodes.get_mut(id.0)?.take()?;
+        if let Some(parent) = node.parent.and_then(|parent| self.node_mut(parent)) {
+            parent.children.retain(|child| *child != id);
+        }
+        for child in node.children.iter() {
+            self.remove(*child);
+        }
+        Some(node)
+    }
+
+    #[inline]
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0)?.as_ref()
+    }
+
+    #[inline]
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0)?.as_mut()
+    }
+
+    /// Iterate the ids of the existing nodes.
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_some())
+            .map(|(index, _)| NodeId(index))
+    }
+
+    /// The transform from the node space to the scene space.
+    pub fn world_transform(&self, id: NodeId) -> Matrix {
+        let mut transform = Matrix::new_identity();
+        let mut current = self.node(id);
+        while let Some(node) = current {
+            transform = Matrix::concat(&node.transform, &transform);
+            current = node.parent.and_then(|parent| self.node(parent));
+        }
+        transform
+    }
+
+    /// The bounds of the node and its descendants in the scene space.
+    pub fn world_bounds(&self, id: NodeId) -> Rect {
+        let mut bounds = Rect::default();
+        self.visit(id, &self.parent_transform(id), &mut |node, transform| {
+            bounds.join(transform.map_rect(node.bounds()).0);
+        });
+        bounds
+    }
+
+    /// The bounds of the whole scene.
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        self.world_bounds(self.root)
+    }
+
+    /// Smooth the transforms between the updates committed with `commit`, `None` presents
+    /// the transforms as they are.
+    pub fn set_interpolation(&mut self, mode: Option<InterpolationMode>) {
+        self.interpolator = mode.map(|mode| {
+            let mut interpolator = TransformInterpolator::new(mode);
+            interpolator.commit(self, Instant::now());
+            interpolator
+        });
+    }
+
+    #[inline]
+    pub fn interpolator_mut(&mut self) -> Option<&mut TransformInterpolator> {
+        self.interpolator.as_mut()
+    }
+
+    /// Mark the end of a logical update, the transforms are interpolated from there. Only
+    /// the area of the nodes changed since the previous commit is redrawn, see `diff`.
+    pub fn commit(&mut self, now: Instant) {
+        if let Some(mut interpolator) = self.interpolator.take() {
+            interpolator.commit(self, now);
+            self.interpolator = Some(interpolator);
+        }
+        let (damage, rotated) = self.diff_nodes();
+        if self.interpolator.is_none() {
+            self.invalidate_damage(damage);
+            return;
+        }
+        // The nodes may still be on their way from the previous commit.
+        self.animated_damage = join(self.animated_damage, damage);
+        self.animated_rotation |= rotated;
+        if self.animated_rotation {
+            self.invalidate_all();
+        } else {
+            self.invalidate_damage(self.animated_damage);
+        }
+    }
+
+    /// The area in the scene space that changed since the previous diff, joining the bounds
+    /// the changed, added and removed nodes had before and have now. A node changed when
+    /// its content was replaced, its world transform or its visibility changed. `None` when
+    /// nothing changed, e.g. for a commit of a frame without updates. Reordering the
+    /// siblings of overlapping nodes isn't detected, invalidate those by hand.
+    #[inline]
+    pub fn diff(&mut self) -> Option<Rect> {
+        self.diff_nodes().0
+    }
+
+    /// The damage of `diff` and whether the world rotation of a node changed.
+    fn diff_nodes(&mut self) -> (Option<Rect>, bool) {
+        let mut drawn = HashMap::with_capacity(self.drawn.len());
+        let local = |_, node: &Node| node.transform;
+        self.visit_with(
+            self.root,
+            &Matrix::new_identity(),
+            &local,
+            &mut |id, node, transform| {
+                if let Some(content) = node.content() {
+                    drawn.insert(
+                        id,
+                        DrawnNode {
+                            content: content.unique_id(),
+                            transform: *transform,
+                            bounds: transform.map_rect(content.cull_rect()).0,
+                        },
+                    );
+                }
+            },
+        );
+
+        let mut damage: Option<Rect> = None;
+        let mut rotated = false;
+        for (id, node) in &drawn {
+            match self.drawn.get(id) {
+                Some(previous) if previous == node => {}
+                Some(previous) => {
+                    damage = join(damage, Some(previous.bounds));
+                    damage = join(damage, Some(node.bounds));
+                    rotated |= rotation(&previous.transform) != rotation(&node.transform);
+                }
+                None => damage = join(damage, Some(node.bounds)),
+            }
+        }
+        for (id, previous) in &self.drawn {
+            if !drawn.contains_key(id) {
+                damage = join(damage, Some(previous.bounds));
+            }
+        }
+        self.drawn = drawn;
+        (damage.filter(|damage| !damage.is_empty()), rotated)
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        self.draw_node(canvas, self.root);
+    }
+
+    /// Draw the node and its descendants, the node is drawn with its world transform.
+    pub fn draw_node(&self, canvas: &mut Canvas, id: NodeId) {
+        self.draw_visited(canvas, id, &|_, node| node.transform);
+    }
+
+    /// Draw the scene with the transforms presented at `now`, interpolated if enabled.
+    pub fn draw_at(&self, canvas: &mut Canvas, now: Instant) {
+        match &self.interpolator {
+            Some(interpolator) => self.draw_visited(canvas, self.root, &|id, node| {
+                interpolator.transform(id, &node.transform, now)
+            }),
+            None => self.draw(canvas),
+        }
+    }
+
+    /// Render the node and its descendants as drawn in the scene into an image of their
+    /// bounds at `scale` times the scene units, transparent around the content. The
+    /// rendering is offscreen on the CPU, independent of the window size, e.g. to export a
+    /// chart at print resolution. `None` for an empty node or past `MAX_EXPORT_SIZE`.
+    pub fn render_node_to_image(&self, id: NodeId, scale: f32) -> Option<Image> {
+        let bounds = self.world_bounds(id);
+        if bounds.is_empty() || scale <= 0. {
+            return None;
+        }
+        let size = (
+            (bounds.width() * scale).ceil() as i32,
+            (bounds.height() * scale).ceil() as i32,
+        );
+        if size.0 > MAX_EXPORT_SIZE || size.1 > MAX_EXPORT_SIZE {
+            eprintln!(
+                "Node export of {}x{} exceeds the maximum size of {MAX_EXPORT_SIZE}",
+                size.0, size.1
+            );
+            return None;
+        }
+        let mut surface = Surface::new_raster_n32_premul(size)?;
+        let canvas = surface.canvas();
+        canvas.clear(Color::TRANSPARENT);
+        canvas.scale((scale, scale));
+        canvas.translate((-bounds.left, -bounds.top));
+        self.draw_node(canvas, id);
+        Some(surface.image_snapshot())
+    }
+
+    fn draw_visited(
+        &self,
+        canvas: &mut Canvas,
+        id: NodeId,
+        local: &impl Fn(NodeId, &Node) -> Matrix,
+    ) {
+        let parent_transform = self.parent_transform(id);
+        self.visit_with(id, &parent_transform, local, &mut |_, node, transform| {
+            if let Some(content) = node.content() {
+                canvas.save();
+                canvas.concat(transform);
+                canvas.draw_picture(content, None, None);
+                canvas.restore();
+            }
+        });
+    }
+
+    /// Invalidate the area in the scene space in window pixels, the whole frame before the
+    /// first one tells where the scene is drawn.
+    fn invalidate_damage(&self, damage: Option<Rect>) {
+        let (Some(invalidator), Some(damage)) = (&self.invalidator, damage) else {
+            return;
+        };
+        match &self.view {
+            // Outset for the antialiased edges.
+            Some(view) => invalidator.invalidate_rect(Rect::from_irect(
+                view.map_rect(damage).0.with_outset((1., 1.)).round_out(),
+            )),
+            None => invalidator.invalidate(),
+        }
+    }
+
+    fn invalidate_all(&self) {
+        if let Some(invalidator) = &self.invalidator {
+            invalidator.invalidate();
+        }
+    }
+
+    fn parent_transform(&self, id: NodeId) -> Matrix {
+        self.node(id)
+            .and_then(|node| node.parent)
+            .map(|parent| self.world_transform(parent))
+            .unwrap_or_else(Matrix::new_identity)
+    }
+
+    /// Visit the visible nodes depth first with their world transforms.
+    fn visit(&self, id: NodeId, parent_transform: &Matrix, f: &mut impl FnMut(&Node, &Matrix)) {
+        self.visit_with(
+            id,
+            parent_transform,
+            &|_, node| node.transform,
+            &mut |_, node, transform| f(node, transform),
+        );
+    }
+
+    /// Visit the visible nodes depth first with the world transforms composed of the
+    /// `local` transforms.
+    fn visit_with(
+        &self,
+        id: NodeId,
+        parent_transform: &Matrix,
+        local: &impl Fn(NodeId, &Node) -> Matrix,
+        f: &mut impl FnMut(NodeId, &Node, &Matrix),
+    ) {
+        let Some(node) = self.node(id) else {
+            return;
+        };
+        if !node.visible {
+            return;
+        }
+        let transform = Matrix::concat(parent_transform, &local(id, node));
+        f(id, node, &transform);
+        for child in node.children.iter() {
+            self.visit_with(*child, &transform, local, f);
+        }
+    }
+}
+
+#[inline]
+fn join(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some(mut a), Some(b)) => {
+            a.join(b);
+            Some(a)
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+/// The angle of the transformed x axis in radians.
+#[inline]
+fn rotation(transform: &Matrix) -> f32 {
+    transform.skew_y().atan2(transform.scale_x())
+}
+
+/// The transform from the scene space to window pixels, the canvas transform without the
+/// pixel scale applied by the backend before the camera.
+fn window_transform(canvas: &Canvas, pixel_scale: f32) -> Matrix {
+    let mut transform = canvas.local_to_device_as_3x3();
+    if pixel_scale > 0. {
+        transform.post_scale((1. / pixel_scale, 1. / pixel_scale), None);
+    }
+    transform
+}
+
+impl Renderer for Scene {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        let now = Instant::now();
+        self.view = Some(window_transform(canvas, info.pixel_scale));
+        self.draw_at(canvas, now);
+        let Some(interpolator) = &self.interpolator else {
+            return;
+        };
+        if interpolator.is_animating(now) {
+            match (interpolator.mode(), self.animated_damage) {
+                // The interpolated transforms stay between the committed ones, unless they
+                // rotate.
+                (InterpolationMode::Interpolate, damage @ Some(_)) if !self.animated_rotation => {
+                    self.invalidate_damage(damage)
+                }
+                _ => self.invalidate_all(),
+            }
+        } else {
+            self.animated_damage = None;
+            self.animated_rotation = false;
+        }
+    }
+
+    fn attach_invalidator(&mut self, invalidator: Invalidator) {
+        self.invalidator = Some(invalidator);
+    }
+
+    fn antialiasing(&self) -> Antialiasing {
+        self.antialiasing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use skia_safe::Paint;
+
+    use super::*;
+    use crate::invalidation::Damage;
+
+    fn add_square(scene: &mut Scene, transform: Matrix) -> NodeId {
+        let bounds = Rect::from_wh(10., 10.);
+        let node = Node::record(bounds, |canvas| {
+            canvas.draw_rect(bounds, &Paint::default());
+        });
+        scene.add(scene.root(), node.with_transform(transform))
+    }
+
+    /// A scene drawn at the window origin, interpolating the committed transforms.
+    fn animated_scene() -> (Scene, Invalidator) {
+        let mut scene = Scene::new();
+        let invalidator = Invalidator::default();
+        scene.attach_invalidator(invalidator.clone());
+        scene.view = Some(Matrix::new_identity());
+        scene.set_interpolation(Some(InterpolationMode::Interpolate));
+        (scene, invalidator)
+    }
+
+    #[test]
+    fn diff_joins_the_old_and_new_bounds() {
+        let mut scene = Scene::new();
+        let id = add_square(&mut scene, Matrix::new_identity());
+        assert_eq!(scene.diff(), Some(Rect::from_wh(10., 10.)));
+        assert_eq!(scene.diff(), None);
+
+        scene.node_mut(id).unwrap().transform = Matrix::translate((20., 0.));
+        assert_eq!(scene.diff(), Some(Rect::new(0., 0., 30., 10.)));
+
+        scene.node_mut(id).unwrap().visible = false;
+        assert_eq!(scene.diff(), Some(Rect::new(20., 0., 30., 10.)));
+        scene.node_mut(id).unwrap().visible = true;
+        scene.diff();
+
+        scene.remove(id);
+        assert_eq!(scene.diff(), Some(Rect::new(20., 0., 30., 10.)));
+    }
+
+    #[test]
+    fn diff_follows_the_parent_transform() {
+        let mut scene = Scene::new();
+        let parent = scene.add(scene.root(), Node::new());
+        let child = Node::record(Rect::from_wh(10., 10.), |_| {});
+        scene.add(parent, child);
+        scene.diff();
+
+        scene.node_mut(parent).unwrap().transform = Matrix::translate((0., 20.));
+        assert_eq!(scene.diff(), Some(Rect::new(0., 0., 10., 30.)));
+    }
+
+    #[test]
+    fn commit_keeps_the_damage_of_the_running_animation() {
+        let (mut scene, invalidator) = animated_scene();
+        let now = Instant::now();
+        let id = add_square(&mut scene, Matrix::new_identity());
+        scene.commit(now);
+        scene.node_mut(id).unwrap().transform = Matrix::translate((20., 0.));
+        scene.commit(now);
+        invalidator.take(now);
+
+        // The node is still presented on its way from the origin.
+        scene.node_mut(id).unwrap().transform = Matrix::translate((40., 0.));
+        scene.commit(now);
+        assert_eq!(
+            invalidator.take(now),
+            Some(Damage::Rect(Rect::new(-1., -1., 51., 11.)))
+        );
+    }
+
+    #[test]
+    fn commit_invalidates_everything_for_a_rotation() {
+        let (mut scene, invalidator) = animated_scene();
+        let now = Instant::now();
+        let id = add_square(&mut scene, Matrix::new_identity());
+        scene.commit(now);
+        invalidator.take(now);
+
+        scene.node_mut(id).unwrap().transform = Matrix::rotate_deg(90.);
+        scene.commit(now);
+        assert_eq!(invalidator.take(now), Some(Damage::Full));
+    }
+}
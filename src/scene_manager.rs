@@ -0,0 +1,293 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use skia_safe::Canvas;
+
+use crate::{
+    focus::{FocusId, FocusNode},
+    input::InputEvent,
+    invalidation::Invalidator,
+    locale::Locale,
+    proxy::BackendProxy,
+    quality::Quality,
+    recovery,
+    renderer::{Antialiasing, FrameInfo, Renderer},
+    theme::SystemTheme,
+    transition::{Transition, TransitionState},
+};
+
+pub enum SceneCommand {
+    Push(Box<dyn Renderer>),
+    Pop,
+    /// Replace the active scene.
+    Replace(Box<dyn Renderer>),
+}
+
+/// Queues scene changes from inside the scenes, e.g. a menu handling a click, they are
+/// applied before the next frame is rendered.
+#[derive(Clone, Default)]
+pub struct SceneHandle {
+    commands: Arc<Mutex<VecDeque<SceneCommand>>>,
+}
+
+impl SceneHandle {
+    #[inline]
+    pub fn send(&self, command: SceneCommand) {
+        self.commands.lock().unwrap().push_back(command);
+    }
+
+    #[inline]
+    pub fn push(&self, scene: impl Renderer + 'static) {
+        self.send(SceneCommand::Push(Box::new(scene)));
+    }
+
+    #[inline]
+    pub fn pop(&self) {
+        self.send(SceneCommand::Pop);
+    }
+
+    #[inline]
+    pub fn replace(&self, scene: impl Renderer + 'static) {
+        self.send(SceneCommand::Replace(Box::new(scene)));
+    }
+}
+
+/// The scene rendered out by the running transition.
+enum Outgoing {
+    /// The scene below the active one, e.g. after a push.
+    Stacked,
+    /// A popped or replaced scene, dropped when the transition finishes.
+    Removed(Box<dyn Renderer>),
+}
+
+/// A stack of scenes, e.g. menu, game and settings, only the top one is rendered and
+/// receives the input.
+#[derive(Default)]
+pub struct SceneManager {
+    stack: Vec<Box<dyn Renderer>>,
+    handle: SceneHandle,
+    transition: Option<Transition>,
+    running: Option<(TransitionState, Outgoing)>,
+    /// Attached to the scenes pushed later with the proxy and the quality.
+    invalidator: Option<Invalidator>,
+    proxy: Option<BackendProxy>,
+    quality: Quality,
+}
+
+impl SceneManager {
+    pub fn new(initial: impl Renderer + 'static) -> Self {
+        Self {
+            stack: vec![Box::new(initial)],
+            handle: SceneHandle::default(),
+            transition: None,
+            running: None,
+            invalidator: None,
+            proxy: None,
+            quality: Quality::default(),
+        }
+    }
+
+    /// Animate the scene changes with the transition.
+    #[inline]
+    pub fn with_transition(mut self, transition: Transition) -> Self {
+        self.transition = Some(transition);
+        self
+    }
+
+    #[inline]
+    pub fn set_transition(&mut self, transition: Option<Transition>) {
+        self.transition = transition;
+    }
+
+    #[inline]
+    pub fn is_transitioning(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// The handle to pass to the scenes changing the scene themselves.
+    #[inline]
+    pub fn handle(&self) -> SceneHandle {
+        self.handle.clone()
+    }
+
+    #[inline]
+    pub fn active(&mut self) -> Option<&mut dyn Renderer> {
+        Some(self.stack.last_mut()?.as_mut())
+    }
+
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn push(&mut self, mut scene: Box<dyn Renderer>) {
+        self.attach(scene.as_mut());
+        let outgoing = (!self.stack.is_empty()).then_some(Outgoing::Stacked);
+        self.stack.push(scene);
+        self.start_transition(outgoing, false);
+    }
+
+    /// Pop the active scene, it is dropped after the transition.
+    pub fn pop(&mut self) {
+        let outgoing = self.stack.pop().map(Outgoing::Removed);
+        let outgoing = outgoing.filter(|_| !self.stack.is_empty());
+        self.start_transition(outgoing, true);
+    }
+
+    /// Replace the active scene, it is dropped after the transition.
+    pub fn replace(&mut self, mut scene: Box<dyn Renderer>) {
+        self.attach(scene.as_mut());
+        let outgoing = self.stack.pop().map(Outgoing::Removed);
+        self.stack.push(scene);
+        self.start_transition(outgoing, false);
+    }
+
+    fn attach(&self, scene: &mut dyn Renderer) {
+        if let Some(invalidator) = &self.invalidator {
+            scene.attach_invalidator(invalidator.clone());
+        }
+        if let Some(proxy) = &self.proxy {
+            scene.attach_proxy(proxy.clone());
+        }
+        if !self.quality.is_full() {
+            scene.quality_changed(self.quality);
+        }
+    }
+
+    fn start_transition(&mut self, outgoing: Option<Outgoing>, reverse: bool) {
+        self.running = self
+            .transition
+            .zip(outgoing)
+            .map(|(transition, outgoing)| (TransitionState::start(transition, reverse), outgoing));
+    }
+
+    /// Apply the commands queued by the handles.
+    pub fn apply_commands(&mut self) {
+        let commands = std::mem::take(&mut *self.handle.commands.lock().unwrap());
+        for command in commands {
+            match command {
+                SceneCommand::Push(scene) => self.push(scene),
+                SceneCommand::Pop => self.pop(),
+                SceneCommand::Replace(scene) => self.replace(scene),
+            }
+        }
+    }
+}
+
+impl Renderer for SceneManager {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        self.apply_commands();
+        if matches!(&self.running, Some((state, _)) if state.is_finished()) {
+            self.running = None;
+        }
+
+        // The transitions animate on their own, they need every frame.
+        if let Some(invalidator) = self.invalidator.as_ref().filter(|_| self.running.is_some()) {
+            invalidator.invalidate();
+        }
+
+        let Some((incoming, below)) = self.stack.split_last_mut() else {
+            return;
+        };
+        match &mut self.running {
+            Some((state, Outgoing::Stacked)) => {
+                let outgoing = below.last_mut().expect("Pushed over an empty stack");
+                state.render(canvas, info, outgoing.as_mut(), incoming.as_mut());
+            }
+            Some((state, Outgoing::Removed(outgoing))) => {
+                state.render(canvas, info, outgoing.as_mut(), incoming.as_mut());
+            }
+            None => incoming.render(canvas, info),
+        }
+    }
+
+    fn render_overlay(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        if let Some(scene) = self.active() {
+            scene.render_overlay(canvas, info);
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        if let Some(scene) = self.active() {
+            scene.handle_input(event);
+        }
+    }
+
+    fn focus_nodes(&self) -> Vec<FocusNode> {
+        self.stack
+            .last()
+            .map_or_else(Vec::new, |scene| scene.focus_nodes())
+    }
+
+    fn handle_focused_key(&mut self, id: FocusId, event: &InputEvent) -> bool {
+        self.active()
+            .map_or(false, |scene| scene.handle_focused_key(id, event))
+    }
+
+    /// Forwarded to every scene of the stack, not only the active one.
+    fn system_theme_changed(&mut self, theme: SystemTheme) {
+        for scene in self.stack.iter_mut() {
+            scene.system_theme_changed(theme);
+        }
+    }
+
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        for scene in self.stack.iter_mut() {
+            scene.scale_factor_changed(scale_factor);
+        }
+    }
+
+    fn locale_changed(&mut self, locale: &Locale) {
+        for scene in self.stack.iter_mut() {
+            scene.locale_changed(locale);
+        }
+    }
+
+    /// The states of every scene of the stack.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        recovery::join_states(self.stack.iter().map(|scene| scene.save_state()))
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        for (scene, state) in self.stack.iter_mut().zip(recovery::split_states(state)) {
+            if let Some(state) = state {
+                scene.restore_state(state);
+            }
+        }
+    }
+
+    fn context_lost(&mut self) {
+        for scene in self.stack.iter_mut() {
+            scene.context_lost();
+        }
+    }
+
+    fn attach_invalidator(&mut self, invalidator: Invalidator) {
+        for scene in self.stack.iter_mut() {
+            scene.attach_invalidator(invalidator.clone());
+        }
+        self.invalidator = Some(invalidator);
+    }
+
+    fn attach_proxy(&mut self, proxy: BackendProxy) {
+        for scene in self.stack.iter_mut() {
+            scene.attach_proxy(proxy.clone());
+        }
+        self.proxy = Some(proxy);
+    }
+
+    fn quality_changed(&mut self, quality: Quality) {
+        for scene in self.stack.iter_mut() {
+            scene.quality_changed(quality);
+        }
+        self.quality = quality;
+    }
+
+    fn antialiasing(&self) -> Antialiasing {
+        self.stack
+            .last()
+            .map_or(Antialiasing::Msaa, |scene| scene.antialiasing())
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use skia_safe::{Canvas, Color, Font, Image, Paint, Rect};
+
+use crate::capture::{self, CaptureOptions};
+
+/// How long the confirmation stays on screen.
+const CONFIRMATION_DURATION: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenshotConfig {
+    /// The directory the screenshots are written to, created if missing.
+    pub dir: PathBuf,
+    pub options: CaptureOptions,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("screenshots"),
+            options: CaptureOptions::default(),
+        }
+    }
+}
+
+struct Job {
+    image: Image,
+    path: PathBuf,
+    options: CaptureOptions,
+}
+
+/// Encodes and writes the screenshots on a worker thread, the frame is only read back on
+/// the rendering thread.
+pub struct Screenshotter {
+    config: ScreenshotConfig,
+    jobs: Option<Sender<Job>>,
+    results: Receiver<io::Result<PathBuf>>,
+    thread: Option<JoinHandle<()>>,
+    confirmation: Option<(String, Instant)>,
+}
+
+impl Default for Screenshotter {
+    fn default() -> Self {
+        Self::new(ScreenshotConfig::default())
+    }
+}
+
+impl Screenshotter {
+    pub fn new(config: ScreenshotConfig) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("screenshot".to_string())
+            .spawn(move || {
+                for job in job_receiver {
+                    let result = capture::save(&job.image, &job.path, &job.options);
+                    if result_sender.send(result.map(|()| job.path)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Failed to spawn screenshot thread");
+
+        Self {
+            config,
+            jobs: Some(jobs),
+            results,
+            thread: Some(thread),
+            confirmation: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &ScreenshotConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn set_config(&mut self, config: ScreenshotConfig) {
+        self.config = config;
+    }
+
+    /// Read back the image and queue it for saving with a timestamped name.
+    pub fn take(&mut self, image: &Image) {
+        if let Err(err) = std::fs::create_dir_all(&self.config.dir) {
+            eprintln!(
+                "Error creating screenshot directory {}: {err}",
+                self.config.dir.display()
+            );
+            return;
+        }
+        let Some(image) = image.to_raster_image(None) else {
+            eprintln!("Error reading back the screenshot");
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = self.config.dir.join(format!(
+            "screenshot_{}_{:03}.{}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            self.config.options.format.extension()
+        ));
+        let job = Job {
+            image,
+            path,
+            options: self.config.options,
+        };
+        if let Some(jobs) = &self.jobs {
+            jobs.send(job).expect("Send screenshot job failed.");
+        }
+    }
+
+    /// Collect the finished screenshots, call it once per frame.
+    pub fn poll(&mut self) {
+        for result in self.results.try_iter() {
+            let message = match result {
+                Ok(path) => {
+                    println!("Saved screenshot to {}", path.display());
+                    format!("Saved {}", path.display())
+                }
+                Err(err) => {
+                    eprintln!("Error saving screenshot: {err}");
+                    format!("Screenshot failed: {err}")
+                }
+            };
+            self.confirmation = Some((message, Instant::now()));
+        }
+        if let Some((_, shown)) = &self.confirmation {
+            if shown.elapsed() > CONFIRMATION_DURATION {
+                self.confirmation = None;
+            }
+        }
+    }
+
+    /// Draw the confirmation of the last screenshot in the bottom left corner.
+    pub fn draw_confirmation(&self, canvas: &mut Canvas, size: (i32, i32)) {
+        const PADDING: f32 = 8.;
+
+        let Some((message, _)) = &self.confirmation else {
+            return;
+        };
+        let font = Font::default()
+            .with_size(13.)
+            .expect("Could not create font");
+        let (width, bounds) = font.measure_str(message, None);
+        let height = bounds.height() + PADDING * 2.;
+        let rect = Rect::from_xywh(
+            PADDING,
+            size.1 as f32 - height - PADDING,
+            width + PADDING * 2.,
+            height,
+        );
+
+        let mut background = Paint::default();
+        background.set_color(Color::from_argb(0xb0, 0, 0, 0));
+        canvas.draw_rect(rect, &background);
+
+        let mut text = Paint::default();
+        text.set_anti_alias(true);
+        text.set_color(Color::WHITE);
+        canvas.draw_str(
+            message,
+            (rect.left + PADDING, rect.bottom - PADDING - bounds.bottom),
+            &font,
+            &text,
+        );
+    }
+}
+
+impl Drop for Screenshotter {
+    fn drop(&mut self) {
+        // Let the worker finish the queued screenshots.
+        self.jobs = None;
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use skia_safe::{Canvas, Picture};
+
+use crate::renderer::{FrameInfo, Renderer};
+
+/// Plays back a serialized `SkPicture`, e.g. a frame dumped by `Backend::dump_skp`.
+pub struct SkpRenderer {
+    picture: Picture,
+}
+
+impl SkpRenderer {
+    #[inline]
+    pub fn new(picture: Picture) -> Self {
+        Self { picture }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let picture = Picture::from_bytes(&bytes)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Could not deserialize skp"))?;
+        Ok(Self::new(picture))
+    }
+
+    #[inline]
+    pub fn picture(&self) -> &Picture {
+        &self.picture
+    }
+}
+
+impl Renderer for SkpRenderer {
+    fn render(&mut self, canvas: &mut Canvas, _: &FrameInfo) {
+        canvas.draw_picture(&self.picture, None, None);
+    }
+}
@@ -0,0 +1,144 @@
+use skia_safe::{Canvas, Matrix, Point, Rect};
+
+/// Snaps local coordinates to the physical pixel grid of a canvas transform, so that
+/// edges and thin strokes cover whole device pixels instead of blurring over two at
+/// fractional scale factors. A stroke an odd number of device pixels wide is centered on a
+/// pixel center, an even one on a pixel edge. Transforms rotating, skewing or with
+/// perspective have no axis aligned grid, the coordinates are returned unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelGrid {
+    /// The device pixels per local unit along each axis.
+    scale: (f32, f32),
+    /// The device position of the local origin.
+    offset: (f32, f32),
+    axis_aligned: bool,
+}
+
+impl PixelGrid {
+    pub fn new(matrix: &Matrix) -> Self {
+        let axis_aligned = matrix.is_scale_translate()
+            && matrix.scale_x().abs() > f32::EPSILON
+            && matrix.scale_y().abs() > f32::EPSILON;
+        Self {
+            scale: (matrix.scale_x(), matrix.scale_y()),
+            offset: (matrix.translate_x(), matrix.translate_y()),
+            axis_aligned,
+        }
+    }
+
+    /// The grid of the current canvas transform, e.g. the scale factor and the camera.
+    #[inline]
+    pub fn of(canvas: &Canvas) -> Self {
+        Self::new(&canvas.local_to_device_as_3x3())
+    }
+
+    #[inline]
+    pub fn is_axis_aligned(&self) -> bool {
+        self.axis_aligned
+    }
+
+    /// The width in local units of one device pixel, the width of a crisp hairline.
+    #[inline]
+    pub fn pixel_width(&self) -> f32 {
+        if self.axis_aligned {
+            1. / self.scale.0.abs()
+        } else {
+            1.
+        }
+    }
+
+    /// The point on the nearest pixel corner, for the edges of fills.
+    #[inline]
+    pub fn point(&self, point: impl Into<Point>) -> Point {
+        let point = point.into();
+        Point::new(self.x(point.x, 0.), self.y(point.y, 0.))
+    }
+
+    /// The rect with its edges on the nearest pixel corners, at least a pixel large.
+    pub fn rect(&self, rect: Rect) -> Rect {
+        if !self.axis_aligned {
+            return rect;
+        }
+        let (left, right) = self.span(rect.left, rect.right, self.scale.0, self.offset.0);
+        let (top, bottom) = self.span(rect.top, rect.bottom, self.scale.1, self.offset.1);
+        Rect::new(left, top, right, bottom)
+    }
+
+    /// The stroke width rounded to whole device pixels, at least one.
+    pub fn stroke_width(&self, width: f32) -> f32 {
+        if !self.axis_aligned {
+            return width;
+        }
+        self.device_width(width) / self.scale.0.abs()
+    }
+
+    /// The center of a stroke `width` wide, on a pixel center when the stroke is an odd
+    /// number of device pixels wide, use with `stroke_width`.
+    #[inline]
+    pub fn stroke_point(&self, point: impl Into<Point>, width: f32) -> Point {
+        let point = point.into();
+        let half = self.half_offset(width);
+        Point::new(self.x(point.x, half), self.y(point.y, half))
+    }
+
+    /// The line `width` wide with its ends snapped for the stroke, horizontal and vertical
+    /// lines come out crisp.
+    #[inline]
+    pub fn line(&self, from: impl Into<Point>, to: impl Into<Point>, width: f32) -> (Point, Point) {
+        (self.stroke_point(from, width), self.stroke_point(to, width))
+    }
+
+    /// The rect to stroke `width` wide, its edges centered so the stroke covers whole
+    /// pixels.
+    pub fn stroke_rect(&self, rect: Rect, width: f32) -> Rect {
+        let top_left = self.stroke_point((rect.left, rect.top), width);
+        let bottom_right = self.stroke_point((rect.right, rect.bottom), width);
+        Rect::new(top_left.x, top_left.y, bottom_right.x, bottom_right.y)
+    }
+
+    /// Snap a horizontal coordinate, `half` is `0.5` to land on pixel centers.
+    #[inline]
+    pub fn x(&self, x: f32, half: f32) -> f32 {
+        if !self.axis_aligned {
+            return x;
+        }
+        snap(x, self.scale.0, self.offset.0, half)
+    }
+
+    #[inline]
+    pub fn y(&self, y: f32, half: f32) -> f32 {
+        if !self.axis_aligned {
+            return y;
+        }
+        snap(y, self.scale.1, self.offset.1, half)
+    }
+
+    fn device_width(&self, width: f32) -> f32 {
+        (width * self.scale.0.abs()).round().max(1.)
+    }
+
+    fn half_offset(&self, width: f32) -> f32 {
+        if self.axis_aligned && self.device_width(width) % 2. == 1. {
+            0.5
+        } else {
+            0.
+        }
+    }
+
+    fn span(&self, start: f32, end: f32, scale: f32, offset: f32) -> (f32, f32) {
+        let start = snap(start, scale, offset, 0.);
+        let end = snap(end, scale, offset, 0.);
+        if end == start {
+            return (start, start + 1. / scale);
+        }
+        (start, end)
+    }
+}
+
+/// The coordinate mapped to the device, rounded to the grid shifted by `half` and mapped
+/// back.
+#[inline]
+fn snap(value: f32, scale: f32, offset: f32, half: f32) -> f32 {
+    let device = value * scale + offset;
+    ((device - half).round() + half - offset) / scale
+}
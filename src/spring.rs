@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+/// A value a spring can animate, e.g. an opacity, a scale or a `(x, y)` position.
+pub trait SpringValue: Copy {
+    fn add(self, other: Self) -> Self;
+    fn sub(self, other: Self) -> Self;
+    fn mul(self, factor: f32) -> Self;
+    /// The largest component magnitude, compared against the precision.
+    fn magnitude(self) -> f32;
+}
+
+impl SpringValue for f32 {
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        self - other
+    }
+
+    #[inline]
+    fn mul(self, factor: f32) -> Self {
+        self * factor
+    }
+
+    #[inline]
+    fn magnitude(self) -> f32 {
+        self.abs()
+    }
+}
+
+impl SpringValue for (f32, f32) {
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        (self.0 + other.0, self.1 + other.1)
+    }
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        (self.0 - other.0, self.1 - other.1)
+    }
+
+    #[inline]
+    fn mul(self, factor: f32) -> Self {
+        (self.0 * factor, self.1 * factor)
+    }
+
+    #[inline]
+    fn magnitude(self) -> f32 {
+        self.0.abs().max(self.1.abs())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpringConfig {
+    /// The time a motion roughly takes, the period of the undamped spring.
+    pub response: Duration,
+    /// The spring settles on the target once the distance and the velocity per second are
+    /// below it, e.g. `0.001` for opacities and `0.1` for pixel positions.
+    pub precision: f32,
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        Self {
+            response: Duration::from_millis(300),
+            precision: 0.001,
+        }
+    }
+}
+
+impl SpringConfig {
+    #[inline]
+    pub fn new(response: Duration, precision: f32) -> Self {
+        Self {
+            response,
+            precision,
+        }
+    }
+
+    /// The angular frequency of the spring.
+    #[inline]
+    fn omega(&self) -> f32 {
+        std::f32::consts::TAU / self.response.as_secs_f32().max(f32::EPSILON)
+    }
+}
+
+/// A critically damped spring moving a value to its target as fast as possible without
+/// overshooting. Retargeting keeps the current velocity, so a motion interrupted midway or
+/// released from a gesture with `set_velocity` continues smoothly instead of restarting
+/// like a tween. It is advanced with the frame time, the closed-form solution keeps it
+/// stable on long frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spring<T: SpringValue> {
+    config: SpringConfig,
+    value: T,
+    velocity: T,
+    target: T,
+}
+
+impl<T: SpringValue> Spring<T> {
+    /// A spring at rest on `value`.
+    #[inline]
+    pub fn new(value: T, config: SpringConfig) -> Self {
+        Self {
+            config,
+            value,
+            velocity: value.sub(value),
+            target: value,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &SpringConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn set_config(&mut self, config: SpringConfig) {
+        self.config = config;
+    }
+
+    #[inline]
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    /// The velocity per second.
+    #[inline]
+    pub fn velocity(&self) -> T {
+        self.velocity
+    }
+
+    #[inline]
+    pub fn target(&self) -> T {
+        self.target
+    }
+
+    /// Move towards `target` from the current value and velocity.
+    #[inline]
+    pub fn set_target(&mut self, target: T) {
+        self.target = target;
+    }
+
+    /// Override the velocity per second, e.g. with the release velocity of a drag.
+    #[inline]
+    pub fn set_velocity(&mut self, velocity: T) {
+        self.velocity = velocity;
+    }
+
+    /// Jump to `value` and rest there, e.g. while a gesture drives the value directly.
+    #[inline]
+    pub fn snap_to(&mut self, value: T) {
+        *self = Self::new(value, self.config);
+    }
+
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        let precision = self.config.precision;
+        self.value.sub(self.target).magnitude() < precision && self.velocity.magnitude() < precision
+    }
+
+    /// Advance by `dt` seconds, returns the new value. The value snaps to the target once
+    /// settled, so callers can stop requesting redraws.
+    pub fn update(&mut self, dt: f32) -> T {
+        if self.is_settled() {
+            self.snap_to(self.target);
+            return self.value;
+        }
+        let omega = self.config.omega();
+        // x(t) = target + (c1 + c2 t) e^(-ωt)
+        let c1 = self.value.sub(self.target);
+        let c2 = self.velocity.add(c1.mul(omega));
+        let decay = (-omega * dt).exp();
+        let offset = c1.add(c2.mul(dt));
+        self.value = self.target.add(offset.mul(decay));
+        self.velocity = c2.sub(offset.mul(omega)).mul(decay);
+        if self.is_settled() {
+            self.snap_to(self.target);
+        }
+        self.value
+    }
+}
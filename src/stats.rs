@@ -0,0 +1,231 @@
+use std::{collections::VecDeque, fmt, path::PathBuf, time::Duration};
+
+use skia_safe::{Canvas, Color, Font, Paint, Rect};
+
+use crate::{
+    frame_hash::Verification,
+    profiler::DrawStats,
+    quality::{Quality, QualityFeature},
+};
+
+/// The dropped frames kept until taken with `Backend::take_dropped_frames`.
+const MAX_DROPPED_FRAMES: usize = 64;
+
+/// A frame that missed its pacing deadline, or whose swap took longer than a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DroppedFrame {
+    pub frame: usize,
+    /// How late the frame started past its deadline.
+    pub lateness: Duration,
+    pub swap_time: Duration,
+    /// The frame duration of the pacer.
+    pub budget: Duration,
+    /// The number of dropped frames in a row, this one included.
+    pub consecutive: u32,
+}
+
+/// How long the phases of a frame took.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameTimings {
+    /// Handling the messages before the frame, only measured with `independent_ui`.
+    pub events: Duration,
+    /// Drawing the frame and processing the frame requests, e.g. the screenshots.
+    pub record: Duration,
+    pub flush: Duration,
+    pub swap: Duration,
+}
+
+impl FrameTimings {
+    #[inline]
+    pub fn total(&self) -> Duration {
+        self.events + self.record + self.flush + self.swap
+    }
+}
+
+impl fmt::Display for FrameTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.;
+        write!(
+            f,
+            "events {:.2} ms  record {:.2} ms  flush {:.2} ms  swap {:.2} ms",
+            ms(self.events),
+            ms(self.record),
+            ms(self.flush),
+            ms(self.swap)
+        )
+    }
+}
+
+/// Log the frames taking longer than the threshold with their timings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowFrameConfig {
+    pub threshold: Duration,
+    /// Save the picture of the slow frames as `slow-frame-<frame>.skp` to the directory.
+    /// Every frame is recorded into a picture then, which costs some time itself.
+    pub picture_dir: Option<PathBuf>,
+}
+
+impl Default for SlowFrameConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Duration::from_millis(50),
+            picture_dir: None,
+        }
+    }
+}
+
+/// The statistics of the last rendered frame.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FrameStats {
+    pub frame: usize,
+    /// The time since the previous frame.
+    pub frame_time: Duration,
+    /// The frame rate smoothed over the recent frames.
+    pub fps: f32,
+    pub draw: DrawStats,
+    pub swap_time: Duration,
+    /// The frames dropped since the start.
+    pub dropped_frames: u64,
+    /// The frames dropped in a row up to the last frame, `0` if it was on time.
+    pub consecutive_dropped: u32,
+    /// The features given up under load, see `Backend::set_quality_policy`.
+    pub quality: Quality,
+    dropped: VecDeque<DroppedFrame>,
+    verification: Option<Verification>,
+}
+
+impl FrameStats {
+    /// Update the stats with the frame that just finished.
+    pub fn update(&mut self, frame: usize, frame_time: Duration, draw: DrawStats) {
+        let fps = 1. / frame_time.as_secs_f32().max(f32::EPSILON);
+        self.fps = if self.fps > 0. {
+            self.fps * 0.9 + fps * 0.1
+        } else {
+            fps
+        };
+        self.frame = frame;
+        self.frame_time = frame_time;
+        self.draw = draw;
+    }
+
+    /// Record the pacing of the frame after its buffers were swapped, returns whether it
+    /// was dropped.
+    pub fn update_pacing(
+        &mut self,
+        lateness: Duration,
+        swap_time: Duration,
+        budget: Duration,
+    ) -> bool {
+        self.swap_time = swap_time;
+        if lateness < budget && swap_time <= budget {
+            self.consecutive_dropped = 0;
+            return false;
+        }
+        self.dropped_frames += 1;
+        self.consecutive_dropped += 1;
+        if self.dropped.len() == MAX_DROPPED_FRAMES {
+            self.dropped.pop_front();
+        }
+        self.dropped.push_back(DroppedFrame {
+            frame: self.frame,
+            lateness,
+            swap_time,
+            budget,
+            consecutive: self.consecutive_dropped,
+        });
+        true
+    }
+
+    /// The dropped frames since the previous call, the oldest are discarded past 64.
+    #[inline]
+    pub fn take_dropped(&mut self) -> Vec<DroppedFrame> {
+        self.dropped.drain(..).collect()
+    }
+
+    /// Report the outcome of the frame hash verification, see `take_verification`.
+    #[inline]
+    pub fn set_verification(&mut self, verification: Verification) {
+        self.verification = Some(verification);
+    }
+
+    /// The outcome of the frame hash verification once it finished, taken once.
+    #[inline]
+    pub fn take_verification(&mut self) -> Option<Verification> {
+        self.verification.take()
+    }
+
+    /// The lines shown by the HUD.
+    pub fn lines(&self) -> Vec<String> {
+        let total = self.draw.total();
+        let mut lines = vec![
+            format!(
+                "frame {}  {:.1} fps  {:.2} ms",
+                self.frame,
+                self.fps,
+                self.frame_time.as_secs_f64() * 1000.
+            ),
+            format!(
+                "dropped {}  {} in a row  swap {:.2} ms",
+                self.dropped_frames,
+                self.consecutive_dropped,
+                self.swap_time.as_secs_f64() * 1000.
+            ),
+            format!(
+                "draw calls {}  {:.3} ms",
+                total.count,
+                total.time.as_secs_f64() * 1000.
+            ),
+        ];
+        if !self.quality.is_full() {
+            let disabled: Vec<_> = self.quality.disabled().map(QualityFeature::name).collect();
+            lines.push(format!("reduced quality: {}", disabled.join(", ")));
+        }
+        lines.extend(self.draw.iter().map(|(category, calls)| {
+            format!(
+                "  {:<8} {:>5}  {:.3} ms",
+                category.name(),
+                calls.count,
+                calls.time.as_secs_f64() * 1000.
+            )
+        }));
+        lines
+    }
+}
+
+/// Draw the stats in the top left corner.
+pub fn draw_hud(canvas: &mut Canvas, stats: &FrameStats) {
+    const LINE_HEIGHT: f32 = 16.;
+    const PADDING: f32 = 8.;
+
+    let lines = stats.lines();
+    let font = Font::default()
+        .with_size(13.)
+        .expect("Could not create font");
+    let width = lines
+        .iter()
+        .map(|line| font.measure_str(line, None).0)
+        .fold(0., f32::max);
+
+    let mut background = Paint::default();
+    background.set_color(Color::from_argb(0xb0, 0, 0, 0));
+    canvas.draw_rect(
+        Rect::from_xywh(
+            0.,
+            0.,
+            width + PADDING * 2.,
+            lines.len() as f32 * LINE_HEIGHT + PADDING * 2.,
+        ),
+        &background,
+    );
+
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true).set_color(Color::WHITE);
+    for (index, line) in lines.iter().enumerate() {
+        canvas.draw_str(
+            line,
+            (PADDING, PADDING + (index + 1) as f32 * LINE_HEIGHT - 4.),
+            &font,
+            &paint,
+        );
+    }
+}
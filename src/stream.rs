@@ -0,0 +1,251 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+use skia_safe::{AlphaType, ColorType, ImageInfo};
+
+use crate::{
+    capture::{self, CaptureFormat, CaptureOptions},
+    readback::Readback,
+    SkiaSurface,
+};
+
+const BOUNDARY: &str = "skia_gl_frame";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamEncoding {
+    /// Encoded images in a `multipart/x-mixed-replace` HTTP response, viewable in a browser.
+    Http(CaptureOptions),
+    /// Plain TCP, every frame is the width and height as little-endian `u32` followed by
+    /// the RGBA8 pixels.
+    Raw,
+}
+
+impl Default for StreamEncoding {
+    fn default() -> Self {
+        StreamEncoding::Http(CaptureOptions {
+            format: CaptureFormat::Jpeg { quality: 80 },
+            scale: 1.,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamConfig {
+    pub addr: SocketAddr,
+    pub encoding: StreamEncoding,
+}
+
+/// The latest encoded frame, with a sequence number the clients wait on.
+#[derive(Default)]
+struct SharedFrame {
+    sequence: u64,
+    bytes: Arc<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    frame: Mutex<SharedFrame>,
+    updated: Condvar,
+    clients: AtomicUsize,
+    closed: AtomicBool,
+}
+
+/// Streams the rendered frames to the connected clients, the frames are only encoded while
+/// a client is connected.
+pub struct FrameStreamer {
+    config: StreamConfig,
+    shared: Arc<Shared>,
+}
+
+impl FrameStreamer {
+    pub fn bind(config: StreamConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(config.addr)?;
+        let shared = Arc::new(Shared::default());
+
+        {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("frame_stream".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if shared.closed.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        match stream {
+                            Ok(stream) => {
+                                let shared = shared.clone();
+                                std::thread::spawn(move || {
+                                    shared.clients.fetch_add(1, Ordering::Relaxed);
+                                    if let Err(err) = serve_client(stream, config.encoding, &shared)
+                                    {
+                                        eprintln!("Frame stream client disconnected: {err}");
+                                    }
+                                    shared.clients.fetch_sub(1, Ordering::Relaxed);
+                                });
+                            }
+                            Err(err) => eprintln!("Error accepting frame stream client: {err}"),
+                        }
+                    }
+                })
+                .expect("Failed to spawn frame stream thread");
+        }
+
+        Ok(Self { config, shared })
+    }
+
+    #[inline]
+    pub fn config(&self) -> &StreamConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn has_clients(&self) -> bool {
+        self.shared.clients.load(Ordering::Relaxed) > 0
+    }
+
+    /// Encode the surface content and send it to the connected clients.
+    pub fn push_frame(&self, surface: &mut SkiaSurface) {
+        if !self.has_clients() {
+            return;
+        }
+
+        let bytes = match self.config.encoding {
+            StreamEncoding::Http(options) => {
+                match capture::encode(&surface.image_snapshot(), &options) {
+                    Some(data) => data.as_bytes().to_vec(),
+                    None => {
+                        eprintln!("Error encoding streamed frame");
+                        return;
+                    }
+                }
+            }
+            StreamEncoding::Raw => {
+                let (width, height) = (surface.width(), surface.height());
+                let info = ImageInfo::new(
+                    (width, height),
+                    ColorType::RGBA8888,
+                    AlphaType::Premul,
+                    None,
+                );
+                let row_bytes = width as usize * 4;
+                let mut bytes = vec![0u8; 8 + row_bytes * height as usize];
+                bytes[..4].copy_from_slice(&(width as u32).to_le_bytes());
+                bytes[4..8].copy_from_slice(&(height as u32).to_le_bytes());
+                if !surface.read_pixels(&info, &mut bytes[8..], row_bytes, (0, 0)) {
+                    eprintln!("Error reading back streamed frame");
+                    return;
+                }
+                bytes
+            }
+        };
+        self.publish(bytes);
+    }
+
+    /// Encode the pixels read back asynchronously and send them to the connected clients.
+    pub fn push_readback(&self, readback: &Readback) {
+        if !self.has_clients() {
+            return;
+        }
+
+        let bytes = match self.config.encoding {
+            StreamEncoding::Http(options) => {
+                match readback
+                    .to_image()
+                    .and_then(|image| capture::encode(&image, &options))
+                {
+                    Some(data) => data.as_bytes().to_vec(),
+                    None => {
+                        eprintln!("Error encoding streamed frame");
+                        return;
+                    }
+                }
+            }
+            StreamEncoding::Raw => {
+                let mut bytes = Vec::with_capacity(8 + readback.pixels.len());
+                bytes.extend_from_slice(&(readback.size.0 as u32).to_le_bytes());
+                bytes.extend_from_slice(&(readback.size.1 as u32).to_le_bytes());
+                bytes.extend_from_slice(&readback.pixels);
+                bytes
+            }
+        };
+        self.publish(bytes);
+    }
+
+    fn publish(&self, bytes: Vec<u8>) {
+        let mut frame = self.shared.frame.lock().unwrap();
+        frame.sequence += 1;
+        frame.bytes = Arc::new(bytes);
+        self.shared.updated.notify_all();
+    }
+}
+
+impl Drop for FrameStreamer {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Relaxed);
+        self.shared.updated.notify_all();
+        // Wake up the accept loop so it notices the streamer is closed.
+        TcpStream::connect(self.config.addr).ok();
+    }
+}
+
+fn serve_client(
+    mut stream: TcpStream,
+    encoding: StreamEncoding,
+    shared: &Shared,
+) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let content_type = match encoding {
+        StreamEncoding::Http(options) => {
+            // Skip the request headers, every path serves the stream.
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line)? > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nCache-Control: no-cache\r\nConnection: close\r\n\
+                 Content-Type: multipart/x-mixed-replace; boundary={BOUNDARY}\r\n\r\n"
+            )?;
+            Some(match options.format {
+                CaptureFormat::Png => "image/png",
+                CaptureFormat::Jpeg { .. } => "image/jpeg",
+                CaptureFormat::Webp { .. } => "image/webp",
+            })
+        }
+        StreamEncoding::Raw => None,
+    };
+
+    let mut sequence = 0;
+    loop {
+        let bytes = {
+            let mut frame = shared.frame.lock().unwrap();
+            while frame.sequence == sequence && !shared.closed.load(Ordering::Relaxed) {
+                frame = shared.updated.wait(frame).unwrap();
+            }
+            if shared.closed.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            sequence = frame.sequence;
+            frame.bytes.clone()
+        };
+
+        if let Some(content_type) = content_type {
+            write!(
+                stream,
+                "--{BOUNDARY}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+                bytes.len()
+            )?;
+            stream.write_all(&bytes)?;
+            stream.write_all(b"\r\n")?;
+        } else {
+            stream.write_all(&bytes)?;
+        }
+    }
+}
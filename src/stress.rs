@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use crate::backend::Backend;
+
+/// The scale factors switched between, like moving across mixed DPI monitors.
+const SCALE_FACTORS: [f64; 5] = [1., 1.25, 1.5, 2., 3.];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressConfig {
+    /// The seed of the action sequence, a failing run is reproduced with its seed.
+    pub seed: u64,
+    /// The time between two actions.
+    pub interval: Duration,
+    /// The number of actions before the test finishes, `None` runs until closed.
+    pub actions: Option<usize>,
+    /// The largest window size resized to, zero and one pixel sizes are included.
+    pub max_size: (u32, u32),
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            interval: Duration::from_millis(16),
+            actions: Some(1000),
+            max_size: (2048, 2048),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StressAction {
+    Resize(u32, u32),
+    ToggleFullscreen,
+    ScaleFactor(f64),
+    Minimize,
+    Restore,
+}
+
+/// Bombards the backend with window changes while it renders, with the GL error checks
+/// on, so a surface creation failure or a GL error panics with the seed and the action.
+#[derive(Debug, Clone)]
+pub struct StressTest {
+    config: StressConfig,
+    state: u64,
+    next_action: Option<Instant>,
+    count: usize,
+    last_action: Option<StressAction>,
+}
+
+impl StressTest {
+    pub fn new(config: StressConfig) -> Self {
+        Self {
+            config,
+            // Xorshift is stuck at zero.
+            state: config.seed.max(1),
+            next_action: None,
+            count: 0,
+            last_action: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &StressConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.config
+            .actions
+            .is_some_and(|actions| self.count >= actions)
+    }
+
+    /// Apply the next action when it is due, returns the applied action.
+    pub fn step(&mut self, backend: &mut Backend, now: Instant) -> Option<StressAction> {
+        if self.is_finished() || self.next_action.is_some_and(|next| now < next) {
+            return None;
+        }
+        if self.next_action.is_none() {
+            backend.set_gl_error_checks(true);
+        }
+        self.next_action = Some(now + self.config.interval);
+
+        let action = self.next();
+        match action {
+            StressAction::Resize(width, height) => backend.request_inner_size((width, height)),
+            StressAction::ToggleFullscreen => backend.toggle_borderless_fullscreen(),
+            StressAction::ScaleFactor(scale_factor) => backend.notify_scale_factor(scale_factor),
+            StressAction::Minimize => backend.set_minimized(true),
+            StressAction::Restore => backend.set_minimized(false),
+        }
+        self.count += 1;
+        self.last_action = Some(action);
+        Some(action)
+    }
+
+    /// When the next action is due, `None` before the first step.
+    #[inline]
+    pub fn next_action(&self) -> Option<Instant> {
+        self.next_action
+    }
+
+    /// The seed, the progress and the last action, to reproduce a failure.
+    pub fn describe(&self) -> String {
+        format!(
+            "stress test seed {} after {} actions, last {:?}",
+            self.config.seed, self.count, self.last_action
+        )
+    }
+
+    fn next(&mut self) -> StressAction {
+        match self.random(10) {
+            0..=4 => {
+                let (max_width, max_height) = self.config.max_size;
+                // Favor the degenerate sizes, they are where the surfaces fail.
+                let mut dimension = |max: u32| match self.random(8) {
+                    0 => 0,
+                    1 => 1,
+                    _ => self.random(u64::from(max) + 1) as u32,
+                };
+                let width = dimension(max_width);
+                let height = dimension(max_height);
+                StressAction::Resize(width, height)
+            }
+            5 | 6 => StressAction::ToggleFullscreen,
+            7 => StressAction::ScaleFactor(
+                SCALE_FACTORS[self.random(SCALE_FACTORS.len() as u64) as usize],
+            ),
+            8 => StressAction::Minimize,
+            _ => StressAction::Restore,
+        }
+    }
+
+    /// A xorshift64* number in `[0, bound)`.
+    fn random(&mut self, bound: u64) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) % bound
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use winit::{event::WindowEvent, window::WindowId};
+
+#[cfg(feature = "independent_ui")]
+use crate::watchdog::WatchdogAction;
+use crate::{backend::Backend, input::InputEvent};
+
+/// Drives the backends of several windows. Each backend owns the GL context and the Skia
+/// `DirectContext` of its window, on the `independent_ui` feature it renders on a thread of
+/// its own, so a slow window doesn't hold back the others. The supervisor routes the window
+/// events to their backend and closes the windows whose render thread stalled or died.
+#[derive(Default)]
+pub struct Supervisor {
+    /// The backends with the number of their last frame.
+    backends: HashMap<WindowId, (Backend, usize)>,
+}
+
+impl Supervisor {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Supervise the backend, keyed by the id of its window.
+    pub fn insert(&mut self, backend: Backend) -> WindowId {
+        let id = backend.window_id().expect("The backend has no window");
+        if let Some((mut previous, _)) = self.backends.insert(id, (backend, 0)) {
+            previous.exit();
+        }
+        id
+    }
+
+    #[inline]
+    pub fn get(&self, id: WindowId) -> Option<&Backend> {
+        self.backends.get(&id).map(|(backend, _)| backend)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut Backend> {
+        self.backends.get_mut(&id).map(|(backend, _)| backend)
+    }
+
+    /// Close the window, stopping its render thread.
+    pub fn close(&mut self, id: WindowId) {
+        if let Some((mut backend, _)) = self.backends.remove(&id) {
+            backend.exit();
+        }
+    }
+
+    /// Route the event to the backend of its window, returns `false` when the window is
+    /// not supervised.
+    pub fn handle_window_event(&mut self, id: WindowId, event: &WindowEvent) -> bool {
+        let Some((backend, frame)) = self.backends.get_mut(&id) else {
+            return false;
+        };
+        if let Some(input) = InputEvent::from_window_event(event) {
+            backend.handle_input(input);
+        }
+        match event {
+            WindowEvent::CloseRequested => self.close(id),
+            WindowEvent::Occluded(occluded) => backend.notify_occluded(*occluded),
+            WindowEvent::Moved(_) => backend.notify_moved(),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                backend.notify_scale_factor(*scale_factor)
+            }
+            WindowEvent::ThemeChanged(theme) => backend.notify_system_theme((*theme).into()),
+            WindowEvent::Resized(physical_size) => backend.notify_resize((*physical_size).into()),
+            WindowEvent::RedrawRequested => {
+                *frame += 1;
+                backend.render(*frame);
+            }
+            _ => (),
+        }
+        true
+    }
+
+    /// Request the frames due at `now` from the paced windows, returns when the next frame
+    /// of a window starts, `None` when all the windows are paused.
+    pub fn request_frames(&mut self, now: Instant) -> Option<Instant> {
+        let mut next_frame: Option<Instant> = None;
+        for (backend, _) in self.backends.values_mut() {
+            if backend.frame_pacer().start_frame(now) && backend.wants_frame(now) {
+                backend.request_redraw();
+            }
+            if !backend.frame_pacer().is_paused() {
+                let start = backend.frame_pacer().next_frame_start();
+                next_frame = Some(next_frame.map_or(start, |next| next.min(start)));
+            }
+        }
+        next_frame
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    /// Whether all the windows are closed, e.g. to leave the event loop.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&WindowId, &mut Backend)> {
+        self.backends
+            .iter_mut()
+            .map(|(id, (backend, _))| (id, backend))
+    }
+
+    /// Run the watchdogs and close the windows whose render thread asked for a shutdown
+    /// or died, e.g. on a panicking frame. Returns the closed windows.
+    #[cfg(feature = "independent_ui")]
+    pub fn check_render_threads(&mut self) -> Vec<WindowId> {
+        let closed: Vec<_> = self
+            .backends
+            .iter_mut()
+            .filter_map(|(id, (backend, _))| {
+                if backend.is_render_thread_finished() {
+                    eprintln!("The render thread of window {id:?} stopped, closing the window");
+                    return Some(*id);
+                }
+                (backend.check_watchdog() == Some(WatchdogAction::Shutdown)).then_some(*id)
+            })
+            .collect();
+        for id in &closed {
+            self.close(*id);
+        }
+        closed
+    }
+
+    /// Close all the windows and wait up to `timeout` for the render threads to stop, so
+    /// they release their GL contexts before the process exits.
+    pub fn exit_all(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut backends: Vec<_> = self
+            .backends
+            .drain()
+            .map(|(_, (backend, _))| backend)
+            .collect();
+        for backend in backends.iter_mut() {
+            backend.exit();
+        }
+        while !backends.iter().all(Backend::is_render_thread_finished) {
+            if Instant::now() >= deadline {
+                eprintln!("Timed out waiting for the render threads to stop");
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
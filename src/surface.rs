@@ -0,0 +1,289 @@
+use std::{fmt, str::FromStr};
+
+use glutin::config::{ColorBufferType, Config, GlConfig};
+use skia_safe::{gpu::gl::Format, ColorType};
+
+/// The pixel format Skia renders the window surface in, it must match the format of the
+/// default framebuffer or the colors come out wrong.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceColorType {
+    /// Detected from the GL config.
+    #[default]
+    Auto,
+    Rgba8888,
+    /// For the default framebuffers storing the blue channel first, which the GL config
+    /// doesn't report, so it is never detected.
+    Bgra8888,
+    /// 10 bits per color channel, e.g. for HDR capable displays.
+    Rgba1010102,
+    /// Half float channels.
+    RgbaF16,
+}
+
+impl SurfaceColorType {
+    /// The color type matching the channel sizes of the config.
+    pub fn detect(config: &Config) -> Self {
+        match config.color_buffer_type() {
+            Some(ColorBufferType::Rgb { r_size: 16, .. }) if config.float_pixels() => {
+                SurfaceColorType::RgbaF16
+            }
+            Some(ColorBufferType::Rgb {
+                r_size: 10,
+                g_size: 10,
+                b_size: 10,
+            }) => SurfaceColorType::Rgba1010102,
+            _ => SurfaceColorType::Rgba8888,
+        }
+    }
+
+    /// The color type to use with the config, the detected one for `Auto` or when the
+    /// config doesn't have the channel sizes of this one.
+    pub fn resolve(self, config: &Config) -> Self {
+        let detected = Self::detect(config);
+        match self {
+            SurfaceColorType::Auto => detected,
+            // Both have 8 bit channels, the channel order isn't reported.
+            SurfaceColorType::Bgra8888 if detected == SurfaceColorType::Rgba8888 => self,
+            _ if self == detected => self,
+            _ => {
+                eprintln!("The GL config has no {self} color buffer, using {detected}");
+                detected
+            }
+        }
+    }
+
+    /// The Skia color type and the GL format of a resolved color type.
+    pub fn formats(self) -> (ColorType, Format) {
+        match self {
+            SurfaceColorType::Auto | SurfaceColorType::Rgba8888 => {
+                (ColorType::RGBA8888, Format::RGBA8)
+            }
+            SurfaceColorType::Bgra8888 => (ColorType::BGRA8888, Format::BGRA8),
+            SurfaceColorType::Rgba1010102 => (ColorType::RGBA1010102, Format::RGB10_A2),
+            SurfaceColorType::RgbaF16 => (ColorType::RGBAF16, Format::RGBA16F),
+        }
+    }
+}
+
+impl FromStr for SurfaceColorType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(SurfaceColorType::Auto),
+            "rgba8888" => Ok(SurfaceColorType::Rgba8888),
+            "bgra8888" => Ok(SurfaceColorType::Bgra8888),
+            "rgba1010102" => Ok(SurfaceColorType::Rgba1010102),
+            "f16" | "rgbaf16" => Ok(SurfaceColorType::RgbaF16),
+            _ => Err(format!("Unknown surface color type `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for SurfaceColorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SurfaceColorType::Auto => "auto",
+            SurfaceColorType::Rgba8888 => "rgba8888",
+            SurfaceColorType::Bgra8888 => "bgra8888",
+            SurfaceColorType::Rgba1010102 => "rgba1010102",
+            SurfaceColorType::RgbaF16 => "f16",
+        })
+    }
+}
+
+/// Which render targets are multisampled, on MSAA configs. The layers of `save_layer`
+/// are multisampled like the surface they are drawn on either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MsaaResolve {
+    /// Skia also multisamples the render targets it creates internally, e.g. the atlas of
+    /// the complex paths, each resolved when it is drawn. The best quality, at the cost of
+    /// the fill-rate of every internal target.
+    #[default]
+    PerLayer,
+    /// Only the window surface is multisampled, resolved once per frame when the buffers
+    /// are swapped. Cheaper on weak GPUs, the paths Skia renders offscreen fall back to the
+    /// coverage antialiasing.
+    PerFrame,
+}
+
+impl MsaaResolve {
+    /// The sample count of the render targets Skia creates internally, set as
+    /// `ContextOptions::internal_multisample_count`, `0` disables their multisampling.
+    pub fn internal_sample_count(self) -> i32 {
+        match self {
+            // The default of Skia.
+            MsaaResolve::PerLayer => 4,
+            MsaaResolve::PerFrame => 0,
+        }
+    }
+}
+
+impl FromStr for MsaaResolve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "layer" | "per-layer" => Ok(MsaaResolve::PerLayer),
+            "frame" | "per-frame" => Ok(MsaaResolve::PerFrame),
+            _ => Err(format!("Unknown msaa resolve `{s}`")),
+        }
+    }
+}
+
+/// How the window surface is created.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SurfaceConfig {
+    pub color_type: SurfaceColorType,
+    /// Changing it recreates the GPU context, the GPU resources of the old context must not
+    /// be used anymore.
+    pub msaa_resolve: MsaaResolve,
+}
+
+/// The properties asked of the GL config the window is created with, negotiated against
+/// the configs of the display with `negotiate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigRequest {
+    /// The MSAA samples of the default framebuffer, Skia antialiases on its own otherwise.
+    pub samples: u8,
+    /// An alpha channel composited with the desktop, for transparent windows.
+    pub alpha: bool,
+    pub srgb: bool,
+    /// The color depth, `Auto` takes any.
+    pub color_type: SurfaceColorType,
+    pub hardware_accelerated: bool,
+    /// Fail when the picked config doesn't match the request instead of falling back to
+    /// the closest one.
+    pub strict: bool,
+}
+
+impl Default for ConfigRequest {
+    fn default() -> Self {
+        Self {
+            samples: 0,
+            alpha: true,
+            srgb: false,
+            color_type: SurfaceColorType::Auto,
+            hardware_accelerated: false,
+            strict: false,
+        }
+    }
+}
+
+impl ConfigRequest {
+    /// The config closest to the request: the hardware acceleration matters most, then the
+    /// color depth, the alpha channel, the sample count and the sRGB support. Among equally
+    /// close configs the one with the fewest samples wins.
+    pub fn negotiate(&self, configs: impl Iterator<Item = Config>) -> Option<Config> {
+        configs.min_by_key(|config| {
+            let report = ConfigReport::new(*self, config);
+            (
+                self.hardware_accelerated && !report.hardware_accelerated,
+                !report.color_type_matches(),
+                self.alpha && !report.alpha,
+                report.samples.abs_diff(self.samples),
+                self.srgb && !report.srgb,
+                report.samples,
+            )
+        })
+    }
+}
+
+/// What the picked GL config provides of the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigReport {
+    pub requested: ConfigRequest,
+    pub samples: u8,
+    pub alpha: bool,
+    pub srgb: bool,
+    pub color_type: SurfaceColorType,
+    pub hardware_accelerated: bool,
+}
+
+impl ConfigReport {
+    pub fn new(requested: ConfigRequest, config: &Config) -> Self {
+        Self {
+            requested,
+            samples: config.num_samples(),
+            // Platforms not reporting the transparency usually composite the alpha.
+            alpha: config.alpha_size() > 0 && config.supports_transparency() != Some(false),
+            srgb: config.srgb_capable(),
+            color_type: SurfaceColorType::detect(config),
+            hardware_accelerated: config.hardware_accelerated(),
+        }
+    }
+
+    /// The requested properties the config lacks, empty when it satisfies the request.
+    pub fn mismatches(&self) -> Vec<String> {
+        let requested = &self.requested;
+        let mut mismatches = vec![];
+        if requested.hardware_accelerated && !self.hardware_accelerated {
+            mismatches.push("not hardware accelerated".to_string());
+        }
+        if !self.color_type_matches() {
+            mismatches.push(format!(
+                "{} color buffer instead of {}",
+                self.color_type, requested.color_type
+            ));
+        }
+        if requested.alpha && !self.alpha {
+            mismatches.push("no transparent alpha channel".to_string());
+        }
+        if self.samples != requested.samples {
+            mismatches.push(format!(
+                "{} samples instead of {}",
+                self.samples, requested.samples
+            ));
+        }
+        if requested.srgb && !self.srgb {
+            mismatches.push("not sRGB capable".to_string());
+        }
+        mismatches
+    }
+
+    #[inline]
+    pub fn is_exact(&self) -> bool {
+        self.mismatches().is_empty()
+    }
+
+    /// An error listing the mismatches when the request is strict and not satisfied.
+    pub fn check(&self) -> Result<(), String> {
+        let mismatches = self.mismatches();
+        if !self.requested.strict || mismatches.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "No GL config matches the request: {}",
+            mismatches.join(", ")
+        ))
+    }
+
+    fn color_type_matches(&self) -> bool {
+        match self.requested.color_type {
+            SurfaceColorType::Auto => true,
+            // The channel order isn't reported.
+            SurfaceColorType::Bgra8888 => self.color_type == SurfaceColorType::Rgba8888,
+            color_type => self.color_type == color_type,
+        }
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} color buffer, {} samples, {}, {}",
+            self.color_type,
+            self.samples,
+            if self.alpha { "alpha" } else { "opaque" },
+            if self.srgb { "sRGB capable" } else { "linear" },
+        )?;
+        if !self.hardware_accelerated {
+            f.write_str(", software")?;
+        }
+        match self.mismatches() {
+            mismatches if mismatches.is_empty() => Ok(()),
+            mismatches => write!(f, " (fallback: {})", mismatches.join(", ")),
+        }
+    }
+}
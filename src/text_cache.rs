@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use skia_safe::{
+    font_style::Weight,
+    textlayout::{
+        FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextAlign, TextDirection,
+        TextStyle,
+    },
+    Canvas, Color, FontMgr, FontStyle, Point,
+};
+
+use crate::{locale::Locale, text_metrics::TextMetrics};
+
+pub const DEFAULT_TEXT_CACHE_CAPACITY: usize = 256;
+
+/// The style of a cached text, hashable unlike the Skia styles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextAppearance {
+    pub font_size: f32,
+    pub color: Color,
+    /// Empty for the default font families.
+    pub font_families: Vec<String>,
+    pub weight: Weight,
+    pub align: TextAlign,
+}
+
+impl Default for TextAppearance {
+    fn default() -> Self {
+        Self {
+            font_size: 14.,
+            color: Color::BLACK,
+            font_families: vec![],
+            weight: Weight::NORMAL,
+            align: TextAlign::Left,
+        }
+    }
+}
+
+impl TextAppearance {
+    #[inline]
+    pub fn new(font_size: f32, color: Color) -> Self {
+        Self {
+            font_size,
+            color,
+            ..Default::default()
+        }
+    }
+
+    /// The style laid out for the locale, its fallback fonts are tried after the font
+    /// families of the appearance.
+    fn paragraph_style(&self, locale: &Locale) -> ParagraphStyle {
+        let mut text_style = TextStyle::new();
+        text_style.set_locale(locale.tag());
+        text_style.set_font_size(self.font_size);
+        text_style.set_color(self.color);
+        text_style.set_font_style(FontStyle::new(
+            self.weight,
+            skia_safe::font_style::Width::NORMAL,
+            skia_safe::font_style::Slant::Upright,
+        ));
+        let locale_families = locale.font_families();
+        if !self.font_families.is_empty() || !locale_families.is_empty() {
+            let families: Vec<&str> = self
+                .font_families
+                .iter()
+                .map(String::as_str)
+                .chain(locale_families.iter().copied())
+                .collect();
+            text_style.set_font_families(&families);
+        }
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style.set_text_style(&text_style);
+        paragraph_style.set_text_align(self.align);
+        paragraph_style.set_text_direction(if locale.is_rtl() {
+            TextDirection::RTL
+        } else {
+            TextDirection::LTR
+        });
+        paragraph_style
+    }
+}
+
+impl Eq for TextAppearance {}
+
+impl Hash for TextAppearance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.font_size.to_bits().hash(state);
+        u32::from(self.color).hash(state);
+        self.font_families.hash(state);
+        (*self.weight).hash(state);
+        (self.align as i32).hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    appearance: TextAppearance,
+    /// The layout width bits, `f32::INFINITY` for unwrapped text.
+    width: u32,
+}
+
+struct Entry {
+    paragraph: Paragraph,
+    last_used: u64,
+}
+
+/// Shaped paragraphs keyed by text, appearance and layout width, the least recently used
+/// ones are evicted past the capacity, so static labels are only shaped once. The texts
+/// are laid out for the locale of the cache.
+pub struct TextCache {
+    fonts: FontCollection,
+    locale: Locale,
+    entries: HashMap<TextKey, Entry>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for TextCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEXT_CACHE_CAPACITY)
+    }
+}
+
+impl TextCache {
+    pub fn new(capacity: usize) -> Self {
+        let mut fonts = FontCollection::new();
+        fonts.set_default_font_manager(FontMgr::new(), None);
+        Self::with_fonts(fonts, capacity)
+    }
+
+    #[inline]
+    pub fn with_fonts(fonts: FontCollection, capacity: usize) -> Self {
+        Self {
+            fonts,
+            locale: Locale::default(),
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    #[inline]
+    pub fn fonts(&self) -> &FontCollection {
+        &self.fonts
+    }
+
+    #[inline]
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Lay the texts out for another locale, the paragraphs shaped for the previous one are
+    /// dropped. Returns whether the locale changed.
+    pub fn set_locale(&mut self, locale: Locale) -> bool {
+        if locale == self.locale {
+            return false;
+        }
+        self.locale = locale;
+        self.clear();
+        true
+    }
+
+    /// The paragraph laid out at `width`, shaped on a miss.
+    pub fn paragraph(&mut self, text: &str, appearance: &TextAppearance, width: f32) -> &Paragraph {
+        self.clock += 1;
+        let key = TextKey {
+            text: text.to_string(),
+            appearance: appearance.clone(),
+            width: width.to_bits(),
+        };
+        if self.entries.contains_key(&key) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            if self.entries.len() >= self.capacity {
+                self.evict();
+            }
+            let mut builder = ParagraphBuilder::new(
+                &appearance.paragraph_style(&self.locale),
+                self.fonts.clone(),
+            );
+            builder.add_text(self.locale.shape_digits(text));
+            let mut paragraph = builder.build();
+            paragraph.layout(width);
+            self.entries.insert(
+                key.clone(),
+                Entry {
+                    paragraph,
+                    last_used: 0,
+                },
+            );
+        }
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.clock;
+        &entry.paragraph
+    }
+
+    /// The caret and line metrics of the text laid out at `width`, e.g. to hit-test it.
+    #[inline]
+    pub fn metrics<'a>(
+        &'a mut self,
+        text: &'a str,
+        appearance: &TextAppearance,
+        width: f32,
+    ) -> TextMetrics<'a> {
+        TextMetrics::new(self.paragraph(text, appearance, width), text)
+    }
+
+    /// Draw the text wrapped to `width` with its top left corner at `origin`.
+    #[inline]
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas,
+        text: &str,
+        appearance: &TextAppearance,
+        width: f32,
+        origin: impl Into<Point>,
+    ) {
+        self.paragraph(text, appearance, width)
+            .paint(canvas, origin);
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop the paragraphs, e.g. after the fonts changed.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The lookups served from the cache and the ones shaping the text.
+    #[inline]
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
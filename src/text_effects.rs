@@ -0,0 +1,169 @@
+use skia_safe::{
+    Canvas, Color, ContourMeasureIter, Font, GlyphId, Paint, PaintJoin, PaintStyle, Path, Point,
+    RSXform, TextBlob, TextBlobBuilder,
+};
+
+/// An outline around the glyphs, drawn outside of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outline {
+    /// The visible width outside the glyph edges.
+    pub width: f32,
+    pub color: Color,
+}
+
+/// The glyph origins of the text starting at `origin`, `letter_spacing` is added after
+/// every glyph.
+pub fn glyph_positions(
+    font: &Font,
+    glyphs: &[GlyphId],
+    origin: impl Into<Point>,
+    letter_spacing: f32,
+) -> Vec<Point> {
+    let origin = origin.into();
+    let mut widths = vec![0.; glyphs.len()];
+    font.get_widths(glyphs, &mut widths);
+    let mut x = origin.x;
+    widths
+        .iter()
+        .map(|width| {
+            let position = Point::new(x, origin.y);
+            x += width + letter_spacing;
+            position
+        })
+        .collect()
+}
+
+/// The advance of the text with the letter spacing, the trailing spacing excluded.
+pub fn measure_spaced(text: &str, font: &Font, letter_spacing: f32) -> f32 {
+    let glyphs = font.str_to_glyphs_vec(text);
+    let mut widths = vec![0.; glyphs.len()];
+    font.get_widths(&glyphs, &mut widths);
+    widths.iter().sum::<f32>() + letter_spacing * glyphs.len().saturating_sub(1) as f32
+}
+
+/// A blob with the letter spacing applied, `None` for an empty text.
+pub fn spaced_text_blob(text: &str, font: &Font, letter_spacing: f32) -> Option<TextBlob> {
+    let glyphs = font.str_to_glyphs_vec(text);
+    if glyphs.is_empty() {
+        return None;
+    }
+    let positions = glyph_positions(font, &glyphs, Point::default(), letter_spacing);
+    let mut builder = TextBlobBuilder::new();
+    let (run_glyphs, run_positions) = builder.alloc_run_pos(font, glyphs.len(), None);
+    run_glyphs.copy_from_slice(&glyphs);
+    run_positions.copy_from_slice(&positions);
+    builder.make()
+}
+
+pub fn draw_spaced_text(
+    canvas: &mut Canvas,
+    text: &str,
+    origin: impl Into<Point>,
+    font: &Font,
+    letter_spacing: f32,
+    paint: &Paint,
+) {
+    if let Some(blob) = spaced_text_blob(text, font, letter_spacing) {
+        canvas.draw_text_blob(blob, origin, paint);
+    }
+}
+
+/// The outlines of the glyphs as one path, e.g. to stroke, clip or combine with other
+/// paths. Glyphs without outlines, like bitmap emoji, are skipped.
+pub fn text_path(text: &str, font: &Font, origin: impl Into<Point>, letter_spacing: f32) -> Path {
+    let glyphs = font.str_to_glyphs_vec(text);
+    let positions = glyph_positions(font, &glyphs, origin, letter_spacing);
+    let mut path = Path::new();
+    for (glyph, position) in glyphs.iter().zip(positions) {
+        if let Some(glyph_path) = font.get_path(*glyph) {
+            path.add_path(&glyph_path, position, None);
+        }
+    }
+    path
+}
+
+/// Draw the text filled with an outline around it. Stroking the text with the fill on top
+/// would halve the outline and let a glyph stroke cover its neighbor's fill, so all
+/// strokes are drawn first, twice as wide and with round joins against spikes at sharp
+/// glyph corners.
+pub fn draw_outlined_text(
+    canvas: &mut Canvas,
+    text: &str,
+    origin: impl Into<Point>,
+    font: &Font,
+    letter_spacing: f32,
+    fill: &Paint,
+    outline: Outline,
+) {
+    let path = text_path(text, font, origin, letter_spacing);
+    let mut stroke = Paint::default();
+    stroke.set_anti_alias(fill.is_anti_alias());
+    stroke.set_color(outline.color);
+    stroke.set_style(PaintStyle::Stroke);
+    stroke.set_stroke_width(outline.width * 2.);
+    stroke.set_stroke_join(PaintJoin::Round);
+    canvas.draw_path(&path, &stroke);
+
+    let mut fill = fill.clone();
+    fill.set_style(PaintStyle::Fill);
+    canvas.draw_path(&path, &fill);
+}
+
+/// Draw the text along the first contour of `path`, starting `offset` along it, with the
+/// baseline on the path. Each glyph is rotated to the tangent at its center, the glyphs
+/// past the end of the contour are dropped.
+pub fn draw_text_on_path(
+    canvas: &mut Canvas,
+    text: &str,
+    path: &Path,
+    offset: f32,
+    font: &Font,
+    letter_spacing: f32,
+    paint: &Paint,
+) {
+    let Some(contour) = ContourMeasureIter::from_path(path, false, None).next() else {
+        return;
+    };
+    let glyphs = font.str_to_glyphs_vec(text);
+    let mut widths = vec![0.; glyphs.len()];
+    font.get_widths(&glyphs, &mut widths);
+
+    let mut xforms = Vec::with_capacity(glyphs.len());
+    let mut distance = offset;
+    for width in widths {
+        let center = distance + width / 2.;
+        let Some((position, tangent)) = contour.pos_tan(center) else {
+            break;
+        };
+        if center > contour.length() {
+            break;
+        }
+        // Move the glyph origin back along the tangent by half its width.
+        xforms.push(RSXform::new(
+            tangent.x,
+            tangent.y,
+            (
+                position.x - tangent.x * width / 2.,
+                position.y - tangent.y * width / 2.,
+            ),
+        ));
+        distance += width + letter_spacing;
+    }
+    // The blob needs one transform per glyph, drop the glyphs past the end.
+    let glyphs = &glyphs[..xforms.len()];
+    let Some(blob) = glyphs_blob(font, glyphs, &xforms) else {
+        return;
+    };
+    canvas.draw_text_blob(blob, Point::default(), paint);
+}
+
+fn glyphs_blob(font: &Font, glyphs: &[GlyphId], xforms: &[RSXform]) -> Option<TextBlob> {
+    if glyphs.is_empty() {
+        return None;
+    }
+    let mut builder = TextBlobBuilder::new();
+    let (run_glyphs, run_xforms) = builder.alloc_run_rsxform(font, glyphs.len());
+    run_glyphs.copy_from_slice(glyphs);
+    run_xforms.copy_from_slice(xforms);
+    builder.make()
+}
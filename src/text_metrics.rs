@@ -0,0 +1,290 @@
+use std::ops::Range;
+
+use skia_safe::{
+    textlayout::{Paragraph, RectHeightStyle, RectWidthStyle},
+    Point, Rect,
+};
+use unicode_segmentation::GraphemeCursor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaretMovement {
+    /// The previous grapheme, a base character with its combining marks or an emoji
+    /// sequence.
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    /// The line above at the same horizontal position.
+    Up,
+    Down,
+    LineStart,
+    LineEnd,
+    TextStart,
+    TextEnd,
+}
+
+/// A line of the laid out paragraph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineInfo {
+    /// The byte range of the line, excluding the line break.
+    pub range: Range<usize>,
+    pub top: f32,
+    pub baseline: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub height: f32,
+    pub left: f32,
+    pub width: f32,
+    /// Whether the line ends with a line break rather than being wrapped.
+    pub hard_break: bool,
+}
+
+impl LineInfo {
+    #[inline]
+    pub fn bottom(&self) -> f32 {
+        self.top + self.height
+    }
+}
+
+/// Maps between the byte indices of a text and the positions in its laid out paragraph,
+/// the base of the selection and the editing. The indices are byte indices into `text`
+/// on grapheme boundaries, Skia's UTF-16 offsets are converted.
+pub struct TextMetrics<'a> {
+    paragraph: &'a Paragraph,
+    text: &'a str,
+    lines: Vec<LineInfo>,
+}
+
+impl<'a> TextMetrics<'a> {
+    /// The paragraph must be laid out from `text`.
+    pub fn new(paragraph: &'a Paragraph, text: &'a str) -> Self {
+        let lines = paragraph
+            .get_line_metrics()
+            .iter()
+            .map(|line| {
+                let start = floor_char_boundary(text, line.start_index);
+                // The trailing spaces belong to the line, the line break doesn't.
+                let end = text[..floor_char_boundary(text, line.end_including_newline)]
+                    .trim_end_matches(['\n', '\r'])
+                    .len()
+                    .max(start);
+                LineInfo {
+                    range: start..end,
+                    top: (line.baseline - line.ascent) as f32,
+                    baseline: line.baseline as f32,
+                    ascent: line.ascent as f32,
+                    descent: line.descent as f32,
+                    height: line.height as f32,
+                    left: line.left as f32,
+                    width: line.width as f32,
+                    hard_break: line.hard_break,
+                }
+            })
+            .collect();
+        Self {
+            paragraph,
+            text,
+            lines,
+        }
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    #[inline]
+    pub fn lines(&self) -> &[LineInfo] {
+        &self.lines
+    }
+
+    /// The line containing the caret at `index`, a caret between two wrapped lines is at
+    /// the start of the second.
+    pub fn line_at(&self, index: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| line.range.start <= index)
+            .unwrap_or(0)
+    }
+
+    /// The caret index closest to the point, in the paragraph space.
+    pub fn index_at(&self, point: impl Into<Point>) -> usize {
+        let position = self.paragraph.get_glyph_position_at_coordinate(point);
+        let index = self.byte_index(position.position.max(0) as usize);
+        self.floor_grapheme(index)
+    }
+
+    /// The caret at `index` as a zero width rect spanning the line height.
+    pub fn caret_rect(&self, index: usize) -> Rect {
+        let index = index.min(self.text.len());
+        let Some(line) = self.lines.get(self.line_at(index)) else {
+            return Rect::default();
+        };
+        let (top, bottom) = (line.top, line.bottom());
+        let x = if index < line.range.end {
+            self.rects(index..self.next_grapheme(index))
+                .first()
+                .map(|rect| rect.left)
+        } else if index > line.range.start {
+            self.rects(self.previous_grapheme(index)..index)
+                .last()
+                .map(|rect| rect.right)
+        } else {
+            None
+        };
+        let x = x.unwrap_or(line.left);
+        Rect::new(x, top, x, bottom)
+    }
+
+    /// The rects covering the byte range, one per line or direction run, e.g. to draw
+    /// the selection.
+    #[inline]
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        self.rects(range)
+    }
+
+    /// The byte range of the word at `index`.
+    pub fn word_at(&self, index: usize) -> Range<usize> {
+        let boundary = self
+            .paragraph
+            .get_word_boundary(self.utf16_index(index) as u32);
+        self.byte_index(boundary.start)..self.byte_index(boundary.end)
+    }
+
+    /// The index of the next grapheme boundary after `index`, the text length at the end.
+    pub fn next_grapheme(&self, index: usize) -> usize {
+        next_grapheme(self.text, index)
+    }
+
+    /// The index of the previous grapheme boundary before `index`, `0` at the start.
+    pub fn previous_grapheme(&self, index: usize) -> usize {
+        previous_grapheme(self.text, index)
+    }
+
+    /// Move the caret, `Up` and `Down` keep the horizontal position.
+    pub fn move_caret(&self, index: usize, movement: CaretMovement) -> usize {
+        let index = self.floor_grapheme(index.min(self.text.len()));
+        match movement {
+            CaretMovement::Left => self.previous_grapheme(index),
+            CaretMovement::Right => self.next_grapheme(index),
+            CaretMovement::WordLeft => {
+                let mut boundary = self.previous_grapheme(index);
+                // Skip the spaces before the word.
+                while boundary > 0 && self.is_space_at(boundary) {
+                    boundary = self.previous_grapheme(boundary);
+                }
+                self.word_at(boundary).start.min(boundary)
+            }
+            CaretMovement::WordRight => {
+                let mut boundary = index;
+                while boundary < self.text.len() && self.is_space_at(boundary) {
+                    boundary = self.next_grapheme(boundary);
+                }
+                if boundary < self.text.len() {
+                    self.word_at(boundary).end.max(self.next_grapheme(boundary))
+                } else {
+                    boundary
+                }
+            }
+            CaretMovement::Up | CaretMovement::Down => {
+                let line = self.line_at(index);
+                let target = if movement == CaretMovement::Up {
+                    line.checked_sub(1)
+                } else {
+                    Some(line + 1).filter(|line| *line < self.lines.len())
+                };
+                match target {
+                    Some(target) => {
+                        let target = &self.lines[target];
+                        let x = self.caret_rect(index).left;
+                        self.index_at((x, target.top + target.height / 2.))
+                    }
+                    None if movement == CaretMovement::Up => 0,
+                    None => self.text.len(),
+                }
+            }
+            CaretMovement::LineStart => self
+                .lines
+                .get(self.line_at(index))
+                .map_or(0, |line| line.range.start),
+            CaretMovement::LineEnd => self
+                .lines
+                .get(self.line_at(index))
+                .map_or(self.text.len(), |line| line.range.end),
+            CaretMovement::TextStart => 0,
+            CaretMovement::TextEnd => self.text.len(),
+        }
+    }
+
+    fn is_space_at(&self, index: usize) -> bool {
+        self.text[index..]
+            .chars()
+            .next()
+            .is_some_and(char::is_whitespace)
+    }
+
+    fn rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let range = self.utf16_index(range.start)..self.utf16_index(range.end);
+        self.paragraph
+            .get_rects_for_range(range, RectHeightStyle::Max, RectWidthStyle::Tight)
+            .iter()
+            .map(|text_box| text_box.rect)
+            .collect()
+    }
+
+    /// The grapheme boundary at or before `index`.
+    fn floor_grapheme(&self, index: usize) -> usize {
+        let previous = self.previous_grapheme(index);
+        if self.next_grapheme(previous) == index {
+            index
+        } else {
+            previous
+        }
+    }
+
+    fn utf16_index(&self, index: usize) -> usize {
+        self.text[..floor_char_boundary(self.text, index)]
+            .chars()
+            .map(char::len_utf16)
+            .sum()
+    }
+
+    fn byte_index(&self, index: usize) -> usize {
+        let mut utf16 = 0;
+        for (byte, c) in self.text.char_indices() {
+            if utf16 >= index {
+                return byte;
+            }
+            utf16 += c.len_utf16();
+        }
+        self.text.len()
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The first extended grapheme cluster boundary after `index`, the text length at the end.
+fn next_grapheme(text: &str, index: usize) -> usize {
+    let index = floor_char_boundary(text, index);
+    GraphemeCursor::new(index, text.len(), true)
+        .next_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(text.len())
+}
+
+/// The last extended grapheme cluster boundary before `index`, `0` at the start.
+fn previous_grapheme(text: &str, index: usize) -> usize {
+    let index = floor_char_boundary(text, index);
+    GraphemeCursor::new(index, text.len(), true)
+        .prev_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
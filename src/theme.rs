@@ -0,0 +1,260 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::Color;
+
+/// The transition used when the theme follows the system theme.
+pub const SYSTEM_THEME_TRANSITION: Duration = Duration::from_millis(300);
+
+/// The light or dark appearance chosen in the OS settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SystemTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl From<winit::window::Theme> for SystemTheme {
+    fn from(theme: winit::window::Theme) -> Self {
+        match theme {
+            winit::window::Theme::Light => SystemTheme::Light,
+            winit::window::Theme::Dark => SystemTheme::Dark,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemeColors {
+    /// The window background, the frame is cleared with it.
+    pub background: Color,
+    /// The background of the widgets.
+    pub surface: Color,
+    pub surface_hovered: Color,
+    pub surface_pressed: Color,
+    pub accent: Color,
+    /// The content drawn on the accent color, e.g. the check mark.
+    pub on_accent: Color,
+    pub text: Color,
+    pub text_disabled: Color,
+    pub border: Color,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Typography {
+    pub font_size: f32,
+    pub small_font_size: f32,
+    pub heading_font_size: f32,
+}
+
+/// The spacing steps, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spacing {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+/// The corner radii, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Radii {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+/// The look of the widgets, also passed to the renderers in the `FrameInfo`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub colors: ThemeColors,
+    pub typography: Typography,
+    pub spacing: Spacing,
+    pub radii: Radii,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            colors: ThemeColors {
+                background: Color::WHITE,
+                surface: Color::from_rgb(0xe0, 0xe0, 0xe0),
+                surface_hovered: Color::from_rgb(0xd0, 0xd0, 0xd0),
+                surface_pressed: Color::from_rgb(0xb8, 0xb8, 0xb8),
+                accent: Color::from_rgb(0x30, 0x78, 0xd8),
+                on_accent: Color::WHITE,
+                text: Color::from_rgb(0x20, 0x20, 0x20),
+                text_disabled: Color::from_rgb(0x90, 0x90, 0x90),
+                border: Color::from_rgb(0xc0, 0xc0, 0xc0),
+            },
+            typography: Typography::default(),
+            spacing: Spacing::default(),
+            radii: Radii::default(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            colors: ThemeColors {
+                background: Color::from_rgb(0x1e, 0x1e, 0x1e),
+                surface: Color::from_rgb(0x3a, 0x3a, 0x3a),
+                surface_hovered: Color::from_rgb(0x48, 0x48, 0x48),
+                surface_pressed: Color::from_rgb(0x58, 0x58, 0x58),
+                accent: Color::from_rgb(0x4c, 0x94, 0xf0),
+                on_accent: Color::WHITE,
+                text: Color::from_rgb(0xe8, 0xe8, 0xe8),
+                text_disabled: Color::from_rgb(0x80, 0x80, 0x80),
+                border: Color::from_rgb(0x50, 0x50, 0x50),
+            },
+            typography: Typography::default(),
+            spacing: Spacing::default(),
+            radii: Radii::default(),
+        }
+    }
+
+    #[inline]
+    pub fn for_system(theme: SystemTheme) -> Self {
+        match theme {
+            SystemTheme::Light => Self::light(),
+            SystemTheme::Dark => Self::dark(),
+        }
+    }
+
+    /// Interpolate every value, `t = 0` is `self` and `t = 1` is `other`.
+    pub fn lerp(&self, other: &Theme, t: f32) -> Theme {
+        let color = |a: Color, b: Color| lerp_color(a, b, t);
+        let value = |a: f32, b: f32| a + (b - a) * t;
+        let (a, b) = (&self.colors, &other.colors);
+        Theme {
+            colors: ThemeColors {
+                background: color(a.background, b.background),
+                surface: color(a.surface, b.surface),
+                surface_hovered: color(a.surface_hovered, b.surface_hovered),
+                surface_pressed: color(a.surface_pressed, b.surface_pressed),
+                accent: color(a.accent, b.accent),
+                on_accent: color(a.on_accent, b.on_accent),
+                text: color(a.text, b.text),
+                text_disabled: color(a.text_disabled, b.text_disabled),
+                border: color(a.border, b.border),
+            },
+            typography: Typography {
+                font_size: value(self.typography.font_size, other.typography.font_size),
+                small_font_size: value(
+                    self.typography.small_font_size,
+                    other.typography.small_font_size,
+                ),
+                heading_font_size: value(
+                    self.typography.heading_font_size,
+                    other.typography.heading_font_size,
+                ),
+            },
+            spacing: Spacing {
+                small: value(self.spacing.small, other.spacing.small),
+                medium: value(self.spacing.medium, other.spacing.medium),
+                large: value(self.spacing.large, other.spacing.large),
+            },
+            radii: Radii {
+                small: value(self.radii.small, other.radii.small),
+                medium: value(self.radii.medium, other.radii.medium),
+                large: value(self.radii.large, other.radii.large),
+            },
+        }
+    }
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            font_size: 14.,
+            small_font_size: 12.,
+            heading_font_size: 20.,
+        }
+    }
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            small: 4.,
+            medium: 8.,
+            large: 16.,
+        }
+    }
+}
+
+impl Default for Radii {
+    fn default() -> Self {
+        Self {
+            small: 2.,
+            medium: 4.,
+            large: 8.,
+        }
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::from_argb(
+        channel(a.a(), b.a()),
+        channel(a.r(), b.r()),
+        channel(a.g(), b.g()),
+        channel(a.b(), b.b()),
+    )
+}
+
+/// Switches the theme with an animated transition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemeAnimator {
+    from: Theme,
+    to: Theme,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Default for ThemeAnimator {
+    fn default() -> Self {
+        Self::new(Theme::default())
+    }
+}
+
+impl ThemeAnimator {
+    #[inline]
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            from: theme,
+            to: theme,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Transition from the current theme to `theme` over `duration`.
+    pub fn set(&mut self, theme: Theme, duration: Duration) {
+        self.from = self.current();
+        self.to = theme;
+        self.start = Instant::now();
+        self.duration = duration;
+    }
+
+    /// The theme the transition ends with.
+    #[inline]
+    pub fn target(&self) -> &Theme {
+        &self.to
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.start.elapsed() < self.duration
+    }
+
+    pub fn current(&self) -> Theme {
+        if !self.is_animating() {
+            return self.to;
+        }
+        let t = self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32();
+        self.from.lerp(&self.to, t * t * (3. - 2. * t))
+    }
+}
@@ -0,0 +1,58 @@
+use thread_priority::{ThreadPriority, ThreadPriorityValue};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderThreadPriority {
+    /// Keep the priority inherited from the spawning thread.
+    #[default]
+    Inherit,
+    High,
+    /// The highest priority the platform allows without special privileges.
+    Max,
+}
+
+/// The scheduling of the `independent_ui` render thread.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderThreadConfig {
+    pub priority: RenderThreadPriority,
+    /// Pin the render thread to the core with this index, where the platform supports it.
+    pub core: Option<usize>,
+}
+
+impl RenderThreadConfig {
+    #[inline]
+    pub fn with_priority(mut self, priority: RenderThreadPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[inline]
+    pub fn with_core(mut self, core: usize) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Apply the config to the calling thread, failures are reported and otherwise ignored.
+    pub fn apply(&self) {
+        let priority = match self.priority {
+            RenderThreadPriority::Inherit => None,
+            RenderThreadPriority::High => Some(ThreadPriority::Crossplatform(
+                ThreadPriorityValue::try_from(75u8).unwrap(),
+            )),
+            RenderThreadPriority::Max => Some(ThreadPriority::Max),
+        };
+        if let Some(priority) = priority {
+            if let Err(err) = thread_priority::set_current_thread_priority(priority) {
+                eprintln!("Error setting render thread priority: {err:?}");
+            }
+        }
+
+        if let Some(core) = self.core {
+            let core_id = core_affinity::get_core_ids()
+                .and_then(|core_ids| core_ids.into_iter().find(|core_id| core_id.id == core));
+            match core_id {
+                Some(core_id) if core_affinity::set_for_current(core_id) => {}
+                _ => eprintln!("Error pinning render thread to core {core}"),
+            }
+        }
+    }
+}
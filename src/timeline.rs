@@ -0,0 +1,473 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The bisection steps solving a cubic bezier curve for `t`, precise to well under a
+/// thousandth of the progress.
+const BEZIER_STEPS: usize = 20;
+
+/// How the value moves from a keyframe to the next one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Hold the value until the next keyframe.
+    Step,
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// The control points `[x1, y1, x2, y2]` of a CSS style `cubic-bezier()` curve.
+    CubicBezier([f32; 4]),
+}
+
+impl Interpolation {
+    /// The eased progress for the progress `t` in `0..=1`.
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Interpolation::Step => 0.,
+            Interpolation::Linear => t,
+            Interpolation::EaseIn => t * t,
+            Interpolation::EaseOut => t * (2. - t),
+            Interpolation::EaseInOut => t * t * (3. - 2. * t),
+            Interpolation::CubicBezier([x1, y1, x2, y2]) => {
+                let bezier = |a: f32, b: f32, s: f32| {
+                    3. * a * s * (1. - s).powi(2) + 3. * b * s * s * (1. - s) + s.powi(3)
+                };
+                // x grows with s for the control points in 0..=1, bisect s for x = t.
+                let (x1, x2) = (x1.clamp(0., 1.), x2.clamp(0., 1.));
+                let (mut low, mut high) = (0., 1.);
+                for _ in 0..BEZIER_STEPS {
+                    let s = (low + high) / 2.;
+                    if bezier(x1, x2, s) < t {
+                        low = s;
+                    } else {
+                        high = s;
+                    }
+                }
+                bezier(y1, y2, (low + high) / 2.)
+            }
+        }
+    }
+}
+
+/// What the timeline does past its duration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    /// Hold the values of the end.
+    #[default]
+    Once,
+    /// Restart from the beginning.
+    Loop,
+    /// Play backwards to the beginning, then forwards again.
+    PingPong,
+}
+
+/// The animated value of a track, interpolated component wise.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Scalar(f32),
+    /// E.g. a position or a scale.
+    Point([f32; 2]),
+    /// E.g. a color in RGBA.
+    Color([f32; 4]),
+}
+
+impl Value {
+    /// The value at `t` from `self` to `to`, values of different kinds switch at the end.
+    pub fn lerp(self, to: Value, t: f32) -> Value {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        match (self, to) {
+            (Value::Scalar(from), Value::Scalar(to)) => Value::Scalar(lerp(from, to)),
+            (Value::Point(from), Value::Point(to)) => {
+                Value::Point([lerp(from[0], to[0]), lerp(from[1], to[1])])
+            }
+            (Value::Color(from), Value::Color(to)) => {
+                Value::Color([0, 1, 2, 3].map(|index| lerp(from[index], to[index])))
+            }
+            _ if t < 1. => self,
+            _ => to,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    /// The seconds from the start of the timeline.
+    pub time: f32,
+    pub value: Value,
+    /// How the value moves to the next keyframe.
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+impl Keyframe {
+    #[inline]
+    pub fn new(time: f32, value: Value, interpolation: Interpolation) -> Self {
+        Self {
+            time,
+            value,
+            interpolation,
+        }
+    }
+}
+
+/// The keyframes of a property, named by the renderer, e.g. `"logo.opacity"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Track {
+    pub target: String,
+    /// Sorted by time, see `Track::insert`, and when deserialized.
+    #[serde(deserialize_with = "sorted_keyframes")]
+    keyframes: Vec<Keyframe>,
+}
+
+/// The keyframes in time order, whatever the order they were authored in.
+fn sorted_keyframes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Keyframe>, D::Error> {
+    let mut keyframes = Vec::<Keyframe>::deserialize(deserializer)?;
+    keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(keyframes)
+}
+
+impl Track {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            keyframes: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Insert the keyframe in time order, replacing the keyframe at the same time.
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        let index = self
+            .keyframes
+            .partition_point(|other| other.time < keyframe.time);
+        match self.keyframes.get_mut(index) {
+            Some(other) if other.time == keyframe.time => *other = keyframe,
+            _ => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    /// Remove the keyframe at the index, e.g. the one selected in an editor.
+    pub fn remove(&mut self, index: usize) -> Option<Keyframe> {
+        (index < self.keyframes.len()).then(|| self.keyframes.remove(index))
+    }
+
+    /// The time of the last keyframe.
+    #[inline]
+    pub fn end(&self) -> f32 {
+        self.keyframes.last().map_or(0., |keyframe| keyframe.time)
+    }
+
+    /// The value at the seconds from the start, held before the first and after the last
+    /// keyframe, `None` without keyframes.
+    pub fn value_at(&self, time: f32) -> Option<Value> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let Some(to) = self.keyframes.get(next) else {
+            return self.keyframes.last().map(|keyframe| keyframe.value);
+        };
+        let Some(from) = next.checked_sub(1).map(|index| &self.keyframes[index]) else {
+            return Some(to.value);
+        };
+        let t = (time - from.time) / (to.time - from.time);
+        Some(from.value.lerp(to.value, from.interpolation.ease(t)))
+    }
+}
+
+/// The keyframe animation of a scene, authored as data instead of hardcoded in the
+/// renderer and evaluated each frame, e.g.
+///
+/// ```json
+/// {
+///   "loop_mode": "ping_pong",
+///   "tracks": [{
+///     "target": "logo.position",
+///     "keyframes": [
+///       { "time": 0, "value": [0, 0] },
+///       { "time": 1.5, "value": [200, 80], "interpolation": "ease_in_out" }
+///     ]
+///   }]
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timeline {
+    /// The seconds of one pass, the end of the last keyframe when `None`.
+    #[serde(default)]
+    pub duration: Option<f32>,
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+}
+
+impl Timeline {
+    #[inline]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    #[inline]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_json(&text).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let text = self
+            .to_json()
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        std::fs::write(path, text)
+    }
+
+    /// The seconds of one pass.
+    pub fn duration(&self) -> f32 {
+        self.duration
+            .unwrap_or_else(|| self.tracks.iter().map(Track::end).fold(0., f32::max))
+    }
+
+    #[inline]
+    pub fn track(&self, target: &str) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.target == target)
+    }
+
+    /// The track of the target, added when missing.
+    pub fn track_mut(&mut self, target: &str) -> &mut Track {
+        match self.tracks.iter().position(|track| track.target == target) {
+            Some(index) => &mut self.tracks[index],
+            None => {
+                self.tracks.push(Track::new(target));
+                self.tracks.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// The time within the pass for the seconds played, following the loop mode.
+    pub fn local_time(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        if duration <= 0. {
+            return 0.;
+        }
+        match self.loop_mode {
+            LoopMode::Once => time.clamp(0., duration),
+            LoopMode::Loop => time.rem_euclid(duration),
+            LoopMode::PingPong => {
+                let time = time.rem_euclid(duration * 2.);
+                if time > duration {
+                    duration * 2. - time
+                } else {
+                    time
+                }
+            }
+        }
+    }
+
+    /// Whether the values stopped changing after the seconds played.
+    #[inline]
+    pub fn is_finished(&self, time: f32) -> bool {
+        self.loop_mode == LoopMode::Once && time >= self.duration()
+    }
+
+    /// The value of the target after the seconds played.
+    pub fn value(&self, target: &str, time: f32) -> Option<Value> {
+        self.track(target)?.value_at(self.local_time(time))
+    }
+
+    /// The values of every track after the seconds played.
+    pub fn evaluate(&self, time: f32) -> impl Iterator<Item = (&str, Value)> {
+        let time = self.local_time(time);
+        self.tracks.iter().filter_map(move |track| {
+            track
+                .value_at(time)
+                .map(|value| (track.target.as_str(), value))
+        })
+    }
+}
+
+/// Plays a timeline on the frame clock, advance it with `FrameInfo::dt` so it follows the
+/// pausing and the slow motion of the clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelinePlayer {
+    pub timeline: Timeline,
+    /// The seconds played.
+    pub time: f32,
+    /// The playback rate, negative plays backwards.
+    pub speed: f32,
+    pub playing: bool,
+}
+
+impl TimelinePlayer {
+    pub fn new(timeline: Timeline) -> Self {
+        Self {
+            timeline,
+            time: 0.,
+            speed: 1.,
+            playing: true,
+        }
+    }
+
+    /// Move the playhead by the seconds, returns whether the values may have changed and
+    /// the frame needs to be rendered.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        let at_end = if self.speed < 0. {
+            self.time <= 0.
+        } else {
+            self.timeline.is_finished(self.time)
+        };
+        if !self.playing || at_end {
+            return false;
+        }
+        self.time = (self.time + dt * self.speed).max(0.);
+        true
+    }
+
+    /// Jump to the seconds, e.g. when scrubbing in an editor.
+    #[inline]
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.);
+    }
+
+    #[inline]
+    pub fn value(&self, target: &str) -> Option<Value> {
+        self.timeline.value(target, self.time)
+    }
+
+    #[inline]
+    pub fn evaluate(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.timeline.evaluate(self.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(value: Option<Value>) -> f32 {
+        match value {
+            Some(Value::Scalar(value)) => value,
+            other => panic!("Not a scalar: {other:?}"),
+        }
+    }
+
+    fn fade(loop_mode: LoopMode) -> Timeline {
+        let mut timeline = Timeline {
+            loop_mode,
+            ..Timeline::default()
+        };
+        let track = timeline.track_mut("opacity");
+        track.insert(Keyframe::new(2., Value::Scalar(1.), Interpolation::Linear));
+        track.insert(Keyframe::new(0., Value::Scalar(0.), Interpolation::Linear));
+        timeline
+    }
+
+    #[test]
+    fn easings_keep_their_ends() {
+        for interpolation in [
+            Interpolation::Linear,
+            Interpolation::EaseIn,
+            Interpolation::EaseOut,
+            Interpolation::EaseInOut,
+            Interpolation::CubicBezier([0.25, 0.1, 0.25, 1.]),
+        ] {
+            assert!(interpolation.ease(0.).abs() < 1e-3, "{interpolation:?}");
+            assert!(
+                (interpolation.ease(1.) - 1.).abs() < 1e-3,
+                "{interpolation:?}"
+            );
+        }
+        assert_eq!(Interpolation::Step.ease(0.99), 0.);
+        let linear = Interpolation::CubicBezier([0., 0., 1., 1.]);
+        assert!((linear.ease(0.3) - 0.3).abs() < 1e-3);
+    }
+
+    #[test]
+    fn values_are_interpolated_and_held() {
+        let track = &fade(LoopMode::Once).tracks[0];
+        assert_eq!(scalar(track.value_at(-1.)), 0.);
+        assert_eq!(scalar(track.value_at(1.)), 0.5);
+        assert_eq!(scalar(track.value_at(3.)), 1.);
+        assert_eq!(Track::new("empty").value_at(1.), None);
+        assert_eq!(
+            Value::Point([0., 10.]).lerp(Value::Point([10., 20.]), 0.5),
+            Value::Point([5., 15.])
+        );
+    }
+
+    #[test]
+    fn insert_replaces_the_keyframe_at_the_same_time() {
+        let mut timeline = fade(LoopMode::Once);
+        let track = timeline.track_mut("opacity");
+        track.insert(Keyframe::new(2., Value::Scalar(0.5), Interpolation::Step));
+        assert_eq!(track.keyframes().len(), 2);
+        assert_eq!(scalar(track.value_at(2.)), 0.5);
+    }
+
+    #[test]
+    fn local_time_follows_the_loop_mode() {
+        assert_eq!(fade(LoopMode::Once).local_time(5.), 2.);
+        assert_eq!(fade(LoopMode::Loop).local_time(5.), 1.);
+        assert_eq!(fade(LoopMode::PingPong).local_time(3.), 1.);
+        assert_eq!(fade(LoopMode::PingPong).local_time(5.), 1.);
+        assert!(fade(LoopMode::Once).is_finished(2.));
+        assert!(!fade(LoopMode::Loop).is_finished(2.));
+    }
+
+    #[test]
+    fn deserialized_keyframes_are_sorted() {
+        #[derive(Deserialize)]
+        struct Config {
+            timeline: Timeline,
+        }
+
+        let json = r#"{ "timeline": { "tracks": [{
+            "target": "opacity",
+            "keyframes": [
+                { "time": 2, "value": 1 },
+                { "time": 0, "value": 0 }
+            ]
+        }] } }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(scalar(config.timeline.value("opacity", 1.)), 0.5);
+        let timeline = Timeline::from_json(&fade(LoopMode::Loop).to_json().unwrap()).unwrap();
+        assert_eq!(timeline, fade(LoopMode::Loop));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let track = r#"{ "tracks": [{ "target": "a", "keyframes": [], "easing": 1 }] }"#;
+        assert!(Timeline::from_json(track).is_err());
+        let keyframe = r#"{ "tracks": [{ "target": "a", "keyframes": [{ "time": 0, "value": 1, "ease": 1 }] }] }"#;
+        assert!(Timeline::from_json(keyframe).is_err());
+    }
+
+    #[test]
+    fn player_stops_at_the_ends() {
+        let mut player = TimelinePlayer::new(fade(LoopMode::Once));
+        assert!(player.advance(1.5));
+        assert!(player.advance(1.5));
+        assert!(!player.advance(1.));
+        assert_eq!(scalar(player.value("opacity")), 1.);
+        player.speed = -1.;
+        assert!(player.advance(5.));
+        assert_eq!(player.time, 0.);
+        assert!(!player.advance(1.));
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::{Canvas, Font, Paint, PaintStyle, Point, RRect, Rect};
+
+use crate::{input::InputEvent, invalidation::Invalidator, renderer::FrameInfo, theme::Theme};
+
+pub const DEFAULT_HOVER_DELAY: Duration = Duration::from_millis(500);
+
+/// The offset of the bubble from the cursor, below the pointer arrow.
+const CURSOR_OFFSET: (f32, f32) = (12., 20.);
+
+/// An area showing a tooltip when hovered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TooltipTarget {
+    pub rect: Rect,
+    /// The lines are split on `\n`.
+    pub text: String,
+}
+
+impl TooltipTarget {
+    #[inline]
+    pub fn new(rect: Rect, text: impl Into<String>) -> Self {
+        Self {
+            rect,
+            text: text.into(),
+        }
+    }
+}
+
+/// Shows a text bubble next to the cursor once it rested over a target for the hover
+/// delay. The targets and the input are in the content space of the renderer, the bubble
+/// is drawn in the overlay pass in window space, moved to stay inside the window. Pressing
+/// a button or a key hides it until the cursor enters another target.
+#[derive(Debug, Clone)]
+pub struct TooltipManager {
+    targets: Vec<TooltipTarget>,
+    delay: Duration,
+    cursor: Option<(f32, f32)>,
+    /// The hovered target and when the hover started.
+    hovered: Option<(usize, Instant)>,
+    suppressed: bool,
+    invalidator: Option<Invalidator>,
+}
+
+impl Default for TooltipManager {
+    fn default() -> Self {
+        Self {
+            targets: vec![],
+            delay: DEFAULT_HOVER_DELAY,
+            cursor: None,
+            hovered: None,
+            suppressed: false,
+            invalidator: None,
+        }
+    }
+}
+
+impl TooltipManager {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Request the frame showing the tooltip once the delay passed, in on-demand rendering.
+    #[inline]
+    pub fn set_invalidator(&mut self, invalidator: Option<Invalidator>) {
+        self.invalidator = invalidator;
+    }
+
+    /// Add a target, the later ones are on top of the earlier ones.
+    pub fn add_target(&mut self, target: TooltipTarget) -> usize {
+        self.targets.push(target);
+        self.targets.len() - 1
+    }
+
+    /// Replace the targets, e.g. after the layout changed.
+    pub fn set_targets(&mut self, targets: Vec<TooltipTarget>) {
+        self.targets = targets;
+        self.update_hovered();
+    }
+
+    #[inline]
+    pub fn targets(&self) -> &[TooltipTarget] {
+        &self.targets
+    }
+
+    pub fn handle_input(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                self.cursor = Some(position);
+                self.update_hovered();
+            }
+            InputEvent::MouseButton { pressed: true, .. }
+            | InputEvent::Key { pressed: true, .. }
+            | InputEvent::Scroll { .. } => self.hide(),
+            InputEvent::Touch { .. } => {
+                // There is no hover on touch screens.
+                self.cursor = None;
+                self.hide();
+            }
+            _ => {}
+        }
+    }
+
+    /// Hide the tooltip until the cursor enters another target.
+    pub fn hide(&mut self) {
+        if self.visible_at(Instant::now()).is_some() {
+            self.request_frame(Duration::ZERO);
+        }
+        self.suppressed = true;
+    }
+
+    /// The target whose tooltip is shown at `now`.
+    pub fn visible_at(&self, now: Instant) -> Option<&TooltipTarget> {
+        let (index, since) = self.hovered?;
+        if self.suppressed || now.saturating_duration_since(since) < self.delay {
+            return None;
+        }
+        self.targets.get(index)
+    }
+
+    /// Draw the tooltip in window space, call from `Renderer::render_overlay`.
+    pub fn draw(&self, canvas: &mut Canvas, info: &FrameInfo) {
+        let (Some(target), Some(cursor)) = (self.visible_at(Instant::now()), self.cursor) else {
+            return;
+        };
+        let theme = info.theme;
+        let anchor = info.camera.matrix().map_point(cursor);
+        let font = Font::default()
+            .with_size(theme.typography.small_font_size)
+            .expect("Could not create font");
+        let (_, metrics) = font.metrics();
+        let line_height = metrics.descent - metrics.ascent + metrics.leading;
+        let lines: Vec<&str> = target.text.lines().collect();
+        let text_width = lines
+            .iter()
+            .map(|line| font.measure_str(line, None).0)
+            .fold(0., f32::max);
+        let padding = theme.spacing.small;
+        let size = (
+            text_width + padding * 2.,
+            line_height * lines.len() as f32 + padding * 2.,
+        );
+        let bubble = place(anchor, size, (info.size.0 as f32, info.size.1 as f32));
+        draw_bubble(canvas, theme, bubble);
+
+        let mut text = Paint::default();
+        text.set_anti_alias(info.anti_alias);
+        text.set_color(theme.colors.text);
+        for (index, line) in lines.iter().enumerate() {
+            let baseline = bubble.top + padding - metrics.ascent + line_height * index as f32;
+            canvas.draw_str(line, (bubble.left + padding, baseline), &font, &text);
+        }
+    }
+
+    fn update_hovered(&mut self) {
+        let hovered = self.cursor.and_then(|cursor| {
+            self.targets
+                .iter()
+                .rposition(|target| target.rect.contains(Point::from(cursor)))
+        });
+        if hovered == self.hovered.map(|(index, _)| index) {
+            return;
+        }
+        if self.visible_at(Instant::now()).is_some() {
+            self.request_frame(Duration::ZERO);
+        }
+        self.suppressed = false;
+        self.hovered = hovered.map(|index| (index, Instant::now()));
+        if self.hovered.is_some() {
+            self.request_frame(self.delay);
+        }
+    }
+
+    fn request_frame(&self, after: Duration) {
+        if let Some(invalidator) = &self.invalidator {
+            invalidator.invalidate_after(after);
+        }
+    }
+}
+
+/// The bubble of `size` below right of the anchor, flipped to the other side of the
+/// cursor where it would leave the window and clamped inside as a last resort.
+fn place(anchor: Point, size: (f32, f32), window: (f32, f32)) -> Rect {
+    let mut left = anchor.x + CURSOR_OFFSET.0;
+    if left + size.0 > window.0 {
+        left = anchor.x - CURSOR_OFFSET.0 - size.0;
+    }
+    let mut top = anchor.y + CURSOR_OFFSET.1;
+    if top + size.1 > window.1 {
+        top = anchor.y - CURSOR_OFFSET.1 - size.1;
+    }
+    let left = left.min(window.0 - size.0).max(0.);
+    let top = top.min(window.1 - size.1).max(0.);
+    Rect::from_xywh(left, top, size.0, size.1)
+}
+
+fn draw_bubble(canvas: &mut Canvas, theme: &Theme, bubble: Rect) {
+    let rrect = RRect::new_rect_xy(bubble, theme.radii.small, theme.radii.small);
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_color(theme.colors.surface);
+    canvas.draw_rrect(rrect, &paint);
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_color(theme.colors.border);
+    canvas.draw_rrect(rrect, &paint);
+}
@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::{canvas::SaveLayerRec, Canvas};
+
+use crate::renderer::{FrameInfo, Renderer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Fade the incoming scene in over the outgoing one.
+    Crossfade,
+    /// Slide the incoming scene in from the right, from the left when going back.
+    Slide,
+    /// Grow the incoming scene from the center while fading it in.
+    Zoom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: Duration,
+}
+
+impl Transition {
+    #[inline]
+    pub fn new(kind: TransitionKind, duration: Duration) -> Self {
+        Self { kind, duration }
+    }
+
+    #[inline]
+    pub fn crossfade(duration: Duration) -> Self {
+        Self::new(TransitionKind::Crossfade, duration)
+    }
+
+    #[inline]
+    pub fn slide(duration: Duration) -> Self {
+        Self::new(TransitionKind::Slide, duration)
+    }
+
+    #[inline]
+    pub fn zoom(duration: Duration) -> Self {
+        Self::new(TransitionKind::Zoom, duration)
+    }
+}
+
+/// A running transition between two scenes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionState {
+    transition: Transition,
+    start: Instant,
+    /// Going back, e.g. popping a scene, reverses the direction of the slide.
+    reverse: bool,
+}
+
+impl TransitionState {
+    #[inline]
+    pub fn start(transition: Transition, reverse: bool) -> Self {
+        Self {
+            transition,
+            start: Instant::now(),
+            reverse,
+        }
+    }
+
+    /// The eased progress in `[0, 1]`.
+    pub fn progress(&self) -> f32 {
+        let duration = self.transition.duration.as_secs_f32();
+        if duration <= 0. {
+            return 1.;
+        }
+        let t = (self.start.elapsed().as_secs_f32() / duration).min(1.);
+        t * t * (3. - 2. * t)
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.start.elapsed() >= self.transition.duration
+    }
+
+    /// Render both scenes into layers composited at the current progress.
+    pub fn render(
+        &self,
+        canvas: &mut Canvas,
+        info: &FrameInfo,
+        outgoing: &mut dyn Renderer,
+        incoming: &mut dyn Renderer,
+    ) {
+        let t = self.progress();
+        let (width, height) = (info.size.0 as f32, info.size.1 as f32);
+        let alpha = |opacity: f32| (opacity.clamp(0., 1.) * 255.).round() as u32;
+
+        match self.transition.kind {
+            TransitionKind::Crossfade => {
+                canvas.save_layer(&SaveLayerRec::default());
+                outgoing.render(canvas, info);
+                canvas.restore();
+                canvas.save_layer_alpha(None, alpha(t));
+                incoming.render(canvas, info);
+                canvas.restore();
+            }
+            TransitionKind::Slide => {
+                let direction = if self.reverse { -1. } else { 1. };
+                canvas.save_layer(&SaveLayerRec::default());
+                canvas.translate((-direction * t * width, 0.));
+                outgoing.render(canvas, info);
+                canvas.restore();
+                canvas.save_layer(&SaveLayerRec::default());
+                canvas.translate((direction * (1. - t) * width, 0.));
+                incoming.render(canvas, info);
+                canvas.restore();
+            }
+            TransitionKind::Zoom => {
+                let center = (width / 2., height / 2.);
+                canvas.save_layer_alpha(None, alpha(1. - t));
+                outgoing.render(canvas, info);
+                canvas.restore();
+                let scale = 0.5 + 0.5 * t;
+                canvas.save_layer_alpha(None, alpha(t));
+                canvas.translate(center);
+                canvas.scale((scale, scale));
+                canvas.translate((-center.0, -center.1));
+                incoming.render(canvas, info);
+                canvas.restore();
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use skia_safe::Canvas;
+
+use crate::renderer::{FrameInfo, Renderer};
+
+const INDEX_MASK: u8 = 0b011;
+/// Set on the back buffer index when it holds a snapshot the subscriber has not read yet.
+const DIRTY: u8 = 0b100;
+
+struct Shared<T> {
+    buffers: [UnsafeCell<T>; 3],
+    /// The index of the buffer exchanged between the publisher and the subscriber.
+    back: AtomicU8,
+}
+
+// The publisher and the subscriber own distinct buffers, the back buffer is only handed
+// over through the atomic swap. Both are `Send` through the `Arc` of it.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+/// Create a lock-free triple buffer, the publisher writes the next snapshot while the
+/// subscriber keeps reading the latest complete one, neither ever waits on the other.
+pub fn triple_buffer<T: Clone + Send>(initial: T) -> (Publisher<T>, Subscriber<T>) {
+    let shared = Arc::new(Shared {
+        buffers: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial),
+        ],
+        back: AtomicU8::new(1),
+    });
+    (
+        Publisher {
+            shared: shared.clone(),
+            write: 0,
+        },
+        Subscriber { shared, read: 2 },
+    )
+}
+
+/// The writing side, e.g. owned by the main thread handling the application state.
+pub struct Publisher<T> {
+    shared: Arc<Shared<T>>,
+    write: u8,
+}
+
+impl<T> Publisher<T> {
+    /// The buffer of the next snapshot, it still holds the snapshot published two times ago.
+    #[inline]
+    pub fn write(&mut self) -> &mut T {
+        unsafe { &mut *self.shared.buffers[self.write as usize].get() }
+    }
+
+    /// Make the written buffer the latest snapshot.
+    #[inline]
+    pub fn publish(&mut self) {
+        let back = self.shared.back.swap(self.write | DIRTY, Ordering::AcqRel);
+        self.write = back & INDEX_MASK;
+    }
+
+    #[inline]
+    pub fn publish_value(&mut self, value: T) {
+        *self.write() = value;
+        self.publish();
+    }
+}
+
+/// The reading side, e.g. owned by the renderer on the render thread.
+pub struct Subscriber<T> {
+    shared: Arc<Shared<T>>,
+    read: u8,
+}
+
+impl<T> Subscriber<T> {
+    #[inline]
+    pub fn has_update(&self) -> bool {
+        self.shared.back.load(Ordering::Relaxed) & DIRTY != 0
+    }
+
+    /// The latest published snapshot.
+    #[inline]
+    pub fn read(&mut self) -> &T {
+        if self.has_update() {
+            let back = self.shared.back.swap(self.read, Ordering::AcqRel);
+            self.read = back & INDEX_MASK;
+        }
+        unsafe { &*self.shared.buffers[self.read as usize].get() }
+    }
+}
+
+/// Draws the latest snapshot published by another thread.
+pub struct SharedStateRenderer<T, F> {
+    state: Subscriber<T>,
+    draw: F,
+}
+
+impl<T, F> SharedStateRenderer<T, F>
+where
+    F: FnMut(&mut Canvas, &FrameInfo, &T),
+{
+    #[inline]
+    pub fn new(state: Subscriber<T>, draw: F) -> Self {
+        Self { state, draw }
+    }
+}
+
+impl<T, F> Renderer for SharedStateRenderer<T, F>
+where
+    T: Send,
+    F: FnMut(&mut Canvas, &FrameInfo, &T) + Send,
+{
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        (self.draw)(canvas, info, self.state.read());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn read_returns_the_latest_published_value() {
+        let (mut publisher, mut subscriber) = triple_buffer(0);
+        assert_eq!(*subscriber.read(), 0);
+        publisher.publish_value(1);
+        publisher.publish_value(2);
+        assert!(subscriber.has_update());
+        assert_eq!(*subscriber.read(), 2);
+        publisher.publish_value(3);
+        assert_eq!(*subscriber.read(), 3);
+    }
+
+    #[test]
+    fn read_without_publish_keeps_the_value() {
+        let (mut publisher, mut subscriber) = triple_buffer(0);
+        publisher.publish_value(1);
+        assert_eq!(*subscriber.read(), 1);
+        assert!(!subscriber.has_update());
+        assert_eq!(*subscriber.read(), 1);
+        // Written but not published yet.
+        *publisher.write() = 2;
+        assert_eq!(*subscriber.read(), 1);
+        publisher.publish();
+        assert_eq!(*subscriber.read(), 2);
+    }
+
+    #[test]
+    fn concurrent_reads_are_never_torn_or_older() {
+        const COUNT: u64 = 100_000;
+        let (mut publisher, mut subscriber) = triple_buffer([0u64; 16]);
+        let writer = thread::spawn(move || {
+            for value in 1..=COUNT {
+                publisher.publish_value([value; 16]);
+            }
+        });
+        let mut last = 0;
+        while last < COUNT {
+            let snapshot = *subscriber.read();
+            assert!(
+                snapshot.iter().all(|&value| value == snapshot[0]),
+                "torn snapshot {snapshot:?}"
+            );
+            assert!(snapshot[0] >= last, "read {} after {last}", snapshot[0]);
+            last = snapshot[0];
+        }
+        writer.join().unwrap();
+    }
+}
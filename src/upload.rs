@@ -0,0 +1,292 @@
+use std::{
+    ffi::{c_void, CString},
+    io,
+    num::NonZeroU32,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use glutin::{
+    context::{NotCurrentContext, NotCurrentGlContext},
+    display::{Display, GetGlDisplay, GlDisplay},
+    surface::{PbufferSurface, Surface, SurfaceAttributesBuilder},
+};
+use skia_safe::{
+    gpu::{self, gl::TextureInfo, BackendTexture, SurfaceOrigin},
+    AlphaType, Canvas, ColorType, Image, ImageInfo,
+};
+
+use crate::backend::GlEnv;
+
+/// A texture uploaded by the worker, waiting to be adopted by the rendering context.
+struct UploadedTexture {
+    id: GLuint,
+    size: (i32, i32),
+    mipmapped: bool,
+}
+
+enum Job {
+    Upload {
+        image: Image,
+        mipmapped: bool,
+        slot: Arc<Mutex<Slot>>,
+    },
+    /// Delete a texture the rendering context never adopted.
+    Delete(GLuint),
+}
+
+enum Slot {
+    Pending,
+    Ready(UploadedTexture),
+    Failed,
+    Taken,
+}
+
+/// The GL functions of the upload context, loaded for it instead of with `gl::load_with`,
+/// which would replace the global function pointers of the rendering context.
+struct UploadGl {
+    gen_textures: unsafe extern "system" fn(GLsizei, *mut GLuint),
+    delete_textures: unsafe extern "system" fn(GLsizei, *const GLuint),
+    bind_texture: unsafe extern "system" fn(GLenum, GLuint),
+    pixel_store_i: unsafe extern "system" fn(GLenum, GLint),
+    #[allow(clippy::type_complexity)]
+    tex_image_2d: unsafe extern "system" fn(
+        GLenum,
+        GLint,
+        GLint,
+        GLsizei,
+        GLsizei,
+        GLint,
+        GLenum,
+        GLenum,
+        *const c_void,
+    ),
+    generate_mipmap: unsafe extern "system" fn(GLenum),
+    finish: unsafe extern "system" fn(),
+}
+
+impl UploadGl {
+    /// Load the functions with the upload context current, `None` if one is missing.
+    fn load(display: &Display) -> Option<Self> {
+        let load = |name: &str| {
+            let function = display.get_proc_address(CString::new(name).unwrap().as_c_str());
+            (!function.is_null()).then_some(function)
+        };
+        // The function pointers have the signatures of the GL specification.
+        unsafe {
+            Some(Self {
+                gen_textures: std::mem::transmute(load("glGenTextures")?),
+                delete_textures: std::mem::transmute(load("glDeleteTextures")?),
+                bind_texture: std::mem::transmute(load("glBindTexture")?),
+                pixel_store_i: std::mem::transmute(load("glPixelStorei")?),
+                tex_image_2d: std::mem::transmute(load("glTexImage2D")?),
+                generate_mipmap: std::mem::transmute(load("glGenerateMipmap")?),
+                finish: std::mem::transmute(load("glFinish")?),
+            })
+        }
+    }
+}
+
+/// The context and its surface, only made current on the upload thread.
+struct UploadContext {
+    context: NotCurrentContext,
+    surface: Surface<PbufferSurface>,
+}
+unsafe impl Send for UploadContext {}
+
+/// Uploads images to textures and generates their mipmaps on a worker thread with a GL
+/// context sharing the rendering context resources, so large uploads don't stall frames.
+/// The renderers get it with `FrameInfo::uploader`.
+#[derive(Clone)]
+pub struct TextureUploader {
+    jobs: Sender<Job>,
+}
+
+impl TextureUploader {
+    /// Create the shared context and its worker. Fails when the platform can't create a
+    /// shared context or a pbuffer surface to make it current, e.g. on some Wayland and
+    /// macOS setups, the images are uploaded by the rendering context then.
+    pub fn new(gl_env: &GlEnv) -> io::Result<Self> {
+        let context = gl_env
+            .create_shared_context()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let attributes = SurfaceAttributesBuilder::<PbufferSurface>::new()
+            .build(NonZeroU32::MIN, NonZeroU32::MIN);
+        let surface = unsafe {
+            gl_env
+                .gl_config()
+                .display()
+                .create_pbuffer_surface(gl_env.gl_config(), &attributes)
+        }
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let upload_context = UploadContext { context, surface };
+
+        let (jobs, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("texture_upload".to_string())
+            .spawn(move || {
+                let UploadContext { context, surface } = upload_context;
+                let display = context.display();
+                let _context = match context.make_current(&surface) {
+                    Ok(context) => context,
+                    Err(err) => {
+                        eprintln!("Error making the upload context current: {err}");
+                        return;
+                    }
+                };
+                let Some(gl) = UploadGl::load(&display) else {
+                    eprintln!("Error loading the GL functions of the upload context");
+                    return;
+                };
+
+                for job in receiver {
+                    match job {
+                        Job::Upload {
+                            image,
+                            mipmapped,
+                            slot,
+                        } => {
+                            let result = match upload(&gl, &image, mipmapped) {
+                                Some(texture) => Slot::Ready(texture),
+                                None => Slot::Failed,
+                            };
+                            let mut slot = slot.lock().unwrap();
+                            match (&*slot, result) {
+                                // The handle was dropped meanwhile.
+                                (Slot::Taken, Slot::Ready(texture)) => unsafe {
+                                    (gl.delete_textures)(1, &texture.id)
+                                },
+                                (_, result) => *slot = result,
+                            }
+                        }
+                        Job::Delete(id) => unsafe { (gl.delete_textures)(1, &id) },
+                    }
+                }
+            })?;
+        Ok(Self { jobs })
+    }
+
+    /// Queue the upload of a raster image, `mipmapped` generates the mipmaps on the worker.
+    pub fn upload(&self, image: Image, mipmapped: bool) -> UploadHandle {
+        let slot = Arc::new(Mutex::new(Slot::Pending));
+        let job = Job::Upload {
+            image,
+            mipmapped,
+            slot: slot.clone(),
+        };
+        if self.jobs.send(job).is_err() {
+            *slot.lock().unwrap() = Slot::Failed;
+        }
+        UploadHandle {
+            slot,
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+/// Upload the pixels to a new texture and wait for the GPU, so the texture is complete
+/// when the rendering context uses it.
+fn upload(gl: &UploadGl, image: &Image, mipmapped: bool) -> Option<UploadedTexture> {
+    let size = (image.width(), image.height());
+    let info = ImageInfo::new(size, ColorType::RGBA8888, AlphaType::Premul, None);
+    let row_bytes = info.min_row_bytes();
+    let mut pixels = vec![0u8; info.compute_min_byte_size()];
+    if !image.read_pixels(
+        &info,
+        &mut pixels,
+        row_bytes,
+        (0, 0),
+        skia_safe::image::CachingHint::Disallow,
+    ) {
+        eprintln!("Error reading the pixels of a {}x{} upload", size.0, size.1);
+        return None;
+    }
+
+    let mut id = 0;
+    unsafe {
+        (gl.gen_textures)(1, &mut id);
+        (gl.bind_texture)(gl::TEXTURE_2D, id);
+        (gl.pixel_store_i)(gl::UNPACK_ALIGNMENT, 4);
+        (gl.tex_image_2d)(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA8 as i32,
+            size.0,
+            size.1,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_ptr().cast(),
+        );
+        if mipmapped {
+            (gl.generate_mipmap)(gl::TEXTURE_2D);
+        }
+        (gl.bind_texture)(gl::TEXTURE_2D, 0);
+        (gl.finish)();
+    }
+    Some(UploadedTexture {
+        id,
+        size,
+        mipmapped,
+    })
+}
+
+/// A queued upload, poll it from the renderer until the image is ready.
+pub struct UploadHandle {
+    slot: Arc<Mutex<Slot>>,
+    jobs: Sender<Job>,
+}
+
+impl UploadHandle {
+    /// Whether the upload is done, or failed.
+    pub fn is_ready(&self) -> bool {
+        !matches!(*self.slot.lock().unwrap(), Slot::Pending)
+    }
+
+    /// The uploaded image, owned by the GPU context of the canvas once returned. Returns
+    /// `None` while the upload is pending and after it was taken or failed.
+    pub fn poll(&self, canvas: &mut Canvas) -> Option<Image> {
+        let mut context = canvas.recording_context()?;
+        let mut slot = self.slot.lock().unwrap();
+        if !matches!(*slot, Slot::Ready(_)) {
+            return None;
+        }
+        let Slot::Ready(texture) = std::mem::replace(&mut *slot, Slot::Taken) else {
+            unreachable!()
+        };
+        let texture_info = TextureInfo {
+            target: gl::TEXTURE_2D,
+            id: texture.id,
+            format: gl::RGBA8,
+            ..Default::default()
+        };
+        let mipmapped = if texture.mipmapped {
+            gpu::Mipmapped::Yes
+        } else {
+            gpu::Mipmapped::No
+        };
+        let backend_texture =
+            unsafe { BackendTexture::new_gl(texture.size, mipmapped, texture_info) };
+        Image::from_adopted_texture(
+            &mut context,
+            &backend_texture,
+            SurfaceOrigin::TopLeft,
+            ColorType::RGBA8888,
+            AlphaType::Premul,
+            None,
+        )
+    }
+}
+
+impl Drop for UploadHandle {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().unwrap();
+        if let Slot::Ready(texture) = std::mem::replace(&mut *slot, Slot::Taken) {
+            self.jobs.send(Job::Delete(texture.id)).ok();
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::{any::Any, time::Instant};
+
+/// An event of the application posted with `Backend::post_event` or
+/// `BackendProxy::post_event`, handed to `Renderer::handle_user_event` at the start of the
+/// next frame, in the posted order. The payload is any type the renderer downcasts to,
+/// e.g. the data of a finished request.
+#[derive(Debug)]
+pub struct UserEvent {
+    /// When the event was posted, e.g. to measure the latency with `timestamp.elapsed()`
+    /// in the handler.
+    pub timestamp: Instant,
+    payload: Box<dyn Any + Send>,
+}
+
+impl UserEvent {
+    #[inline]
+    pub fn new<T: Any + Send>(payload: T) -> Self {
+        Self {
+            timestamp: Instant::now(),
+            payload: Box::new(payload),
+        }
+    }
+
+    #[inline]
+    pub fn is<T: Any>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+
+    #[inline]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.payload.downcast_ref()
+    }
+
+    /// The payload, or the event back when it is of another type.
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        match self.payload.downcast() {
+            Ok(payload) => Ok(*payload),
+            Err(payload) => Err(Self {
+                timestamp: self.timestamp,
+                payload,
+            }),
+        }
+    }
+}
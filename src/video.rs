@@ -0,0 +1,283 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use skia_safe::{
+    gpu::{DirectContext, Mipmapped},
+    AlphaType, Canvas, ColorType, Data, Image, ImageInfo, Rect,
+};
+
+use crate::{
+    focus::{FocusId, FocusNode},
+    input::InputEvent,
+    invalidation::Invalidator,
+    locale::Locale,
+    proxy::BackendProxy,
+    quality::Quality,
+    renderer::{Antialiasing, FrameInfo, Renderer},
+    theme::SystemTheme,
+    user_event::UserEvent,
+};
+
+/// A decoded RGBA8 video frame, e.g. from an ffmpeg or gstreamer callback.
+#[derive(Debug, Clone)]
+pub struct VideoFrame {
+    pub width: i32,
+    pub height: i32,
+    pub row_bytes: usize,
+    pub pixels: Vec<u8>,
+    /// The presentation time relative to the start of the stream.
+    pub pts: Duration,
+}
+
+/// The producer side of the frame queue, cheap to clone and send to the decoder threads.
+#[derive(Debug, Clone)]
+pub struct VideoSink {
+    queue: Arc<Mutex<VecDeque<VideoFrame>>>,
+    capacity: usize,
+}
+
+impl VideoSink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Queue the frame, drops the oldest frame when the queue is full.
+    pub fn push(&self, frame: VideoFrame) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= self.capacity {
+            queue.pop_front();
+        }
+        queue.push_back(frame);
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        self.queue.lock().unwrap().clear();
+    }
+
+    /// Take the latest frame due at `pts`, the older due frames are dropped.
+    fn take_due(&self, pts: Duration) -> Option<VideoFrame> {
+        let mut queue = self.queue.lock().unwrap();
+        let mut due = None;
+        while queue.front().map_or(false, |frame| frame.pts <= pts) {
+            due = queue.pop_front();
+        }
+        due
+    }
+
+    fn first_pts(&self) -> Option<Duration> {
+        self.queue.lock().unwrap().front().map(|frame| frame.pts)
+    }
+}
+
+/// Uploads the queued frames as textures on the GL thread, synchronized with their
+/// presentation time.
+pub struct VideoPlayer {
+    sink: VideoSink,
+    /// The instant and pts the playback started at.
+    start: Option<(Instant, Duration)>,
+    image: Option<Image>,
+}
+
+impl VideoPlayer {
+    pub fn new(sink: VideoSink) -> Self {
+        Self {
+            sink,
+            start: None,
+            image: None,
+        }
+    }
+
+    #[inline]
+    pub fn sink(&self) -> &VideoSink {
+        &self.sink
+    }
+
+    /// The frame currently presented, backed by a texture of the context passed to `update`.
+    #[inline]
+    pub fn image(&self) -> Option<&Image> {
+        self.image.as_ref()
+    }
+
+    /// Restart the presentation clock from the next queued frame.
+    #[inline]
+    pub fn restart(&mut self) {
+        self.start = None;
+    }
+
+    /// Upload the frame due for presentation, must be called on the thread owning the context.
+    pub fn update(&mut self, context: &mut DirectContext) -> Option<&Image> {
+        if self.start.is_none() {
+            self.start = self.sink.first_pts().map(|pts| (Instant::now(), pts));
+        }
+        let Some((start, start_pts)) = self.start else {
+            return self.image.as_ref();
+        };
+
+        if let Some(frame) = self.sink.take_due(start_pts + start.elapsed()) {
+            match upload(&frame, context) {
+                Some(image) => self.image = Some(image),
+                None => eprintln!("Error uploading video frame at {:?}", frame.pts),
+            }
+        }
+        self.image.as_ref()
+    }
+}
+
+fn upload(frame: &VideoFrame, context: &mut DirectContext) -> Option<Image> {
+    let info = ImageInfo::new(
+        (frame.width, frame.height),
+        ColorType::RGBA8888,
+        AlphaType::Unpremul,
+        None,
+    );
+    Image::from_raster_data(&info, Data::new_copy(&frame.pixels), frame.row_bytes)?
+        .new_texture_image(context, Mipmapped::No)
+}
+
+/// Draws the video scaled to fit the frame, with the ui renderer composited on top.
+pub struct VideoRenderer {
+    player: VideoPlayer,
+    ui: Option<Box<dyn Renderer>>,
+    invalidator: Option<Invalidator>,
+}
+
+impl VideoRenderer {
+    pub fn new(sink: VideoSink, ui: Option<Box<dyn Renderer>>) -> Self {
+        Self {
+            player: VideoPlayer::new(sink),
+            ui,
+            invalidator: None,
+        }
+    }
+
+    #[inline]
+    pub fn player(&mut self) -> &mut VideoPlayer {
+        &mut self.player
+    }
+}
+
+impl Renderer for VideoRenderer {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        if let Some(mut context) = canvas.direct_context() {
+            self.player.update(&mut context);
+        }
+        // Poll the sink for the next frame.
+        if let Some(invalidator) = &self.invalidator {
+            invalidator.invalidate();
+        }
+
+        if let Some(image) = self.player.image() {
+            let (width, height) = (info.size.0 as f32, info.size.1 as f32);
+            let scale = (width / image.width() as f32).min(height / image.height() as f32);
+            let (image_width, image_height) =
+                (image.width() as f32 * scale, image.height() as f32 * scale);
+            let dst = Rect::from_xywh(
+                (width - image_width) / 2.,
+                (height - image_height) / 2.,
+                image_width,
+                image_height,
+            );
+            canvas.draw_image_rect(image, None, dst, &Default::default());
+        }
+
+        if let Some(ui) = self.ui.as_mut() {
+            ui.render(canvas, info);
+        }
+    }
+
+    fn render_overlay(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.render_overlay(canvas, info);
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.handle_input(event);
+        }
+    }
+
+    fn focus_nodes(&self) -> Vec<FocusNode> {
+        self.ui
+            .as_ref()
+            .map_or_else(Vec::new, |ui| ui.focus_nodes())
+    }
+
+    fn handle_focused_key(&mut self, id: FocusId, event: &InputEvent) -> bool {
+        self.ui
+            .as_mut()
+            .map_or(false, |ui| ui.handle_focused_key(id, event))
+    }
+
+    fn system_theme_changed(&mut self, theme: SystemTheme) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.system_theme_changed(theme);
+        }
+    }
+
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.scale_factor_changed(scale_factor);
+        }
+    }
+
+    fn locale_changed(&mut self, locale: &Locale) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.locale_changed(locale);
+        }
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        self.ui.as_ref()?.save_state()
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.restore_state(state);
+        }
+    }
+
+    fn context_lost(&mut self) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.context_lost();
+        }
+    }
+
+    fn attach_invalidator(&mut self, invalidator: Invalidator) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.attach_invalidator(invalidator.clone());
+        }
+        self.invalidator = Some(invalidator);
+    }
+
+    fn attach_proxy(&mut self, proxy: BackendProxy) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.attach_proxy(proxy);
+        }
+    }
+
+    fn handle_user_event(&mut self, event: UserEvent) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.handle_user_event(event);
+        }
+    }
+
+    fn quality_changed(&mut self, quality: Quality) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.quality_changed(quality);
+        }
+    }
+
+    fn antialiasing(&self) -> Antialiasing {
+        self.ui
+            .as_ref()
+            .map_or(Antialiasing::Msaa, |ui| ui.antialiasing())
+    }
+}
@@ -0,0 +1,340 @@
+use std::{collections::HashMap, time::Instant};
+
+use skia_safe::{Canvas, ClipOp, Color, Contains, Paint, PaintStyle, Point, Rect};
+
+use crate::{
+    camera::Camera,
+    input::{InputEvent, InputState, MouseButton, TouchPhase},
+    invalidation::Invalidator,
+    locale::Locale,
+    proxy::BackendProxy,
+    quality::Quality,
+    recovery,
+    renderer::{FrameInfo, Renderer},
+    theme::SystemTheme,
+};
+
+/// A region of the window with its own clip, camera and renderer.
+pub struct Viewport {
+    /// The region in window fractions, e.g. `Rect::new(0., 0., 0.5, 1.)` is the left half.
+    pub bounds: Rect,
+    pub camera: Camera,
+    pub background: Option<Color>,
+    pub border: Option<Color>,
+    renderer: Box<dyn Renderer>,
+    input: InputState,
+}
+
+impl Viewport {
+    pub fn new(bounds: Rect, renderer: Box<dyn Renderer>) -> Self {
+        Self {
+            bounds,
+            camera: Camera::default(),
+            background: None,
+            border: None,
+            renderer,
+            input: InputState::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    #[inline]
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    #[inline]
+    pub fn with_border(mut self, color: Color) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    #[inline]
+    pub fn renderer(&mut self) -> &mut dyn Renderer {
+        self.renderer.as_mut()
+    }
+
+    /// The viewport rect in window pixels.
+    #[inline]
+    pub fn rect(&self, size: (i32, i32)) -> Rect {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        Rect::new(
+            self.bounds.left * width,
+            self.bounds.top * height,
+            self.bounds.right * width,
+            self.bounds.bottom * height,
+        )
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, rect: Rect) {
+        let event = event.map_position(|position| (position.0 - rect.left, position.1 - rect.top));
+        self.input.handle(&event);
+        if !self.camera.handle_input(&event, &self.input) {
+            let camera = &self.camera;
+            let event = event.map_position(|position| camera.to_content(position));
+            self.renderer.handle_input(&event);
+        }
+    }
+
+    /// Render into the rect of the window, the frame info of the window is narrowed to the
+    /// viewport.
+    fn render(&mut self, canvas: &mut Canvas, rect: Rect, dt: f32, info: &FrameInfo) {
+        self.camera.update(dt);
+
+        canvas.save();
+        canvas.clip_rect(rect, ClipOp::Intersect, false);
+        canvas.translate((rect.left, rect.top));
+
+        if let Some(background) = self.background {
+            canvas.clear(background);
+        }
+
+        canvas.save();
+        canvas.concat(&self.camera.matrix());
+        let info = FrameInfo {
+            size: (rect.width() as i32, rect.height() as i32),
+            safe_area: Rect::from_wh(rect.width(), rect.height()),
+            input: &self.input,
+            camera: &self.camera,
+            previous_frame: None,
+            ..*info
+        };
+        self.renderer.render(canvas, &info);
+        canvas.restore();
+
+        self.renderer.render_overlay(canvas, &info);
+
+        if let Some(border) = self.border {
+            let mut paint = Paint::default();
+            paint.set_style(PaintStyle::Stroke);
+            paint.set_color(border);
+            canvas.draw_rect(Rect::from_wh(rect.width(), rect.height()), &paint);
+        }
+
+        canvas.restore();
+    }
+}
+
+/// Composites multiple viewports in one frame, e.g. a main view plus side previews.
+///
+/// The viewports have their own cameras, so the window camera should be disabled with
+/// `Backend::set_camera_config(CameraConfig::disabled())`.
+pub struct ViewportLayout {
+    viewports: Vec<Viewport>,
+    size: (i32, i32),
+    /// The viewport receiving the events without a position.
+    active: Option<usize>,
+    touches: HashMap<u64, usize>,
+    input: InputState,
+    last_frame: Instant,
+    /// Requests the frames while the momentum moves a camera, attached to the viewports
+    /// pushed later with the proxy and the quality.
+    invalidator: Option<Invalidator>,
+    proxy: Option<BackendProxy>,
+    quality: Quality,
+}
+
+impl Default for ViewportLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewportLayout {
+    pub fn new() -> Self {
+        Self {
+            viewports: vec![],
+            size: (0, 0),
+            active: None,
+            touches: HashMap::new(),
+            input: InputState::default(),
+            last_frame: Instant::now(),
+            invalidator: None,
+            proxy: None,
+            quality: Quality::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.push(viewport);
+        self
+    }
+
+    /// Add the viewport on top of the previous ones, returns its index.
+    #[inline]
+    pub fn push(&mut self, mut viewport: Viewport) -> usize {
+        if let Some(invalidator) = &self.invalidator {
+            viewport.renderer.attach_invalidator(invalidator.clone());
+        }
+        if let Some(proxy) = &self.proxy {
+            viewport.renderer.attach_proxy(proxy.clone());
+        }
+        if !self.quality.is_full() {
+            viewport.renderer.quality_changed(self.quality);
+        }
+        self.viewports.push(viewport);
+        self.viewports.len() - 1
+    }
+
+    #[inline]
+    pub fn viewport(&mut self, index: usize) -> Option<&mut Viewport> {
+        self.viewports.get_mut(index)
+    }
+
+    #[inline]
+    pub fn viewports(&self) -> &[Viewport] {
+        &self.viewports
+    }
+
+    /// The top most viewport containing the window position.
+    pub fn viewport_at(&self, position: (f32, f32)) -> Option<usize> {
+        self.viewports
+            .iter()
+            .rposition(|viewport| viewport.rect(self.size).contains(Point::from(position)))
+    }
+
+    fn dispatch(&mut self, index: usize, event: &InputEvent) {
+        let rect = self.viewports[index].rect(self.size);
+        self.viewports[index].handle_input(event, rect);
+    }
+}
+
+impl Renderer for ViewportLayout {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.size = info.size;
+        for viewport in self.viewports.iter_mut() {
+            let rect = viewport.rect(info.size);
+            viewport.render(canvas, rect, dt, info);
+        }
+        if self
+            .viewports
+            .iter()
+            .any(|viewport| viewport.camera.is_moving())
+        {
+            if let Some(invalidator) = &self.invalidator {
+                invalidator.invalidate();
+            }
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        self.input.handle(event);
+
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                // Keep routing to the viewport capturing the pressed buttons, e.g. dragging.
+                let captured = self.input.is_pressed(MouseButton::Left)
+                    || self.input.is_pressed(MouseButton::Middle)
+                    || self.input.is_pressed(MouseButton::Right);
+                if !captured {
+                    self.active = self.viewport_at(position);
+                }
+                if let Some(index) = self.active {
+                    self.dispatch(index, event);
+                }
+            }
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+                ..
+            } => {
+                if phase == TouchPhase::Started {
+                    if let Some(index) = self.viewport_at(position) {
+                        self.touches.insert(id, index);
+                    }
+                }
+                let index = match phase {
+                    TouchPhase::Ended | TouchPhase::Cancelled => self.touches.remove(&id),
+                    _ => self.touches.get(&id).copied(),
+                };
+                if let Some(index) = index {
+                    self.dispatch(index, event);
+                }
+            }
+            InputEvent::Modifiers(_) => {
+                for index in 0..self.viewports.len() {
+                    self.dispatch(index, event);
+                }
+            }
+            _ => {
+                if let Some(index) = self.active {
+                    self.dispatch(index, event);
+                }
+            }
+        }
+    }
+
+    fn system_theme_changed(&mut self, theme: SystemTheme) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.system_theme_changed(theme);
+        }
+    }
+
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.scale_factor_changed(scale_factor);
+        }
+    }
+
+    fn locale_changed(&mut self, locale: &Locale) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.locale_changed(locale);
+        }
+    }
+
+    /// The states of the renderers of every viewport.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        recovery::join_states(
+            self.viewports
+                .iter()
+                .map(|viewport| viewport.renderer.save_state()),
+        )
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        for (viewport, state) in self.viewports.iter_mut().zip(recovery::split_states(state)) {
+            if let Some(state) = state {
+                viewport.renderer.restore_state(state);
+            }
+        }
+    }
+
+    fn context_lost(&mut self) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.context_lost();
+        }
+    }
+
+    fn attach_invalidator(&mut self, invalidator: Invalidator) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.attach_invalidator(invalidator.clone());
+        }
+        self.invalidator = Some(invalidator);
+    }
+
+    fn attach_proxy(&mut self, proxy: BackendProxy) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.attach_proxy(proxy.clone());
+        }
+        self.proxy = Some(proxy);
+    }
+
+    fn quality_changed(&mut self, quality: Quality) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.renderer.quality_changed(quality);
+        }
+        self.quality = quality;
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// What the watchdog does when the render thread stalls, besides logging.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    #[default]
+    Log,
+    /// Abandon the GPU context and create a new one, helps after a GPU reset but not when
+    /// the render thread is deadlocked.
+    RecreateContext,
+    /// Close the window and leave the event loop.
+    Shutdown,
+}
+
+impl FromStr for WatchdogAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "log" => Ok(WatchdogAction::Log),
+            "recreate" => Ok(WatchdogAction::RecreateContext),
+            "shutdown" => Ok(WatchdogAction::Shutdown),
+            _ => Err(format!("Unknown watchdog action `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for WatchdogAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WatchdogAction::Log => "log",
+            WatchdogAction::RecreateContext => "recreate",
+            WatchdogAction::Shutdown => "shutdown",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchdogConfig {
+    /// The time without a frame after which the render thread counts as stalled.
+    pub timeout: Duration,
+    pub action: WatchdogAction,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            action: WatchdogAction::Log,
+        }
+    }
+}
+
+/// The time of the last frame the render thread produced, readable without locking, so a
+/// deadlocked render thread can't block the watchdog.
+#[derive(Debug)]
+pub struct Heartbeat {
+    start: Instant,
+    /// Nanoseconds since `start`.
+    last: AtomicU64,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            last: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Heartbeat {
+    /// Report a frame was produced.
+    #[inline]
+    pub fn beat(&self) {
+        self.last
+            .store(self.start.elapsed().as_nanos() as u64, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn last(&self) -> Instant {
+        self.start + Duration::from_nanos(self.last.load(Ordering::Acquire))
+    }
+}
+
+/// Detects from the main thread when the render thread stopped producing frames.
+#[derive(Debug)]
+pub struct Watchdog {
+    config: WatchdogConfig,
+    /// Frames are only expected after it, e.g. after the rendering resumed.
+    armed_since: Instant,
+    stalled: bool,
+}
+
+impl Watchdog {
+    #[inline]
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            armed_since: Instant::now(),
+            stalled: false,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    /// Restart the timeout, e.g. when the rendering resumes after a pause.
+    #[inline]
+    pub fn rearm(&mut self) {
+        self.armed_since = Instant::now();
+    }
+
+    /// Returns the time since the last frame when the render thread just stalled, once per
+    /// stall.
+    pub fn check(&mut self, heartbeat: &Heartbeat, now: Instant) -> Option<Duration> {
+        let since = now.saturating_duration_since(heartbeat.last().max(self.armed_since));
+        if since <= self.config.timeout {
+            self.stalled = false;
+            return None;
+        }
+        if std::mem::replace(&mut self.stalled, true) {
+            return None;
+        }
+        Some(now.saturating_duration_since(heartbeat.last()))
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Instant,
+};
+
+use nokhwa::{
+    pixel_format::RgbAFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera, NokhwaError,
+};
+
+use crate::video::{VideoFrame, VideoSink};
+
+/// Captures frames from a webcam on a background thread into a `VideoSink`,
+/// draw them with a `VideoRenderer`.
+pub struct Webcam {
+    sink: VideoSink,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Webcam {
+    /// Open the camera at `index` with its highest frame rate and start capturing.
+    pub fn open(index: u32, capacity: usize) -> Result<Self, NokhwaError> {
+        let format =
+            RequestedFormat::new::<RgbAFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+        let mut camera = Camera::new(CameraIndex::Index(index), format)?;
+        camera.open_stream()?;
+
+        let sink = VideoSink::new(capacity);
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let sink = sink.clone();
+            let running = running.clone();
+            std::thread::Builder::new()
+                .name("webcam".to_string())
+                .spawn(move || capture(camera, sink, running))
+                .expect("Failed to spawn webcam thread")
+        };
+
+        Ok(Self {
+            sink,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    #[inline]
+    pub fn sink(&self) -> &VideoSink {
+        &self.sink
+    }
+}
+
+impl Drop for Webcam {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+fn capture(mut camera: Camera, sink: VideoSink, running: Arc<AtomicBool>) {
+    let start = Instant::now();
+    while running.load(Ordering::Relaxed) {
+        let image = match camera
+            .frame()
+            .and_then(|buffer| buffer.decode_image::<RgbAFormat>())
+        {
+            Ok(image) => image,
+            Err(err) => {
+                eprintln!("Error capturing webcam frame: {err}");
+                break;
+            }
+        };
+
+        let (width, height) = image.dimensions();
+        sink.push(VideoFrame {
+            width: width as i32,
+            height: height as i32,
+            row_bytes: width as usize * 4,
+            pixels: image.into_raw(),
+            pts: start.elapsed(),
+        });
+    }
+    camera.stop_stream().ok();
+}
@@ -0,0 +1,481 @@
+use std::collections::VecDeque;
+
+use skia_safe::{Canvas, Font, Paint, PaintStyle, Point, RRect, Rect, Typeface};
+
+use crate::{
+    focus::{FocusId, FocusNode},
+    input::{InputEvent, KeyCode, MouseButton},
+    layout::LayoutTree,
+    locale::Locale,
+    renderer::{FrameInfo, Renderer},
+    theme::Theme,
+};
+
+/// The bounds of a slider in order, a NaN bound takes the other one, e.g. for `f32::clamp`
+/// which panics on unordered bounds.
+fn ordered(min: f32, max: f32) -> (f32, f32) {
+    match (min.min(max), max.max(min)) {
+        (min, max) if min <= max => (min, max),
+        _ => (0., 0.),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WidgetId(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetKind {
+    Label { text: String },
+    Button { text: String },
+    Checkbox { text: String, checked: bool },
+    Slider { value: f32, min: f32, max: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Widget {
+    pub rect: Rect,
+    pub kind: WidgetKind,
+    pub enabled: bool,
+    hovered: bool,
+}
+
+impl Widget {
+    #[inline]
+    pub fn new(rect: Rect, kind: WidgetKind) -> Self {
+        Self {
+            rect,
+            kind,
+            enabled: true,
+            hovered: false,
+        }
+    }
+
+    #[inline]
+    pub fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    #[inline]
+    fn is_interactive(&self) -> bool {
+        self.enabled && !matches!(self.kind, WidgetKind::Label { .. })
+    }
+}
+
+/// The fraction of the slider range an arrow key moves the value by.
+const SLIDER_KEY_STEP: f32 = 0.05;
+
+/// The changes made by the user, read them with `WidgetPanel::take_events`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WidgetEvent {
+    Clicked(WidgetId),
+    Toggled(WidgetId, bool),
+    ValueChanged(WidgetId, f32),
+}
+
+/// A retained set of widgets positioned in the canvas space, e.g. a tool panel. The texts
+/// are drawn for the locale of the panel, see `Renderer::locale_changed`.
+#[derive(Default)]
+pub struct WidgetPanel {
+    widgets: Vec<Widget>,
+    /// The widget the left button was pressed on.
+    active: Option<WidgetId>,
+    cursor: Option<(f32, f32)>,
+    events: VecDeque<WidgetEvent>,
+    locale: Locale,
+    typeface: Option<Typeface>,
+    /// Positions the bound widgets in the frame size before they are drawn.
+    layout: Option<LayoutTree>,
+}
+
+impl WidgetPanel {
+    pub fn add(&mut self, widget: Widget) -> WidgetId {
+        self.widgets.push(widget);
+        WidgetId(self.widgets.len() - 1)
+    }
+
+    #[inline]
+    pub fn add_label(&mut self, rect: Rect, text: impl Into<String>) -> WidgetId {
+        self.add(Widget::new(rect, WidgetKind::Label { text: text.into() }))
+    }
+
+    #[inline]
+    pub fn add_button(&mut self, rect: Rect, text: impl Into<String>) -> WidgetId {
+        self.add(Widget::new(rect, WidgetKind::Button { text: text.into() }))
+    }
+
+    #[inline]
+    pub fn add_checkbox(&mut self, rect: Rect, text: impl Into<String>, checked: bool) -> WidgetId {
+        self.add(Widget::new(
+            rect,
+            WidgetKind::Checkbox {
+                text: text.into(),
+                checked,
+            },
+        ))
+    }
+
+    /// Bounds given in the wrong order are swapped.
+    #[inline]
+    pub fn add_slider(&mut self, rect: Rect, value: f32, min: f32, max: f32) -> WidgetId {
+        let (min, max) = ordered(min, max);
+        self.add(Widget::new(
+            rect,
+            WidgetKind::Slider {
+                value: value.clamp(min, max),
+                min,
+                max,
+            },
+        ))
+    }
+
+    /// Lay the widgets bound with `LayoutTree::bind_widget` out in the frame size before
+    /// drawing them, the texts of the tree are measured for the locale of the panel.
+    pub fn set_layout(&mut self, layout: Option<LayoutTree>) {
+        self.layout = layout.map(|mut layout| {
+            layout.set_locale(self.locale.clone());
+            layout
+        });
+    }
+
+    #[inline]
+    pub fn layout_mut(&mut self) -> Option<&mut LayoutTree> {
+        self.layout.as_mut()
+    }
+
+    #[inline]
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Draw the texts with the digits and the typeface of the locale, see
+    /// `Locale::typeface`.
+    pub fn set_locale(&mut self, locale: Locale) {
+        if locale == self.locale {
+            return;
+        }
+        if let Some(layout) = self.layout.as_mut() {
+            layout.set_locale(locale.clone());
+        }
+        self.typeface = locale.typeface();
+        self.locale = locale;
+    }
+
+    #[inline]
+    pub fn widget(&self, id: WidgetId) -> &Widget {
+        &self.widgets[id.0]
+    }
+
+    #[inline]
+    pub fn widget_mut(&mut self, id: WidgetId) -> &mut Widget {
+        &mut self.widgets[id.0]
+    }
+
+    pub fn widgets(&self) -> impl Iterator<Item = (WidgetId, &Widget)> {
+        self.widgets
+            .iter()
+            .enumerate()
+            .map(|(index, widget)| (WidgetId(index), widget))
+    }
+
+    #[inline]
+    pub fn take_events(&mut self) -> impl Iterator<Item = WidgetEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn widget_at(&self, position: (f32, f32)) -> Option<WidgetId> {
+        self.widgets()
+            .rev()
+            .find(|(_, widget)| {
+                widget.is_interactive() && widget.rect.contains(Point::from(position))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Handle the input event, returns whether a widget consumed it.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        let cursor = self.cursor;
+        match *event {
+            InputEvent::CursorMoved { position } => {
+                self.cursor = Some(position);
+                let hovered = self.widget_at(position);
+                for (index, widget) in self.widgets.iter_mut().enumerate() {
+                    widget.hovered = hovered == Some(WidgetId(index));
+                }
+                if let Some(id) = self.active {
+                    self.drag(id, position);
+                    return true;
+                }
+                false
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            } => {
+                let Some(id) = cursor.and_then(|position| self.widget_at(position)) else {
+                    return false;
+                };
+                self.active = Some(id);
+                if let Some(position) = cursor {
+                    self.drag(id, position);
+                }
+                true
+            }
+            InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            } => {
+                let Some(id) = self.active.take() else {
+                    return false;
+                };
+                let released_inside = cursor.is_some_and(|position| {
+                    self.widgets[id.0].rect.contains(Point::from(position))
+                });
+                if released_inside {
+                    self.click(id);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn click(&mut self, id: WidgetId) {
+        match &mut self.widgets[id.0].kind {
+            WidgetKind::Button { .. } => self.events.push_back(WidgetEvent::Clicked(id)),
+            WidgetKind::Checkbox { checked, .. } => {
+                *checked = !*checked;
+                self.events.push_back(WidgetEvent::Toggled(id, *checked));
+            }
+            WidgetKind::Label { .. } | WidgetKind::Slider { .. } => {}
+        }
+    }
+
+    /// Handle a key for the focused widget, Space and Enter click, the arrows, Home and End
+    /// move the sliders. Returns whether the widget consumed the key.
+    pub fn handle_key(&mut self, id: WidgetId, code: KeyCode) -> bool {
+        let widget = &mut self.widgets[id.0];
+        if !widget.is_interactive() {
+            return false;
+        }
+        match (&mut widget.kind, code) {
+            (
+                WidgetKind::Button { .. } | WidgetKind::Checkbox { .. },
+                KeyCode::Space | KeyCode::Enter,
+            ) => {
+                self.click(id);
+                true
+            }
+            (WidgetKind::Slider { value, min, max }, code) => {
+                let step = (*max - *min) * SLIDER_KEY_STEP;
+                let new_value = match code {
+                    KeyCode::ArrowLeft | KeyCode::ArrowDown => *value - step,
+                    KeyCode::ArrowRight | KeyCode::ArrowUp => *value + step,
+                    KeyCode::Home => *min,
+                    KeyCode::End => *max,
+                    _ => return false,
+                };
+                // The bounds may have been swapped through `widget_mut`.
+                let (low, high) = ordered(*min, *max);
+                let new_value = new_value.clamp(low, high);
+                if new_value != *value {
+                    *value = new_value;
+                    self.events
+                        .push_back(WidgetEvent::ValueChanged(id, new_value));
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn drag(&mut self, id: WidgetId, position: (f32, f32)) {
+        let widget = &mut self.widgets[id.0];
+        if let WidgetKind::Slider { value, min, max } = &mut widget.kind {
+            let width = widget.rect.width().max(f32::EPSILON);
+            let t = ((position.0 - widget.rect.left) / width).clamp(0., 1.);
+            let new_value = *min + t * (*max - *min);
+            if new_value != *value {
+                *value = new_value;
+                self.events
+                    .push_back(WidgetEvent::ValueChanged(id, new_value));
+            }
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, theme: &Theme) {
+        let font = match &self.typeface {
+            Some(typeface) => Font::from_typeface(typeface, theme.typography.font_size),
+            None => Font::default()
+                .with_size(theme.typography.font_size)
+                .expect("Could not create font"),
+        };
+        for (id, widget) in self.widgets() {
+            draw_widget(
+                canvas,
+                theme,
+                &font,
+                &self.locale,
+                widget,
+                self.active == Some(id),
+            );
+        }
+    }
+}
+
+fn draw_widget(
+    canvas: &mut Canvas,
+    theme: &Theme,
+    font: &Font,
+    locale: &Locale,
+    widget: &Widget,
+    pressed: bool,
+) {
+    let colors = &theme.colors;
+    let mut fill = Paint::default();
+    fill.set_anti_alias(true);
+    let mut text = fill.clone();
+    text.set_color(if widget.enabled {
+        colors.text
+    } else {
+        colors.text_disabled
+    });
+    let background = if pressed {
+        colors.surface_pressed
+    } else if widget.hovered {
+        colors.surface_hovered
+    } else {
+        colors.surface
+    };
+    let rect = widget.rect;
+    let baseline = rect.center_y() + theme.typography.font_size * 0.35;
+
+    match &widget.kind {
+        WidgetKind::Label { text: label } => {
+            canvas.draw_str(
+                locale.shape_digits(label),
+                (rect.left, baseline),
+                font,
+                &text,
+            );
+        }
+        WidgetKind::Button { text: label } => {
+            fill.set_color(background);
+            canvas.draw_rrect(
+                RRect::new_rect_xy(rect, theme.radii.medium, theme.radii.medium),
+                &fill,
+            );
+            let label = locale.shape_digits(label);
+            let width = font.measure_str(&label, None).0;
+            canvas.draw_str(label, (rect.center_x() - width / 2., baseline), font, &text);
+        }
+        WidgetKind::Checkbox {
+            text: label,
+            checked,
+        } => {
+            let size = rect.height().min(theme.typography.font_size + 4.);
+            let box_rect = Rect::from_xywh(rect.left, rect.center_y() - size / 2., size, size);
+            let radius = theme.radii.small;
+            fill.set_color(if *checked { colors.accent } else { background });
+            canvas.draw_rrect(RRect::new_rect_xy(box_rect, radius, radius), &fill);
+            if *checked {
+                let mut check = Paint::default();
+                check.set_anti_alias(true);
+                check.set_style(PaintStyle::Stroke);
+                check.set_stroke_width(2.);
+                check.set_color(colors.on_accent);
+                let (x, y) = (box_rect.left, box_rect.top);
+                canvas.draw_line(
+                    (x + size * 0.2, y + size * 0.5),
+                    (x + size * 0.42, y + size * 0.72),
+                    &check,
+                );
+                canvas.draw_line(
+                    (x + size * 0.42, y + size * 0.72),
+                    (x + size * 0.8, y + size * 0.3),
+                    &check,
+                );
+            }
+            canvas.draw_str(
+                locale.shape_digits(label),
+                (box_rect.right + size / 2., baseline),
+                font,
+                &text,
+            );
+        }
+        WidgetKind::Slider { value, min, max } => {
+            let t = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.
+            };
+            let track = Rect::from_xywh(rect.left, rect.center_y() - 2., rect.width(), 4.);
+            fill.set_color(background);
+            canvas.draw_rrect(RRect::new_rect_xy(track, 2., 2.), &fill);
+            fill.set_color(colors.accent);
+            canvas.draw_rrect(
+                RRect::new_rect_xy(
+                    Rect {
+                        right: track.left + track.width() * t,
+                        ..track
+                    },
+                    2.,
+                    2.,
+                ),
+                &fill,
+            );
+            let knob = (track.left + track.width() * t, rect.center_y());
+            let radius = (rect.height() / 2.).min(8.);
+            fill.set_color(if pressed || widget.hovered {
+                colors.accent
+            } else {
+                colors.on_accent
+            });
+            canvas.draw_circle(knob, radius, &fill);
+            let mut outline = Paint::default();
+            outline.set_anti_alias(true);
+            outline.set_style(PaintStyle::Stroke);
+            outline.set_color(colors.accent);
+            canvas.draw_circle(knob, radius, &outline);
+        }
+    }
+}
+
+impl Renderer for WidgetPanel {
+    fn render(&mut self, canvas: &mut Canvas, info: &FrameInfo) {
+        if let Some(mut layout) = self.layout.take() {
+            if layout.compute((info.size.0 as f32, info.size.1 as f32)) {
+                layout.apply_to_widgets(self);
+            }
+            self.layout = Some(layout);
+        }
+        self.draw(canvas, info.theme);
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) {
+        self.handle(event);
+    }
+
+    /// The interactive widgets in the order they were added.
+    fn focus_nodes(&self) -> Vec<FocusNode> {
+        self.widgets()
+            .filter(|(_, widget)| widget.is_interactive())
+            .map(|(id, widget)| FocusNode::new(FocusId(id.0 as u64), widget.rect))
+            .collect()
+    }
+
+    fn handle_focused_key(&mut self, id: FocusId, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::Key {
+                code,
+                pressed: true,
+            } => self.handle_key(WidgetId(id.0 as usize), code),
+            // Consume the releases of the keys handled on press.
+            InputEvent::Key { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn locale_changed(&mut self, locale: &Locale) {
+        self.set_locale(locale.clone());
+    }
+}
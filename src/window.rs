@@ -0,0 +1,94 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use glutin::{
+    config::ConfigTemplateBuilder,
+    context::{ContextApi, ContextAttributesBuilder, Version},
+    display::{GetGlDisplay, GlDisplay},
+    surface::{SurfaceAttributesBuilder, WindowSurface},
+};
+use glutin_winit::DisplayBuilder;
+use raw_window_handle::HasRawWindowHandle;
+use winit::{
+    event_loop::EventLoop,
+    window::{Window, WindowBuilder},
+};
+
+use crate::{
+    backend::{GlCtx, GlEnv},
+    surface::{ConfigReport, ConfigRequest},
+};
+
+/// Create the window with the GL config negotiated against the request, and its GL context
+/// and surface. Fails when the picked config fails a strict request.
+pub fn create_window<T>(
+    el: &EventLoop<T>,
+    window_builder: WindowBuilder,
+    config_request: ConfigRequest,
+) -> Result<(Arc<Window>, Arc<GlEnv>), String> {
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_transparency(true);
+
+    let display_builder = DisplayBuilder::new().with_window_builder(Some(window_builder));
+    let (window, gl_config) = display_builder
+        .build(el, template, |configs| {
+            // Prefer the configs without samples. Usually Skia takes care of anti-aliasing and
+            // may not be able to create appropriate Surfaces for samples > 0.
+            // See https://github.com/rust-skia/rust-skia/issues/782
+            // And https://github.com/rust-skia/rust-skia/issues/764
+            config_request
+                .negotiate(configs)
+                .expect("The display offered no GL config")
+        })
+        .map_err(|err| format!("Could not build the display: {err}"))?;
+    let config_report = ConfigReport::new(config_request, &gl_config);
+    println!("Picked a config: {config_report}");
+    config_report.check()?;
+    let window = Arc::new(window.ok_or("Could not create window with OpenGL context")?);
+    let raw_window_handle = window.raw_window_handle();
+
+    // The context creation part. It can be created before surface and that's how
+    // it's expected in multithreaded + multiwindow operation mode, since you
+    // can send NotCurrentContext, but not Surface.
+    let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
+
+    // Since glutin by default tries to create OpenGL core context, which may not be
+    // present we should try gles.
+    let fallback_context_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::Gles(None))
+        .build(Some(raw_window_handle));
+
+    // There are also some old devices that support neither modern OpenGL nor GLES.
+    // To support these we can try and create a 2.1 context.
+    let legacy_context_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::OpenGl(Some(Version::new(2, 1))))
+        .build(Some(raw_window_handle));
+
+    let display = gl_config.display();
+    let not_current_gl_context = unsafe {
+        display
+            .create_context(&gl_config, &context_attributes)
+            .or_else(|_| display.create_context(&gl_config, &fallback_context_attributes))
+            .or_else(|_| display.create_context(&gl_config, &legacy_context_attributes))
+            .map_err(|err| format!("Failed to create context: {err}"))?
+    };
+
+    let (width, height): (u32, u32) = window.inner_size().into();
+    let (Some(width), Some(height)) = (NonZeroU32::new(width), NonZeroU32::new(height)) else {
+        return Err(format!("Could not create a surface of {width}x{height}"));
+    };
+    let attrs =
+        SurfaceAttributesBuilder::<WindowSurface>::new().build(raw_window_handle, width, height);
+
+    let gl_surface = unsafe {
+        display
+            .create_window_surface(&gl_config, &attrs)
+            .map_err(|err| format!("Could not create gl window surface: {err}"))?
+    };
+
+    let gl_env = Arc::new(
+        GlEnv::new(gl_surface, GlCtx::new(not_current_gl_context), gl_config)
+            .with_config_report(config_report),
+    );
+    Ok((window, gl_env))
+}